@@ -0,0 +1,500 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const CPU_PERIOD_USEC: u64 = 100_000;
+const LIMITED_SLICE: &str = "kern.slice";
+const LIMITED_SCOPE: &str = "kern-limited.scope";
+// Memory limits at or above this (2^62 bytes) mean "no limit"
+const MEMORY_UNLIMITED_BYTES: u64 = 1 << 62;
+
+/// Where a limited process was placed, so the limit can be lifted later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CgroupPlacement {
+    /// Moved into kern.slice/kern-limited.scope; `original` is the cgroup to move it back to
+    Direct { original: PathBuf },
+    /// Adopted by a systemd transient scope (fallback without a writable cgroup tree)
+    SystemdScope { unit: String, user: bool },
+}
+
+/// Limits of the cgroup kern itself runs in (a container, or a systemd slice
+/// with MemoryMax/CPUQuota), taking the tightest of it and its ancestors
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CgroupLimits {
+    pub memory_max_bytes: Option<u64>,
+    pub memory_current_bytes: Option<u64>, // Of the cgroup that sets memory_max_bytes
+    pub cpu_max_cores: Option<f64>,
+    pub cpu_cgroup: Option<PathBuf>, // The cgroup that sets cpu_max_cores
+}
+
+impl CgroupLimits {
+    // Keep `max` if it is the tightest memory limit seen so far
+    fn tighten_memory(&mut self, max: u64, current: impl FnOnce() -> Option<String>) {
+        if self.memory_max_bytes.filter(|tightest| *tightest <= max).is_none() {
+            self.memory_max_bytes = Some(max);
+            self.memory_current_bytes = current().and_then(|value| value.trim().parse().ok());
+        }
+    }
+
+    /// Cumulative CPU time of the CPU-limited cgroup (usage_usec from cpu.stat)
+    pub fn cpu_usage_usec(&self) -> Option<u64> {
+        let content = std::fs::read_to_string(self.cpu_cgroup.as_ref()?.join("cpu.stat")).ok()?;
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix("usage_usec "))
+            .and_then(|value| value.trim().parse().ok())
+    }
+}
+
+/// Moves processes into a CPU/memory-limited cgroup v2 scope.
+///
+/// All limited processes share one `kern.slice/kern-limited.scope`, created
+/// under the cgroup root when running as root, or under the user's systemd
+/// manager (`user@<uid>.service`, which is delegated to the user) otherwise.
+#[derive(Debug, Clone)]
+pub struct CgroupLimiter {
+    root: PathBuf,
+    proc_root: PathBuf,
+}
+
+impl Default for CgroupLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CgroupLimiter {
+    pub fn new() -> Self {
+        Self::with_roots(PathBuf::from(CGROUP_ROOT), PathBuf::from("/proc"))
+    }
+
+    /// Use a different cgroup and /proc root (fixtures in tests)
+    pub fn with_roots(root: PathBuf, proc_root: PathBuf) -> Self {
+        Self { root, proc_root }
+    }
+
+    /// cgroup v2 unified hierarchy mounted at the root
+    pub fn is_unified(&self) -> bool {
+        self.root.join("cgroup.controllers").exists()
+    }
+
+    // cgroup (relative to the root) that `pid` currently lives in
+    fn cgroup_of(&self, pid: &str) -> Result<PathBuf> {
+        let path = self.proc_root.join(pid).join("cgroup");
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        parse_unified_cgroup(&content)
+            .ok_or_else(|| anyhow!("No cgroup v2 entry in {}", path.display()))
+    }
+
+    /// Limits on kern's own cgroup and its ancestors; None when there are none.
+    /// Memory falls back to the cgroup v1 memory controller (older Docker hosts).
+    pub fn own_limits(&self) -> Option<CgroupLimits> {
+        let content = std::fs::read_to_string(self.proc_root.join("self").join("cgroup")).ok()?;
+        let mut limits = CgroupLimits::default();
+
+        if let Some(own) = parse_unified_cgroup(&content) {
+            for dir in ancestors(&self.root, &own) {
+                let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok();
+                if let Some(max) = read("memory.max").and_then(|value| parse_memory_max(&value)) {
+                    limits.tighten_memory(max, || read("memory.current"));
+                }
+                if let Some(cores) = read("cpu.max").and_then(|value| parse_cpu_max(&value)) {
+                    if limits.cpu_max_cores.filter(|tightest| *tightest <= cores).is_none() {
+                        limits.cpu_max_cores = Some(cores);
+                        limits.cpu_cgroup = Some(dir.to_path_buf());
+                    }
+                }
+            }
+        }
+
+        if limits.memory_max_bytes.is_none() {
+            if let Some(own) = parse_v1_cgroup(&content, "memory") {
+                for dir in ancestors(&self.root.join("memory"), &own) {
+                    let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok();
+                    if let Some(max) = read("memory.limit_in_bytes").and_then(|value| parse_memory_max(&value)) {
+                        limits.tighten_memory(max, || read("memory.usage_in_bytes"));
+                    }
+                }
+            }
+        }
+
+        (limits.memory_max_bytes.is_some() || limits.cpu_max_cores.is_some()).then_some(limits)
+    }
+
+    // Parent cgroups that may hold kern.slice, in order of preference
+    fn candidate_parents(&self) -> Vec<PathBuf> {
+        let mut parents = vec![self.root.clone()];
+        if let Some(user_service) = self.cgroup_of("self").ok().and_then(|c| user_service_cgroup(&c)) {
+            parents.push(self.root.join(user_service));
+        }
+        parents
+    }
+
+    // Create (or reuse) the limited scope with the cpu and memory controllers enabled
+    fn limited_scope(&self) -> Result<PathBuf> {
+        if !self.is_unified() {
+            return Err(anyhow!(
+                "cgroup v2 unified hierarchy not mounted at {}",
+                self.root.display()
+            ));
+        }
+
+        let parent = self
+            .candidate_parents()
+            .into_iter()
+            .find(|parent| is_writable(&parent.join("cgroup.subtree_control")))
+            .ok_or_else(|| anyhow!("No writable cgroup found (run as root or use a systemd user session)"))?;
+
+        let slice = parent.join(LIMITED_SLICE);
+        let scope = slice.join(LIMITED_SCOPE);
+        std::fs::create_dir_all(&scope)
+            .with_context(|| format!("Failed to create {}", scope.display()))?;
+
+        // Processes may only live in leaves, so controllers are enabled on the way down
+        for dir in [&parent, &slice] {
+            write_value(&dir.join("cgroup.subtree_control"), "+cpu +memory")?;
+        }
+
+        Ok(scope)
+    }
+
+    /// Limit `pid` to `cpu_max_percent` of the machine and `memory_high_bytes` (None = no memory limit)
+    pub fn limit(&self, pid: u32, cpu_max_percent: f64, memory_high_bytes: Option<u64>) -> Result<CgroupPlacement> {
+        let original = self.cgroup_of(&pid.to_string())?;
+
+        let direct_error = match self.limited_scope() {
+            Ok(scope) => {
+                write_value(&scope.join("cpu.max"), &cpu_max_value(cpu_max_percent, cpu_count()))?;
+                let memory_high = memory_high_bytes.map_or("max".to_string(), |bytes| bytes.to_string());
+                write_value(&scope.join("memory.high"), &memory_high)?;
+                write_value(&scope.join("cgroup.procs"), &pid.to_string())?;
+                return Ok(CgroupPlacement::Direct { original });
+            }
+            Err(e) => e,
+        };
+
+        // Fall back to a systemd transient scope, which systemd creates on our behalf
+        limit_with_systemd(pid, cpu_max_percent, memory_high_bytes).map_err(|systemd_error| {
+            anyhow!("Cannot limit PID {}: {}; systemd fallback failed: {}", pid, direct_error, systemd_error)
+        })
+    }
+
+    /// Lift the limit on `pid`. A process that has exited counts as released.
+    pub fn release(&self, pid: u32, placement: &CgroupPlacement) -> Result<()> {
+        if !self.proc_root.join(pid.to_string()).exists() {
+            return Ok(());
+        }
+
+        match placement {
+            CgroupPlacement::Direct { original } => {
+                write_value(&self.root.join(original).join("cgroup.procs"), &pid.to_string())
+            }
+            CgroupPlacement::SystemdScope { unit, user } => {
+                let mut command = Command::new("systemctl");
+                if *user {
+                    command.arg("--user");
+                }
+                command.args(["set-property", "--runtime", unit, "CPUQuota=", "MemoryHigh=infinity"]);
+                run(&mut command)
+            }
+        }
+    }
+}
+
+// Limit through `StartTransientUnit`, adopting the running process into a new scope
+fn limit_with_systemd(pid: u32, cpu_max_percent: f64, memory_high_bytes: Option<u64>) -> Result<CgroupPlacement> {
+    let user = !is_root();
+    let unit = format!("kern-limited-{}.scope", pid);
+    let quota_usec = (cpu_max_percent / 100.0 * cpu_count() as f64 * 1_000_000.0) as u64;
+    let memory_high = memory_high_bytes.unwrap_or(u64::MAX); // u64::MAX = infinity
+
+    let mut command = Command::new("busctl");
+    if user {
+        command.arg("--user");
+    }
+    command.args([
+        "call",
+        "org.freedesktop.systemd1",
+        "/org/freedesktop/systemd1",
+        "org.freedesktop.systemd1.Manager",
+        "StartTransientUnit",
+        "ssa(sv)a(sa(sv))",
+        &unit,
+        "fail",
+        "3",
+        "PIDs",
+        "au",
+        "1",
+        &pid.to_string(),
+        "CPUQuotaPerSecUSec",
+        "t",
+        &quota_usec.to_string(),
+        "MemoryHigh",
+        "t",
+        &memory_high.to_string(),
+        "0",
+    ]);
+    run(&mut command)?;
+
+    Ok(CgroupPlacement::SystemdScope { unit, user })
+}
+
+/// cgroup v2 path from /proc/<pid>/cgroup content (the `0::` line), relative to the root
+pub fn parse_unified_cgroup(content: &str) -> Option<PathBuf> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| PathBuf::from(path.trim().trim_start_matches('/')))
+}
+
+/// cgroup path of one v1 controller from /proc/<pid>/cgroup content (e.g. the
+/// `4:memory:/docker/<id>` line), relative to that controller's mount
+pub fn parse_v1_cgroup(content: &str, controller: &str) -> Option<PathBuf> {
+    content.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        controllers
+            .split(',')
+            .any(|name| name == controller)
+            .then(|| PathBuf::from(path.trim().trim_start_matches('/')))
+    })
+}
+
+/// memory.max (v2) or memory.limit_in_bytes (v1) in bytes; None for "max" or
+/// v1's "unlimited", which is reported as a number near 2^63
+pub fn parse_memory_max(content: &str) -> Option<u64> {
+    content.trim().parse().ok().filter(|bytes| *bytes < MEMORY_UNLIMITED_BYTES)
+}
+
+// The cgroup at `cgroup` (relative to `root`) and its ancestors up to `root`
+fn ancestors(root: &Path, cgroup: &Path) -> Vec<PathBuf> {
+    let own = root.join(cgroup);
+    own.ancestors().take_while(|dir| dir.starts_with(root)).map(Path::to_path_buf).collect()
+}
+
+/// cpu.max ("<quota> <period>") as a number of CPUs; None for "max" (unlimited)
+pub fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut fields = content.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next().unwrap_or("100000").parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// The `user@<uid>.service` ancestor of a cgroup, if it is inside a user session
+pub fn user_service_cgroup(cgroup: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in cgroup.components() {
+        path.push(component);
+        let name = component.as_os_str().to_string_lossy();
+        if name.starts_with("user@") && name.ends_with(".service") {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// cpu.max value: quota and period in microseconds, the quota covering
+/// `percent` of all CPUs
+pub fn cpu_max_value(percent: f64, cpus: usize) -> String {
+    let quota = (percent / 100.0 * cpus as f64 * CPU_PERIOD_USEC as f64) as u64;
+    format!("{} {}", quota.max(1000), CPU_PERIOD_USEC)
+}
+
+fn cpu_count() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+fn is_root() -> bool {
+    nix::unistd::geteuid().is_root()
+}
+
+fn is_writable(path: &Path) -> bool {
+    OpenOptions::new().write(true).open(path).is_ok()
+}
+
+fn write_value(path: &Path, value: &str) -> Result<()> {
+    std::fs::write(path, value).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // Fake cgroup v2 tree plus /proc entries for kern itself and one target process
+    fn fixture(pid: u32) -> (TempDir, CgroupLimiter) {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("cgroup");
+        let proc_root = temp_dir.path().join("proc");
+
+        std::fs::create_dir_all(root.join("user.slice/user-1000.slice/user@1000.service/app.slice")).unwrap();
+        std::fs::write(root.join("cgroup.controllers"), "cpu memory pids").unwrap();
+        std::fs::write(root.join("cgroup.subtree_control"), "").unwrap();
+
+        for (name, cgroup) in [
+            ("self".to_string(), "/system.slice/kern.service"),
+            (pid.to_string(), "/user.slice/user-1000.slice/user@1000.service/app.slice"),
+        ] {
+            std::fs::create_dir_all(proc_root.join(&name)).unwrap();
+            std::fs::write(proc_root.join(&name).join("cgroup"), format!("0::{}\n", cgroup)).unwrap();
+        }
+
+        (temp_dir, CgroupLimiter::with_roots(root, proc_root))
+    }
+
+    #[test]
+    fn test_parse_unified_cgroup() {
+        let content = "12:memory:/legacy\n0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            parse_unified_cgroup(content),
+            Some(PathBuf::from("user.slice/user-1000.slice/session-2.scope"))
+        );
+        assert_eq!(parse_unified_cgroup("0::/\n"), Some(PathBuf::from("")));
+        assert_eq!(parse_unified_cgroup("4:memory:/foo\n"), None);
+    }
+
+    #[test]
+    fn test_user_service_cgroup() {
+        let cgroup = Path::new("user.slice/user-1000.slice/user@1000.service/app.slice/app-code.scope");
+        assert_eq!(
+            user_service_cgroup(cgroup),
+            Some(PathBuf::from("user.slice/user-1000.slice/user@1000.service"))
+        );
+        assert_eq!(user_service_cgroup(Path::new("system.slice/sshd.service")), None);
+    }
+
+    #[test]
+    fn test_cpu_max_value() {
+        assert_eq!(cpu_max_value(25.0, 4), "100000 100000"); // One full CPU
+        assert_eq!(cpu_max_value(50.0, 1), "50000 100000");
+        assert_eq!(cpu_max_value(0.1, 1), "1000 100000"); // Kernel minimum
+    }
+
+    #[test]
+    fn test_limit_and_release_direct() {
+        let pid = 10_000_001;
+        let (dir, limiter) = fixture(pid);
+        assert!(limiter.is_unified());
+
+        let placement = limiter.limit(pid, 50.0, Some(512 * 1024 * 1024)).unwrap();
+        let original = PathBuf::from("user.slice/user-1000.slice/user@1000.service/app.slice");
+        assert_eq!(placement, CgroupPlacement::Direct { original: original.clone() });
+
+        let scope = dir.path().join("cgroup/kern.slice/kern-limited.scope");
+        assert_eq!(std::fs::read_to_string(scope.join("cgroup.procs")).unwrap(), pid.to_string());
+        assert_eq!(std::fs::read_to_string(scope.join("memory.high")).unwrap(), "536870912");
+        assert!(std::fs::read_to_string(scope.join("cpu.max")).unwrap().ends_with(" 100000"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("cgroup/cgroup.subtree_control")).unwrap(),
+            "+cpu +memory"
+        );
+
+        limiter.release(pid, &placement).unwrap();
+        let procs = dir.path().join("cgroup").join(original).join("cgroup.procs");
+        assert_eq!(std::fs::read_to_string(procs).unwrap(), pid.to_string());
+    }
+
+    #[test]
+    fn test_release_of_exited_process_is_ok() {
+        let (_dir, limiter) = fixture(10_000_001);
+        let placement = CgroupPlacement::Direct { original: PathBuf::from("gone.slice") };
+        assert!(limiter.release(10_000_002, &placement).is_ok());
+    }
+
+    #[test]
+    fn test_missing_unified_hierarchy_is_detected() {
+        let (dir, limiter) = fixture(10_000_001);
+        std::fs::remove_file(dir.path().join("cgroup/cgroup.controllers")).unwrap();
+        assert!(!limiter.is_unified());
+
+        let error = limiter.limited_scope().unwrap_err().to_string();
+        assert!(error.contains("cgroup v2"));
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_memory_max("4294967296\n"), Some(4 * 1024 * 1024 * 1024));
+        assert_eq!(parse_memory_max("max\n"), None);
+        assert_eq!(parse_memory_max("9223372036854771712\n"), None); // cgroup v1 unlimited
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cpu_max("50000 100000"), Some(0.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+    }
+
+    #[test]
+    fn test_own_limits_takes_the_tightest_ancestor() {
+        let (dir, limiter) = fixture(10_000_001);
+        assert_eq!(limiter.own_limits(), None);
+
+        let root = dir.path().join("cgroup");
+        let service = root.join("system.slice/kern.service");
+        std::fs::create_dir_all(&service).unwrap();
+        std::fs::write(root.join("system.slice/memory.max"), "4294967296\n").unwrap();
+        std::fs::write(root.join("system.slice/memory.current"), "1073741824\n").unwrap();
+        std::fs::write(root.join("system.slice/cpu.max"), "max 100000\n").unwrap();
+        std::fs::write(service.join("memory.max"), "max\n").unwrap();
+        std::fs::write(service.join("cpu.max"), "150000 100000\n").unwrap();
+        std::fs::write(service.join("cpu.stat"), "usage_usec 123456\nuser_usec 100000\n").unwrap();
+
+        let limits = limiter.own_limits().unwrap();
+        assert_eq!(limits.memory_max_bytes, Some(4294967296));
+        assert_eq!(limits.memory_current_bytes, Some(1073741824));
+        assert_eq!(limits.cpu_max_cores, Some(1.5));
+        assert_eq!(limits.cpu_usage_usec(), Some(123456));
+    }
+
+    #[test]
+    fn test_parse_v1_cgroup() {
+        let content = "12:cpu,cpuacct:/docker/abc\n4:memory:/docker/abc\n0::/\n";
+        assert_eq!(parse_v1_cgroup(content, "memory"), Some(PathBuf::from("docker/abc")));
+        assert_eq!(parse_v1_cgroup(content, "cpu"), Some(PathBuf::from("docker/abc")));
+        assert_eq!(parse_v1_cgroup(content, "pids"), None);
+        assert_eq!(parse_v1_cgroup("0::/user.slice\n", "memory"), None);
+    }
+
+    #[test]
+    fn test_own_limits_reads_cgroup_v1_memory() {
+        let (dir, limiter) = fixture(10_000_001);
+        let proc_self = dir.path().join("proc/self/cgroup");
+        std::fs::write(&proc_self, "4:memory:/docker/abc\n1:name=systemd:/docker/abc\n").unwrap();
+        let memory = dir.path().join("cgroup/memory");
+        std::fs::create_dir_all(memory.join("docker/abc")).unwrap();
+        // The host-wide controller root reports v1's "unlimited"
+        std::fs::write(memory.join("memory.limit_in_bytes"), "9223372036854771712\n").unwrap();
+        assert_eq!(limiter.own_limits(), None);
+
+        std::fs::write(memory.join("docker/abc/memory.limit_in_bytes"), "2147483648\n").unwrap();
+        std::fs::write(memory.join("docker/abc/memory.usage_in_bytes"), "536870912\n").unwrap();
+        let limits = limiter.own_limits().unwrap();
+        assert_eq!(limits.memory_max_bytes, Some(2147483648));
+        assert_eq!(limits.memory_current_bytes, Some(536870912));
+        assert_eq!(limits.cpu_max_cores, None);
+    }
+
+    #[test]
+    fn test_real_cgroup_hierarchy_detection() {
+        // Only meaningful on systems with cgroups; must never panic elsewhere
+        let limiter = CgroupLimiter::new();
+        if limiter.is_unified() {
+            assert!(Path::new(CGROUP_ROOT).join("cgroup.procs").exists());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use clap::ValueEnum;
+use std::io::IsTerminal;
+
+/// When to color terminal output (`--color`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    #[default]
+    Auto, // Only when stdout is a terminal and NO_COLOR is unset
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Yellow,
+    Red,
+    Green,
+}
+
+impl Color {
+    fn code(&self) -> &'static str {
+        match self {
+            Color::Yellow => "33",
+            Color::Red => "31",
+            Color::Green => "32",
+        }
+    }
+}
+
+/// Wraps values in ANSI colors, or leaves them untouched when color is off
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Painter {
+    enabled: bool,
+}
+
+impl Painter {
+    /// `--color always` wins over NO_COLOR, which only disables the auto mode
+    /// (https://no-color.org)
+    pub fn new(choice: ColorChoice, no_color: bool, is_terminal: bool) -> Self {
+        let enabled = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && !no_color,
+        };
+        Self { enabled }
+    }
+
+    pub fn from_env(choice: ColorChoice) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self::new(choice, no_color, std::io::stdout().is_terminal())
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pad before painting: escape codes would otherwise count towards the width
+    pub fn paint(&self, text: &str, color: Option<Color>) -> String {
+        match color {
+            Some(color) if self.enabled => format!("\x1b[{}m{}\x1b[0m", color.code(), text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// Yellow at or above `warning`, red at or above `critical`
+pub fn threshold_color(value: f64, warning: f64, critical: f64) -> Option<Color> {
+    if value >= critical {
+        Some(Color::Red)
+    } else if value >= warning {
+        Some(Color::Yellow)
+    } else {
+        None
+    }
+}
+
+/// Red once `value` is over `limit`
+pub fn limit_color(value: f64, limit: f64) -> Option<Color> {
+    (value > limit).then_some(Color::Red)
+}
+
+/// Red for a rise, green for a drop (e.g. memory since a snapshot)
+pub fn change_color(delta: f64) -> Option<Color> {
+    if delta > 0.0 {
+        Some(Color::Red)
+    } else if delta < 0.0 {
+        Some(Color::Green)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        assert!(Painter::new(ColorChoice::Auto, false, true).enabled);
+        assert!(!Painter::new(ColorChoice::Auto, false, false).enabled);
+        assert!(!Painter::new(ColorChoice::Auto, true, true).enabled);
+        assert!(Painter::new(ColorChoice::Always, true, false).enabled);
+        assert!(!Painter::new(ColorChoice::Never, false, true).enabled);
+    }
+
+    #[test]
+    fn test_paint() {
+        let on = Painter::new(ColorChoice::Always, false, false);
+        assert_eq!(on.paint("91.0", Some(Color::Red)), "\x1b[31m91.0\x1b[0m");
+        assert_eq!(on.paint("40.0", None), "40.0");
+        let off = Painter::new(ColorChoice::Never, false, false);
+        assert_eq!(off.paint("91.0", Some(Color::Red)), "91.0");
+    }
+
+    #[test]
+    fn test_threshold_colors() {
+        assert_eq!(threshold_color(60.0, 75.0, 90.0), None);
+        assert_eq!(threshold_color(75.0, 75.0, 90.0), Some(Color::Yellow));
+        assert_eq!(threshold_color(95.0, 75.0, 90.0), Some(Color::Red));
+        assert_eq!(limit_color(80.0, 80.0), None);
+        assert_eq!(limit_color(80.1, 80.0), Some(Color::Red));
+        assert_eq!(change_color(2.5), Some(Color::Red));
+        assert_eq!(change_color(-2.5), Some(Color::Green));
+        assert_eq!(change_color(0.0), None);
+    }
+}
@@ -1,46 +1,147 @@
 use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KernConfig { // overall configuration
     #[serde(default = "default_profile")]
     pub default_profile: String,
 
-    // Monitoring interval in seconds (how often to check system stats)
-    #[serde(default = "default_monitor_interval")]
+    // Monitoring interval in milliseconds (how often to check system stats).
+    // Written "500ms" or "2s"; a bare number is the old form, in seconds
+    #[serde(default = "default_monitor_interval", with = "interval_ms")]
     pub monitor_interval: u64,
 
+    // The enforcer logs a one-line status summary this often, in seconds,
+    // even when it takes no action (null = never)
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: Option<u64>,
+
     // Temperature thresholds for warnings and critical states
     #[serde(default)]
     pub temperature: TemperatureConfig,
 
+    // How readings from multiple thermal zones are combined ("max" or "avg")
+    #[serde(default)]
+    pub temperature_strategy: TemperatureStrategy,
+
+    // Read temperature from this file only (millidegrees, e.g.
+    // /sys/class/hwmon/hwmon2/temp1_input) instead of scanning thermal zones
+    #[serde(default)]
+    pub temperature_sensor_path: Option<PathBuf>,
+
+    // How per-process memory is measured ("rss" or "pss")
+    #[serde(default)]
+    pub memory_metric: MemoryMetric,
+
+    // Show per-process CPU as a share of the whole machine (0-100) instead of
+    // per core (100 = one core, so up to 100 × CPUs); global CPU is always a share
+    #[serde(default)]
+    pub normalize_cpu: bool,
+
+    // Whether limits are enforced or only alerted on (profiles can override)
+    #[serde(default)]
+    pub enforcement: EnforcementConfig,
+
+    // Emergency mode kill limits
+    #[serde(default)]
+    pub emergency: EmergencyConfig,
+
+    // Memory emergency: kill the heaviest memory user while swap is nearly full
+    #[serde(default)]
+    pub swap_emergency: SwapEmergencyConfig,
+
+    // When processes in a profile's restart_after_kill are relaunched
+    #[serde(default)]
+    pub restart: RestartConfig,
+
     //  Default resource limits
     #[serde(default)]
     pub limits: ResourceLimits,
 
+    // Check the CPU limit against a percentile of the recent samples instead
+    // of only the latest one, so short spikes can be ignored
+    #[serde(default)]
+    pub cpu_percentile: CpuPercentileConfig,
+
     // List of processes that should never be killed
     #[serde(default = "default_protected_processes")]
     pub protected_processes: Vec<String>,
 
+    // Also spare processes whose parent (1), grandparent (2), ... is protected,
+    // up to killer::MAX_ANCESTRY_DEPTH levels; 0 checks only the process itself
+    #[serde(default = "default_ancestry_depth")]
+    pub ancestry_depth: u8,
+
+    // Extra critical processes, added to the built-in list
+    #[serde(default)]
+    pub critical_processes: Vec<String>,
+
+    // DANGEROUS: exact names removed from the critical set (built-in or configured),
+    // so kern is allowed to kill them. A warning is printed whenever this is used.
+    #[serde(default)]
+    pub critical_override_allow: Vec<String>,
+
     // Notification settings
     #[serde(default)]
     pub notifications: NotificationConfig,
 
+    // Which bus `kern dbus` serves on
+    #[serde(default)]
+    pub dbus: DbusConfig,
+
+    // Unix socket `kern dbus` also serves, for machines without DBus
+    #[serde(default)]
+    pub control_socket: ControlSocketConfig,
+
     // Process killer settings
     #[serde(default = "default_kill_graceful")]
     pub kill_graceful: bool,
 
-    #[serde(default = "default_kill_timeout_seconds")]
-    pub kill_timeout_seconds: u32,
+    // How long a graceful kill waits after SIGTERM before sending SIGKILL (0-60)
+    #[serde(default = "default_kill_graceful_timeout_secs", alias = "kill_timeout_seconds")]
+    pub kill_graceful_timeout_secs: u64,
 
+    // How often a graceful kill checks whether the process has exited (10-1000 ms).
+    // Shorter notices a quick exit sooner, at the cost of more wakeups.
+    #[serde(default = "default_kill_graceful_poll_ms", alias = "graceful_poll_ms")]
+    pub kill_graceful_poll_ms: u64,
+
+    // Ask before `kern kill` kills more than this many processes.
+    // 0 = always ask, even for a single process; usize::MAX = never ask.
     #[serde(default = "default_kill_confirmation_threshold")]
     pub kill_confirmation_threshold: usize,
+
+    // Stop killing once the enforcer has killed this many processes in one
+    // run (monitoring and notifications continue). 0 = unlimited.
+    #[serde(default = "default_kill_count_limit")]
+    pub kill_count_limit: usize,
+
+    // What to do when several limits (CPU, RAM, pressure, temperature) are
+    // exceeded in the same cycle
+    #[serde(default)]
+    pub multi_breach: MultiBreach,
+
+    // How much CPU and memory count when a profile with kill_strategy: weighted
+    // ranks victims, per resource whose limit was exceeded
+    #[serde(default)]
+    pub victim_weights: VictimWeights,
+
+    // Where kill actions are logged (defaults to ~/.config/kern/kern.log)
+    #[serde(default)]
+    pub kill_log_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemperatureConfig { // temperature thresholds
+    // false where no sensor can be read (e.g. macOS without root for
+    // powermetrics): the enforcer then ignores temperature entirely
+    #[serde(default = "default_temp_enabled")]
+    pub enabled: bool,
+
     // Warning threshold in °C
     #[serde(default = "default_temp_warning")]
     pub warning: f64,
@@ -50,6 +151,162 @@ pub struct TemperatureConfig { // temperature thresholds
     pub critical: f64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureStrategy { // aggregation across thermal zones
+    // Hottest zone
+    #[default]
+    Max,
+    // Mean of all zones reporting a non-zero value
+    Avg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryMetric { // how per-process memory is measured
+    // Resident set size (VmRSS): cheap, but counts shared pages in every process
+    #[default]
+    Rss,
+    // Proportional set size (smaps_rollup): shared pages split between their users
+    Pss,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnforcementConfig { // enforce or only alert
+    // "alert" evaluates every limit and notifies, but never kills, limits or throttles
+    #[serde(default)]
+    pub mode: EnforcementMode,
+    // false: only processes owned by the user kern runs as are ever acted on
+    #[serde(default)]
+    pub all_users: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforcementMode { // what the enforcer does about an exceeded limit
+    // Kill, cgroup-limit or throttle, as the profile says
+    #[default]
+    Enforce,
+    // Notify and report what would have been done, touching no process
+    Alert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyConfig { // emergency mode behaviour
+    // Maximum number of processes killed per enforcement cycle
+    #[serde(default = "default_emergency_max_kills_per_cycle")]
+    pub max_kills_per_cycle: usize,
+
+    // Hard cap on kills during a single emergency episode
+    #[serde(default = "default_emergency_max_total_kills")]
+    pub max_total_kills: usize,
+
+    // Stop killing once CPU usage drops below this percentage (0-100),
+    // even if the temperature hasn't caught up yet
+    #[serde(default = "default_emergency_cpu_floor")]
+    pub cpu_floor_percent: f64,
+
+    // Consecutive samples above critical required to enter emergency mode
+    #[serde(default = "default_emergency_trigger_samples")]
+    pub trigger_samples: u32,
+
+    // Consecutive samples below warning required to leave emergency mode
+    #[serde(default = "default_emergency_exit_samples")]
+    pub exit_samples: u32,
+
+    // Ordered actions taken in emergency mode (throttle_cpu, kill)
+    #[serde(default = "default_emergency_actions")]
+    pub actions: Vec<EmergencyAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapEmergencyConfig { // memory emergency mode, entered on swap usage
+    // Off by default: plenty of swap in use isn't always thrashing
+    #[serde(default)]
+    pub enabled: bool,
+
+    // Enter memory emergency mode above this swap usage (0-100%), and kill the
+    // heaviest memory user every cycle while it stays above
+    #[serde(default = "default_swap_emergency_threshold")]
+    pub threshold_percent: f64,
+
+    // Leave memory emergency mode once swap usage drops below this (0-100%)
+    #[serde(default = "default_swap_emergency_exit")]
+    pub exit_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuPercentileConfig { // what the CPU limit is compared with
+    // Recent CPU samples kept (one per enforcement cycle); 1 = the latest only
+    #[serde(default = "default_cpu_percentile_samples")]
+    pub samples: usize,
+
+    // Percentile (0-100) of those samples that must exceed the limit
+    #[serde(default = "default_cpu_percentile")]
+    pub percentile: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartConfig { // relaunching processes listed in a profile's restart_after_kill
+    // CPU, RAM and temperature must stay under the profile's limits this long
+    // after a kill before the process is relaunched
+    #[serde(default = "default_restart_stabilization_secs")]
+    pub stabilization_secs: u64,
+
+    // Relaunches of one process name allowed per hour; further ones are skipped
+    #[serde(default = "default_restart_max_per_hour")]
+    pub max_per_hour: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyAction { // one step of the emergency response
+    // Cap CPU frequency / switch to the powersave governor, restored on exit
+    ThrottleCpu,
+    // Kill the heaviest non-protected processes
+    Kill,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VictimWeights { // kill_strategy: weighted, per exceeded limit
+    // CPU limit (max_cpu_percent / max_cpu_cores)
+    #[serde(default = "default_cpu_victim_weights")]
+    pub cpu: ScoreWeights,
+
+    // RAM limit and memory pressure
+    #[serde(default = "default_memory_victim_weights")]
+    pub memory: ScoreWeights,
+
+    // Temperature warning and emergency mode
+    #[serde(default = "default_temperature_victim_weights")]
+    pub temperature: ScoreWeights,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreWeights { // a process scores cpu * its CPU share + memory * its memory share
+    // Each share is relative to the heaviest candidate, so 1.0 is the top consumer
+    #[serde(default)]
+    pub cpu: f64,
+
+    #[serde(default)]
+    pub memory: f64,
+}
+
+impl ScoreWeights {
+    /// CPU and memory count the same (kill_strategy: heaviest_cpu_plus_memory)
+    pub const EQUAL: ScoreWeights = ScoreWeights { cpu: 0.5, memory: 0.5 };
+
+    /// The same weights scaled to add up to 1, so they can be averaged with others
+    pub fn normalized(&self) -> ScoreWeights {
+        let total = self.cpu + self.memory;
+        if total > 0.0 {
+            ScoreWeights { cpu: self.cpu / total, memory: self.memory / total }
+        } else {
+            ScoreWeights::EQUAL
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceLimits { // resource usage limits
     // Maximum CPU usage percentage (0-100)
@@ -68,13 +325,209 @@ pub struct NotificationConfig { // notification settings
     #[serde(default = "default_notifications_enabled")]
     pub enabled: bool,
 
-    // Show notification when a process is killed
+    // Show notification when a process is killed, or a kill fails
     #[serde(default = "default_show_on_kill")]
     pub show_on_kill: bool,
 
     // Show notification when profile is switched
     #[serde(default = "default_show_on_profile_switch")]
     pub show_on_profile_switch: bool,
+
+    // Minimum seconds between two notifications of the same category
+    // (e.g. kills of one process, warnings for one resource); 0 disables it
+    #[serde(default = "default_cooldown_per_category_secs")]
+    pub cooldown_per_category_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiBreach { // limit actions per enforcement cycle
+    // One kill (or cgroup limit) per cycle, aimed at the worst overall
+    // offender; the next cycle re-checks the limits
+    #[default]
+    KillOnce,
+    // One per exceeded limit, so up to four processes in a single cycle
+    KillEach,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DbusConfig { // DBus service settings
+    // "session" for a per-user kern, "system" when kern runs as a root service
+    // (needs a bus policy allowing it to own the name)
+    #[serde(default)]
+    pub bus: DbusBus,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlSocketConfig { // JSON control socket (`kern ctl`)
+    // Off unless enabled: anyone who can open the socket controls kern
+    #[serde(default)]
+    pub enabled: bool,
+
+    // Defaults to $XDG_RUNTIME_DIR/kern/control.sock
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl ControlSocketConfig {
+    /// The configured path, else the default under XDG_RUNTIME_DIR
+    /// (None when that isn't set either)
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(|| {
+            std::env::var_os("XDG_RUNTIME_DIR")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join("kern").join("control.sock"))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbusBus { // message bus the service name is requested on
+    #[default]
+    Session,
+    System,
+}
+
+/// Settings to layer over a loaded `KernConfig`: each `Some` replaces the
+/// configured value, `None` keeps it. See `KernConfig::merge`.
+#[derive(Debug, Clone, Default)]
+pub struct KernConfigOverride {
+    pub default_profile: Option<String>,
+    pub monitor_interval: Option<u64>,
+    pub report_interval_secs: Option<Option<u64>>,
+    pub temperature: TemperatureOverride,
+    pub temperature_strategy: Option<TemperatureStrategy>,
+    pub temperature_sensor_path: Option<PathBuf>,
+    pub memory_metric: Option<MemoryMetric>,
+    pub normalize_cpu: Option<bool>,
+    pub enforcement: EnforcementOverride,
+    pub emergency: Option<EmergencyConfig>,
+    pub swap_emergency: Option<SwapEmergencyConfig>,
+    pub restart: Option<RestartConfig>,
+    pub limits: ResourceLimitsOverride,
+    pub cpu_percentile: Option<CpuPercentileConfig>,
+    pub protected_processes: Option<Vec<String>>,
+    pub ancestry_depth: Option<u8>,
+    pub critical_processes: Option<Vec<String>>,
+    pub critical_override_allow: Option<Vec<String>>,
+    pub notifications: Option<NotificationConfig>,
+    pub dbus: Option<DbusConfig>,
+    pub control_socket: Option<ControlSocketConfig>,
+    pub kill_graceful: Option<bool>,
+    pub kill_graceful_timeout_secs: Option<u64>,
+    pub kill_graceful_poll_ms: Option<u64>,
+    pub kill_confirmation_threshold: Option<usize>,
+    pub kill_count_limit: Option<usize>,
+    pub multi_breach: Option<MultiBreach>,
+    pub victim_weights: Option<VictimWeights>,
+    pub kill_log_path: Option<PathBuf>,
+}
+
+/// Per-field overrides of `temperature`
+#[derive(Debug, Clone, Default)]
+pub struct TemperatureOverride {
+    pub enabled: Option<bool>,
+    pub warning: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+/// Per-field overrides of `enforcement`
+#[derive(Debug, Clone, Default)]
+pub struct EnforcementOverride {
+    pub mode: Option<EnforcementMode>,
+    pub all_users: Option<bool>,
+}
+
+/// Per-field overrides of `limits`
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimitsOverride {
+    pub max_cpu_percent: Option<f64>,
+    pub max_ram_percent: Option<f64>,
+}
+
+impl KernConfigOverride {
+    /// Overrides from the `KERN_*` environment variables (empty ones are ignored)
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    /// Overrides from `var`, which looks up an environment variable by name.
+    ///
+    /// | Variable | Setting |
+    /// |---|---|
+    /// | `KERN_DEFAULT_PROFILE` | `default_profile` |
+    /// | `KERN_MONITOR_INTERVAL` | `monitor_interval` ("500ms", "2s", or seconds) |
+    /// | `KERN_REPORT_INTERVAL_SECS` | `report_interval_secs` (0 = never) |
+    /// | `KERN_TEMP_ENABLED` | `temperature.enabled` |
+    /// | `KERN_TEMP_WARNING` | `temperature.warning` |
+    /// | `KERN_TEMP_CRITICAL` | `temperature.critical` |
+    /// | `KERN_TEMPERATURE_STRATEGY` | `temperature_strategy` |
+    /// | `KERN_TEMPERATURE_SENSOR_PATH` | `temperature_sensor_path` |
+    /// | `KERN_MEMORY_METRIC` | `memory_metric` |
+    /// | `KERN_NORMALIZE_CPU` | `normalize_cpu` |
+    /// | `KERN_ENFORCEMENT_MODE` | `enforcement.mode` |
+    /// | `KERN_ENFORCEMENT_ALL_USERS` | `enforcement.all_users` |
+    /// | `KERN_MAX_CPU_PERCENT` | `limits.max_cpu_percent` |
+    /// | `KERN_MAX_RAM_PERCENT` | `limits.max_ram_percent` |
+    /// | `KERN_ANCESTRY_DEPTH` | `ancestry_depth` |
+    /// | `KERN_KILL_GRACEFUL` | `kill_graceful` |
+    /// | `KERN_KILL_GRACEFUL_TIMEOUT_SECS` | `kill_graceful_timeout_secs` |
+    /// | `KERN_KILL_GRACEFUL_POLL_MS` | `kill_graceful_poll_ms` |
+    /// | `KERN_KILL_CONFIRMATION_THRESHOLD` | `kill_confirmation_threshold` |
+    /// | `KERN_KILL_COUNT_LIMIT` | `kill_count_limit` |
+    /// | `KERN_MULTI_BREACH` | `multi_breach` |
+    /// | `KERN_KILL_LOG_PATH` | `kill_log_path` |
+    ///
+    /// Values are written as in kern.yaml; one that doesn't parse is an error.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let monitor_interval = var("KERN_MONITOR_INTERVAL")
+            .map(|text| {
+                interval_ms::deserialize(serde_yaml::Deserializer::from_str(&text))
+                    .map_err(|err| anyhow!("Invalid KERN_MONITOR_INTERVAL={:?}: {}", text, err))
+            })
+            .transpose()?;
+        let report_interval_secs: Option<u64> = env_value(&var, "KERN_REPORT_INTERVAL_SECS")?;
+
+        Ok(Self {
+            default_profile: var("KERN_DEFAULT_PROFILE"),
+            monitor_interval,
+            report_interval_secs: report_interval_secs.map(|secs| (secs > 0).then_some(secs)),
+            temperature: TemperatureOverride {
+                enabled: env_value(&var, "KERN_TEMP_ENABLED")?,
+                warning: env_value(&var, "KERN_TEMP_WARNING")?,
+                critical: env_value(&var, "KERN_TEMP_CRITICAL")?,
+            },
+            temperature_strategy: env_value(&var, "KERN_TEMPERATURE_STRATEGY")?,
+            temperature_sensor_path: var("KERN_TEMPERATURE_SENSOR_PATH").map(PathBuf::from),
+            memory_metric: env_value(&var, "KERN_MEMORY_METRIC")?,
+            normalize_cpu: env_value(&var, "KERN_NORMALIZE_CPU")?,
+            enforcement: EnforcementOverride {
+                mode: env_value(&var, "KERN_ENFORCEMENT_MODE")?,
+                all_users: env_value(&var, "KERN_ENFORCEMENT_ALL_USERS")?,
+            },
+            limits: ResourceLimitsOverride {
+                max_cpu_percent: env_value(&var, "KERN_MAX_CPU_PERCENT")?,
+                max_ram_percent: env_value(&var, "KERN_MAX_RAM_PERCENT")?,
+            },
+            ancestry_depth: env_value(&var, "KERN_ANCESTRY_DEPTH")?,
+            kill_graceful: env_value(&var, "KERN_KILL_GRACEFUL")?,
+            kill_graceful_timeout_secs: env_value(&var, "KERN_KILL_GRACEFUL_TIMEOUT_SECS")?,
+            kill_graceful_poll_ms: env_value(&var, "KERN_KILL_GRACEFUL_POLL_MS")?,
+            kill_confirmation_threshold: env_value(&var, "KERN_KILL_CONFIRMATION_THRESHOLD")?,
+            kill_count_limit: env_value(&var, "KERN_KILL_COUNT_LIMIT")?,
+            multi_breach: env_value(&var, "KERN_MULTI_BREACH")?,
+            kill_log_path: var("KERN_KILL_LOG_PATH").map(PathBuf::from),
+            ..Self::default()
+        })
+    }
+}
+
+// A `KERN_*` variable written as in kern.yaml (e.g. "80", "true", "pss")
+fn env_value<T: DeserializeOwned>(var: &impl Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>> {
+    var(name)
+        .map(|value| serde_yaml::from_str(&value).map_err(|err| anyhow!("Invalid {}={:?}: {}", name, value, err)))
+        .transpose()
 }
 
 // Default values
@@ -83,7 +536,80 @@ fn default_profile() -> String {
 }
 
 fn default_monitor_interval() -> u64 {
-    2
+    2000
+}
+
+fn default_report_interval_secs() -> Option<u64> {
+    Some(60)
+}
+
+/// Shortest and longest allowed `monitor_interval`, in milliseconds: reading
+/// every process more often than every 100ms just keeps sysinfo busy
+pub const MIN_MONITOR_INTERVAL_MS: u64 = 100;
+pub const MAX_MONITOR_INTERVAL_MS: u64 = 3_600_000;
+
+/// Most CPU samples cpu_percentile.samples may keep
+pub const MAX_CPU_PERCENTILE_SAMPLES: usize = 1000;
+
+/// Longest SIGTERM-to-SIGKILL wait kill_graceful_timeout_secs (or `kern kill --timeout`) allows
+pub const MAX_KILL_GRACEFUL_TIMEOUT_SECS: u64 = 60;
+
+/// Range kill_graceful_poll_ms allows
+pub const MIN_KILL_GRACEFUL_POLL_MS: u64 = 10;
+pub const MAX_KILL_GRACEFUL_POLL_MS: u64 = 1000;
+
+/// "2s" for whole seconds, else "500ms"
+pub fn format_interval(ms: u64) -> String {
+    if ms % 1000 == 0 {
+        format!("{}s", ms / 1000)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+/// Parse "500ms", "2s" or "1.5s" into milliseconds
+pub fn parse_interval(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let (number, scale) = match text.strip_suffix("ms") {
+        Some(number) => (number, 1.0),
+        None => match text.strip_suffix('s') {
+            Some(number) => (number, 1000.0),
+            None => return Err(anyhow!("Invalid interval {:?} (expected e.g. \"500ms\" or \"2s\")", text)),
+        },
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok((value * scale).round() as u64),
+        _ => Err(anyhow!("Invalid interval {:?} (expected e.g. \"500ms\" or \"2s\")", text)),
+    }
+}
+
+// `monitor_interval` in kern.yaml: "500ms" / "2s", or a plain number of seconds
+// from before intervals had units (migrated by multiplying by 1000)
+mod interval_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Interval {
+        Seconds(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(ms: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_interval(*ms))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match Interval::deserialize(deserializer)? {
+            Interval::Seconds(secs) if secs.is_finite() && secs >= 0.0 => Ok((secs * 1000.0).round() as u64),
+            Interval::Seconds(secs) => Err(serde::de::Error::custom(format!("Invalid monitor_interval: {}", secs))),
+            Interval::Text(text) => super::parse_interval(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+fn default_temp_enabled() -> bool {
+    true
 }
 
 fn default_temp_warning() -> f64 {
@@ -94,6 +620,66 @@ fn default_temp_critical() -> f64 {
     85.0
 }
 
+fn default_emergency_max_kills_per_cycle() -> usize {
+    3
+}
+
+fn default_emergency_max_total_kills() -> usize {
+    10
+}
+
+fn default_emergency_cpu_floor() -> f64 {
+    50.0
+}
+
+fn default_emergency_trigger_samples() -> u32 {
+    2
+}
+
+fn default_emergency_exit_samples() -> u32 {
+    2
+}
+
+fn default_emergency_actions() -> Vec<EmergencyAction> {
+    vec![EmergencyAction::Kill]
+}
+
+fn default_cpu_percentile_samples() -> usize {
+    1
+}
+
+fn default_cpu_percentile() -> f64 {
+    99.0
+}
+
+fn default_swap_emergency_threshold() -> f64 {
+    80.0
+}
+
+fn default_swap_emergency_exit() -> f64 {
+    50.0
+}
+
+fn default_restart_stabilization_secs() -> u64 {
+    120
+}
+
+fn default_restart_max_per_hour() -> u32 {
+    3
+}
+
+fn default_cpu_victim_weights() -> ScoreWeights {
+    ScoreWeights { cpu: 0.8, memory: 0.2 }
+}
+
+fn default_memory_victim_weights() -> ScoreWeights {
+    ScoreWeights { cpu: 0.2, memory: 0.8 }
+}
+
+fn default_temperature_victim_weights() -> ScoreWeights {
+    ScoreWeights { cpu: 0.9, memory: 0.1 }
+}
+
 fn default_max_cpu() -> f64 {
     90.0
 }
@@ -106,6 +692,10 @@ fn default_protected_processes() -> Vec<String> {
     vec!["systemd".to_string(), "gnome-shell".to_string(), "kern".to_string()]
 }
 
+fn default_ancestry_depth() -> u8 {
+    1
+}
+
 fn default_notifications_enabled() -> bool {
     true
 }
@@ -118,27 +708,98 @@ fn default_show_on_profile_switch() -> bool {
     true
 }
 
+fn default_cooldown_per_category_secs() -> u64 {
+    10
+}
+
 fn default_kill_graceful() -> bool {
     true
 }
 
-fn default_kill_timeout_seconds() -> u32 {
+fn default_kill_graceful_timeout_secs() -> u64 {
     5
 }
 
+fn default_kill_graceful_poll_ms() -> u64 {
+    100
+}
+
 fn default_kill_confirmation_threshold() -> usize {
     5
 }
 
+fn default_kill_count_limit() -> usize {
+    0
+}
+
 impl Default for TemperatureConfig {
     fn default() -> Self {
         Self {
+            enabled: default_temp_enabled(),
             warning: default_temp_warning(),
             critical: default_temp_critical(),
         }
     }
 }
 
+impl Default for EmergencyConfig {
+    fn default() -> Self {
+        Self {
+            max_kills_per_cycle: default_emergency_max_kills_per_cycle(),
+            max_total_kills: default_emergency_max_total_kills(),
+            cpu_floor_percent: default_emergency_cpu_floor(),
+            trigger_samples: default_emergency_trigger_samples(),
+            exit_samples: default_emergency_exit_samples(),
+            actions: default_emergency_actions(),
+        }
+    }
+}
+
+impl Default for CpuPercentileConfig {
+    fn default() -> Self {
+        Self {
+            samples: default_cpu_percentile_samples(),
+            percentile: default_cpu_percentile(),
+        }
+    }
+}
+
+impl Default for SwapEmergencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_percent: default_swap_emergency_threshold(),
+            exit_percent: default_swap_emergency_exit(),
+        }
+    }
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            stabilization_secs: default_restart_stabilization_secs(),
+            max_per_hour: default_restart_max_per_hour(),
+        }
+    }
+}
+
+impl RestartConfig {
+    /// How long conditions must stay under the limits before a relaunch
+    pub fn stabilization(&self) -> Duration {
+        Duration::from_secs(self.stabilization_secs)
+    }
+}
+
+impl Default for VictimWeights {
+    fn default() -> Self {
+        Self {
+            cpu: default_cpu_victim_weights(),
+            memory: default_memory_victim_weights(),
+            temperature: default_temperature_victim_weights(),
+        }
+    }
+}
+
 impl Default for ResourceLimits {
     fn default() -> Self {
         Self {
@@ -154,6 +815,7 @@ impl Default for NotificationConfig {
             enabled: default_notifications_enabled(),
             show_on_kill: default_show_on_kill(),
             show_on_profile_switch: default_show_on_profile_switch(),
+            cooldown_per_category_secs: default_cooldown_per_category_secs(),
         }
     }
 }
@@ -163,13 +825,33 @@ impl Default for KernConfig {
         Self {
             default_profile: default_profile(),
             monitor_interval: default_monitor_interval(),
+            report_interval_secs: default_report_interval_secs(),
             temperature: TemperatureConfig::default(),
+            temperature_strategy: TemperatureStrategy::default(),
+            temperature_sensor_path: None,
+            memory_metric: MemoryMetric::default(),
+            normalize_cpu: false,
+            enforcement: EnforcementConfig::default(),
+            emergency: EmergencyConfig::default(),
+            swap_emergency: SwapEmergencyConfig::default(),
+            restart: RestartConfig::default(),
             limits: ResourceLimits::default(),
+            cpu_percentile: CpuPercentileConfig::default(),
             protected_processes: default_protected_processes(),
+            ancestry_depth: default_ancestry_depth(),
+            critical_processes: Vec::new(),
+            critical_override_allow: Vec::new(),
             notifications: NotificationConfig::default(),
+            dbus: DbusConfig::default(),
+            control_socket: ControlSocketConfig::default(),
             kill_graceful: default_kill_graceful(),
-            kill_timeout_seconds: default_kill_timeout_seconds(),
+            kill_graceful_timeout_secs: default_kill_graceful_timeout_secs(),
+            kill_graceful_poll_ms: default_kill_graceful_poll_ms(),
             kill_confirmation_threshold: default_kill_confirmation_threshold(),
+            kill_count_limit: default_kill_count_limit(),
+            multi_breach: MultiBreach::default(),
+            victim_weights: VictimWeights::default(),
+            kill_log_path: None,
         }
     }
 }
@@ -181,7 +863,17 @@ impl KernConfig {
     /// 1. ~/.config/kern/kern.yaml (user config)
     /// 2. /etc/kern/kern.yaml (system config)
     /// 3. Compiled-in defaults
+    ///
+    /// `KERN_*` environment variables (see `KernConfigOverride::from_vars`)
+    /// then override whichever was loaded.
     pub fn load() -> Result<Self> {
+        let mut config = Self::load_without_env()?;
+        config.merge(KernConfigOverride::from_env()?);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn load_without_env() -> Result<Self> {
         // Try user config first
         if let Some(config_path) = Self::user_config_path() {
             if config_path.exists() {
@@ -199,13 +891,98 @@ impl KernConfig {
         Ok(Self::default())
     }
 
-    fn load_from_file(path: &PathBuf) -> Result<Self> { // load config from specified path
+    /// Replace every setting `overrides` has a value for. The result isn't
+    /// validated; call `validate()` afterwards.
+    pub fn merge(&mut self, overrides: KernConfigOverride) {
+        fn set<T>(field: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        set(&mut self.default_profile, overrides.default_profile);
+        set(&mut self.monitor_interval, overrides.monitor_interval);
+        set(&mut self.report_interval_secs, overrides.report_interval_secs);
+        set(&mut self.temperature.enabled, overrides.temperature.enabled);
+        set(&mut self.temperature.warning, overrides.temperature.warning);
+        set(&mut self.temperature.critical, overrides.temperature.critical);
+        set(&mut self.temperature_strategy, overrides.temperature_strategy);
+        set(&mut self.temperature_sensor_path, overrides.temperature_sensor_path.map(Some));
+        set(&mut self.memory_metric, overrides.memory_metric);
+        set(&mut self.normalize_cpu, overrides.normalize_cpu);
+        set(&mut self.enforcement.mode, overrides.enforcement.mode);
+        set(&mut self.enforcement.all_users, overrides.enforcement.all_users);
+        set(&mut self.emergency, overrides.emergency);
+        set(&mut self.swap_emergency, overrides.swap_emergency);
+        set(&mut self.restart, overrides.restart);
+        set(&mut self.limits.max_cpu_percent, overrides.limits.max_cpu_percent);
+        set(&mut self.limits.max_ram_percent, overrides.limits.max_ram_percent);
+        set(&mut self.cpu_percentile, overrides.cpu_percentile);
+        set(&mut self.protected_processes, overrides.protected_processes);
+        set(&mut self.ancestry_depth, overrides.ancestry_depth);
+        set(&mut self.critical_processes, overrides.critical_processes);
+        set(&mut self.critical_override_allow, overrides.critical_override_allow);
+        set(&mut self.notifications, overrides.notifications);
+        set(&mut self.dbus, overrides.dbus);
+        set(&mut self.control_socket, overrides.control_socket);
+        set(&mut self.kill_graceful, overrides.kill_graceful);
+        set(&mut self.kill_graceful_timeout_secs, overrides.kill_graceful_timeout_secs);
+        set(&mut self.kill_graceful_poll_ms, overrides.kill_graceful_poll_ms);
+        set(&mut self.kill_confirmation_threshold, overrides.kill_confirmation_threshold);
+        set(&mut self.kill_count_limit, overrides.kill_count_limit);
+        set(&mut self.multi_breach, overrides.multi_breach);
+        set(&mut self.victim_weights, overrides.victim_weights);
+        set(&mut self.kill_log_path, overrides.kill_log_path.map(Some));
+    }
+
+    /// Load and validate a config file. Critical overrides are reported
+    /// through `log::set_warning_handler`.
+    pub fn load_from_file(path: &Path) -> Result<Self> {
         let contents = fs::read_to_string(path)?;
         let config: KernConfig = serde_yaml::from_str(&contents)?;
         config.validate()?;
+        for warning in config.critical_override_warnings() {
+            crate::log::warning(&warning);
+        }
         Ok(config)
     }
 
+    /// How long a graceful kill waits for the process to exit before SIGKILL
+    pub fn kill_graceful_timeout(&self) -> Duration {
+        Duration::from_secs(self.kill_graceful_timeout_secs)
+    }
+
+    /// The graceful kill's timeout and poll interval, for `killer::kill_process`
+    /// (without a watchdog to feed)
+    pub fn kill_graceful_wait(&self) -> crate::killer::GracefulWait<'static> {
+        crate::killer::GracefulWait {
+            timeout: self.kill_graceful_timeout(),
+            poll: Duration::from_millis(self.kill_graceful_poll_ms),
+            watchdog: None,
+        }
+    }
+
+    /// Whether `name` is critical after applying `critical_processes` and `critical_override_allow`
+    pub fn is_critical_process(&self, name: &str) -> bool {
+        crate::killer::is_critical_process(name, &self.critical_processes, &self.critical_override_allow)
+    }
+
+    /// One warning per name in `critical_override_allow`
+    pub fn critical_override_warnings(&self) -> Vec<String> {
+        self.critical_override_allow
+            .iter()
+            .map(|name| {
+                if crate::killer::is_critical_process(name, &self.critical_processes, &[]) {
+                    format!(
+                        "critical_override_allow: '{}' is no longer treated as critical and may be killed",
+                        name
+                    )
+                } else {
+                    format!("critical_override_allow: '{}' is not a critical process (no effect)", name)
+                }
+            })
+            .collect()
+    }
+
     fn user_config_path() -> Option<PathBuf> { // get user config path following XDG standard
         if let Ok(config_home) = std::env::var("XDG_CONFIG_HOME") {
             Some(PathBuf::from(config_home).join("kern").join("kern.yaml"))
@@ -216,19 +993,58 @@ impl KernConfig {
         }
     }
 
-    fn validate(&self) -> Result<()> { // validate config values
+    /// Check every value is in range.
+    ///
+    /// ```
+    /// let mut config = kern::config::KernConfig::default();
+    /// assert!(config.validate().is_ok());
+    /// config.limits.max_cpu_percent = 150.0;
+    /// assert!(config.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
         // Validate monitor interval
-        if self.monitor_interval < 1 {
+        if self.monitor_interval < MIN_MONITOR_INTERVAL_MS {
             return Err(anyhow!(
-                "Invalid monitor_interval: {} (must be >= 1 second)",
-                self.monitor_interval
+                "Invalid monitor_interval: {} (must be >= {})",
+                format_interval(self.monitor_interval),
+                format_interval(MIN_MONITOR_INTERVAL_MS)
             ));
         }
 
-        if self.monitor_interval > 3600 {
+        if self.monitor_interval > MAX_MONITOR_INTERVAL_MS {
             return Err(anyhow!(
-                "Invalid monitor_interval: {} (must be <= 3600 seconds)",
-                self.monitor_interval
+                "Invalid monitor_interval: {} (must be <= {})",
+                format_interval(self.monitor_interval),
+                format_interval(MAX_MONITOR_INTERVAL_MS)
+            ));
+        }
+
+        if self.report_interval_secs == Some(0) {
+            return Err(anyhow!("Invalid report_interval_secs: 0 (must be >= 1, or null to disable reports)"));
+        }
+
+        if self.kill_graceful_timeout_secs > MAX_KILL_GRACEFUL_TIMEOUT_SECS {
+            return Err(anyhow!(
+                "Invalid kill_graceful_timeout_secs: {} (must be 0-{})",
+                self.kill_graceful_timeout_secs,
+                MAX_KILL_GRACEFUL_TIMEOUT_SECS
+            ));
+        }
+
+        if !(MIN_KILL_GRACEFUL_POLL_MS..=MAX_KILL_GRACEFUL_POLL_MS).contains(&self.kill_graceful_poll_ms) {
+            return Err(anyhow!(
+                "Invalid kill_graceful_poll_ms: {} (must be {}-{})",
+                self.kill_graceful_poll_ms,
+                MIN_KILL_GRACEFUL_POLL_MS,
+                MAX_KILL_GRACEFUL_POLL_MS
+            ));
+        }
+
+        if self.ancestry_depth > crate::killer::MAX_ANCESTRY_DEPTH {
+            return Err(anyhow!(
+                "Invalid ancestry_depth: {} (must be 0-{})",
+                self.ancestry_depth,
+                crate::killer::MAX_ANCESTRY_DEPTH
             ));
         }
 
@@ -271,34 +1087,291 @@ impl KernConfig {
             ));
         }
 
+        self.check_temperature_sensor()?;
+
+        // Validate emergency kill limits
+        if self.emergency.max_kills_per_cycle < 1 {
+            return Err(anyhow!(
+                "Invalid emergency.max_kills_per_cycle: {} (must be >= 1)",
+                self.emergency.max_kills_per_cycle
+            ));
+        }
+
+        if self.emergency.max_total_kills < self.emergency.max_kills_per_cycle {
+            return Err(anyhow!(
+                "Invalid emergency.max_total_kills: {} (must be >= max_kills_per_cycle {})",
+                self.emergency.max_total_kills,
+                self.emergency.max_kills_per_cycle
+            ));
+        }
+
+        if !(0.0..=100.0).contains(&self.emergency.cpu_floor_percent) {
+            return Err(anyhow!(
+                "Invalid emergency.cpu_floor_percent: {} (must be 0-100)",
+                self.emergency.cpu_floor_percent
+            ));
+        }
+
+        if self.emergency.trigger_samples < 1 || self.emergency.exit_samples < 1 {
+            return Err(anyhow!(
+                "Invalid emergency samples: trigger_samples ({}) and exit_samples ({}) must be >= 1",
+                self.emergency.trigger_samples,
+                self.emergency.exit_samples
+            ));
+        }
+
+        if !(1..=MAX_CPU_PERCENTILE_SAMPLES).contains(&self.cpu_percentile.samples) {
+            return Err(anyhow!(
+                "Invalid cpu_percentile.samples: {} (must be 1-{})",
+                self.cpu_percentile.samples,
+                MAX_CPU_PERCENTILE_SAMPLES
+            ));
+        }
+
+        if !(0.0..=100.0).contains(&self.cpu_percentile.percentile) {
+            return Err(anyhow!(
+                "Invalid cpu_percentile.percentile: {} (must be 0-100)",
+                self.cpu_percentile.percentile
+            ));
+        }
+
+        let swap = &self.swap_emergency;
+        if !(0.0..=100.0).contains(&swap.threshold_percent) {
+            return Err(anyhow!(
+                "Invalid swap_emergency.threshold_percent: {} (must be 0-100)",
+                swap.threshold_percent
+            ));
+        }
+        if !(0.0..=100.0).contains(&swap.exit_percent) || swap.exit_percent >= swap.threshold_percent {
+            return Err(anyhow!(
+                "Invalid swap_emergency.exit_percent: {} (must be 0-100 and below threshold_percent {})",
+                swap.exit_percent,
+                swap.threshold_percent
+            ));
+        }
+
+        if self.restart.max_per_hour < 1 {
+            return Err(anyhow!(
+                "Invalid restart.max_per_hour: {} (must be >= 1; leave restart_after_kill empty to never restart)",
+                self.restart.max_per_hour
+            ));
+        }
+
+        for (resource, weights) in [
+            ("cpu", self.victim_weights.cpu),
+            ("memory", self.victim_weights.memory),
+            ("temperature", self.victim_weights.temperature),
+        ] {
+            let valid = |weight: f64| weight.is_finite() && weight >= 0.0;
+            if !valid(weights.cpu) || !valid(weights.memory) || weights.cpu + weights.memory <= 0.0 {
+                return Err(anyhow!(
+                    "Invalid victim_weights.{}: cpu {} and memory {} (must be >= 0, not both 0)",
+                    resource,
+                    weights.cpu,
+                    weights.memory
+                ));
+            }
+        }
+
+        if self.emergency.actions.is_empty() {
+            return Err(anyhow!("Invalid emergency.actions: at least one action is required"));
+        }
+
+        // Overrides must name processes exactly, never a pattern
+        for name in &self.critical_override_allow {
+            if name.trim().is_empty() || name.contains(['*', '?', '[']) {
+                return Err(anyhow!(
+                    "Invalid critical_override_allow entry: {:?} (must be an exact process name)",
+                    name
+                ));
+            }
+        }
+
+        for (i, action) in self.emergency.actions.iter().enumerate() {
+            if self.emergency.actions[..i].contains(action) {
+                return Err(anyhow!("Invalid emergency.actions: {:?} is listed more than once", action));
+            }
+        }
+
+        if let Some(path) = self.control_socket.path.as_ref().filter(|path| !path.is_absolute()) {
+            return Err(anyhow!("Invalid control_socket.path: {} (must be absolute)", path.display()));
+        }
+
         Ok(())
     }
 
-    // Print configuration summary
-    pub fn print_summary(&self) {
-        println!(" KERN Configuration Summary");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("Default Profile: {}", self.default_profile);
-        println!("Monitor Interval: {} seconds", self.monitor_interval);
-        println!(
-            "Temperature Warning: {:.0}°C, Critical: {:.0}°C",
-            self.temperature.warning, self.temperature.critical
-        );
-        println!(
+    /// A configured sensor must exist and hold a number: one that can't be
+    /// read would silently report 0°C forever
+    pub fn check_temperature_sensor(&self) -> Result<()> {
+        if let Some(path) = &self.temperature_sensor_path {
+            crate::monitor::read_temperature_sensor(path)
+                .map_err(|e| anyhow!("Invalid temperature_sensor_path: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Whether killing `count` processes needs interactive confirmation
+    pub fn needs_kill_confirmation(&self, count: usize) -> bool {
+        count > 0
+            && (self.kill_confirmation_threshold == 0 || count > self.kill_confirmation_threshold)
+    }
+
+    fn confirmation_summary(&self) -> String {
+        match self.kill_confirmation_threshold {
+            0 => "always".to_string(),
+            usize::MAX => "never".to_string(),
+            n => format!("above {} processes", n),
+        }
+    }
+
+    /// Configuration summary, one line per setting group
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            " KERN Configuration Summary".to_string(),
+            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+            format!("Default Profile: {}", self.default_profile),
+            format!("Monitor Interval: {}", format_interval(self.monitor_interval)),
+            format!(
+                "Status Reports: {}",
+                match self.report_interval_secs {
+                    Some(secs) => format!("every {}s", secs),
+                    None => "off".to_string(),
+                }
+            ),
+        ];
+        if self.temperature.enabled {
+            lines.push(format!(
+                "Temperature Warning: {:.0}°C, Critical: {:.0}°C ({:?} of all zones)",
+                self.temperature.warning, self.temperature.critical, self.temperature_strategy
+            ));
+        } else {
+            lines.push("Temperature: disabled (no temperature-based enforcement)".to_string());
+        }
+        if let Some(path) = &self.temperature_sensor_path {
+            lines.push(format!("Temperature Sensor: {}", path.display()));
+        }
+        lines.push(format!("Process Memory Metric: {:?}", self.memory_metric));
+        lines.push(format!(
+            "Process CPU: {}",
+            if self.normalize_cpu { "share of all CPUs (0-100%)" } else { "per core (100% = one core)" }
+        ));
+        if self.enforcement.mode == EnforcementMode::Alert {
+            lines.push("Enforcement: alert only (no process is killed, limited or throttled)".to_string());
+        }
+        lines.push(format!(
+            "Emergency: max {} kills/cycle, {} per episode, CPU floor {}%, enter/exit after {}/{} samples",
+            self.emergency.max_kills_per_cycle,
+            self.emergency.max_total_kills,
+            self.emergency.cpu_floor_percent,
+            self.emergency.trigger_samples,
+            self.emergency.exit_samples
+        ));
+        lines.push(format!("Emergency Actions: {:?}", self.emergency.actions));
+        lines.push(format!(
             "Resource Limits: CPU {}%, RAM {}%",
             self.limits.max_cpu_percent, self.limits.max_ram_percent
-        );
-        println!(
-            "Notifications: {} (kill: {}, profile: {})",
+        ));
+        lines.push(format!(
+            "Notifications: {} (kill: {}, profile: {}, cooldown: {}s)",
             if self.notifications.enabled { "enabled" } else { "disabled" },
             self.notifications.show_on_kill,
-            self.notifications.show_on_profile_switch
-        );
-        println!("Protected Processes: {}", self.protected_processes.join(", "));
-        println!(
-            "Killer Settings: graceful={}, timeout={}s, confirmation_threshold={}",
-            self.kill_graceful, self.kill_timeout_seconds, self.kill_confirmation_threshold
-        );
+            self.notifications.show_on_profile_switch,
+            self.notifications.cooldown_per_category_secs
+        ));
+        lines.push(format!("DBus: {:?} bus", self.dbus.bus));
+        if self.control_socket.enabled {
+            lines.push(format!(
+                "Control Socket: {}",
+                self.control_socket
+                    .socket_path()
+                    .map_or_else(|| "no path (set control_socket.path or XDG_RUNTIME_DIR)".to_string(), |p| p.display().to_string())
+            ));
+        }
+        lines.push(format!("Protected Processes: {}", self.protected_processes.join(", ")));
+        if self.ancestry_depth > 0 {
+            lines.push(format!("Protected Ancestry: {} level(s)", self.ancestry_depth));
+        }
+        if !self.critical_processes.is_empty() {
+            lines.push(format!("Extra Critical Processes: {}", self.critical_processes.join(", ")));
+        }
+        if !self.critical_override_allow.is_empty() {
+            lines.push(format!(
+                "⚠️  Critical Overrides (may be killed): {}",
+                self.critical_override_allow.join(", ")
+            ));
+        }
+        lines.push(format!(
+            "Killer Settings: graceful={}, timeout={}s, confirm={}, limit={}",
+            self.kill_graceful,
+            self.kill_graceful_timeout_secs,
+            self.confirmation_summary(),
+            match self.kill_count_limit {
+                0 => "unlimited".to_string(),
+                limit => format!("{} per run", limit),
+            }
+        ));
+        lines.push(format!(
+            "Multiple Limits Exceeded: {}",
+            match self.multi_breach {
+                MultiBreach::KillOnce => "one action per cycle",
+                MultiBreach::KillEach => "one action per limit",
+            }
+        ));
+        let weights = |w: ScoreWeights| format!("{}/{}", w.cpu, w.memory);
+        lines.push(format!(
+            "Weighted Victim Scores (cpu/memory): CPU limit {}, RAM limit {}, temperature {}",
+            weights(self.victim_weights.cpu),
+            weights(self.victim_weights.memory),
+            weights(self.victim_weights.temperature)
+        ));
+        lines.push(format!("Kill Log: {}", crate::killer::get_kill_log_path(Some(self)).display()));
+        lines
+    }
+}
+
+/// A setting whose loaded value differs from the compiled-in default
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigDiff {
+    pub path: String, // Dotted, e.g. "temperature.warning"
+    pub default_value: String,
+    pub current_value: String,
+}
+
+/// Settings in `config` that differ from `KernConfig::default()`, ordered by path
+pub fn diff_from_defaults(config: &KernConfig) -> Vec<ConfigDiff> {
+    let default = serde_json::to_value(KernConfig::default()).unwrap_or_default();
+    let current = serde_json::to_value(config).unwrap_or_default();
+    let mut diffs = Vec::new();
+    diff_values(String::new(), &default, &current, &mut diffs);
+    diffs
+}
+
+// Recurse into sections so only the changed leaf is reported, not the whole block
+fn diff_values(path: String, default: &serde_json::Value, current: &serde_json::Value, diffs: &mut Vec<ConfigDiff>) {
+    use serde_json::Value;
+
+    if let (Value::Object(default), Value::Object(current)) = (default, current) {
+        let keys: std::collections::BTreeSet<&String> = default.keys().chain(current.keys()).collect();
+        for key in keys {
+            let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            diff_values(
+                child,
+                default.get(key).unwrap_or(&Value::Null),
+                current.get(key).unwrap_or(&Value::Null),
+                diffs,
+            );
+        }
+    } else if default != current {
+        // Strings unquoted (`pss`, not `"pss"`); everything else as JSON
+        let display = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        diffs.push(ConfigDiff {
+            path,
+            default_value: display(default),
+            current_value: display(current),
+        });
     }
 }
 
@@ -310,7 +1383,7 @@ mod tests {
     fn test_default_config() {
         let config = KernConfig::default();
         assert_eq!(config.default_profile, "normal");
-        assert_eq!(config.monitor_interval, 2);
+        assert_eq!(config.monitor_interval, 2000);
         assert_eq!(config.limits.max_cpu_percent, 90.0);
         assert_eq!(config.limits.max_ram_percent, 85.0);
     }
@@ -322,13 +1395,17 @@ mod tests {
         // Invalid: too low
         config.monitor_interval = 0;
         assert!(config.validate().is_err());
+        config.monitor_interval = 99;
+        assert!(config.validate().is_err());
 
         // Invalid: too high
-        config.monitor_interval = 7200;
+        config.monitor_interval = 3_600_001;
         assert!(config.validate().is_err());
 
         // Valid
-        config.monitor_interval = 5;
+        config.monitor_interval = 100;
+        assert!(config.validate().is_ok());
+        config.monitor_interval = 5000;
         assert!(config.validate().is_ok());
     }
 
@@ -365,6 +1442,103 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_config_validation_emergency() {
+        let mut config = KernConfig::default();
+        assert_eq!(config.emergency.max_kills_per_cycle, 3);
+        assert!(config.validate().is_ok());
+
+        // Invalid: must be allowed to kill at least one process
+        config.emergency.max_kills_per_cycle = 0;
+        assert!(config.validate().is_err());
+
+        // Invalid: episode cap below the per-cycle cap
+        config.emergency.max_kills_per_cycle = 5;
+        config.emergency.max_total_kills = 2;
+        assert!(config.validate().is_err());
+
+        // Invalid: floor out of range
+        config.emergency.max_total_kills = 10;
+        config.emergency.cpu_floor_percent = 150.0;
+        assert!(config.validate().is_err());
+
+        config.emergency.cpu_floor_percent = 40.0;
+        assert!(config.validate().is_ok());
+
+        // Invalid: debounce needs at least one sample
+        config.emergency.trigger_samples = 0;
+        assert!(config.validate().is_err());
+        config.emergency.trigger_samples = 2;
+        config.emergency.exit_samples = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_cpu_percentile() {
+        let config = KernConfig::default();
+        assert_eq!(config.cpu_percentile.samples, 1);
+        assert_eq!(config.cpu_percentile.percentile, 99.0);
+
+        let config: KernConfig = serde_yaml::from_str("cpu_percentile:\n  samples: 30\n  percentile: 90\n").unwrap();
+        assert!(config.validate().is_ok());
+        let config: KernConfig = serde_yaml::from_str("cpu_percentile:\n  samples: 0\n").unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("cpu_percentile.samples"));
+        let config: KernConfig = serde_yaml::from_str("cpu_percentile:\n  percentile: 120\n").unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("cpu_percentile.percentile"));
+    }
+
+    #[test]
+    fn test_config_restart_settings() {
+        let config: KernConfig = serde_yaml::from_str("restart:\n  stabilization_secs: 30\n").unwrap();
+        assert_eq!(config.restart.stabilization(), Duration::from_secs(30));
+        assert_eq!(config.restart.max_per_hour, 3);
+        assert!(config.validate().is_ok());
+
+        let config: KernConfig = serde_yaml::from_str("restart:\n  max_per_hour: 0\n").unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("restart.max_per_hour"));
+    }
+
+    #[test]
+    fn test_config_swap_emergency() {
+        let config = KernConfig::default();
+        assert!(!config.swap_emergency.enabled);
+        assert_eq!(config.swap_emergency.threshold_percent, 80.0);
+
+        let config: KernConfig = serde_yaml::from_str("swap_emergency:\n  enabled: true\n  threshold_percent: 90\n").unwrap();
+        assert!(config.swap_emergency.enabled);
+        assert_eq!(config.swap_emergency.exit_percent, 50.0);
+        assert!(config.validate().is_ok());
+
+        let config: KernConfig = serde_yaml::from_str("swap_emergency:\n  threshold_percent: 40\n").unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("swap_emergency.exit_percent"));
+        let config: KernConfig = serde_yaml::from_str("swap_emergency:\n  threshold_percent: 120\n").unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("swap_emergency.threshold_percent"));
+    }
+
+    #[test]
+    fn test_config_validation_emergency_actions() {
+        let mut config = KernConfig::default();
+        assert_eq!(config.emergency.actions, vec![EmergencyAction::Kill]);
+
+        config.emergency.actions = vec![EmergencyAction::ThrottleCpu, EmergencyAction::Kill];
+        assert!(config.validate().is_ok());
+
+        // Invalid: no actions at all
+        config.emergency.actions.clear();
+        assert!(config.validate().is_err());
+
+        // Invalid: duplicate action
+        config.emergency.actions = vec![EmergencyAction::Kill, EmergencyAction::Kill];
+        assert!(config.validate().is_err());
+
+        let parsed: KernConfig =
+            serde_yaml::from_str("emergency:\n  actions: [throttle_cpu, kill]").unwrap();
+        assert_eq!(
+            parsed.emergency.actions,
+            vec![EmergencyAction::ThrottleCpu, EmergencyAction::Kill]
+        );
+    }
+
     #[test]
     fn test_parse_yaml() {
         let yaml = r#"
@@ -388,7 +1562,7 @@ notifications:
 
         let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
         assert_eq!(config.default_profile, "coding");
-        assert_eq!(config.monitor_interval, 3);
+        assert_eq!(config.monitor_interval, 3000);
         assert_eq!(config.limits.max_cpu_percent, 80.0);
         assert!(config.protected_processes.contains(&"code".to_string()));
         assert!(config.validate().is_ok());
@@ -403,7 +1577,358 @@ default_profile: "normal"
         let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
         assert_eq!(config.default_profile, "normal");
         // Other fields should use defaults
-        assert_eq!(config.monitor_interval, 2);
+        assert_eq!(config.monitor_interval, 2000);
         assert_eq!(config.limits.max_cpu_percent, 90.0);
+        assert!(config.kill_log_path.is_none());
+    }
+
+    #[test]
+    fn test_parse_critical_processes() {
+        let config: KernConfig = serde_yaml::from_str("default_profile: normal").unwrap();
+        assert!(config.critical_processes.is_empty());
+
+        let config: KernConfig =
+            serde_yaml::from_str("critical_processes:\n  - sway\n  - pipewire").unwrap();
+        assert_eq!(config.critical_processes, vec!["sway".to_string(), "pipewire".to_string()]);
+    }
+
+    #[test]
+    fn test_critical_override_allow() {
+        let config: KernConfig = serde_yaml::from_str(
+            "critical_processes:\n  - sway\ncritical_override_allow:\n  - sshd\n  - firefox",
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!config.is_critical_process("sshd"));
+        assert!(config.is_critical_process("sway"));
+        assert!(config.is_critical_process("systemd"));
+
+        let warnings = config.critical_override_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("'sshd' is no longer treated as critical"));
+        assert!(warnings[1].contains("no effect"));
+    }
+
+    #[test]
+    fn test_critical_override_allow_requires_exact_names() {
+        for entry in ["\"*\"", "\"ssh?\"", "\"\""] {
+            let yaml = format!("critical_override_allow:\n  - {}", entry);
+            let config: KernConfig = serde_yaml::from_str(&yaml).unwrap();
+            assert!(config.validate().is_err(), "{} should be rejected", entry);
+        }
+    }
+
+    #[test]
+    fn test_parse_temperature_strategy() {
+        let config: KernConfig = serde_yaml::from_str("default_profile: normal").unwrap();
+        assert_eq!(config.temperature_strategy, TemperatureStrategy::Max);
+
+        let config: KernConfig = serde_yaml::from_str("temperature_strategy: avg").unwrap();
+        assert_eq!(config.temperature_strategy, TemperatureStrategy::Avg);
+
+        assert!(serde_yaml::from_str::<KernConfig>("temperature_strategy: median").is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_metric() {
+        let config: KernConfig = serde_yaml::from_str("default_profile: normal").unwrap();
+        assert_eq!(config.memory_metric, MemoryMetric::Rss);
+
+        let config: KernConfig = serde_yaml::from_str("memory_metric: pss").unwrap();
+        assert_eq!(config.memory_metric, MemoryMetric::Pss);
+
+        assert!(serde_yaml::from_str::<KernConfig>("memory_metric: uss").is_err());
+    }
+
+    #[test]
+    fn test_kill_confirmation_threshold() {
+        let mut config = KernConfig::default();
+        assert!(!config.needs_kill_confirmation(5));
+        assert!(config.needs_kill_confirmation(6));
+
+        // 0 = always confirm, but there's nothing to confirm for no processes
+        config.kill_confirmation_threshold = 0;
+        assert!(config.needs_kill_confirmation(1));
+        assert!(!config.needs_kill_confirmation(0));
+
+        config.kill_confirmation_threshold = usize::MAX;
+        assert!(!config.needs_kill_confirmation(1));
+        assert!(!config.needs_kill_confirmation(100_000));
+    }
+
+    #[test]
+    fn test_parse_kill_confirmation_threshold_max() {
+        let yaml = format!("kill_confirmation_threshold: {}\n", usize::MAX);
+        let config: KernConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config.kill_confirmation_threshold, usize::MAX);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_diff_from_defaults() {
+        // An empty file is all defaults
+        let config: KernConfig = serde_yaml::from_str("{}").unwrap();
+        assert!(diff_from_defaults(&config).is_empty());
+
+        let yaml = "monitor_interval: 5\ntemperature:\n  warning: 70\nmemory_metric: pss\n";
+        let config: KernConfig = serde_yaml::from_str(yaml).unwrap();
+        let diffs = diff_from_defaults(&config);
+        let paths: Vec<&str> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["memory_metric", "monitor_interval", "temperature.warning"]);
+        assert_eq!(
+            diffs[1],
+            ConfigDiff {
+                path: "monitor_interval".to_string(),
+                default_value: "2s".to_string(),
+                current_value: "5s".to_string(),
+            }
+        );
+        assert_eq!((diffs[0].default_value.as_str(), diffs[0].current_value.as_str()), ("rss", "pss"));
+    }
+
+    #[test]
+    fn test_parse_monitor_interval() {
+        let parse = |yaml: &str| serde_yaml::from_str::<KernConfig>(yaml).map(|config| config.monitor_interval);
+        // Old configs: whole seconds
+        assert_eq!(parse("monitor_interval: 2").unwrap(), 2000);
+        assert_eq!(parse("monitor_interval: 0.5").unwrap(), 500);
+        assert_eq!(parse("monitor_interval: 500ms").unwrap(), 500);
+        assert_eq!(parse("monitor_interval: 2s").unwrap(), 2000);
+        assert_eq!(parse("monitor_interval: 1.5s").unwrap(), 1500);
+        assert_eq!(parse("monitor_interval: 500").unwrap(), 500_000);
+        assert!(parse("monitor_interval: fast").is_err());
+        assert!(parse("monitor_interval: -1").is_err());
+
+        assert_eq!(format_interval(500), "500ms");
+        assert_eq!(format_interval(2000), "2s");
+        assert_eq!(format_interval(1500), "1500ms");
+
+        // Written back in the form it is read
+        let config = KernConfig { monitor_interval: 750, ..Default::default() };
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("monitor_interval: 750ms"), "{}", yaml);
+        assert_eq!(serde_yaml::from_str::<KernConfig>(&yaml).unwrap().monitor_interval, 750);
+    }
+
+    #[test]
+    fn test_parse_temperature_enabled() {
+        assert!(KernConfig::default().temperature.enabled);
+
+        let yaml = r#"
+temperature:
+  enabled: false
+"#;
+        let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert!(!config.temperature.enabled);
+        assert_eq!(config.temperature.warning, 75.0);
+    }
+
+    #[test]
+    fn test_parse_kill_log_path() {
+        let yaml = r#"
+kill_log_path: "/var/log/kern.log"
+"#;
+
+        let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(config.kill_log_path, Some(PathBuf::from("/var/log/kern.log")));
+    }
+
+    #[test]
+    fn test_parse_dbus_bus() {
+        assert_eq!(KernConfig::default().dbus.bus, DbusBus::Session);
+
+        let config: KernConfig = serde_yaml::from_str("dbus:\n  bus: system\n").expect("Failed to parse YAML");
+        assert_eq!(config.dbus.bus, DbusBus::System);
+
+        assert!(serde_yaml::from_str::<KernConfig>("dbus:\n  bus: user\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_ancestry_depth() {
+        assert_eq!(KernConfig::default().ancestry_depth, 1);
+        let config: KernConfig = serde_yaml::from_str("ancestry_depth: 3\n").expect("Failed to parse YAML");
+        assert_eq!(config.ancestry_depth, 3);
+        assert!(config.validate().is_ok());
+
+        let config: KernConfig = serde_yaml::from_str("ancestry_depth: 6\n").expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("ancestry_depth"));
+    }
+
+    #[test]
+    fn test_parse_kill_graceful_timeout() {
+        assert_eq!(KernConfig::default().kill_graceful_timeout(), Duration::from_secs(5));
+        let config: KernConfig = serde_yaml::from_str("kill_graceful_timeout_secs: 15\n").expect("Failed to parse YAML");
+        assert_eq!(config.kill_graceful_timeout(), Duration::from_secs(15));
+        assert!(config.validate().is_ok());
+
+        // The old, never-used name is still accepted
+        let config: KernConfig = serde_yaml::from_str("kill_timeout_seconds: 0\n").expect("Failed to parse YAML");
+        assert_eq!(config.kill_graceful_timeout_secs, 0);
+        assert!(config.validate().is_ok());
+
+        assert_eq!(KernConfig::default().kill_graceful_wait().poll, Duration::from_millis(100));
+        let config: KernConfig = serde_yaml::from_str("graceful_poll_ms: 20\n").expect("Failed to parse YAML");
+        assert_eq!(config.kill_graceful_wait().poll, Duration::from_millis(20));
+        assert!(config.validate().is_ok());
+        let config: KernConfig = serde_yaml::from_str("kill_graceful_poll_ms: 5\n").expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("kill_graceful_poll_ms"));
+
+        let config: KernConfig = serde_yaml::from_str("kill_graceful_timeout_secs: 61\n").expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("kill_graceful_timeout_secs"));
+    }
+
+    #[test]
+    fn test_parse_victim_weights() {
+        let weights = KernConfig::default().victim_weights;
+        assert!(weights.cpu.cpu > weights.cpu.memory);
+        assert!(weights.memory.memory > weights.memory.cpu);
+
+        let yaml = "victim_weights:\n  memory:\n    cpu: 1\n    memory: 3\n";
+        let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(config.victim_weights.memory, ScoreWeights { cpu: 1.0, memory: 3.0 });
+        assert_eq!(config.victim_weights.memory.normalized(), ScoreWeights { cpu: 0.25, memory: 0.75 });
+        assert_eq!(config.victim_weights.cpu, weights.cpu);
+        assert!(config.validate().is_ok());
+
+        let config: KernConfig = serde_yaml::from_str("victim_weights:\n  cpu:\n    cpu: 0\n").expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("victim_weights.cpu"));
+        let config: KernConfig = serde_yaml::from_str("victim_weights:\n  temperature:\n    cpu: -1\n    memory: 2\n")
+            .expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("victim_weights.temperature"));
+    }
+
+    #[test]
+    fn test_parse_report_interval() {
+        assert_eq!(KernConfig::default().report_interval_secs, Some(60));
+        let config: KernConfig = serde_yaml::from_str("report_interval_secs: 300\n").expect("Failed to parse YAML");
+        assert_eq!(config.report_interval_secs, Some(300));
+        assert!(config.summary_lines().contains(&"Status Reports: every 300s".to_string()));
+
+        let config: KernConfig = serde_yaml::from_str("report_interval_secs: null\n").expect("Failed to parse YAML");
+        assert_eq!(config.report_interval_secs, None);
+        assert!(config.validate().is_ok());
+        assert!(config.summary_lines().contains(&"Status Reports: off".to_string()));
+
+        let config: KernConfig = serde_yaml::from_str("report_interval_secs: 0\n").expect("Failed to parse YAML");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_multi_breach() {
+        assert_eq!(KernConfig::default().multi_breach, MultiBreach::KillOnce);
+        let config: KernConfig = serde_yaml::from_str("multi_breach: kill_each\n").expect("Failed to parse YAML");
+        assert_eq!(config.multi_breach, MultiBreach::KillEach);
+        assert!(serde_yaml::from_str::<KernConfig>("multi_breach: kill_all\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_control_socket() {
+        let config = KernConfig::default();
+        assert!(!config.control_socket.enabled);
+
+        let yaml = "control_socket:\n  enabled: true\n  path: /run/kern/control.sock\n";
+        let config: KernConfig = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert!(config.control_socket.enabled);
+        assert_eq!(config.control_socket.socket_path(), Some(PathBuf::from("/run/kern/control.sock")));
+        assert!(config.validate().is_ok());
+
+        let config: KernConfig =
+            serde_yaml::from_str("control_socket:\n  path: kern.sock\n").expect("Failed to parse YAML");
+        assert!(config.validate().unwrap_err().to_string().contains("control_socket.path"));
+    }
+
+    #[test]
+    fn test_temperature_sensor_path_validation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sensor = temp_dir.path().join("temp1_input");
+        std::fs::write(&sensor, "52000\n").unwrap();
+
+        let mut config = KernConfig {
+            temperature_sensor_path: Some(sensor.clone()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        std::fs::write(&sensor, "garbage\n").unwrap();
+        assert!(config.validate().is_err());
+
+        config.temperature_sensor_path = Some(temp_dir.path().join("missing"));
+        assert!(config.check_temperature_sensor().is_err());
+    }
+
+    fn env_vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: std::collections::HashMap<String, String> =
+            vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_env_overrides_win_over_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("kern.yaml");
+        std::fs::write(
+            &path,
+            "monitor_interval: 5\ntemperature:\n  warning: 70\n  critical: 90\nlimits:\n  max_cpu_percent: 70\n  max_ram_percent: 80\nenforcement:\n  all_users: true\n",
+        )
+        .unwrap();
+        let mut config = KernConfig::load_from_file(&path).unwrap();
+
+        let overrides = KernConfigOverride::from_vars(env_vars(&[
+            ("KERN_MONITOR_INTERVAL", "500ms"),
+            ("KERN_TEMP_CRITICAL", "95"),
+            ("KERN_MAX_CPU_PERCENT", "60.5"),
+            ("KERN_ENFORCEMENT_MODE", "alert"),
+            ("KERN_MEMORY_METRIC", "pss"),
+            ("KERN_REPORT_INTERVAL_SECS", "0"),
+            ("KERN_KILL_LOG_PATH", "/var/log/kern.log"),
+        ]))
+        .unwrap();
+        config.merge(overrides);
+        assert!(config.validate().is_ok());
+
+        assert_eq!(config.monitor_interval, 500);
+        assert_eq!(config.temperature.critical, 95.0);
+        assert_eq!(config.limits.max_cpu_percent, 60.5);
+        assert_eq!(config.enforcement.mode, EnforcementMode::Alert);
+        assert_eq!(config.memory_metric, MemoryMetric::Pss);
+        assert_eq!(config.report_interval_secs, None);
+        assert_eq!(config.kill_log_path, Some(PathBuf::from("/var/log/kern.log")));
+        // Settings without a variable keep their file values
+        assert_eq!(config.temperature.warning, 70.0);
+        assert_eq!(config.limits.max_ram_percent, 80.0);
+        assert!(config.enforcement.all_users);
+        assert_eq!(config.default_profile, "normal");
+
+        // A bare number is seconds, as in kern.yaml
+        let overrides = KernConfigOverride::from_vars(env_vars(&[("KERN_MONITOR_INTERVAL", "3")])).unwrap();
+        assert_eq!(overrides.monitor_interval, Some(3000));
+    }
+
+    #[test]
+    fn test_empty_override_keeps_config() {
+        let mut config: KernConfig =
+            serde_yaml::from_str("monitor_interval: 1s\nnormalize_cpu: true\nkill_count_limit: 4\n").unwrap();
+        let before = serde_yaml::to_string(&config).unwrap();
+
+        config.merge(KernConfigOverride::from_vars(|_| None).unwrap());
+        assert_eq!(serde_yaml::to_string(&config).unwrap(), before);
+
+        config.merge(KernConfigOverride { kill_count_limit: Some(0), ..Default::default() });
+        assert_eq!(config.kill_count_limit, 0);
+        assert!(config.normalize_cpu);
+    }
+
+    #[test]
+    fn test_invalid_env_override() {
+        let err = KernConfigOverride::from_vars(env_vars(&[("KERN_MAX_CPU_PERCENT", "lots")])).unwrap_err();
+        assert!(err.to_string().contains("KERN_MAX_CPU_PERCENT"), "{}", err);
+        let err = KernConfigOverride::from_vars(env_vars(&[("KERN_MONITOR_INTERVAL", "soon")])).unwrap_err();
+        assert!(err.to_string().contains("KERN_MONITOR_INTERVAL"), "{}", err);
+        assert!(KernConfigOverride::from_vars(env_vars(&[("KERN_ENFORCEMENT_MODE", "panic")])).is_err());
+
+        // Parses, but out of range once merged
+        let mut config = KernConfig::default();
+        config.merge(KernConfigOverride::from_vars(env_vars(&[("KERN_TEMP_CRITICAL", "500")])).unwrap());
+        assert!(config.validate().is_err());
     }
 }
@@ -0,0 +1,297 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+use crate::dbus_server::KernDBusInterface;
+
+/// One request on the control socket, sent as a single JSON line such as
+/// `{"command": "set_mode", "profile": "coding"}`. Each maps to the DBus
+/// method of the same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    GetStatus,
+    GetEnforcerStatus,
+    ListProcesses {
+        #[serde(default)]
+        count: u32, // 0 = all
+    },
+    SetMode {
+        profile: String,
+    },
+    Kill {
+        name: String,
+    },
+    GetKillLog {
+        #[serde(default)]
+        limit: Option<i32>, // None = the whole log
+    },
+    Pause,
+    Resume,
+}
+
+/// Listen on `path`, readable and writable by the owner only. A stale socket
+/// left by a crashed daemon is replaced; one that still answers is an error.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(anyhow!("{} is in use (is another kern daemon running?)", path.display()));
+        }
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove stale {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Take over a listening socket systemd opened for us (socket activation)
+pub fn from_fd(fd: RawFd) -> Result<UnixListener> {
+    // SAFETY: systemd hands each passed fd to this process alone, and it is only taken once
+    let listener = unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true)?;
+    UnixListener::from_std(listener).context("The socket passed by systemd is not a listening Unix socket")
+}
+
+/// Answer connections on `listener` until accepting fails
+pub async fn serve(listener: UnixListener, iface: KernDBusInterface) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let iface = iface.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, iface).await {
+                eprintln!("⚠️  Control socket connection failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Read requests line by line, answering each with one JSON line, until the
+/// client hangs up
+pub async fn handle_connection<S>(stream: S, iface: KernDBusInterface) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = respond(&iface, &line).await.to_string();
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+// {"ok": true, "result": ...} or {"ok": false, "error": {"code": ..., "message": ...}}
+async fn respond(iface: &KernDBusInterface, line: &str) -> Value {
+    let request = match serde_json::from_str::<ControlRequest>(line) {
+        Ok(request) => request,
+        Err(e) if e.is_data() => return error_response("invalid_request", &e.to_string()),
+        Err(e) => return error_response("invalid_json", &e.to_string()),
+    };
+    match iface.control(request).await {
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(e) => {
+            let code = match &e {
+                zbus::fdo::Error::AccessDenied(_) => "access_denied",
+                zbus::fdo::Error::InvalidArgs(_) => "invalid_args",
+                _ => "failed",
+            };
+            let message = match e {
+                zbus::fdo::Error::AccessDenied(m) | zbus::fdo::Error::InvalidArgs(m) | zbus::fdo::Error::Failed(m) => m,
+                other => other.to_string(),
+            };
+            error_response(code, &message)
+        }
+    }
+}
+
+fn error_response(code: &str, message: &str) -> Value {
+    json!({ "ok": false, "error": { "code": code, "message": message } })
+}
+
+/// Send one request to the daemon at `path` and return its result; an error
+/// response becomes an Err carrying the daemon's message
+pub fn request(path: &Path, request: &ControlRequest) -> Result<Value> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path).with_context(|| {
+        format!(
+            "Could not connect to {} (is `kern dbus` running with control_socket.enabled?)",
+            path.display()
+        )
+    })?;
+    // Killing gracefully can take kill_graceful_timeout_secs per process
+    stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    std::io::BufReader::new(&stream).read_line(&mut reply)?;
+    let reply: Value = serde_json::from_str(&reply).map_err(|e| anyhow!("Invalid reply from kern daemon: {}", e))?;
+    if reply["ok"] == true {
+        return Ok(reply["result"].clone());
+    }
+    Err(anyhow!(
+        "{} ({})",
+        reply["error"]["message"].as_str().unwrap_or("unknown error"),
+        reply["error"]["code"].as_str().unwrap_or("failed")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KernConfig;
+    use crate::profiles::ProfileManager;
+    use tempfile::TempDir;
+    use tokio::io::Lines;
+    use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::net::UnixStream;
+
+    fn test_interface(temp_dir: &TempDir) -> KernDBusInterface {
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+        std::fs::write(profiles_dir.join("other.yaml"), "name: other\ndescription: Other profile\n").unwrap();
+        let mut profile_manager = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        profile_manager.switch_to("test").unwrap();
+        let config = KernConfig { kill_log_path: Some(temp_dir.path().join("kern.log")), ..Default::default() };
+        KernDBusInterface::new(profile_manager, config)
+    }
+
+    async fn ask(writer: &mut OwnedWriteHalf, replies: &mut Lines<BufReader<OwnedReadHalf>>, line: &str) -> Value {
+        writer.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+        let reply = replies.next_line().await.unwrap().expect("a reply line");
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_protocol_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("kern.log"), "first kill\nsecond kill\n").unwrap();
+        let (client, server) = UnixStream::pair().unwrap();
+        let server = tokio::spawn(handle_connection(server, test_interface(&temp_dir)));
+        let (reader, mut writer) = client.into_split();
+        let mut replies = BufReader::new(reader).lines();
+
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "set_mode", "profile": "other"}"#).await;
+        assert_eq!(reply, json!({ "ok": true, "result": true }));
+
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "pause"}"#).await;
+        assert_eq!(reply, json!({ "ok": true, "result": null }));
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_enforcer_status"}"#).await;
+        assert_eq!(reply["result"]["active_profile"], "other");
+        assert_eq!(reply["result"]["enforcement_paused"], true);
+        ask(&mut writer, &mut replies, r#"{"command": "resume"}"#).await;
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_enforcer_status"}"#).await;
+        assert_eq!(reply["result"]["enforcement_paused"], false);
+
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_kill_log", "limit": 1}"#).await;
+        assert_eq!(reply["result"], json!(["second kill"]));
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_kill_log"}"#).await;
+        assert_eq!(reply["result"], json!(["second kill", "first kill"]));
+
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "list_processes", "count": 3}"#).await;
+        assert!(reply["result"].as_array().unwrap().len() <= 3);
+
+        // The DBus method's errors come back structured
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_kill_log", "limit": -1}"#).await;
+        assert_eq!(reply["ok"], false);
+        assert_eq!(reply["error"]["code"], "invalid_args");
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "kill", "name": "systemd"}"#).await;
+        assert_eq!(reply["error"]["code"], "access_denied");
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "set_mode", "profile": "missing"}"#).await;
+        assert_eq!(reply["error"]["code"], "failed");
+        assert!(reply["error"]["message"].as_str().unwrap().contains("not found"));
+
+        drop(writer);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_requests_get_error_responses() {
+        let temp_dir = TempDir::new().unwrap();
+        let (client, server) = UnixStream::pair().unwrap();
+        tokio::spawn(handle_connection(server, test_interface(&temp_dir)));
+        let (reader, mut writer) = client.into_split();
+        let mut replies = BufReader::new(reader).lines();
+
+        for (line, code) in [
+            (r#"{"command": "#, "invalid_json"),
+            ("not json", "invalid_json"),
+            (r#"{"command": "reboot"}"#, "invalid_request"),
+            (r#"{"command": "set_mode"}"#, "invalid_request"),
+            (r#"{"profile": "other"}"#, "invalid_request"),
+        ] {
+            let reply = ask(&mut writer, &mut replies, line).await;
+            assert_eq!(reply["ok"], false, "{}", line);
+            assert_eq!(reply["error"]["code"], code, "{}", line);
+            assert!(reply["error"]["message"].is_string());
+        }
+
+        // The connection survives bad requests
+        let reply = ask(&mut writer, &mut replies, r#"{"command": "get_enforcer_status"}"#).await;
+        assert_eq!(reply["ok"], true);
+    }
+
+    #[test]
+    fn test_request_serializes_as_a_command() {
+        let request = ControlRequest::SetMode { profile: "coding".to_string() };
+        assert_eq!(serde_json::to_value(&request).unwrap(), json!({ "command": "set_mode", "profile": "coding" }));
+        let parsed: ControlRequest = serde_json::from_str(r#"{"command": "list_processes"}"#).unwrap();
+        assert_eq!(parsed, ControlRequest::ListProcesses { count: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_bind_is_owner_only_and_replaces_stale_sockets() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("kern").join("control.sock");
+
+        let listener = bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let dir_mode = std::fs::metadata(path.parent().unwrap()).unwrap().permissions().mode();
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        // Still answering: refuse to take it over
+        assert!(bind(&path).unwrap_err().to_string().contains("in use"));
+
+        drop(listener);
+        assert!(bind(&path).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_over_a_bound_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("control.sock");
+        let listener = bind(&path).unwrap();
+        tokio::spawn(serve(listener, test_interface(&temp_dir)));
+
+        let result = tokio::task::spawn_blocking(move || {
+            let ok = request(&path, &ControlRequest::SetMode { profile: "other".to_string() });
+            let err = request(&path, &ControlRequest::SetMode { profile: "missing".to_string() });
+            (ok, err)
+        })
+        .await
+        .unwrap();
+        assert_eq!(result.0.unwrap(), json!(true));
+        assert!(result.1.unwrap_err().to_string().contains("(failed)"));
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Result};
+use zbus::Connection;
+
+use crate::dbus_server::{connect, OBJECT_PATH, SERVICE_NAME};
+use kern::config::DbusBus;
+use kern::monitor::{ProcessFootprint, ProcessInfo, SystemStats};
+
+/// A running kern daemon (`kern dbus`) the CLI hands work to, so the daemon
+/// and the CLI never disagree about the current profile
+pub struct Daemon {
+    runtime: tokio::runtime::Runtime,
+    connection: Connection,
+}
+
+impl Daemon {
+    /// The daemon on the session bus, else on the system bus (kern running as
+    /// a root service); None when neither has one
+    pub fn find() -> Option<Self> {
+        let runtime = tokio::runtime::Runtime::new().ok()?;
+        let connection = runtime.block_on(find_service())?;
+        Some(Self { runtime, connection })
+    }
+
+    fn call<B>(&self, method: &str, body: &B) -> Result<zbus::Message>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.runtime.block_on(async {
+            let reply = self
+                .connection
+                .call_method(Some(SERVICE_NAME), OBJECT_PATH, Some(SERVICE_NAME), method, body)
+                .await?;
+            Ok(reply)
+        })
+    }
+
+    /// PID of the process owning the service name, as the bus reports it
+    pub fn pid(&self) -> Option<u32> {
+        self.runtime.block_on(async {
+            let dbus = zbus::fdo::DBusProxy::new(&self.connection).await.ok()?;
+            let name = zbus::names::BusName::try_from(SERVICE_NAME).ok()?;
+            dbus.get_connection_unix_process_id(name).await.ok()
+        })
+    }
+
+    // Methods that answer with a JSON string
+    fn call_json<B>(&self, method: &str, body: &B) -> Result<serde_json::Value>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        let text: String = self.call(method, body)?.body().deserialize()?;
+        serde_json::from_str(&text).map_err(|e| anyhow!("{} returned invalid JSON: {}", method, e))
+    }
+
+    /// SetMode: the daemon saves the choice and runs kill_on_activate
+    pub fn set_mode(&self, profile: &str) -> Result<bool> {
+        Ok(self.call("SetMode", &(profile,))?.body().deserialize()?)
+    }
+
+    /// GetStatus, as the stats `kern status` prints
+    pub fn status(&self) -> Result<SystemStats> {
+        Ok(stats_from_status(&self.call_json("GetStatus", &())?))
+    }
+
+    /// GetEnforcerStatus
+    pub fn enforcer_status(&self) -> Result<serde_json::Value> {
+        self.call_json("GetEnforcerStatus", &())
+    }
+
+    /// KillProcessesByName: {"matched", "killed", "failed", "zombies"} PID lists
+    pub fn kill_by_name(&self, name: &str) -> Result<serde_json::Value> {
+        self.call_json("KillProcessesByName", &(name,))
+    }
+}
+
+/// The daemon answered with an error, as opposed to not answering at all
+pub fn is_method_error(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<zbus::Error>(), Some(zbus::Error::MethodError(..) | zbus::Error::FDO(_)))
+}
+
+async fn find_service() -> Option<Connection> {
+    for bus in [DbusBus::Session, DbusBus::System] {
+        let Ok(connection) = connect(bus).await else { continue };
+        let Ok(dbus) = zbus::fdo::DBusProxy::new(&connection).await else { continue };
+        let Ok(name) = SERVICE_NAME.try_into() else { return None };
+        if let Ok(true) = dbus.name_has_owner(name).await {
+            return Some(connection);
+        }
+    }
+    None
+}
+
+/// Rebuild `SystemStats` from GetStatus JSON. Only the fields GetStatus carries
+/// are set: the process lists hold its top 10 and cgroup details are dropped.
+/// Per-process CPU comes back per core even from a daemon with normalize_cpu set.
+pub fn stats_from_status(status: &serde_json::Value) -> SystemStats {
+    let number = |key: &str| status[key].as_f64().unwrap_or(0.0);
+    let pressure = |key: &str| status[key].as_f64();
+
+    // Both lists are subsets of the full process table; merge them by PID
+    let mut top_processes: Vec<ProcessInfo> = Vec::new();
+    for entry in ["top_processes", "top_cpu_processes"]
+        .iter()
+        .filter_map(|key| status[*key].as_array())
+        .flatten()
+    {
+        let Some(pid) = entry["pid"].as_u64().and_then(|pid| u32::try_from(pid).ok()) else { continue };
+        if top_processes.iter().any(|p| p.pid == pid) {
+            continue;
+        }
+        top_processes.push(ProcessInfo {
+            pid,
+            name: entry["name"].as_str().unwrap_or_default().to_string(),
+            memory_gb: entry["memory_gb"].as_f64().unwrap_or(0.0),
+            cpu_percentage: entry["cpu_percentage"].as_f64().unwrap_or(0.0),
+            uid: entry["uid"].as_u64().and_then(|uid| u32::try_from(uid).ok()),
+            ..Default::default()
+        });
+    }
+    top_processes.sort_by(|a, b| kern::monitor::descending(a.memory_gb, b.memory_gb));
+
+    let cpu_count = status["cpu_count"].as_u64().unwrap_or(0) as usize;
+    let mut footprint = footprint_from_json(&status["self"]);
+    if status["cpu_normalized"] == true && cpu_count > 0 {
+        for process in &mut top_processes {
+            process.cpu_percentage *= cpu_count as f64;
+        }
+        if let Some(footprint) = footprint.as_mut() {
+            footprint.cpu_percent *= cpu_count as f64;
+        }
+    }
+
+    SystemStats {
+        cpu_usage: number("cpu_usage"),
+        cpu_count,
+        total_memory_gb: number("total_memory_gb"),
+        used_memory_gb: number("used_memory_gb"),
+        memory_percentage: number("memory_percentage"),
+        swap_percentage: number("swap_percentage"),
+        temperature: number("temperature"),
+        temperature_avg: number("temperature_avg"),
+        temperature_max: number("temperature_max"),
+        top_processes,
+        cgroup: None,
+        cpu_pressure: pressure("cpu_pressure"),
+        mem_pressure: pressure("mem_pressure"),
+        uptime_secs: status["uptime_secs"].as_u64().unwrap_or(0),
+        boot_time: status["boot_time"].as_u64().unwrap_or(0),
+        footprint,
+    }
+}
+
+// The daemon's own footprint, as GetStatus reports it under "self"
+fn footprint_from_json(footprint: &serde_json::Value) -> Option<ProcessFootprint> {
+    Some(ProcessFootprint {
+        pid: footprint["pid"].as_u64().and_then(|pid| u32::try_from(pid).ok())?,
+        memory_mb: footprint["memory_mb"].as_f64().unwrap_or(0.0),
+        cpu_percent: footprint["cpu_percent"].as_f64().unwrap_or(0.0),
+        cpu_time_secs: footprint["cpu_time_secs"].as_f64().unwrap_or(0.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stats_from_status() {
+        let status = json!({
+            "cpu_usage": 42.5,
+            "cpu_count": 8,
+            "total_memory_gb": 16.0,
+            "used_memory_gb": 8.0,
+            "memory_percentage": 50.0,
+            "temperature": 65.0,
+            "temperature_avg": 58.0,
+            "temperature_max": 65.0,
+            "cpu_pressure": 1.5,
+            "mem_pressure": null,
+            "uptime_secs": 273600,
+            "boot_time": 1_760_000_000,
+            "self": { "pid": 999, "memory_mb": 12.5, "cpu_percent": 0.4, "cpu_time_secs": 30.0 },
+            "top_processes": [
+                { "pid": 1234, "name": "chrome", "memory_gb": 2.5, "cpu_percentage": 15.0 },
+                { "pid": 42, "name": "rustc", "memory_gb": 0.8, "cpu_percentage": 97.0 },
+            ],
+            "top_cpu_processes": [
+                { "pid": 42, "name": "rustc", "memory_gb": 0.8, "cpu_percentage": 97.0 },
+                { "pid": 7, "name": "spinner", "memory_gb": 0.1, "cpu_percentage": 50.0 },
+            ],
+        });
+
+        let stats = stats_from_status(&status);
+        assert_eq!(stats.cpu_usage, 42.5);
+        assert_eq!(stats.cpu_count, 8);
+        assert_eq!(stats.memory_percentage, 50.0);
+        assert_eq!(stats.temperature_avg, 58.0);
+        assert_eq!(stats.cpu_pressure, Some(1.5));
+        assert_eq!(stats.mem_pressure, None);
+        assert_eq!(stats.uptime_secs, 273600);
+        assert_eq!(stats.boot_time, 1_760_000_000);
+        assert_eq!(stats.footprint.map(|f| (f.pid, f.memory_mb)), Some((999, 12.5)));
+        let pids: Vec<u32> = stats.top_processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1234, 42, 7]);
+        assert_eq!(stats.top_cpu_processes()[0].name, "rustc");
+
+        // Missing fields read as zero rather than failing
+        let empty = stats_from_status(&json!({}));
+        assert_eq!(empty.cpu_usage, 0.0);
+        assert!(empty.top_processes.is_empty());
+        assert_eq!(empty.footprint, None);
+    }
+
+    #[test]
+    fn test_stats_from_normalized_status_is_per_core() {
+        let status = json!({
+            "cpu_count": 4,
+            "cpu_normalized": true,
+            "self": { "pid": 999, "memory_mb": 12.5, "cpu_percent": 0.25, "cpu_time_secs": 30.0 },
+            "top_processes": [{ "pid": 42, "name": "rustc", "memory_gb": 0.8, "cpu_percentage": 50.0 }],
+        });
+        let stats = stats_from_status(&status);
+        assert_eq!(stats.top_processes[0].cpu_percentage, 200.0);
+        assert_eq!(stats.footprint.map(|f| f.cpu_percent), Some(1.0));
+    }
+}
@@ -1,21 +1,39 @@
 use anyhow::Result;
 use serde_json::json;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use zbus::dbus_interface;
 use zbus::Connection;
 
-use crate::config::KernConfig;
+use crate::config::{DbusBus, KernConfig};
+use crate::control_socket::{self, ControlRequest};
+use crate::enforcer::{Enforcer, EnforcerEvent, SharedEnforcerStatus};
+use crate::idle::{self, IdleSwitcher};
+use crate::log::{LogLevel, Logger};
 use crate::monitor;
+use crate::output;
 use crate::profiles::ProfileManager;
+use crate::systemd::{self, Notifier};
+
+pub const SERVICE_NAME: &str = "org.gnome.Shell.Extensions.Kern";
+pub const OBJECT_PATH: &str = "/org/gnome/Shell/Extensions/Kern";
 
 /// DBus interface implementation for Kern
 /// Service: org.gnome.Shell.Extensions.Kern
 /// Object Path: /org/gnome/Shell/Extensions/Kern
+///
+/// Clones share all state, so the control socket can serve a clone
+#[derive(Clone)]
 pub struct KernDBusInterface {
     profile_manager: Arc<RwLock<ProfileManager>>,
-    #[allow(dead_code)]
     config: Arc<KernConfig>,
+    enforcer_status: SharedEnforcerStatus, // Stays "not running" unless the service enforces
+    profile_locked: Arc<AtomicBool>,       // SetProfileLock: SetMode is refused while set
+    enforcement_paused: Arc<AtomicBool>,   // PauseEnforcement: the enforcer only monitors
+    simulate: Arc<AtomicBool>,             // SetSimulateMode: the enforcer acts on nothing
 }
 
 impl KernDBusInterface {
@@ -23,6 +41,10 @@ impl KernDBusInterface {
         Self {
             profile_manager: Arc::new(RwLock::new(profile_manager)),
             config: Arc::new(config),
+            enforcer_status: SharedEnforcerStatus::default(),
+            profile_locked: Arc::new(AtomicBool::new(false)),
+            enforcement_paused: Arc::new(AtomicBool::new(false)),
+            simulate: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -30,37 +52,105 @@ impl KernDBusInterface {
 #[dbus_interface(name = "org.gnome.Shell.Extensions.Kern")]
 impl KernDBusInterface {
     /// GetStatus() → (s)
-    /// Returns the current system status as a JSON string
+    /// Returns the current system status as a JSON string. Per-process CPU is
+    /// per core unless the daemon's normalize_cpu is set ("cpu_normalized").
     async fn get_status(&self) -> zbus::fdo::Result<String> {
-        let stats = monitor::get_system_stats()
+        let mut stats = monitor::get_system_stats(self.config.memory_metric, self.config.temperature_sensor_path.as_deref())
             .map_err(|e| zbus::fdo::Error::Failed(format!("Failed to get system stats: {}", e)))?;
+        if self.config.normalize_cpu {
+            stats.normalize_process_cpu();
+        }
 
-        let top: Vec<serde_json::Value> = stats
-            .top_processes
-            .iter()
-            .take(10)
-            .map(|p| {
-                json!({
-                    "pid": p.pid,
-                    "name": p.name,
-                    "memory_gb": p.memory_gb,
-                    "cpu_percentage": p.cpu_percentage,
-                })
+        let summary = |p: &monitor::ProcessInfo| {
+            json!({
+                "pid": p.pid,
+                "name": p.name,
+                "memory_gb": p.memory_gb,
+                "cpu_percentage": p.cpu_percentage,
+                "uid": p.uid,
+                "user": p.uid.and_then(monitor::user_name),
             })
-            .collect();
+        };
+        let top: Vec<serde_json::Value> = stats.top_processes.iter().take(10).map(summary).collect();
+        let top_cpu: Vec<serde_json::Value> = stats.top_cpu_processes().into_iter().take(10).map(summary).collect();
 
         let status_json = json!({
             "cpu_usage": stats.cpu_usage,
+            "cpu_count": stats.cpu_count,
+            "cpu_normalized": self.config.normalize_cpu,
             "total_memory_gb": stats.total_memory_gb,
             "used_memory_gb": stats.used_memory_gb,
             "memory_percentage": stats.memory_percentage,
+            "swap_percentage": stats.swap_percentage,
             "temperature": stats.temperature,
+            "temperature_avg": stats.temperature_avg,
+            "temperature_max": stats.temperature_max,
+            "cpu_pressure": stats.cpu_pressure,
+            "mem_pressure": stats.mem_pressure,
+            "cgroup_limited": stats.cgroup.is_some(),
+            "memory_source": stats.memory_source().as_str(),
+            "uptime_secs": stats.uptime_secs,
+            "boot_time": stats.boot_time,
+            "self": stats.footprint,
             "top_processes": top,
+            "top_cpu_processes": top_cpu,
         });
 
         Ok(serde_json::to_string(&status_json).unwrap_or_else(|_| "{}".to_string()))
     }
 
+    /// GetStatusSummary() → (s)
+    /// Returns only the system-wide metrics as a JSON string, without the
+    /// process lists: cheap enough for the panel indicator to poll often
+    async fn get_status_summary(&self) -> zbus::fdo::Result<String> {
+        let stats = monitor::get_system_summary(self.config.temperature_sensor_path.as_deref())
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Failed to get system stats: {}", e)))?;
+
+        let summary_json = json!({
+            "cpu_usage": stats.cpu_usage,
+            "memory_percentage": stats.memory_percentage,
+            "temperature": stats.temperature,
+            "temperature_avg": stats.temperature_avg,
+            "temperature_max": stats.temperature_max,
+            "cpu_pressure": stats.cpu_pressure,
+            "mem_pressure": stats.mem_pressure,
+            "cgroup_limited": stats.cgroup.is_some(),
+            "memory_source": stats.memory_source().as_str(),
+        });
+
+        Ok(serde_json::to_string(&summary_json).unwrap_or_else(|_| "{}".to_string()))
+    }
+
+    /// GetEnforcerStatus() → (s)
+    /// Returns what the service's enforcer is doing as a JSON string
+    async fn get_enforcer_status(&self) -> zbus::fdo::Result<String> {
+        let mut status = self
+            .enforcer_status
+            .lock()
+            .map(|status| status.to_json())
+            .map_err(|_| zbus::fdo::Error::Failed("Enforcer status unavailable".to_string()))?;
+        if status["running"] == false {
+            status["active_profile"] = json!(self.profile_manager.read().await.current_name());
+        }
+        status["profile_locked"] = json!(self.profile_locked.load(Ordering::SeqCst));
+        status["enforcement_paused"] = json!(self.enforcement_paused.load(Ordering::SeqCst));
+        status["simulate"] = json!(self.simulate.load(Ordering::SeqCst));
+
+        Ok(serde_json::to_string(&status).unwrap_or_else(|_| "{}".to_string()))
+    }
+
+    /// CurrentMode property: the active profile, as returned by GetCurrentMode
+    #[dbus_interface(property)]
+    async fn current_mode(&self) -> String {
+        self.profile_manager.read().await.current_name().to_string()
+    }
+
+    /// EmergencyMode property: whether the service's enforcer is in emergency mode
+    #[dbus_interface(property)]
+    async fn emergency_mode(&self) -> bool {
+        self.enforcer_status.lock().map(|status| status.emergency_mode()).unwrap_or(false)
+    }
+
     /// GetCurrentMode() → (s)
     /// Returns the name of the currently active profile
     async fn get_current_mode(&self) -> zbus::fdo::Result<String> {
@@ -80,6 +170,13 @@ impl KernDBusInterface {
     async fn set_mode(&self, profile_name: &str) -> zbus::fdo::Result<bool> {
         let mut manager = self.profile_manager.write().await;
 
+        if self.profile_locked.load(Ordering::SeqCst) {
+            return Err(zbus::fdo::Error::AccessDenied(format!(
+                "Profile is locked to '{}' (SetProfileLock false to unlock)",
+                manager.current_name()
+            )));
+        }
+
         if !manager.list_names().contains(&profile_name.to_string()) {
             return Err(zbus::fdo::Error::Failed(format!(
                 "Profile '{}' not found",
@@ -87,66 +184,433 @@ impl KernDBusInterface {
             )));
         }
 
+        let old_profile = manager.current().cloned().unwrap_or_default();
         manager.switch_to(profile_name).map_err(|e| {
             zbus::fdo::Error::Failed(format!("Failed to switch profile: {}", e))
         })?;
 
+        // An enforcing service runs kill_on_activate from its own loop; otherwise do it here,
+        // so it happens once whether the switch came from the CLI or the extension
+        let enforcing = self.enforcer_status.lock().map(|status| status.running).unwrap_or(false);
+        if !enforcing {
+            if let Ok(new_profile) = manager.current() {
+                let mut enforcer = Enforcer::new((*self.config).clone(), old_profile);
+                enforcer
+                    .switch_profile(new_profile.clone())
+                    .map_err(|e| zbus::fdo::Error::Failed(format!("Switched, but kill_on_activate failed: {}", e)))?;
+            }
+        }
+
         Ok(true)
     }
 
+    /// SetProfileLock(b: locked) → ()
+    /// While locked, SetMode is refused and the service's enforcer keeps its profile
+    async fn set_profile_lock(&self, locked: bool) {
+        self.profile_locked.store(locked, Ordering::SeqCst);
+    }
+
+    /// PauseEnforcement() → ()
+    /// The service's enforcer keeps monitoring but stops acting, as if the
+    /// active profile were monitor-only, until ResumeEnforcement
+    async fn pause_enforcement(&self) {
+        self.enforcement_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// ResumeEnforcement() → ()
+    async fn resume_enforcement(&self) {
+        self.enforcement_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// SetSimulateMode(b: simulate) → ()
+    /// The service's enforcer keeps running its full loop, but only logs the
+    /// kills, cgroup limits and CPU throttling it would have carried out
+    async fn set_simulate_mode(&self, simulate: bool) {
+        self.simulate.store(simulate, Ordering::SeqCst);
+    }
+
+    /// ListProcesses(u: count) → (s)
+    /// The `count` heaviest processes by memory (0 = all, kernel threads left
+    /// out) as a JSON array of {"pid", "name", "memory_gb", "cpu_percentage"},
+    /// with CPU following the daemon's normalize_cpu like GetStatus
+    async fn list_processes(&self, count: u32) -> zbus::fdo::Result<String> {
+        let mut processes = monitor::get_all_processes(self.config.memory_metric, monitor::IncludeThreads::No)
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Failed to list processes: {}", e)))?;
+        if self.config.normalize_cpu {
+            monitor::normalize_process_cpu(&mut processes, monitor::cpu_count());
+        }
+        let count = if count == 0 { usize::MAX } else { count as usize };
+        let list: Vec<serde_json::Value> = processes
+            .iter()
+            .filter(|p| !p.kernel_thread)
+            .take(count)
+            .map(|p| {
+                json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "memory_gb": p.memory_gb,
+                    "cpu_percentage": p.cpu_percentage,
+                })
+            })
+            .collect();
+        Ok(serde_json::Value::from(list).to_string())
+    }
+
+    /// KillProcessesByName(s: name) → (s)
+    /// Kills every process whose name contains `name`, like `kern kill`, and returns
+    /// {"matched", "killed", "failed", "zombies"} (PID lists) as a JSON string.
+    /// Critical and protected processes are refused with AccessDenied.
+    async fn kill_processes_by_name(&self, name: &str) -> zbus::fdo::Result<String> {
+        if self.config.is_critical_process(name) {
+            return Err(zbus::fdo::Error::AccessDenied(format!("'{}' is a critical system process", name)));
+        }
+        if crate::killer::is_protected(name, &self.config.protected_processes) {
+            return Err(zbus::fdo::Error::AccessDenied(format!("'{}' is in the protected process list", name)));
+        }
+
+        let matched = crate::killer::find_processes_by_name(name);
+        let (zombies, pids): (Vec<u32>, Vec<u32>) = matched
+            .iter()
+            .copied()
+            .partition(|pid| monitor::read_proc_stat(*pid).is_some_and(|stat| stat.state == 'Z'));
+        let graceful = self.config.kill_graceful;
+        let wait = self.config.kill_graceful_wait();
+        let (killed, failed): (Vec<u32>, Vec<u32>) =
+            pids.into_iter().partition(|pid| crate::killer::kill_process(*pid, graceful, wait).is_ok());
+        for (pids, success) in [(&killed, true), (&failed, false)] {
+            for pid in pids {
+                crate::killer::log_kill_action(Some(&self.config), *pid, name, success, graceful, "manual");
+            }
+        }
+
+        let result = json!({ "matched": matched, "killed": killed, "failed": failed, "zombies": zombies });
+        Ok(result.to_string())
+    }
+
+    /// SetProfileLimit(s: profile, s: key, s: value) → (b)
+    /// Sets one limit of a profile (e.g. "max_cpu_percent" to "75") and saves it;
+    /// the service's enforcer applies it on its next cycle if the profile is active
+    async fn set_profile_limit(&self, profile: &str, key: &str, value: &str) -> zbus::fdo::Result<bool> {
+        let mut manager = self.profile_manager.write().await;
+        manager
+            .set_limit(profile, key, value)
+            .map_err(|e| zbus::fdo::Error::InvalidArgs(format!("Failed to set {}: {}", key, e)))?;
+        Ok(true)
+    }
+
+    /// AddProtectedProcess(s: name) → (b)
+    /// Protects a process in the active profile; false if it already was
+    async fn add_protected_process(&self, name: &str) -> zbus::fdo::Result<bool> {
+        let mut manager = self.profile_manager.write().await;
+        let profile = manager.current_name().to_string();
+        manager
+            .add_protected(&profile, name)
+            .map_err(|e| zbus::fdo::Error::InvalidArgs(format!("Failed to protect '{}': {}", name, e)))
+    }
+
+    /// RemoveProtectedProcess(s: name) → (b)
+    /// Stops protecting a process in the active profile; false if it wasn't protected
+    async fn remove_protected_process(&self, name: &str) -> zbus::fdo::Result<bool> {
+        let mut manager = self.profile_manager.write().await;
+        let profile = manager.current_name().to_string();
+        manager
+            .remove_protected(&profile, name)
+            .map_err(|e| zbus::fdo::Error::InvalidArgs(format!("Failed to unprotect '{}': {}", name, e)))
+    }
+
     /// GetProcessKillLog(i: limit) → (as)
-    /// Returns recent process kill events
+    /// Returns the `limit` most recent kill log lines, newest first. 0 returns
+    /// none, a negative limit is rejected and anything above 10000 is capped.
     async fn get_process_kill_log(&self, limit: i32) -> zbus::fdo::Result<Vec<String>> {
-        let limit = limit.max(0) as usize;
+        let limit = usize::try_from(limit)
+            .map_err(|_| zbus::fdo::Error::InvalidArgs(format!("limit must not be negative, got {}", limit)))?;
+        self.read_kill_log(limit.min(MAX_KILL_LOG_LINES))
+    }
+
+    /// GetProcessKillLogAll() → (as)
+    /// Returns every kill log line, newest first
+    async fn get_process_kill_log_all(&self) -> zbus::fdo::Result<Vec<String>> {
+        self.read_kill_log(usize::MAX)
+    }
+
+    /// GetVersion() → (s)
+    /// Returns kern's version, so clients can tell which methods the service has
+    async fn get_version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_string()
+    }
+}
 
-        // Read kill log from file
-        let log_file = crate::killer::get_kill_log_path();
+/// Most lines `GetProcessKillLog` returns in one call
+const MAX_KILL_LOG_LINES: usize = 10_000;
 
-        if !log_file.exists() {
+impl KernDBusInterface {
+    /// Answer a control socket request with the DBus method of the same name,
+    /// so both surfaces behave the same. JSON strings come back parsed.
+    pub async fn control(&self, request: ControlRequest) -> zbus::fdo::Result<serde_json::Value> {
+        let parse = |text: String| {
+            serde_json::from_str(&text).map_err(|e| zbus::fdo::Error::Failed(format!("Invalid JSON reply: {}", e)))
+        };
+        match request {
+            ControlRequest::GetStatus => parse(self.get_status().await?),
+            ControlRequest::GetEnforcerStatus => parse(self.get_enforcer_status().await?),
+            ControlRequest::ListProcesses { count } => parse(self.list_processes(count).await?),
+            ControlRequest::SetMode { profile } => Ok(json!(self.set_mode(&profile).await?)),
+            ControlRequest::Kill { name } => parse(self.kill_processes_by_name(&name).await?),
+            ControlRequest::GetKillLog { limit: Some(limit) } => Ok(json!(self.get_process_kill_log(limit).await?)),
+            ControlRequest::GetKillLog { limit: None } => Ok(json!(self.get_process_kill_log_all().await?)),
+            ControlRequest::Pause => {
+                self.pause_enforcement().await;
+                Ok(json!(null))
+            }
+            ControlRequest::Resume => {
+                self.resume_enforcement().await;
+                Ok(json!(null))
+            }
+        }
+    }
+
+    fn read_kill_log(&self, limit: usize) -> zbus::fdo::Result<Vec<String>> {
+        let log_file = crate::killer::get_kill_log_path(Some(&self.config));
+        if limit == 0 || !log_file.exists() {
             return Ok(Vec::new());
         }
 
         let contents = std::fs::read_to_string(&log_file)
             .map_err(|e| zbus::fdo::Error::Failed(format!("Failed to read log file: {}", e)))?;
-
-        let lines: Vec<String> = if limit == 0 {
-            contents.lines().map(|s| s.to_string()).collect()
-        } else {
-            contents
-                .lines()
-                .rev()
-                .take(limit)
-                .map(|s| s.to_string())
-                .collect()
-        };
-
-        Ok(lines)
+        Ok(contents.lines().rev().take(limit).map(str::to_string).collect())
     }
 }
 
-/// Start the DBus server
+/// Start the DBus server, running the enforcer alongside it if `enforce` is set.
+/// With `control_socket.enabled` the same interface is also served on a Unix
+/// socket, which keeps running on its own if there is no bus to connect to.
+/// A socket passed by systemd socket activation is served as the control
+/// socket whatever the config says. With a `status_file`, GetStatus's answer
+/// is also written there every `monitor_interval`.
 pub async fn start_dbus_server(
     profile_manager: ProfileManager,
     config: KernConfig,
+    enforce: bool,
+    status_file: Option<PathBuf>,
 ) -> Result<()> {
-    let kern_iface = KernDBusInterface::new(profile_manager, config);
+    let bus = config.dbus.bus;
+    let kern_iface = KernDBusInterface::new(profile_manager, config.clone());
+    let notifier = Notifier::from_env();
+
+    let listener = if let Some(&fd) = systemd::listen_fds().first() {
+        eprintln!("✅ Control socket passed in by systemd");
+        Some(control_socket::from_fd(fd)?)
+    } else if config.control_socket.enabled {
+        let path = config.control_socket.socket_path().ok_or_else(|| {
+            anyhow::anyhow!("control_socket is enabled but has no path: set control_socket.path or XDG_RUNTIME_DIR")
+        })?;
+        let listener = control_socket::bind(&path)?;
+        eprintln!("✅ Control socket listening: {}", path.display());
+        Some(listener)
+    } else {
+        None
+    };
+    let control = listener.map(|listener| tokio::spawn(control_socket::serve(listener, kern_iface.clone())));
+    if let Some(path) = status_file {
+        eprintln!("✅ Writing status to {}", path.display());
+        let interval = Duration::from_millis(config.monitor_interval);
+        tokio::spawn(write_status_file(kern_iface.clone(), path, interval));
+    }
+
+    if enforce {
+        let profile_manager = kern_iface.profile_manager.clone();
+        let status = kern_iface.enforcer_status.clone();
+        let switches = EnforcerSwitches {
+            locked: kern_iface.profile_locked.clone(),
+            paused: kern_iface.enforcement_paused.clone(),
+            simulate: kern_iface.simulate.clone(),
+        };
+        let notifier = notifier.clone();
+        std::thread::spawn(move || run_enforcer(config, profile_manager, status, switches, notifier));
+    } else if let Some(timeout) = notifier.watchdog_timeout() {
+        // Nothing to stall but the runtime itself
+        let notifier = notifier.clone();
+        tokio::spawn(async move {
+            loop {
+                notifier.watchdog();
+                tokio::time::sleep(timeout / 2).await;
+            }
+        });
+    }
 
-    let connection = Connection::session().await?;
+    let connection = match (connect(bus).await, control) {
+        (Ok(connection), _) => connection,
+        (Err(e), Some(control)) => {
+            eprintln!("⚠️  No {:?} bus ({}); serving the control socket only", bus, e);
+            ready(&notifier, enforce, "control socket only");
+            return control.await?;
+        }
+        (Err(e), None) => return Err(e.into()),
+    };
 
     connection
         .object_server()
-        .at("/org/gnome/Shell/Extensions/Kern", kern_iface)
+        .at(OBJECT_PATH, kern_iface)
         .await?;
 
-    connection
-        .request_name("org.gnome.Shell.Extensions.Kern")
-        .await?;
+    if let Err(e) = connection.request_name(SERVICE_NAME).await {
+        return Err(match bus {
+            DbusBus::System => anyhow::anyhow!(
+                "The system bus refused {} ({}). Install config/dbus/{}.conf into \
+                 /etc/dbus-1/system.d/ and run kern as root, or set dbus.bus: session",
+                SERVICE_NAME, e, SERVICE_NAME
+            ),
+            DbusBus::Session => anyhow::anyhow!("Could not own {} on the session bus: {}", SERVICE_NAME, e),
+        });
+    }
 
-    eprintln!("✅ DBus server started: org.gnome.Shell.Extensions.Kern");
+    eprintln!("✅ DBus server started: {} ({:?} bus)", SERVICE_NAME, bus);
+    ready(&notifier, enforce, &format!("serving {} on the {:?} bus", SERVICE_NAME, bus));
 
-    // Keep the connection alive
+    // Keep the connection alive, announcing property changes so clients
+    // can listen for PropertiesChanged instead of polling
+    let iface_ref = connection
+        .object_server()
+        .interface::<_, KernDBusInterface>(OBJECT_PATH)
+        .await?;
+    let mut published = {
+        let iface = iface_ref.get().await;
+        (iface.current_mode().await, iface.emergency_mode().await)
+    };
     loop {
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+        let iface = iface_ref.get().await;
+        let (mode, emergency) = (iface.current_mode().await, iface.emergency_mode().await);
+        if mode != published.0 {
+            iface.current_mode_changed(iface_ref.signal_context()).await?;
+        }
+        if emergency != published.1 {
+            iface.emergency_mode_changed(iface_ref.signal_context()).await?;
+        }
+        published = (mode, emergency);
+    }
+}
+
+// `kern dbus --status-file`: a failed write is reported and retried next interval
+async fn write_status_file(iface: KernDBusInterface, path: PathBuf, interval: Duration) {
+    loop {
+        let written = match iface.control(ControlRequest::GetStatus).await {
+            Ok(status) => output::write_output_file(&path, status).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = written {
+            eprintln!("⚠️  Status file not updated: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// Tell systemd the service is up. When enforcing, the enforcer thread reports
+// its own status every cycle.
+fn ready(notifier: &Notifier, enforce: bool, serving: &str) {
+    if !enforce {
+        notifier.status(&format!("Not enforcing; {}", serving));
+    }
+    notifier.ready();
+}
+
+// What SetProfileLock, PauseEnforcement and SetSimulateMode set, read by the enforcer every cycle
+struct EnforcerSwitches {
+    locked: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    simulate: Arc<AtomicBool>,
+}
+
+// Enforce the active profile (following SetMode and runtime edits) until the process exits,
+// publishing the enforcer's state for GetEnforcerStatus and systemd. While paused the
+// profile is enforced as monitor-only.
+fn run_enforcer(
+    config: KernConfig,
+    profile_manager: Arc<RwLock<ProfileManager>>,
+    status: SharedEnforcerStatus,
+    switches: EnforcerSwitches,
+    notifier: Notifier,
+) {
+    // Profiles are keyed by file name, which can differ from Profile::name
+    let (mut active, initial_profile) = {
+        let manager = profile_manager.blocking_read();
+        (manager.current_name().to_string(), manager.current().cloned().unwrap_or_default())
+    };
+    let mut enforcer = Enforcer::new(config.clone(), initial_profile);
+    let logger = Logger::stderr(LogLevel::Info).shared();
+
+    let (observed, event_logger) = (status.clone(), logger.clone());
+    enforcer.set_observer(move |event: &EnforcerEvent| {
+        if let Ok(mut status) = observed.lock() {
+            status.record(event);
+        }
+        if let Ok(mut logger) = event_logger.lock() {
+            logger.write(event.level(), &event.to_string());
+        }
+    });
+    enforcer.set_watchdog(notifier.clone());
+    enforcer.restore_cpu_frequency();
+    let mut idle_switcher = IdleSwitcher::from_profiles(&profile_manager.blocking_read());
+    let mut idle_unavailable_reported = false;
+
+    let interval = Duration::from_millis(config.monitor_interval);
+    loop {
+        enforcer.set_profile_lock(switches.locked.load(Ordering::SeqCst));
+        enforcer.set_simulate(switches.simulate.load(Ordering::SeqCst));
+        // Idle triggers switch through the profile manager, so GetStatus and SetMode see them
+        if !idle_switcher.is_empty() && !enforcer.profile_locked() {
+            let idle_time = idle::session_idle_time();
+            if let Some(switch) = idle_switcher.update(idle_time, &active, enforcer.profile()) {
+                if let Ok(mut logger) = logger.lock() {
+                    logger.write(LogLevel::Info, &switch.to_string());
+                }
+                let _ = profile_manager.blocking_write().switch_to(&switch.key);
+            } else if idle_time.is_none() && !idle_unavailable_reported {
+                idle_unavailable_reported = true;
+                if let Ok(mut logger) = logger.lock() {
+                    logger.write(LogLevel::Warn, "Session idle time unavailable; idle triggers skipped");
+                }
+            }
+        }
+        let current = {
+            let manager = profile_manager.blocking_read();
+            manager.current().ok().map(|profile| (manager.current_name().to_string(), profile.clone()))
+        };
+        let current = current.map(|(name, mut profile)| {
+            profile.enforce &= !switches.paused.load(Ordering::SeqCst);
+            (name, profile)
+        });
+        match current {
+            // Locked: ignore the switch and keep enforcing the active profile
+            Some((name, _)) if name != active && enforcer.profile_locked() => {}
+            Some((name, profile)) if name != active => {
+                active = name;
+                let _ = enforcer.switch_profile(profile);
+            }
+            Some((_, profile)) if profile != *enforcer.profile() => enforcer.update_profile(profile),
+            _ => {}
+        }
+
+        if let Err(e) = enforcer.enforce_once() {
+            if let Ok(mut logger) = logger.lock() {
+                logger.write(LogLevel::Error, &format!("Enforcer error: {}", e));
+            }
+        }
+        if let Ok(mut status) = status.lock() {
+            status.sync(&enforcer);
+        }
+        notifier.status(&enforcer.status_line());
+
+        notifier.sleep(interval);
+    }
+}
+
+/// Connect to the session or system bus
+pub async fn connect(bus: DbusBus) -> zbus::Result<Connection> {
+    match bus {
+        DbusBus::Session => Connection::session().await,
+        DbusBus::System => Connection::system().await,
     }
 }
 
@@ -188,6 +652,154 @@ limits:
         assert!(!iface.profile_manager.read().await.list_names().is_empty());
     }
 
+    #[tokio::test]
+    async fn test_get_enforcer_status_without_enforcer() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+
+        let status: serde_json::Value =
+            serde_json::from_str(&iface.get_enforcer_status().await.unwrap()).unwrap();
+        assert_eq!(status["running"], false);
+        assert_eq!(status["emergency_mode"], false);
+        assert_eq!(status["active_profile"], "test");
+        assert!(status["last_action"].is_null());
+        assert_eq!(status["actions_last_hour"], 0);
+        assert_eq!(iface.current_mode().await, "test");
+        assert!(!iface.emergency_mode().await);
+    }
+
+    #[tokio::test]
+    async fn test_set_profile_limit_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+
+        assert!(iface.set_profile_limit("test", "max_cpu_percent", "50").await.unwrap());
+        let error = iface.set_profile_limit("test", "max_ram_percent", "150").await.unwrap_err();
+        assert!(error.to_string().contains("must be 0-100"), "{}", error);
+
+        assert!(iface.add_protected_process("obs").await.unwrap());
+        assert!(!iface.add_protected_process("obs").await.unwrap());
+
+        // In memory and on disk
+        {
+            let manager = iface.profile_manager.read().await;
+            let profile = manager.get("test").unwrap();
+            assert_eq!(profile.limits.max_cpu_percent, 50.0);
+            assert_eq!(profile.limits.max_ram_percent, 85.0);
+            assert_eq!(profile.protected, vec!["obs".to_string()]);
+        }
+        let reloaded = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        assert_eq!(reloaded.get("test").unwrap().limits.max_cpu_percent, 50.0);
+        assert_eq!(reloaded.get("test").unwrap().protected, vec!["obs".to_string()]);
+
+        assert!(iface.remove_protected_process("obs").await.unwrap());
+        assert!(iface.profile_manager.read().await.get("test").unwrap().protected.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_process_kill_log_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+        let log_path = temp_dir.path().join("kern.log");
+        let lines: Vec<String> = (0..MAX_KILL_LOG_LINES + 5).map(|i| format!("kill {}", i)).collect();
+        std::fs::write(&log_path, lines.join("\n")).unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        let config = KernConfig { kill_log_path: Some(log_path), ..Default::default() };
+        let iface = KernDBusInterface::new(profile_manager, config);
+
+        assert!(iface.get_process_kill_log(0).await.unwrap().is_empty());
+        assert_eq!(iface.get_process_kill_log(2).await.unwrap(), vec!["kill 10004", "kill 10003"]);
+
+        let error = iface.get_process_kill_log(-1).await.unwrap_err();
+        assert!(matches!(error, zbus::fdo::Error::InvalidArgs(_)), "{}", error);
+
+        let capped = iface.get_process_kill_log(i32::MAX).await.unwrap();
+        assert_eq!(capped.len(), MAX_KILL_LOG_LINES);
+        assert_eq!(capped[0], "kill 10004");
+
+        let all = iface.get_process_kill_log_all().await.unwrap();
+        assert_eq!(all.len(), MAX_KILL_LOG_LINES + 5);
+        assert_eq!(all.last().unwrap(), "kill 0");
+    }
+
+    #[tokio::test]
+    async fn test_profile_lock_blocks_set_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+        std::fs::write(profiles_dir.join("other.yaml"), "name: other\ndescription: Other profile\n").unwrap();
+
+        let mut profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        profile_manager.switch_to("test").unwrap();
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+
+        iface.set_profile_lock(true).await;
+        let error = iface.set_mode("other").await.unwrap_err();
+        assert!(matches!(error, zbus::fdo::Error::AccessDenied(_)), "{}", error);
+        assert_eq!(iface.get_current_mode().await.unwrap(), "test");
+        let status: serde_json::Value =
+            serde_json::from_str(&iface.get_enforcer_status().await.unwrap()).unwrap();
+        assert_eq!(status["profile_locked"], true);
+
+        iface.set_profile_lock(false).await;
+        assert!(iface.set_mode("other").await.unwrap());
+        assert_eq!(iface.get_current_mode().await.unwrap(), "other");
+    }
+
+    #[tokio::test]
+    async fn test_set_simulate_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+        let simulate = || async {
+            let status: serde_json::Value =
+                serde_json::from_str(&iface.get_enforcer_status().await.unwrap()).unwrap();
+            status["simulate"].clone()
+        };
+
+        assert_eq!(simulate().await, false);
+        iface.set_simulate_mode(true).await;
+        assert_eq!(simulate().await, true);
+        iface.set_simulate_mode(false).await;
+        assert_eq!(simulate().await, false);
+    }
+
+    #[tokio::test]
+    async fn test_get_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(profiles_dir.join("test.yaml"), "name: test\ndescription: Test profile\n").unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(temp_dir.path().to_path_buf())).expect("Failed to create PM");
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+        assert_eq!(iface.get_version().await, env!("CARGO_PKG_VERSION"));
+    }
+
     #[tokio::test]
     async fn test_get_current_mode() {
         let temp_dir = TempDir::new().unwrap();
@@ -346,5 +958,32 @@ description: "Test profile"
         assert!(parsed.get("memory_percentage").is_some());
         assert!(parsed.get("temperature").is_some());
         assert!(parsed.get("top_processes").is_some());
+        assert!(parsed.get("top_cpu_processes").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_status_summary_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path();
+
+        let profiles_dir = config_path.join("profiles");
+        std::fs::create_dir_all(&profiles_dir).unwrap();
+        std::fs::write(
+            profiles_dir.join("test.yaml"),
+            "name: \"test\"\ndescription: \"Test profile\"\n",
+        )
+        .unwrap();
+
+        let profile_manager =
+            ProfileManager::new(Some(config_path.to_path_buf())).expect("Failed to create PM");
+        let iface = KernDBusInterface::new(profile_manager, KernConfig::default());
+
+        let summary_json = iface.get_status_summary().await.unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&summary_json).unwrap();
+        assert!(parsed["cpu_usage"].is_number());
+        assert!(parsed["memory_percentage"].is_number());
+        assert!(parsed["temperature"].is_number());
+        assert!(parsed.get("top_processes").is_none());
+        assert!(parsed.get("top_cpu_processes").is_none());
     }
 }
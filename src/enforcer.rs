@@ -1,9 +1,716 @@
-use std::time::{Duration, Instant};
-use crate::monitor::{get_system_stats, SystemStats};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::monitor::{self, descending, get_system_stats, group_processes, sort_groups, IoSampler, ProcessInfo, ProcessSort, SystemStats};
 use crate::killer;
-use crate::config::KernConfig;
-use crate::profiles::Profile;
+use crate::config::{EmergencyAction, EnforcementMode, KernConfig, MultiBreach, ScoreWeights, VictimWeights};
+use crate::cgroups::CgroupPlacement;
+use crate::idle::{self, IdleSwitcher};
+use crate::profiles::{KillStrategy, LimitAction, Profile};
+use crate::restart::{self, RestartCommand, RestartQueue, RestartSkip};
 use crate::notify::NotificationManager;
+use crate::throttle::CpuThrottle;
+use crate::log::{LogLevel, Logger, SharedLogger};
+
+/// Why the enforcer decided to kill a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillReason {
+    CpuLimit,
+    RamLimit,
+    MemoryPressure,
+    TemperatureWarning,
+    Emergency,
+    ProfileActivation,
+    IoLimit,
+    SwapEmergency,
+}
+
+impl KillReason {
+    /// Strategy used when the profile doesn't override it: target the
+    /// process consuming the resource that actually tripped the limit
+    pub fn default_strategy(&self) -> KillStrategy {
+        match self {
+            KillReason::RamLimit | KillReason::MemoryPressure | KillReason::SwapEmergency => KillStrategy::HeaviestMemory,
+            KillReason::CpuLimit
+            | KillReason::TemperatureWarning
+            | KillReason::Emergency
+            | KillReason::ProfileActivation => KillStrategy::HeaviestCpu,
+            KillReason::IoLimit => KillStrategy::HeaviestIo,
+        }
+    }
+
+    /// The `victim_weights` entry for the resource behind this reason, used by
+    /// `KillStrategy::Weighted`
+    pub fn victim_weights(&self, weights: &VictimWeights) -> ScoreWeights {
+        match self {
+            KillReason::RamLimit | KillReason::MemoryPressure | KillReason::SwapEmergency => weights.memory,
+            KillReason::TemperatureWarning | KillReason::Emergency => weights.temperature,
+            KillReason::CpuLimit | KillReason::ProfileActivation => weights.cpu,
+            // Only processes over the I/O limit are candidates; CPU and memory weigh the same
+            KillReason::IoLimit => ScoreWeights::EQUAL,
+        }
+    }
+}
+
+impl fmt::Display for KillReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            KillReason::CpuLimit => "CPU limit",
+            KillReason::RamLimit => "RAM limit",
+            KillReason::MemoryPressure => "memory pressure",
+            KillReason::TemperatureWarning => "temperature warning",
+            KillReason::Emergency => "emergency mode",
+            KillReason::ProfileActivation => "profile activation",
+            KillReason::IoLimit => "disk I/O limit",
+            KillReason::SwapEmergency => "memory emergency",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// A single kill attempted during an enforcement cycle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KillRecord {
+    pub pid: u32,
+    pub name: String,
+    pub reason: KillReason,
+    pub strategy: KillStrategy,
+    pub success: bool,
+}
+
+impl KillRecord {
+    /// Human-readable reason, e.g. "CPU limit, highest CPU consumer"
+    pub fn describe(&self) -> String {
+        format!("{}, {}", self.reason, self.strategy.describe())
+    }
+}
+
+/// A step simulate mode held back (see `Enforcer::set_simulate`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulatedAction {
+    Kill(KillRecord),
+    Limit { pid: u32, name: String, reason: KillReason },
+    ThrottleCpu,
+}
+
+/// Everything the enforcer did during one call to `enforce_once`
+#[derive(Debug, Clone, Default)]
+pub struct EnforcementReport {
+    pub kills: Vec<KillRecord>,
+    pub would_kill: Vec<KillRecord>, // Alert-only mode: kills that were held back (success is always false)
+    pub would_limit: Vec<u32>,       // Alert-only mode: PIDs a cgroup_limit profile would have limited
+    pub critical_samples: u32, // Consecutive samples above the critical temperature
+    pub cool_samples: u32,     // Consecutive samples below the warning temperature (in emergency)
+    pub cpu_throttled: bool,   // CPU frequency was capped during this cycle
+    pub cgroup_limited: Vec<u32>, // PIDs moved into the limited cgroup during this cycle
+    pub skipped: SkipCounts, // Kill candidates passed over during this cycle, by reason
+}
+
+// cgroup limits are lifted once CPU, RAM and memory pressure are below this fraction of the profile limits
+const CGROUP_RELEASE_RATIO: f64 = 0.8;
+
+/// Emergency mode change caused by a temperature sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmergencyTransition {
+    Entered,
+    Exited,
+}
+
+impl EnforcementReport {
+    /// True if at least one process was successfully killed
+    pub fn action_taken(&self) -> bool {
+        self.kills.iter().any(|k| k.success)
+    }
+}
+
+/// Build a structured enforcer event for `--json` output
+pub fn json_event(kind: &str, fields: serde_json::Value) -> serde_json::Value {
+    let mut event = json!({
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "event": kind,
+    });
+    if let (Some(event), serde_json::Value::Object(fields)) = (event.as_object_mut(), fields) {
+        event.extend(fields);
+    }
+    event
+}
+
+/// Resource whose limit was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitResource {
+    Cpu,
+    Ram,
+    MemoryPressure,
+    Temperature,
+}
+
+/// Why a kill was deliberately not carried out
+#[derive(Debug, Clone, PartialEq)]
+pub enum Skip {
+    /// The profile is monitor-only
+    MonitorOnly { profile: String, reason: KillReason },
+    /// kill_on_activate named a critical process
+    Critical { name: String },
+    /// kill_on_activate named a protected process
+    Protected { name: String },
+}
+
+/// Why a kill candidate was passed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Protected,
+    ProtectedAncestor, // A parent, or an ancestor within ancestry_depth, is protected
+    Critical,
+    PermissionDenied, // EPERM this cycle or within PERMISSION_DENIED_COOLDOWN
+    Unkillable,       // Survived an earlier kill
+    KillFailed,       // Signalling failed this cycle for another reason
+    Zombie,
+    KernelThread,
+    OtherUser, // Owned by someone other than kern's user, without enforcement.all_users
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            SkipReason::Protected => "protected",
+            SkipReason::ProtectedAncestor => "child of a protected process",
+            SkipReason::Critical => "critical",
+            SkipReason::PermissionDenied => "permission denied",
+            SkipReason::Unkillable => "unkillable",
+            SkipReason::KillFailed => "kill failed",
+            SkipReason::Zombie => "zombie",
+            SkipReason::KernelThread => "kernel thread",
+            SkipReason::OtherUser => "other user",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Kill candidates passed over, counted by reason. Displays as e.g.
+/// "4 protected, 2 critical, 1 permission denied".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct SkipCounts(BTreeMap<SkipReason, usize>);
+
+impl SkipCounts {
+    pub fn add(&mut self, reason: SkipReason) {
+        *self.0.entry(reason).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, reason: SkipReason) -> usize {
+        self.0.get(&reason).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn merge(&mut self, other: &SkipCounts) {
+        for (reason, count) in &other.0 {
+            *self.0.entry(*reason).or_insert(0) += count;
+        }
+    }
+}
+
+impl fmt::Display for SkipCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no candidates");
+        }
+        let parts: Vec<String> = self.0.iter().map(|(reason, count)| format!("{} {}", count, reason)).collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Everything the enforcer reports, in the order it happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnforcerEvent {
+    /// `used` is in the resource's own unit (cores, GB, °C); `cap` is set when
+    /// max_cpu_cores / max_ram_gb is stricter than the percentage
+    LimitExceeded { resource: LimitResource, value: f64, limit: f64, used: f64, cap: Option<f64> },
+    /// A single process reads and writes more than the profile's max_io_mbps
+    IoLimitExceeded { pid: u32, name: String, read_mbps: f64, write_mbps: f64, limit: f64 },
+    /// An absolute profile limit this machine can never reach
+    LimitUnreachable { profile: String, warning: String },
+    ProcessKilled(KillRecord),
+    KillFailed { record: KillRecord, error: String },
+    /// Still running several cycles after a kill; no longer targeted
+    ProcessUnkillable { pid: u32, name: String, cycles: u32 },
+    /// Processes currently skipped because kern may not signal them (EPERM)
+    PermissionDenied { processes: usize },
+    /// Periodic summary (report_interval_secs), whether or not anything happened
+    StatusReport { cpu: f64, ram: f64, temperature: f64, profile: String, kills: usize },
+    ProcessLimited { pid: u32, name: String, reason: KillReason, cpu_percent: f64, memory_high_bytes: Option<u64> },
+    CgroupLimitFailed { pid: u32, name: String, error: String },
+    CgroupLimitReleased { pid: u32 },
+    CgroupReleaseFailed { pid: u32, error: String },
+    EmergencyEntered { temperature: f64, critical: f64, warning: f64 },
+    EmergencyExited { temperature: f64 },
+    /// Swap usage over swap_emergency.threshold_percent: the system is thrashing
+    SwapEmergencyEntered { swap_percentage: f64, threshold: f64 },
+    SwapEmergencyExited { swap_percentage: f64 },
+    /// Emergency temperature under a monitor-only profile
+    EmergencyMonitorOnly { temperature: f64, critical: f64 },
+    /// Emergency temperature in alert-only mode, with the processes that would have been killed
+    EmergencyAlert { temperature: f64, critical: f64, would_kill: Vec<String> },
+    /// Alert-only mode: the kill this process would have got
+    WouldKill(KillRecord),
+    /// Alert-only mode: the cgroup limit this process would have got
+    WouldLimit { pid: u32, name: String, reason: KillReason },
+    /// Simulate mode: a kill, cgroup limit or CPU throttle that was decided but not carried out
+    Simulated(SimulatedAction),
+    EmergencyKillCapReached { kills: usize },
+    /// kill_count_limit reached: no more kills this run
+    KillLimitReached { kills: usize },
+    CpuThrottled { cpus: usize },
+    ThrottleFailed { error: String },
+    CpuThrottleRestored { cpus: usize },
+    ThrottleRestoreFailed { error: String },
+    ProfileSwitched { from: String, to: String },
+    Skipped(Skip),
+    /// A limit (or emergency mode) called for a kill, but every candidate was skipped
+    NothingKillable { reason: KillReason, skipped: SkipCounts },
+    /// A process in the profile's restart_after_kill was relaunched after conditions settled
+    ProcessRestarted { name: String, pid: u32, command: String },
+    RestartSkipped { name: String, reason: RestartSkip },
+    RestartFailed { name: String, error: String },
+    /// `run_enforcer_loop` stopped: what ended it, how long it ran and what it did
+    SessionEnded { stop: StopReason, duration_secs: u64, cycles: u64, kills: usize },
+    /// An enforcement cycle failed
+    Error { message: String },
+}
+
+impl EnforcerEvent {
+    /// Event name used in `--json` output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EnforcerEvent::LimitExceeded { .. } => "limit_exceeded",
+            EnforcerEvent::IoLimitExceeded { .. } => "io_limit_exceeded",
+            EnforcerEvent::LimitUnreachable { .. } => "limit_unreachable",
+            EnforcerEvent::ProcessKilled(_) => "process_killed",
+            EnforcerEvent::KillFailed { .. } => "kill_failed",
+            EnforcerEvent::ProcessUnkillable { .. } => "process_unkillable",
+            EnforcerEvent::PermissionDenied { .. } => "permission_denied",
+            EnforcerEvent::StatusReport { .. } => "status_report",
+            EnforcerEvent::ProcessLimited { .. } => "process_limited",
+            EnforcerEvent::CgroupLimitFailed { .. } => "cgroup_limit_failed",
+            EnforcerEvent::CgroupLimitReleased { .. } => "cgroup_limit_released",
+            EnforcerEvent::CgroupReleaseFailed { .. } => "cgroup_release_failed",
+            EnforcerEvent::EmergencyEntered { .. } => "emergency_activated",
+            EnforcerEvent::EmergencyExited { .. } => "emergency_resolved",
+            EnforcerEvent::SwapEmergencyEntered { .. } => "swap_emergency_activated",
+            EnforcerEvent::SwapEmergencyExited { .. } => "swap_emergency_resolved",
+            EnforcerEvent::EmergencyMonitorOnly { .. } => "emergency_monitor_only",
+            EnforcerEvent::EmergencyAlert { .. } => "emergency_alert",
+            EnforcerEvent::WouldKill(_) => "would_kill",
+            EnforcerEvent::WouldLimit { .. } => "would_limit",
+            EnforcerEvent::Simulated(_) => "simulated",
+            EnforcerEvent::EmergencyKillCapReached { .. } => "emergency_kill_cap_reached",
+            EnforcerEvent::KillLimitReached { .. } => "kill_limit_reached",
+            EnforcerEvent::CpuThrottled { .. } => "cpu_throttled",
+            EnforcerEvent::ThrottleFailed { .. } => "throttle_failed",
+            EnforcerEvent::CpuThrottleRestored { .. } => "cpu_throttle_restored",
+            EnforcerEvent::ThrottleRestoreFailed { .. } => "throttle_restore_failed",
+            EnforcerEvent::ProfileSwitched { .. } => "profile_switched",
+            EnforcerEvent::Skipped(_) => "kill_skipped",
+            EnforcerEvent::NothingKillable { .. } => "nothing_killable",
+            EnforcerEvent::ProcessRestarted { .. } => "process_restarted",
+            EnforcerEvent::RestartSkipped { .. } => "restart_skipped",
+            EnforcerEvent::RestartFailed { .. } => "restart_failed",
+            EnforcerEvent::SessionEnded { .. } => "session_ended",
+            EnforcerEvent::Error { .. } => "error",
+        }
+    }
+
+    /// Log level of the human-readable message
+    pub fn level(&self) -> LogLevel {
+        match self {
+            EnforcerEvent::KillFailed { .. }
+            | EnforcerEvent::ThrottleFailed { .. }
+            | EnforcerEvent::ThrottleRestoreFailed { .. }
+            | EnforcerEvent::CgroupLimitFailed { .. }
+            | EnforcerEvent::CgroupReleaseFailed { .. }
+            | EnforcerEvent::RestartFailed { .. }
+            | EnforcerEvent::Error { .. } => LogLevel::Error,
+            EnforcerEvent::LimitExceeded { .. }
+            | EnforcerEvent::IoLimitExceeded { .. }
+            | EnforcerEvent::EmergencyEntered { .. }
+            | EnforcerEvent::SwapEmergencyEntered { .. }
+            | EnforcerEvent::EmergencyMonitorOnly { .. }
+            | EnforcerEvent::EmergencyAlert { .. }
+            | EnforcerEvent::WouldKill(_)
+            | EnforcerEvent::WouldLimit { .. }
+            | EnforcerEvent::EmergencyKillCapReached { .. }
+            | EnforcerEvent::KillLimitReached { .. }
+            | EnforcerEvent::LimitUnreachable { .. }
+            | EnforcerEvent::ProcessUnkillable { .. }
+            | EnforcerEvent::PermissionDenied { .. }
+            | EnforcerEvent::NothingKillable { .. }
+            | EnforcerEvent::RestartSkipped { .. } => LogLevel::Warn,
+            _ => LogLevel::Info,
+        }
+    }
+
+    /// The event as one `--json` line (see `json_event`)
+    pub fn to_json(&self) -> serde_json::Value {
+        let fields = match self {
+            EnforcerEvent::LimitExceeded { resource, value, limit, used, cap } => {
+                let mut fields = json!({ "resource": resource, "value": value, "limit": limit });
+                let extra = match resource {
+                    LimitResource::Cpu => Some(json!({ "cores": used, "max_cpu_cores": cap })),
+                    LimitResource::Ram => Some(json!({ "used_gb": used, "max_ram_gb": cap })),
+                    LimitResource::MemoryPressure | LimitResource::Temperature => None,
+                };
+                if let (Some(fields), Some(serde_json::Value::Object(extra))) = (fields.as_object_mut(), extra) {
+                    fields.extend(extra);
+                }
+                fields
+            }
+            EnforcerEvent::IoLimitExceeded { pid, name, read_mbps, write_mbps, limit } => json!({
+                "pid": pid, "name": name, "read_mbps": read_mbps, "write_mbps": write_mbps, "limit": limit,
+            }),
+            EnforcerEvent::LimitUnreachable { profile, warning } => json!({ "profile": profile, "warning": warning }),
+            EnforcerEvent::ProcessKilled(record) => serde_json::to_value(record).unwrap_or_else(|_| json!({})),
+            EnforcerEvent::KillFailed { record, error } => {
+                let mut fields = serde_json::to_value(record).unwrap_or_else(|_| json!({}));
+                if let Some(fields) = fields.as_object_mut() {
+                    fields.insert("error".to_string(), json!(error));
+                }
+                fields
+            }
+            EnforcerEvent::ProcessUnkillable { pid, name, cycles } => json!({ "pid": pid, "name": name, "cycles": cycles }),
+            EnforcerEvent::PermissionDenied { processes } => json!({ "processes": processes }),
+            EnforcerEvent::StatusReport { cpu, ram, temperature, profile, kills } => json!({
+                "cpu_usage": cpu, "memory_percentage": ram, "temperature": temperature, "profile": profile, "kills": kills,
+            }),
+            EnforcerEvent::ProcessLimited { pid, name, reason, cpu_percent, memory_high_bytes } => json!({
+                "pid": pid, "name": name, "reason": reason, "cpu_percent": cpu_percent, "memory_high_bytes": memory_high_bytes,
+            }),
+            EnforcerEvent::CgroupLimitFailed { pid, name, error } => json!({ "pid": pid, "name": name, "error": error }),
+            EnforcerEvent::CgroupLimitReleased { pid } => json!({ "pid": pid }),
+            EnforcerEvent::CgroupReleaseFailed { pid, error } => json!({ "pid": pid, "error": error }),
+            EnforcerEvent::EmergencyEntered { temperature, critical, warning } => {
+                json!({ "temperature": temperature, "critical": critical, "warning": warning })
+            }
+            EnforcerEvent::EmergencyExited { temperature } => json!({ "temperature": temperature }),
+            EnforcerEvent::SwapEmergencyEntered { swap_percentage, threshold } => {
+                json!({ "swap_percentage": swap_percentage, "threshold": threshold })
+            }
+            EnforcerEvent::SwapEmergencyExited { swap_percentage } => json!({ "swap_percentage": swap_percentage }),
+            EnforcerEvent::EmergencyMonitorOnly { temperature, critical } => json!({ "temperature": temperature, "critical": critical }),
+            EnforcerEvent::EmergencyAlert { temperature, critical, would_kill } => {
+                json!({ "temperature": temperature, "critical": critical, "would_kill": would_kill })
+            }
+            EnforcerEvent::WouldKill(record) => serde_json::to_value(record).unwrap_or_else(|_| json!({})),
+            EnforcerEvent::WouldLimit { pid, name, reason } => json!({ "pid": pid, "name": name, "reason": reason }),
+            EnforcerEvent::Simulated(SimulatedAction::Kill(record)) => {
+                let mut fields = serde_json::to_value(record).unwrap_or_else(|_| json!({}));
+                if let Some(fields) = fields.as_object_mut() {
+                    fields.insert("action".to_string(), json!("kill"));
+                }
+                fields
+            }
+            EnforcerEvent::Simulated(SimulatedAction::Limit { pid, name, reason }) => {
+                json!({ "action": "limit", "pid": pid, "name": name, "reason": reason })
+            }
+            EnforcerEvent::Simulated(SimulatedAction::ThrottleCpu) => json!({ "action": "throttle_cpu" }),
+            EnforcerEvent::EmergencyKillCapReached { kills } | EnforcerEvent::KillLimitReached { kills } => {
+                json!({ "kills": kills })
+            }
+            EnforcerEvent::CpuThrottled { cpus } | EnforcerEvent::CpuThrottleRestored { cpus } => json!({ "cpus": cpus }),
+            EnforcerEvent::ThrottleFailed { error } | EnforcerEvent::ThrottleRestoreFailed { error } => json!({ "error": error }),
+            EnforcerEvent::ProfileSwitched { from, to } => json!({ "from": from, "to": to }),
+            EnforcerEvent::Skipped(Skip::MonitorOnly { profile, reason }) => json!({ "reason": reason, "profile": profile }),
+            EnforcerEvent::Skipped(Skip::Critical { name }) => json!({ "name": name, "skip": "critical" }),
+            EnforcerEvent::Skipped(Skip::Protected { name }) => json!({ "name": name, "skip": "protected" }),
+            EnforcerEvent::NothingKillable { reason, skipped } => {
+                json!({ "reason": reason, "candidates": skipped.total(), "skipped": skipped })
+            }
+            EnforcerEvent::ProcessRestarted { name, pid, command } => json!({ "name": name, "pid": pid, "command": command }),
+            EnforcerEvent::RestartSkipped { name, reason } => json!({ "name": name, "skip": reason }),
+            EnforcerEvent::RestartFailed { name, error } => json!({ "name": name, "error": error }),
+            EnforcerEvent::SessionEnded { stop, duration_secs, cycles, kills } => {
+                json!({ "stop": stop, "duration_secs": duration_secs, "cycles": cycles, "kills": kills })
+            }
+            EnforcerEvent::Error { message } => json!({ "message": message }),
+        };
+        json_event(self.kind(), fields)
+    }
+}
+
+/// The human-readable message printed for the event
+impl fmt::Display for EnforcerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnforcerEvent::LimitExceeded { resource: LimitResource::Cpu, value, limit, used, cap } => {
+                write!(f, "⚠️  CPU limit exceeded: {:.1}% > {:.1}%", value, limit)?;
+                match cap {
+                    Some(max) => write!(f, " ({:.1} cores > {} cores)", used, max),
+                    None => Ok(()),
+                }
+            }
+            EnforcerEvent::LimitExceeded { resource: LimitResource::Ram, value, limit, used, cap } => {
+                write!(f, "⚠️  RAM limit exceeded: {:.1}% > {:.1}%", value, limit)?;
+                match cap {
+                    Some(max) => write!(f, " ({:.1} GB > {} GB)", used, max),
+                    None => Ok(()),
+                }
+            }
+            EnforcerEvent::LimitExceeded { resource: LimitResource::MemoryPressure, value, limit, .. } => {
+                write!(f, "⚠️  Memory pressure limit exceeded: stalled {:.1}% > {:.1}%", value, limit)
+            }
+            EnforcerEvent::LimitExceeded { resource: LimitResource::Temperature, value, limit, .. } => {
+                write!(f, "🟡 Temperature warning: {:.1}°C > {:.1}°C", value, limit)
+            }
+            EnforcerEvent::IoLimitExceeded { pid, name, read_mbps, write_mbps, limit } => write!(
+                f,
+                "⚠️  Disk I/O limit exceeded: {} (PID: {}) {:.1} MB/s > {:.1} MB/s (read {:.1}, write {:.1})",
+                name,
+                pid,
+                read_mbps + write_mbps,
+                limit,
+                read_mbps,
+                write_mbps
+            ),
+            EnforcerEvent::LimitUnreachable { profile, warning } => write!(f, "⚠️  Profile '{}': {}", profile, warning),
+            EnforcerEvent::ProcessKilled(record) => match record.reason {
+                KillReason::Emergency => write!(f, "  ⚠️  Killed {} (PID: {}) - {}", record.name, record.pid, record.describe()),
+                KillReason::ProfileActivation => write!(f, "  Killed {} (PID: {}) on profile activation", record.name, record.pid),
+                _ => write!(f, "  ✓ Killed {} (PID: {}) - {}", record.name, record.pid, record.describe()),
+            },
+            EnforcerEvent::KillFailed { record, error } => {
+                write!(f, "  Failed to kill {} (PID: {}): {}", record.name, record.pid, error)
+            }
+            EnforcerEvent::ProcessUnkillable { pid, name, cycles } => write!(
+                f,
+                "  ⚠️  Unkillable process {} (PID: {}) still running {} cycles after being killed - no longer targeting it",
+                name, pid, cycles
+            ),
+            EnforcerEvent::PermissionDenied { processes } => write!(
+                f,
+                "⚠️  {} {} skipped due to permissions - consider running the enforcer as root or granting CAP_KILL",
+                processes,
+                if *processes == 1 { "process" } else { "processes" }
+            ),
+            EnforcerEvent::StatusReport { cpu, ram, temperature, profile, kills } => write!(
+                f,
+                "[{}] CPU: {:.0}% | RAM: {:.0}% | Temp: {:.0}°C | Mode: {} | Kills: {}",
+                chrono::Local::now().format("%H:%M:%S"),
+                cpu, ram, temperature, profile, kills
+            ),
+            EnforcerEvent::ProcessLimited { pid, name, reason, cpu_percent, .. } => {
+                write!(f, "  🐢 Limited {} (PID: {}) to {}% CPU - {}", name, pid, cpu_percent, reason)
+            }
+            EnforcerEvent::CgroupLimitFailed { pid, name, error } => {
+                write!(f, "  Failed to limit {} (PID: {}), not killing it: {}", name, pid, error)
+            }
+            EnforcerEvent::CgroupLimitReleased { pid } => write!(f, "  Lifted cgroup limit on PID {}", pid),
+            EnforcerEvent::CgroupReleaseFailed { pid, error } => write!(f, "  Failed to lift cgroup limit on PID {}: {}", pid, error),
+            EnforcerEvent::EmergencyEntered { temperature, critical, .. } => write!(
+                f,
+                "🔴 EMERGENCY MODE ACTIVATED - Temperature {:.1}°C > {:.1}°C (critical)",
+                temperature, critical
+            ),
+            EnforcerEvent::EmergencyExited { temperature } => {
+                write!(f, "🟢 Emergency mode disabled - temperature cooled to {:.1}°C", temperature)
+            }
+            EnforcerEvent::SwapEmergencyEntered { swap_percentage, threshold } => write!(
+                f,
+                "🔴 MEMORY EMERGENCY - Swap {:.1}% full > {:.1}%, killing the largest memory user",
+                swap_percentage, threshold
+            ),
+            EnforcerEvent::SwapEmergencyExited { swap_percentage } => {
+                write!(f, "🟢 Memory emergency over - swap down to {:.1}%", swap_percentage)
+            }
+            EnforcerEvent::EmergencyMonitorOnly { temperature, critical } => write!(
+                f,
+                "🔴 Emergency: {:.1}°C > {:.1}°C - monitor-only profile, no processes killed",
+                temperature, critical
+            ),
+            EnforcerEvent::EmergencyAlert { temperature, critical, would_kill } => {
+                write!(f, "🔴 Emergency: {:.1}°C > {:.1}°C - alert-only, ", temperature, critical)?;
+                if would_kill.is_empty() {
+                    write!(f, "nothing to kill")
+                } else {
+                    write!(f, "would kill {}", would_kill.join(", "))
+                }
+            }
+            EnforcerEvent::WouldKill(record) => match record.reason {
+                KillReason::ProfileActivation => {
+                    write!(f, "  🔔 Would kill {} (PID: {}) on profile activation (alert-only)", record.name, record.pid)
+                }
+                _ => write!(f, "  🔔 Would kill {} (PID: {}) - {} (alert-only)", record.name, record.pid, record.describe()),
+            },
+            EnforcerEvent::WouldLimit { pid, name, reason } => {
+                write!(f, "  🔔 Would limit {} (PID: {}) - {} (alert-only)", name, pid, reason)
+            }
+            EnforcerEvent::Simulated(SimulatedAction::Kill(record)) => {
+                write!(f, "[SIMULATE] would kill pid={} name={} ({})", record.pid, record.name, record.describe())
+            }
+            EnforcerEvent::Simulated(SimulatedAction::Limit { pid, name, reason }) => {
+                write!(f, "[SIMULATE] would limit pid={} name={} ({})", pid, name, reason)
+            }
+            EnforcerEvent::Simulated(SimulatedAction::ThrottleCpu) => write!(f, "[SIMULATE] would throttle CPU frequency"),
+            EnforcerEvent::EmergencyKillCapReached { kills } => {
+                write!(f, "🛑 Emergency kill cap reached ({} kills) - no more kills this episode", kills)
+            }
+            EnforcerEvent::KillLimitReached { kills } => {
+                write!(f, "🛑 Kill count limit reached ({} kills) - monitoring only for the rest of this run", kills)
+            }
+            EnforcerEvent::CpuThrottled { cpus } => write!(f, "  🧊 Throttled {} CPUs to minimum frequency", cpus),
+            EnforcerEvent::ThrottleFailed { error } => write!(f, "  Failed to throttle CPU: {}", error),
+            EnforcerEvent::CpuThrottleRestored { cpus } => write!(f, "  Restored original frequency settings on {} CPUs", cpus),
+            EnforcerEvent::ThrottleRestoreFailed { error } => write!(f, "  Failed to restore CPU frequency: {}", error),
+            EnforcerEvent::ProfileSwitched { from, to } => write!(f, "Switching profile: {} → {}", from, to),
+            EnforcerEvent::Skipped(Skip::MonitorOnly { profile, reason }) => {
+                write!(f, "  Monitor-only profile '{}' - not killing ({})", profile, reason)
+            }
+            EnforcerEvent::Skipped(Skip::Critical { name }) => write!(f, "  Skipping kill of {} (critical process)", name),
+            EnforcerEvent::Skipped(Skip::Protected { name }) => write!(f, "  Skipping kill of {} (protected process)", name),
+            EnforcerEvent::NothingKillable { reason, skipped } => {
+                write!(f, "⚠️  No killable process for {}: {}", reason, skipped)
+            }
+            EnforcerEvent::ProcessRestarted { name, pid, command } => {
+                write!(f, "  🔄 Restarted {} (PID: {}) - conditions back under limits: {}", name, pid, command)
+            }
+            EnforcerEvent::RestartSkipped { name, reason } => write!(f, "  Not restarting {} ({})", name, reason),
+            EnforcerEvent::RestartFailed { name, error } => write!(f, "  Failed to restart {}: {}", name, error),
+            EnforcerEvent::SessionEnded { stop, duration_secs, cycles, kills } => write!(
+                f,
+                "Enforcer stopped ({}) after {}: {} {}, {} {}",
+                stop,
+                humantime::format_duration(Duration::from_secs(*duration_secs)),
+                cycles,
+                if *cycles == 1 { "cycle" } else { "cycles" },
+                kills,
+                if *kills == 1 { "kill" } else { "kills" }
+            ),
+            EnforcerEvent::Error { message } => write!(f, "Enforcer error: {}", message),
+        }
+    }
+}
+
+/// Order kill candidates so the best victim for `strategy` comes first.
+/// `weights` only applies to `KillStrategy::Weighted`.
+pub fn order_candidates(processes: &[ProcessInfo], strategy: KillStrategy, weights: ScoreWeights) -> Vec<&ProcessInfo> {
+    let mut candidates: Vec<&ProcessInfo> = processes.iter().collect();
+    match strategy {
+        KillStrategy::HeaviestCpu => {
+            candidates.sort_by(|a, b| descending(a.cpu_percentage, b.cpu_percentage))
+        }
+        KillStrategy::HeaviestMemory => {
+            candidates.sort_by(|a, b| descending(a.memory_gb, b.memory_gb))
+        }
+        KillStrategy::HeaviestCpuPlusMemory | KillStrategy::Weighted => {
+            let weights = if strategy == KillStrategy::Weighted { weights } else { ScoreWeights::EQUAL };
+            let usage: Vec<(f64, f64)> = processes.iter().map(|p| (p.cpu_percentage, p.memory_gb)).collect();
+            let score = combined_scorer(&usage, weights);
+            candidates.sort_by(|a, b| {
+                descending(score(a.cpu_percentage, a.memory_gb), score(b.cpu_percentage, b.memory_gb))
+            })
+        }
+        KillStrategy::HeaviestIo => candidates.sort_by(|a, b| descending(a.io_mbps(), b.io_mbps())),
+        KillStrategy::Oldest => candidates.sort_by_key(|p| p.start_time),
+        KillStrategy::Newest => candidates.sort_by_key(|p| std::cmp::Reverse(p.start_time)),
+    }
+    candidates
+}
+
+// Score for HeaviestCpuPlusMemory and Weighted: CPU and memory as shares of the
+// heaviest in `usage` (cpu, memory), so neither unit dominates, then weighted
+fn combined_scorer(usage: &[(f64, f64)], weights: ScoreWeights) -> impl Fn(f64, f64) -> f64 {
+    let (max_cpu, max_memory) = usage.iter().fold((0.0_f64, 0.0_f64), |(cpu, memory), (c, m)| (cpu.max(*c), memory.max(*m)));
+    let share = |value: f64, max: f64| if max > 0.0 { value / max } else { 0.0 };
+    move |cpu, memory| weights.cpu * share(cpu, max_cpu) + weights.memory * share(memory, max_memory)
+}
+
+// A NaN or infinite reading says nothing about a limit: skip the check
+// rather than treating it as exceeded
+fn exceeds(value: f64, limit: f64) -> bool {
+    value.is_finite() && value > limit
+}
+
+/// Like `order_candidates`, but ranks applications (processes sharing a name) by their
+/// combined usage first, then orders each application's own processes by `strategy`
+pub fn order_candidates_grouped(processes: &[ProcessInfo], strategy: KillStrategy, weights: ScoreWeights) -> Vec<&ProcessInfo> {
+    let mut candidates = order_candidates(processes, strategy, weights);
+    let mut groups = group_processes(processes);
+    let rank: HashMap<String, usize> = match strategy {
+        KillStrategy::HeaviestCpu | KillStrategy::HeaviestMemory => {
+            let sort = if strategy == KillStrategy::HeaviestCpu { ProcessSort::Cpu } else { ProcessSort::Memory };
+            sort_groups(&mut groups, sort);
+            groups.into_iter().enumerate().map(|(i, g)| (g.name, i)).collect()
+        }
+        KillStrategy::HeaviestCpuPlusMemory | KillStrategy::Weighted => {
+            let weights = if strategy == KillStrategy::Weighted { weights } else { ScoreWeights::EQUAL };
+            let usage: Vec<(f64, f64)> = groups.iter().map(|g| (g.cpu_percentage, g.memory_gb)).collect();
+            let score = combined_scorer(&usage, weights);
+            groups.sort_by(|a, b| {
+                descending(score(a.cpu_percentage, a.memory_gb), score(b.cpu_percentage, b.memory_gb))
+                    .then(a.name.cmp(&b.name))
+            });
+            groups.into_iter().enumerate().map(|(i, g)| (g.name, i)).collect()
+        }
+        // Age and I/O have no aggregate here: an application ranks where its
+        // oldest/newest/heaviest I/O process does
+        KillStrategy::HeaviestIo | KillStrategy::Oldest | KillStrategy::Newest => {
+            let mut rank = HashMap::new();
+            for process in &candidates {
+                let next = rank.len();
+                rank.entry(process.name.clone()).or_insert(next);
+            }
+            rank
+        }
+    };
+    // Stable: processes keep their per-strategy order within an application
+    candidates.sort_by_key(|p| rank[&p.name]);
+    candidates
+}
+
+/// Receives every `EnforcerEvent` as it happens
+pub type EventObserver = Arc<dyn Fn(&EnforcerEvent) + Send + Sync>;
+
+// Observer slot; a closure has no useful Debug output
+#[derive(Clone)]
+struct Observer(EventObserver);
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+/// Where `enforce_once` gets its sample from, instead of reading the system
+pub type StatsProvider = Arc<Mutex<dyn FnMut() -> anyhow::Result<SystemStats> + Send>>;
+
+// Stats provider slot, Debug like the observer's
+#[derive(Clone)]
+struct StatsSource(StatsProvider);
+
+impl fmt::Debug for StatsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StatsSource")
+    }
+}
+
+// Cycles a killed process may still show up (exit cleanup, graceful shutdown)
+// before it is written off as unkillable, e.g. stuck in uninterruptible sleep
+const UNKILLABLE_AFTER_CYCLES: u32 = 3;
+
+// How long a process whose kill failed with EPERM is left alone before it may
+// be picked again (it may have changed owner, or kern gained privileges)
+const PERMISSION_DENIED_COOLDOWN: Duration = Duration::from_secs(600);
 
 /// Core enforcer state
 #[derive(Debug, Clone)]
@@ -11,9 +718,36 @@ pub struct Enforcer {
     config: KernConfig,
     current_profile: Profile,
     emergency_mode: bool,
+    swap_emergency: bool, // Swap went over swap_emergency.threshold_percent and hasn't dropped below exit_percent
     emergency_since: Option<Instant>,
+    emergency_kills: usize, // Kills during the current emergency episode
+    total_kills: usize,     // Kills during this run, checked against kill_count_limit
+    critical_samples: u32,  // Debounce streak for entering emergency mode
+    cool_samples: u32,      // Debounce streak for leaving emergency mode
     last_enforcement: Instant,
+    last_report: Instant, // Last StatusReport, or startup
     notification_manager: NotificationManager,
+    cpu_throttle: CpuThrottle,
+    unkillable: HashSet<(u32, u64)>, // (pid, start_time) that survived a kill
+    permission_denied: HashMap<(u32, u64), Instant>, // (pid, start_time) whose kill failed with EPERM, and when
+    permission_reported: bool, // PermissionDenied was emitted for the current episode of EPERM skips
+    recent_kills: HashMap<(u32, u64), u32>, // Killed (pid, start_time) -> cycles seen alive since
+    cgroup_limited: HashMap<u32, CgroupPlacement>, // Processes currently held in the limited cgroup
+    capacity_checked: bool, // Absolute limits of the current profile were compared to the machine
+    profile_locked: bool,   // switch_profile is ignored (e.g. while debugging a profile by hand)
+    dry_run: bool,          // Decide everything but signal, limit and throttle nothing (see preview)
+    simulate: bool,         // Like dry_run, but for a running enforcer, reporting each held-back step (set_simulate)
+    alerted: HashSet<(u32, u64)>, // (pid, start_time) already written to the kill log as held back in alert-only mode
+    io_sampler: IoSampler, // Per-process disk I/O rates, sampled while the profile has max_io_mbps
+    idle_switcher: Option<IdleSwitcher>, // Profiles with `idle` auto-activation triggers
+    idle_unavailable_reported: bool,     // Warned once that idle time can't be determined
+    logger: SharedLogger,
+    observer: Option<Observer>, // Replaces the human-readable log when set
+    watchdog: Option<crate::systemd::Notifier>, // Pinged while a graceful kill waits (set_watchdog)
+    stats_source: Option<StatsSource>, // Replaces get_system_stats when set (tests, simulations)
+    restarts: RestartQueue, // Killed restart_after_kill processes waiting for conditions to settle
+    cpu_samples: VecDeque<f64>, // Latest cpu_percentile.samples global CPU readings, oldest first
+    uid: u32, // Real UID kern runs as; only its processes are acted on unless enforcement.all_users
 }
 
 impl Enforcer {
@@ -23,290 +757,3226 @@ impl Enforcer {
             config,
             current_profile,
             emergency_mode: false,
+            swap_emergency: false,
             emergency_since: None,
+            emergency_kills: 0,
+            total_kills: 0,
+            critical_samples: 0,
+            cool_samples: 0,
             last_enforcement: Instant::now(),
+            last_report: Instant::now(),
             notification_manager,
+            cpu_throttle: CpuThrottle::new(),
+            unkillable: HashSet::new(),
+            permission_denied: HashMap::new(),
+            permission_reported: false,
+            recent_kills: HashMap::new(),
+            cgroup_limited: HashMap::new(),
+            capacity_checked: false,
+            profile_locked: false,
+            dry_run: false,
+            simulate: false,
+            alerted: HashSet::new(),
+            io_sampler: IoSampler::new(),
+            idle_switcher: None,
+            idle_unavailable_reported: false,
+            logger: Logger::stderr(LogLevel::Info).shared(),
+            observer: None,
+            watchdog: None,
+            stats_source: None,
+            restarts: RestartQueue::new(),
+            cpu_samples: VecDeque::new(),
+            uid: nix::unistd::getuid().as_raw(),
         }
     }
 
-    pub fn enforce_once(&mut self) -> anyhow::Result<bool> {
-        let stats = get_system_stats()?;
-        let mut action_taken = false;
+    /// Hand every event to `observer` instead of logging its human-readable
+    /// message (the default)
+    ///
+    /// ```no_run
+    /// use kern::config::KernConfig;
+    /// use kern::enforcer::{Enforcer, EnforcerEvent};
+    /// use kern::profiles::Profile;
+    ///
+    /// let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+    /// enforcer.set_observer(|event: &EnforcerEvent| println!("{}", event.to_json()));
+    /// let report = enforcer.enforce_once()?;
+    /// println!("{} kill attempts", report.kills.len());
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_observer(&mut self, observer: impl Fn(&EnforcerEvent) + Send + Sync + 'static) {
+        self.observer = Some(Observer(Arc::new(observer)));
+    }
 
-        // Check if we should exit emergency mode (temperature cooled)
-        if self.emergency_mode {
-            if stats.temperature < self.config.temperature.warning {
-                eprintln!("🟢 Emergency mode disabled - temperature cooled to {:.1}°C", stats.temperature);
-                self.emergency_mode = false;
-                self.emergency_since = None;
-                let _ = self.notification_manager.notify_emergency_mode_resolved(stats.temperature);
-            }
+    /// Take each cycle's sample from `provider` instead of the running system, so
+    /// the decisions can be driven by synthetic stats. Its samples are used as
+    /// they are: disk I/O rates included, nothing is read from /proc. Clones of
+    /// this enforcer share the provider.
+    ///
+    /// ```no_run
+    /// use kern::config::KernConfig;
+    /// use kern::enforcer::Enforcer;
+    /// use kern::monitor::SystemStats;
+    /// use kern::profiles::Profile;
+    ///
+    /// let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+    /// enforcer.set_stats_provider(|| Ok(SystemStats { temperature_max: 95.0, ..Default::default() }));
+    /// enforcer.enforce_once()?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_stats_provider(&mut self, provider: impl FnMut() -> anyhow::Result<SystemStats> + Send + 'static) {
+        self.stats_source = Some(StatsSource(Arc::new(Mutex::new(provider))));
+    }
+
+    /// Send human-readable output to this logger instead of stderr
+    pub fn set_logger(&mut self, logger: SharedLogger) {
+        self.logger = logger;
+    }
+
+    /// Keep systemd's watchdog fed while a graceful kill waits out
+    /// kill_graceful_timeout_secs, which can outlast WatchdogSec
+    pub fn set_watchdog(&mut self, notifier: crate::systemd::Notifier) {
+        self.watchdog = Some(notifier);
+    }
+
+    // Write a message to the shared logger
+    fn log(&self, level: LogLevel, message: &str) {
+        if let Ok(mut logger) = self.logger.lock() {
+            logger.write(level, message);
         }
+    }
 
-        // Check for emergency condition (temp > critical threshold)
-        if !self.emergency_mode && stats.temperature > self.config.temperature.critical {
-            eprintln!("🔴 EMERGENCY MODE ACTIVATED - Temperature {:.1}°C > {:.1}°C (critical)", 
-                stats.temperature, self.config.temperature.critical);
-            self.emergency_mode = true;
-            self.emergency_since = Some(Instant::now());
-            let _ = self.notification_manager.notify_emergency_mode(stats.temperature, self.config.temperature.critical);
-            
-            // Kill all non-protected processes immediately
-            action_taken = self.handle_emergency_mode(&stats)?;
-        } else if self.emergency_mode {
-            // In emergency mode - continue killing processes
-            action_taken = self.handle_emergency_mode(&stats)?;
-        } else {
-            // Normal operation - check profile limits
-            action_taken = self.enforce_resource_limits(&stats)?;
+    // Report an event: to the observer if there is one, otherwise its message in the log
+    fn emit(&self, event: EnforcerEvent) {
+        match &self.observer {
+            Some(Observer(observer)) => observer(&event),
+            None => self.log(event.level(), &event.to_string()),
         }
+    }
 
-        self.last_enforcement = Instant::now();
-        Ok(action_taken)
+    pub fn enforce_once(&mut self) -> anyhow::Result<EnforcementReport> {
+        self.check_idle();
+        if let Some(StatsSource(provider)) = &self.stats_source {
+            let stats = (provider.lock().unwrap_or_else(|e| e.into_inner()))()?;
+            return self.enforce_stats(&stats);
+        }
+        let mut stats = get_system_stats(self.config.memory_metric, self.config.temperature_sensor_path.as_deref())?;
+        // Rates need the previous cycle's totals; without an I/O limit nothing is read
+        if self.current_profile.limits.max_io_mbps.is_some() {
+            self.io_sampler.update(&mut stats.top_processes);
+        } else if self.io_sampler.has_baseline() {
+            self.io_sampler = IoSampler::new();
+        }
+        self.enforce_stats(&stats)
     }
 
-    // Handle emergency mode - kill all non-critical, non-protected processes
-    fn handle_emergency_mode(&mut self, stats: &SystemStats) -> anyhow::Result<bool> {
-        let mut killed_count = 0;
+    // One enforcement cycle against an already collected sample
+    fn enforce_stats(&mut self, stats: &SystemStats) -> anyhow::Result<EnforcementReport> {
+        let mut report = EnforcementReport::default();
+        self.reap_recent_kills(stats);
+        self.sample_cpu(stats.cpu_usage);
+        if !self.capacity_checked && stats.cpu_count > 0 {
+            self.warn_unreachable_limits(stats);
+        }
+        let (warning, critical) = self.temperature_thresholds();
+        let temperature = stats.effective_temperature(self.config.temperature_strategy);
 
-        for process in &stats.top_processes {
-            // Skip protected processes
-            if killer::is_protected(&process.name, &self.current_profile.protected) 
-                || killer::is_protected(&process.name, &self.config.protected_processes)
-                || killer::is_critical_process(&process.name) {
-                continue;
+        // With temperature disabled there are no samples, so emergency mode never starts
+        let transition = if self.config.temperature.enabled { self.observe_temperature(temperature) } else { None };
+        match transition {
+            Some(EmergencyTransition::Exited) => {
+                self.emit(EnforcerEvent::EmergencyExited { temperature });
+                let _ = self.notification_manager.notify_emergency_mode_resolved(temperature);
+                self.restore_cpu_frequency();
             }
-
-            // Kill the process
-            match killer::kill_process(process.pid, self.config.kill_graceful) {
-                Ok(_) => {
-                    eprintln!("  ⚠️  Killed {} (PID: {}) - emergency mode", process.name, process.pid);
-                    killer::log_kill_action(process.pid, &process.name, true, self.config.kill_graceful);
-                    killed_count += 1;
+            Some(EmergencyTransition::Entered) => {
+                self.emit(EnforcerEvent::EmergencyEntered { temperature, critical, warning });
+                let alert_only = self.is_alert_only();
+                if !alert_only {
+                    // Alert-only mode notifies below, every cycle
+                    let _ = self.notification_manager.notify_emergency_mode(temperature, critical);
                 }
-                Err(e) => {
-                    eprintln!("  Failed to kill {} (PID: {}): {}", process.name, process.pid, e);
-                    killer::log_kill_action(process.pid, &process.name, false, self.config.kill_graceful);
+                if self.current_profile.enforce && !alert_only && self.has_action(EmergencyAction::ThrottleCpu) {
+                    report.cpu_throttled = self.throttle_cpu_frequency();
                 }
             }
+            None => {}
+        }
+
+        // Memory emergency: independent of temperature, and of the RAM limit
+        if self.config.swap_emergency.enabled {
+            self.observe_swap(stats.swap_percentage);
+        }
+        let swap_kill = self.swap_emergency && stats.swap_percentage > self.config.swap_emergency.threshold_percent;
+        if swap_kill {
+            self.act_on_swap_emergency(stats, &mut report)?;
         }
 
-        if killed_count > 0 {
-            let _ = self.notification_manager.notify_process_killed(0, "emergency", killed_count);
+        if self.emergency_mode && !self.current_profile.enforce {
+            // Monitor-only profile: keep alerting instead of killing
+            self.emit(EnforcerEvent::EmergencyMonitorOnly { temperature, critical });
+            let _ = self.notification_manager.notify_emergency_mode(temperature, critical);
+        } else if self.emergency_mode && self.is_alert_only() {
+            // Alert-only: name the would-be victims and escalate, every cycle
+            if self.has_action(EmergencyAction::Kill) {
+                self.handle_emergency_mode(stats, &mut report)?;
+            }
+            let would_kill: Vec<String> = report.would_kill.iter().map(|record| record.name.clone()).collect();
+            let _ = self.notification_manager.notify_emergency_alert(temperature, critical, &would_kill);
+            self.emit(EnforcerEvent::EmergencyAlert { temperature, critical, would_kill });
+        } else if self.emergency_mode {
+            // Kill the heaviest CPU consumers until the load is addressed,
+            // unless throttling ran first and deserves a cycle to take effect
+            if self.has_action(EmergencyAction::Kill)
+                && !(report.cpu_throttled && self.throttle_precedes_kill())
+            {
+                self.handle_emergency_mode(stats, &mut report)?;
+            }
+        } else if !swap_kill {
+            // Normal operation - check profile limits
+            self.enforce_resource_limits(stats, &mut report)?;
         }
 
-        Ok(killed_count > 0)
+        self.restart_killed(stats, temperature, warning);
+        self.report_permission_skips();
+        self.report_status(stats, temperature);
+        report.critical_samples = self.critical_samples;
+        report.cool_samples = self.cool_samples;
+        self.last_enforcement = Instant::now();
+        Ok(report)
     }
 
-    // Enforce resource limits for the current profile
-    fn enforce_resource_limits(&mut self, stats: &SystemStats) -> anyhow::Result<bool> {
-        let mut action_taken = false;
+    /// Switch profiles on session idle time (see [`IdleSwitcher`]); ignored
+    /// while the profile is locked
+    pub fn set_idle_switcher(&mut self, switcher: IdleSwitcher) {
+        self.idle_switcher = (!switcher.is_empty()).then_some(switcher);
+    }
 
-        // Check CPU limit
-        if stats.cpu_usage > self.current_profile.limits.max_cpu_percent {
-            eprintln!("⚠️  CPU limit exceeded: {:.1}% > {:.1}%", 
-                stats.cpu_usage, self.current_profile.limits.max_cpu_percent);
-            let _ = self.notification_manager.notify_resource_limit_exceeded(
-                "CPU",
-                stats.cpu_usage,
-                self.current_profile.limits.max_cpu_percent,
-            );
-            action_taken |= self.kill_heaviest_process(&stats)?;
+    // Poll the session idle time and apply an idle trigger's switch
+    fn check_idle(&mut self) {
+        if self.profile_locked {
+            return;
         }
-
-        // Check RAM limit
-        if stats.memory_percentage > self.current_profile.limits.max_ram_percent {
-            eprintln!("⚠️  RAM limit exceeded: {:.1}% > {:.1}%", 
-                stats.memory_percentage, self.current_profile.limits.max_ram_percent);
-            let _ = self.notification_manager.notify_resource_limit_exceeded(
-                "RAM",
-                stats.memory_percentage,
-                self.current_profile.limits.max_ram_percent,
-            );
-            action_taken |= self.kill_heaviest_process(&stats)?;
+        let Some(switcher) = self.idle_switcher.as_mut() else {
+            return;
+        };
+        let idle_time = idle::session_idle_time();
+        let name = self.current_profile.name.clone();
+        let switch = switcher.update(idle_time, &name, &self.current_profile);
+        if idle_time.is_none() && !self.idle_unavailable_reported {
+            self.idle_unavailable_reported = true;
+            self.log(LogLevel::Warn, "Session idle time unavailable (no screensaver on the session bus, no input activity); idle triggers skipped");
         }
+        if let Some(switch) = switch {
+            self.log(LogLevel::Info, &switch.to_string());
+            let _ = self.switch_profile(switch.profile);
+        }
+    }
 
-        // Check temperature warning (not critical)
-        if stats.temperature > self.config.temperature.warning && stats.temperature < self.config.temperature.critical {
-            eprintln!("🟡 Temperature warning: {:.1}°C > {:.1}°C", 
-                stats.temperature, self.config.temperature.warning);
-            let _ = self.notification_manager.notify_temperature_warning(
-                stats.temperature,
-                self.config.temperature.warning,
-            );
-            // Kill one process to cool down
-            action_taken |= self.kill_heaviest_process(&stats)?;
+    /// Feed one temperature sample into the debounce streaks.
+    ///
+    /// Emergency mode is entered only after `emergency.trigger_samples`
+    /// consecutive samples above the critical threshold, and left only after
+    /// `emergency.exit_samples` consecutive samples below the warning
+    /// threshold, so a single spurious sensor reading can't flip the state.
+    pub fn observe_temperature(&mut self, temperature: f64) -> Option<EmergencyTransition> {
+        let (warning, critical) = self.temperature_thresholds();
+
+        // A broken sensor reading neither extends nor resets a streak
+        if !temperature.is_finite() {
+            return None;
         }
 
-        Ok(action_taken)
-    }
+        if self.emergency_mode {
+            if temperature < warning {
+                self.cool_samples += 1;
+            } else {
+                self.cool_samples = 0;
+            }
 
-    // Kill the process using the most CPU (excluding protected/critical)
-    fn kill_heaviest_process(&mut self, stats: &SystemStats) -> anyhow::Result<bool> {
-        for process in &stats.top_processes {
-            // Skip protected processes
-            if killer::is_protected(&process.name, &self.current_profile.protected) 
-                || killer::is_protected(&process.name, &self.config.protected_processes)
-                || killer::is_critical_process(&process.name) {
-                continue;
+            if self.cool_samples >= self.config.emergency.exit_samples {
+                self.emergency_mode = false;
+                self.emergency_since = None;
+                self.cool_samples = 0;
+                return Some(EmergencyTransition::Exited);
+            }
+        } else {
+            if temperature > critical {
+                self.critical_samples += 1;
+            } else {
+                self.critical_samples = 0;
             }
 
-            // Kill this process
-            match killer::kill_process(process.pid, self.config.kill_graceful) {
-                Ok(_) => {
-                    eprintln!("  ✓ Killed {} (PID: {}) - high resource usage", process.name, process.pid);
-                    killer::log_kill_action(process.pid, &process.name, true, self.config.kill_graceful);
-                    let _ = self.notification_manager.notify_process_killed(process.pid, &process.name, 1);
-                    return Ok(true);
-                }
-                Err(e) => {
-                    eprintln!("  Failed to kill {} (PID: {}): {}", process.name, process.pid, e);
-                    killer::log_kill_action(process.pid, &process.name, false, self.config.kill_graceful);
-                    // Continue to try the next process
-                }
+            if self.critical_samples >= self.config.emergency.trigger_samples {
+                self.emergency_mode = true;
+                self.emergency_since = Some(Instant::now());
+                self.emergency_kills = 0;
+                self.critical_samples = 0;
+                return Some(EmergencyTransition::Entered);
             }
         }
 
-        Ok(false)
-    }
-
-    // Get the current emergency status
-    pub fn is_emergency_mode(&self) -> bool {
-        self.emergency_mode
+        None
     }
 
-    // Get time in emergency mode (if active)
-    pub fn emergency_duration(&self) -> Option<Duration> {
-        self.emergency_since.map(|since| since.elapsed())
+    // Enter memory emergency mode above swap_emergency.threshold_percent, leave it
+    // below exit_percent
+    fn observe_swap(&mut self, swap_percentage: f64) {
+        let config = &self.config.swap_emergency;
+        if !swap_percentage.is_finite() {
+            return;
+        }
+        if !self.swap_emergency && swap_percentage > config.threshold_percent {
+            self.swap_emergency = true;
+            let threshold = config.threshold_percent;
+            self.emit(EnforcerEvent::SwapEmergencyEntered { swap_percentage, threshold });
+            let _ = self.notification_manager.notify_swap_emergency(swap_percentage, threshold);
+        } else if self.swap_emergency && swap_percentage < config.exit_percent {
+            self.swap_emergency = false;
+            self.emit(EnforcerEvent::SwapEmergencyExited { swap_percentage });
+            let _ = self.notification_manager.notify_swap_emergency_resolved(swap_percentage);
+        }
     }
 
-    // Switch to a new profile
-    pub fn switch_profile(&mut self, new_profile: Profile) -> anyhow::Result<()> {
-        let old_name = self.current_profile.name.clone();
-        eprintln!("Switching profile: {} → {}", old_name, new_profile.name);
-        
-        // Kill processes marked for killing on activate (only if not protected/critical)
-        for proc_name in &new_profile.kill_on_activate {
-            let pids = killer::find_processes_by_name(proc_name);
-            
-            for pid in pids {
-                if killer::is_critical_process(proc_name) {
-                    eprintln!("  Skipping kill of {} (critical process)", proc_name);
-                    continue;
-                }
-                
-                match killer::kill_process(pid, self.config.kill_graceful) {
-                    Ok(_) => {
-                        eprintln!("  Killed {} (PID: {}) on profile activation", proc_name, pid);
-                        killer::log_kill_action(pid, proc_name, true, self.config.kill_graceful);
-                    }
-                    Err(e) => {
-                        eprintln!("  Failed to kill {} (PID: {}): {}", proc_name, pid, e);
-                    }
-                }
-            }
+    // Thrashing: kill the heaviest memory user right away, whatever the RAM limit
+    // and the profile's limit action (a cgroup limit would only slow it down further)
+    fn act_on_swap_emergency(&mut self, stats: &SystemStats, report: &mut EnforcementReport) -> anyhow::Result<()> {
+        let reason = KillReason::SwapEmergency;
+        let weights = reason.victim_weights(&self.config.victim_weights);
+        if self.current_profile.enforce && self.is_alert_only() {
+            self.alert_on_limit(stats, reason, KillStrategy::HeaviestMemory, weights, report);
+        } else {
+            self.kill_heaviest_process(stats, reason, KillStrategy::HeaviestMemory, weights, report)?;
         }
-
-        self.current_profile = new_profile;
-        self.emergency_mode = false;
-        self.emergency_since = None;
-        
-        let _ = self.notification_manager.notify_profile_switched(&old_name, &self.current_profile.name);
-        
         Ok(())
     }
 
-    /// Get current profile
-    pub fn profile(&self) -> &Profile {
-        &self.current_profile
+    /// Effective (warning, critical) temperature thresholds: the active
+    /// profile's overrides when present, otherwise the global config
+    pub fn temperature_thresholds(&self) -> (f64, f64) {
+        let limits = &self.current_profile.limits;
+        (
+            limits.temp_warning.unwrap_or(self.config.temperature.warning),
+            limits.temp_critical.unwrap_or(self.config.temperature.critical),
+        )
     }
 
-    /// Get system stats at the time of last enforcement
-    pub fn last_enforcement_time(&self) -> Instant {
-        self.last_enforcement
+    // Strategy for a given violation, honoring the profile's override
+    fn strategy_for(&self, reason: KillReason) -> KillStrategy {
+        self.current_profile
+            .kill_strategy
+            .unwrap_or_else(|| reason.default_strategy())
     }
-}
 
-/// Run the enforcer in a continuous loop (blocking)
-/// Periodically checks system stats and enforces resource limits
-pub fn run_enforcer_loop(config: KernConfig, initial_profile: Profile) -> anyhow::Result<()> {
-    let mut enforcer = Enforcer::new(config.clone(), initial_profile);
-    let interval = Duration::from_secs(config.monitor_interval);
+    // One strategy covering several violations at once: theirs if they agree,
+    // else combined CPU and memory, so a single victim is the worst overall
+    fn strategy_for_all(&self, reasons: &[KillReason]) -> KillStrategy {
+        let mut strategies = reasons.iter().map(|reason| self.strategy_for(*reason));
+        let first = strategies.next().unwrap_or(KillStrategy::HeaviestCpuPlusMemory);
+        if strategies.all(|strategy| strategy == first) {
+            first
+        } else {
+            KillStrategy::HeaviestCpuPlusMemory
+        }
+    }
 
-    eprintln!("Starting enforcer loop (interval: {:?})", interval);
-    eprintln!("Press Ctrl+C to stop");
-    eprintln!();
+    // Weights for KillStrategy::Weighted covering every exceeded limit: each
+    // limit's victim_weights, scaled to add up to 1 and averaged
+    fn weights_for_all(&self, reasons: &[KillReason]) -> ScoreWeights {
+        let weights: Vec<ScoreWeights> = reasons
+            .iter()
+            .map(|reason| reason.victim_weights(&self.config.victim_weights).normalized())
+            .collect();
+        if weights.is_empty() {
+            return ScoreWeights::EQUAL;
+        }
+        let count = weights.len() as f64;
+        ScoreWeights {
+            cpu: weights.iter().map(|w| w.cpu).sum::<f64>() / count,
+            memory: weights.iter().map(|w| w.memory).sum::<f64>() / count,
+        }
+    }
 
-    loop {
-        match enforcer.enforce_once() {
-            Ok(action_taken) => {
-                if action_taken {
-                    if enforcer.is_emergency_mode() {
-                        if let Some(duration) = enforcer.emergency_duration() {
-                            eprintln!("[Emergency mode - {:.1}s]", duration.as_secs_f64());
-                        }
+    // Kill candidates in victim order, grouped by application if the profile asks for it
+    fn candidates<'a>(&self, stats: &'a SystemStats, strategy: KillStrategy, weights: ScoreWeights) -> Vec<&'a ProcessInfo> {
+        if self.current_profile.group_processes {
+            order_candidates_grouped(&stats.top_processes, strategy, weights)
+        } else if strategy == KillStrategy::HeaviestCpu {
+            stats.top_cpu_processes()
+        } else {
+            order_candidates(&stats.top_processes, strategy, weights)
+        }
+    }
+
+    // Whether a process may be killed at all (not protected itself or through an
+    // ancestor, critical, a zombie or a kernel thread)
+    fn is_killable(&self, process: &ProcessInfo) -> bool {
+        self.skip_reason(process).is_none()
+    }
+
+    // Why a process may not be killed, None if it may. The ancestry walk reads
+    // /proc, so it comes last.
+    fn skip_reason(&self, process: &ProcessInfo) -> Option<SkipReason> {
+        let key = (process.pid, process.start_time);
+        if process.is_zombie() {
+            Some(SkipReason::Zombie)
+        } else if process.kernel_thread {
+            Some(SkipReason::KernelThread)
+        } else if !self.owns(process.uid) {
+            Some(SkipReason::OtherUser)
+        } else if killer::is_protected(&process.name, &self.current_profile.protected)
+            || killer::is_protected(&process.name, &self.config.protected_processes)
+        {
+            Some(SkipReason::Protected)
+        } else if self.config.is_critical_process(&process.name) {
+            Some(SkipReason::Critical)
+        } else if self.unkillable.contains(&key) {
+            Some(SkipReason::Unkillable)
+        } else if self.permission_denied.contains_key(&key) {
+            Some(SkipReason::PermissionDenied)
+        } else if self.protected_by_ancestry(process.pid, &self.current_profile) {
+            Some(SkipReason::ProtectedAncestor)
+        } else {
+            None
+        }
+    }
+
+    // Whether a process owned by `uid` is in scope: kern's own user's, or anyone's
+    // with enforcement.all_users. An unknown owner counts as someone else.
+    fn owns(&self, uid: Option<u32>) -> bool {
+        self.config.enforcement.all_users || uid == Some(self.uid)
+    }
+
+    // A kill was called for but every candidate was skipped: log and notify
+    // why, once for the whole candidate list
+    fn report_nothing_killable(&mut self, reason: KillReason, skipped: SkipCounts, report: &mut EnforcementReport) {
+        let _ = self.notification_manager.notify_nothing_killable(&reason.to_string(), &skipped.to_string());
+        report.skipped.merge(&skipped);
+        self.emit(EnforcerEvent::NothingKillable { reason, skipped });
+    }
+
+    // A parent (or an ancestor up to ancestry_depth levels) is protected, globally or by `profile`
+    fn protected_by_ancestry(&self, pid: u32, profile: &Profile) -> bool {
+        self.config.ancestry_depth > 0
+            && killer::protected_ancestor(std::path::Path::new("/proc"), pid, self.config.ancestry_depth, |name| {
+                killer::is_protected(name, &profile.protected)
+                    || killer::is_protected(name, &self.config.protected_processes)
+            })
+            .is_some()
+    }
+
+    // What to relaunch `process` with once it is killed, if the profile asks for
+    // that; read before the kill, while its /proc entry still exists
+    fn restart_command(&self, process: &ProcessInfo) -> Option<RestartCommand> {
+        if self.hands_off() || !self.current_profile.restart_after_kill.contains(&process.name) {
+            return None;
+        }
+        // A different owner than the enforcer saw means the PID was reused
+        restart::capture(process.pid, &process.name).filter(|command| process.uid.map_or(true, |uid| uid == command.uid))
+    }
+
+    // Relaunch killed restart_after_kill processes once CPU, RAM and temperature
+    // have stayed under the limits for restart.stabilization_secs. A process
+    // running again under the same name, or one over restart.max_per_hour, is
+    // dropped instead.
+    fn restart_killed(&mut self, stats: &SystemStats, temperature: f64, warning: f64) {
+        if self.restarts.is_empty() {
+            return;
+        }
+        let limits = &self.current_profile.limits;
+        let pressure = self.emergency_mode
+            || self.swap_emergency
+            || exceeds(stats.cpu_usage, limits.effective_cpu_percent(stats.cpu_capacity()))
+            || exceeds(stats.memory_percentage, limits.effective_ram_percent(stats.total_memory_gb))
+            || (self.config.temperature.enabled && exceeds(temperature, warning));
+        let now = Instant::now();
+        for command in self.restarts.due(!pressure, now, self.config.restart.stabilization()) {
+            let name = command.name.clone();
+            if stats.top_processes.iter().any(|p| p.name == name) {
+                self.emit(EnforcerEvent::RestartSkipped { name, reason: RestartSkip::AlreadyRunning });
+                continue;
+            }
+            let max_per_hour = self.config.restart.max_per_hour;
+            if !self.restarts.allow(&name, now, max_per_hour) {
+                self.emit(EnforcerEvent::RestartSkipped { name, reason: RestartSkip::RateLimited { max_per_hour } });
+                continue;
+            }
+            let command_line = command.cmdline.join(" ");
+            match restart::relaunch(&command) {
+                Ok(pid) => {
+                    killer::log_restart_action(Some(&self.config), Some(pid), &name, true, &command_line);
+                    self.emit(EnforcerEvent::ProcessRestarted { name, pid, command: command_line });
+                }
+                Err(e) => {
+                    killer::log_restart_action(Some(&self.config), None, &name, false, &command_line);
+                    self.emit(EnforcerEvent::RestartFailed { name, error: e.to_string() });
+                }
+            }
+        }
+    }
+
+    // Nothing is signalled, limited, throttled or relaunched: a preview, or simulate mode
+    fn hands_off(&self) -> bool {
+        self.dry_run || self.simulate
+    }
+
+    // Kill a process, or in a dry run pretend the kill worked
+    fn kill(&self, pid: u32) -> Result<(), killer::KillError> {
+        if self.hands_off() {
+            return Ok(());
+        }
+        let wait = killer::GracefulWait { watchdog: self.watchdog.as_ref(), ..self.config.kill_graceful_wait() };
+        killer::kill_process(pid, self.config.kill_graceful, wait)
+    }
+
+    // Report a kill that went through; in simulate mode, the kill that would have
+    fn emit_killed(&self, record: KillRecord) {
+        if self.simulate {
+            self.emit(EnforcerEvent::Simulated(SimulatedAction::Kill(record)));
+        } else {
+            self.emit(EnforcerEvent::ProcessKilled(record));
+        }
+    }
+
+    // Append a kill to the kill log, unless nothing was actually killed
+    fn log_kill(&self, pid: u32, name: &str, success: bool, reason: &str) {
+        if !self.hands_off() {
+            killer::log_kill_action(Some(&self.config), pid, name, success, self.config.kill_graceful, reason);
+        }
+    }
+
+    // Stop retrying a process for PERMISSION_DENIED_COOLDOWN once a kill failed
+    // permanently (EPERM), so victim selection moves on to one kern can signal.
+    // Keyed by start time too, so a recycled PID is not skipped.
+    fn remember_unkillable(&mut self, process: &ProcessInfo, error: &killer::KillError) {
+        if error.permanent {
+            self.permission_denied.insert((process.pid, process.start_time), Instant::now());
+        }
+    }
+
+    // One-line summary every report_interval_secs, so a quiet enforcer can be seen to be alive
+    fn report_status(&mut self, stats: &SystemStats, temperature: f64) {
+        let Some(secs) = self.config.report_interval_secs else { return };
+        if self.last_report.elapsed() < Duration::from_secs(secs) {
+            return;
+        }
+        self.last_report = Instant::now();
+        self.emit(EnforcerEvent::StatusReport {
+            cpu: stats.cpu_usage,
+            ram: stats.memory_percentage,
+            temperature,
+            profile: self.current_profile.name.clone(),
+            kills: self.total_kills,
+        });
+    }
+
+    // Report processes skipped for lack of permission once per episode (until
+    // a cycle ends with none skipped) instead of on every cycle
+    fn report_permission_skips(&mut self) {
+        if self.permission_denied.is_empty() {
+            self.permission_reported = false;
+        } else if !self.permission_reported {
+            self.permission_reported = true;
+            self.emit(EnforcerEvent::PermissionDenied { processes: self.permission_denied.len() });
+        }
+    }
+
+    // Count a successful kill toward kill_count_limit, warning once when it is reached
+    fn count_kill(&mut self) {
+        self.total_kills += 1;
+        if self.config.kill_count_limit > 0 && self.total_kills == self.config.kill_count_limit {
+            self.emit(EnforcerEvent::KillLimitReached { kills: self.total_kills });
+        }
+    }
+
+    // kill_count_limit has been used up for this run
+    fn kill_limit_reached(&self) -> bool {
+        self.config.kill_count_limit > 0 && self.total_kills >= self.config.kill_count_limit
+    }
+
+    // Remember a delivered kill signal. Re-signalling a process that is still
+    // exiting keeps its original count, so a stuck process is written off on time.
+    fn remember_kill(&mut self, process: &ProcessInfo) {
+        self.recent_kills.entry((process.pid, process.start_time)).or_insert(0);
+    }
+
+    // Check on processes killed in earlier cycles. Ones that are gone are
+    // forgotten (in both sets, so neither grows forever); ones still alive after
+    // UNKILLABLE_AFTER_CYCLES are no longer targeted, reported once.
+    fn reap_recent_kills(&mut self, stats: &SystemStats) {
+        let alive: HashMap<(u32, u64), &ProcessInfo> =
+            stats.top_processes.iter().map(|p| ((p.pid, p.start_time), p)).collect();
+        self.unkillable.retain(|key| alive.contains_key(key));
+        self.alerted.retain(|key| alive.contains_key(key));
+        self.permission_denied
+            .retain(|key, since| alive.contains_key(key) && since.elapsed() < PERMISSION_DENIED_COOLDOWN);
+
+        let mut survivors = Vec::new();
+        self.recent_kills.retain(|key, cycles| {
+            let Some(process) = alive.get(key) else {
+                return false;
+            };
+            *cycles += 1;
+            if *cycles >= UNKILLABLE_AFTER_CYCLES {
+                survivors.push(*process);
+                return false;
+            }
+            true
+        });
+
+        for process in survivors {
+            self.unkillable.insert((process.pid, process.start_time));
+            self.emit(EnforcerEvent::ProcessUnkillable {
+                pid: process.pid,
+                name: process.name.clone(),
+                cycles: UNKILLABLE_AFTER_CYCLES,
+            });
+        }
+    }
+
+    // How many processes emergency mode may kill this cycle. Zero once CPU
+    // load is below the floor (the temperature lags behind the load) or the
+    // episode's kill cap has been used up.
+    fn emergency_kill_budget(&self, stats: &SystemStats) -> usize {
+        let emergency = &self.config.emergency;
+        // Unknown load: don't kill on the temperature alone
+        if !stats.cpu_usage.is_finite() || stats.cpu_usage < emergency.cpu_floor_percent {
+            return 0;
+        }
+        let remaining = emergency.max_total_kills.saturating_sub(self.emergency_kills);
+        emergency.max_kills_per_cycle.min(remaining)
+    }
+
+    fn has_action(&self, action: EmergencyAction) -> bool {
+        self.config.emergency.actions.contains(&action)
+    }
+
+    // True when emergency.actions lists throttle_cpu before kill
+    fn throttle_precedes_kill(&self) -> bool {
+        let actions = &self.config.emergency.actions;
+        let position = |action| actions.iter().position(|a| *a == action);
+        matches!(
+            (position(EmergencyAction::ThrottleCpu), position(EmergencyAction::Kill)),
+            (Some(throttle), Some(kill)) if throttle < kill
+        )
+    }
+
+    // Cap CPU frequency for the emergency episode; returns true on success
+    fn throttle_cpu_frequency(&mut self) -> bool {
+        if self.simulate {
+            self.emit(EnforcerEvent::Simulated(SimulatedAction::ThrottleCpu));
+        }
+        if self.hands_off() {
+            return true;
+        }
+        match self.cpu_throttle.throttle() {
+            Ok(cpus) => {
+                self.emit(EnforcerEvent::CpuThrottled { cpus });
+                true
+            }
+            Err(e) => {
+                self.emit(EnforcerEvent::ThrottleFailed { error: e.to_string() });
+                false
+            }
+        }
+    }
+
+    /// Undo a CPU throttle applied in emergency mode, including one left
+    /// behind by a previous run that crashed mid-emergency
+    pub fn restore_cpu_frequency(&mut self) {
+        if self.hands_off() || !self.cpu_throttle.is_throttled() {
+            return;
+        }
+        match self.cpu_throttle.restore() {
+            Ok(cpus) => self.emit(EnforcerEvent::CpuThrottleRestored { cpus }),
+            Err(e) => self.emit(EnforcerEvent::ThrottleRestoreFailed { error: e.to_string() }),
+        }
+    }
+
+    // Handle emergency mode - kill the heaviest non-critical, non-protected
+    // processes, a few per cycle, until the load is addressed
+    fn handle_emergency_mode(&mut self, stats: &SystemStats, report: &mut EnforcementReport) -> anyhow::Result<()> {
+        let reason = KillReason::Emergency;
+        let strategy = self.strategy_for(reason);
+        let weights = reason.victim_weights(&self.config.victim_weights);
+        let budget = self.emergency_kill_budget(stats);
+        let mut killed_count = 0;
+        let mut skipped = SkipCounts::default();
+
+        for process in self.candidates(stats, strategy, weights) {
+            if killed_count >= budget || self.kill_limit_reached() {
+                break;
+            }
+
+            // Skip protected processes
+            if let Some(skip) = self.skip_reason(process) {
+                skipped.add(skip);
+                continue;
+            }
+
+            let mut record = KillRecord {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason,
+                strategy,
+                success: false,
+            };
+            if self.is_alert_only() {
+                self.hold_back_kill(process.start_time, record, report);
+                killed_count += 1;
+                continue;
+            }
+
+            // Kill the process
+            let restart = self.restart_command(process);
+            match self.kill(process.pid) {
+                Ok(_) => {
+                    record.success = true;
+                    self.emit_killed(record.clone());
+                    if !self.simulate {
+                        self.remember_kill(process);
+                    }
+                    if let Some(command) = restart {
+                        self.restarts.push(command);
+                    }
+                    self.count_kill();
+                    killed_count += 1;
+                }
+                Err(e) => {
+                    self.emit(EnforcerEvent::KillFailed { record: record.clone(), error: e.to_string() });
+                    let _ = self.notification_manager.notify_kill_failed(process.pid, &process.name, &e.to_string());
+                    self.remember_unkillable(process, &e);
+                    skipped.add(if e.permanent { SkipReason::PermissionDenied } else { SkipReason::KillFailed });
+                }
+            }
+            self.log_kill(process.pid, &process.name, record.success, &record.describe());
+            report.kills.push(record);
+        }
+
+        // A CPU floor or a used-up cap is already reported; only an empty-handed search is news
+        if killed_count == 0 && budget > 0 && !self.kill_limit_reached() {
+            self.report_nothing_killable(reason, skipped, report);
+        } else {
+            report.skipped.merge(&skipped);
+        }
+
+        if killed_count > 0 && !self.is_alert_only() {
+            self.emergency_kills += killed_count;
+            let description = format!("{}, {}", reason, strategy.describe());
+            if !self.simulate {
+                let _ = self.notification_manager.notify_process_killed(0, "emergency", killed_count, &description);
+            }
+
+            if self.emergency_kills >= self.config.emergency.max_total_kills {
+                self.emit(EnforcerEvent::EmergencyKillCapReached { kills: self.emergency_kills });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Keep the latest cpu_percentile.samples CPU readings; an unreadable one is skipped
+    fn sample_cpu(&mut self, cpu_usage: f64) {
+        if !cpu_usage.is_finite() {
+            return;
+        }
+        self.cpu_samples.push_back(cpu_usage);
+        while self.cpu_samples.len() > self.config.cpu_percentile.samples {
+            self.cpu_samples.pop_front();
+        }
+    }
+
+    // What the CPU limit is checked against: cpu_percentile.percentile of the
+    // recent samples, which with the default single sample is the latest reading
+    fn cpu_for_limit(&self, stats: &SystemStats) -> f64 {
+        let mut samples: Vec<f64> = self.cpu_samples.iter().copied().collect();
+        crate::stats::percentile(&mut samples, self.config.cpu_percentile.percentile).unwrap_or(stats.cpu_usage)
+    }
+
+    // Enforce resource limits for the current profile
+    fn enforce_resource_limits(&mut self, stats: &SystemStats, report: &mut EnforcementReport) -> anyhow::Result<()> {
+        let limits = &self.current_profile.limits;
+        let cpu_limit = limits.effective_cpu_percent(stats.cpu_capacity());
+        let ram_limit = limits.effective_ram_percent(stats.total_memory_gb);
+        let (max_cpu_cores, max_ram_gb) = (limits.max_cpu_cores, limits.max_ram_gb);
+        let mut breaches = Vec::new();
+
+        // Check CPU limit (the percentage or max_cpu_cores, whichever is stricter)
+        let cpu = self.cpu_for_limit(stats);
+        if exceeds(cpu, cpu_limit) {
+            self.emit(EnforcerEvent::LimitExceeded {
+                resource: LimitResource::Cpu,
+                value: cpu,
+                limit: cpu_limit,
+                used: stats.cpu_cores_used(),
+                cap: max_cpu_cores.filter(|_| cpu_limit < self.current_profile.limits.max_cpu_percent),
+            });
+            let _ = self.notification_manager.notify_resource_limit_exceeded(
+                "CPU",
+                cpu,
+                cpu_limit,
+            );
+            self.limit_breached(stats, KillReason::CpuLimit, &mut breaches, report)?;
+        }
+
+        // Check RAM limit (the percentage or max_ram_gb, whichever is stricter)
+        if exceeds(stats.memory_percentage, ram_limit) {
+            self.emit(EnforcerEvent::LimitExceeded {
+                resource: LimitResource::Ram,
+                value: stats.memory_percentage,
+                limit: ram_limit,
+                used: stats.used_memory_gb,
+                cap: max_ram_gb.filter(|_| ram_limit < self.current_profile.limits.max_ram_percent),
+            });
+            let _ = self.notification_manager.notify_resource_limit_exceeded(
+                "RAM",
+                stats.memory_percentage,
+                ram_limit,
+            );
+            self.limit_breached(stats, KillReason::RamLimit, &mut breaches, report)?;
+        }
+
+        // Check memory pressure (PSI); skipped on kernels without it
+        if let (Some(pressure), Some(max)) = (stats.mem_pressure, self.current_profile.limits.max_mem_pressure) {
+            if exceeds(pressure, max) {
+                self.emit(EnforcerEvent::LimitExceeded {
+                    resource: LimitResource::MemoryPressure,
+                    value: pressure,
+                    limit: max,
+                    used: pressure,
+                    cap: None,
+                });
+                let _ = self.notification_manager.notify_resource_limit_exceeded("Memory pressure", pressure, max);
+                self.limit_breached(stats, KillReason::MemoryPressure, &mut breaches, report)?;
+            }
+        }
+
+        // Check temperature warning (not critical)
+        let (warning, critical) = self.temperature_thresholds();
+        let temperature = stats.effective_temperature(self.config.temperature_strategy);
+        if self.config.temperature.enabled && exceeds(temperature, warning) && temperature < critical {
+            self.emit(EnforcerEvent::LimitExceeded {
+                resource: LimitResource::Temperature,
+                value: temperature,
+                limit: warning,
+                used: temperature,
+                cap: None,
+            });
+            let _ = self.notification_manager.notify_temperature_warning(
+                temperature,
+                warning,
+            );
+            // Always a kill, never a cgroup limit (see act_on_limit)
+            self.limit_breached(stats, KillReason::TemperatureWarning, &mut breaches, report)?;
+        }
+
+        // kill_once: a single action for everything exceeded this cycle
+        if let Some(&reason) = breaches.first().filter(|_| self.config.multi_breach == MultiBreach::KillOnce) {
+            let strategy = self.strategy_for_all(&breaches);
+            let weights = self.weights_for_all(&breaches);
+            self.act_on_limit(stats, reason, strategy, weights, report)?;
+        }
+
+        // Per-process disk I/O, acted on separately: only processes over the limit qualify
+        if let Some(max_io) = self.current_profile.limits.max_io_mbps {
+            self.enforce_io_limit(stats, max_io, report)?;
+        }
+
+        // Lift cgroup limits once usage has normalized
+        if !self.cgroup_limited.is_empty() && self.usage_normalized(stats) {
+            self.release_cgroup_limits();
+        }
+
+        Ok(())
+    }
+
+    // Act on a process reading and writing more than `max_io` MB/s, picked among
+    // those over the limit by the profile's strategy (heaviest I/O by default)
+    fn enforce_io_limit(&mut self, stats: &SystemStats, max_io: f64, report: &mut EnforcementReport) -> anyhow::Result<()> {
+        let over: Vec<ProcessInfo> =
+            stats.top_processes.iter().filter(|p| exceeds(p.io_mbps(), max_io)).cloned().collect();
+        let Some(heaviest) = over.iter().max_by(|a, b| a.io_mbps().total_cmp(&b.io_mbps())) else {
+            return Ok(());
+        };
+        self.emit(EnforcerEvent::IoLimitExceeded {
+            pid: heaviest.pid,
+            name: heaviest.name.clone(),
+            read_mbps: heaviest.io_read_mbps,
+            write_mbps: heaviest.io_write_mbps,
+            limit: max_io,
+        });
+        let _ = self.notification_manager.notify_io_limit_exceeded(&heaviest.name, heaviest.io_mbps(), max_io);
+
+        let reason = KillReason::IoLimit;
+        let mut candidates = stats.clone();
+        candidates.top_processes = over;
+        let weights = reason.victim_weights(&self.config.victim_weights);
+        self.act_on_limit(&candidates, reason, self.strategy_for(reason), weights, report)
+    }
+
+    // Warn once per profile about absolute limits this machine can never reach
+    fn warn_unreachable_limits(&mut self, stats: &SystemStats) {
+        let mut warnings = self.current_profile.limits.capacity_warnings(stats.cpu_capacity(), stats.total_memory_gb);
+        if self.current_profile.limits.max_mem_pressure.is_some() && stats.mem_pressure.is_none() {
+            warnings.push("max_mem_pressure is ignored: this kernel doesn't report pressure (PSI)".to_string());
+        }
+        for warning in warnings {
+            self.emit(EnforcerEvent::LimitUnreachable { profile: self.current_profile.name.clone(), warning });
+        }
+        self.capacity_checked = true;
+    }
+
+    // Record an exceeded limit; with kill_each it is acted on right away
+    fn limit_breached(
+        &mut self,
+        stats: &SystemStats,
+        reason: KillReason,
+        breaches: &mut Vec<KillReason>,
+        report: &mut EnforcementReport,
+    ) -> anyhow::Result<()> {
+        breaches.push(reason);
+        if self.config.multi_breach == MultiBreach::KillEach {
+            let weights = reason.victim_weights(&self.config.victim_weights);
+            self.act_on_limit(stats, reason, self.strategy_for(reason), weights, report)?;
+        }
+        Ok(())
+    }
+
+    // Kill or cgroup-limit the heaviest process, depending on the profile's limit action.
+    // A temperature warning always kills: a throttled process still heats the machine.
+    fn act_on_limit(
+        &mut self,
+        stats: &SystemStats,
+        reason: KillReason,
+        strategy: KillStrategy,
+        weights: ScoreWeights,
+        report: &mut EnforcementReport,
+    ) -> anyhow::Result<()> {
+        if self.current_profile.enforce && self.is_alert_only() {
+            self.alert_on_limit(stats, reason, strategy, weights, report);
+        } else if self.current_profile.enforce
+            && self.current_profile.limits.action == LimitAction::CgroupLimit
+            && reason != KillReason::TemperatureWarning
+        {
+            self.limit_heaviest_process(stats, reason, strategy, weights, report);
+        } else {
+            self.kill_heaviest_process(stats, reason, strategy, weights, report)?;
+        }
+        Ok(())
+    }
+
+    // Alert-only mode: pick the process act_on_limit would have killed or limited,
+    // and report and notify it instead
+    fn alert_on_limit(
+        &mut self,
+        stats: &SystemStats,
+        reason: KillReason,
+        strategy: KillStrategy,
+        weights: ScoreWeights,
+        report: &mut EnforcementReport,
+    ) {
+        let Some(process) = self.candidates(stats, strategy, weights).into_iter().find(|p| self.is_killable(p)).cloned() else {
+            return;
+        };
+        if self.current_profile.limits.action == LimitAction::CgroupLimit
+            && !matches!(reason, KillReason::TemperatureWarning | KillReason::SwapEmergency)
+        {
+            self.emit(EnforcerEvent::WouldLimit { pid: process.pid, name: process.name.clone(), reason });
+            if !self.hands_off() && self.alerted.insert((process.pid, process.start_time)) {
+                killer::log_alert_action(Some(&self.config), process.pid, &process.name, "limit", &reason.to_string());
+            }
+            report.would_limit.push(process.pid);
+            return;
+        }
+        let record = KillRecord { pid: process.pid, name: process.name, reason, strategy, success: false };
+        let _ = self.notification_manager.notify_would_kill(record.pid, &record.name, &record.describe());
+        self.hold_back_kill(process.start_time, record, report);
+    }
+
+    // Report a kill alert-only mode didn't carry out, logging it to the kill log
+    // once per process rather than on every cycle it stays over the limit
+    fn hold_back_kill(&mut self, start_time: u64, record: KillRecord, report: &mut EnforcementReport) {
+        self.emit(EnforcerEvent::WouldKill(record.clone()));
+        if !self.hands_off() && self.alerted.insert((record.pid, start_time)) {
+            killer::log_alert_action(Some(&self.config), record.pid, &record.name, "kill", &record.describe());
+        }
+        report.would_kill.push(record);
+    }
+
+    // Move the heaviest killable process that isn't limited yet into the limited cgroup.
+    // Without cgroup support this only reports the failure; it never falls back to killing.
+    fn limit_heaviest_process(
+        &mut self,
+        stats: &SystemStats,
+        reason: KillReason,
+        strategy: KillStrategy,
+        weights: ScoreWeights,
+        report: &mut EnforcementReport,
+    ) {
+        let Some(process) = self.candidates(stats, strategy, weights)
+            .into_iter()
+            .find(|p| self.is_killable(p) && !self.cgroup_limited.contains_key(&p.pid))
+            .cloned()
+        else {
+            return;
+        };
+
+        let cpu_percent = self.current_profile.limits.cgroup_cpu_percent;
+        let memory_high = self.current_profile.limits.cgroup_memory_high_mb.map(|mb| mb * 1024 * 1024);
+        let placement = if self.simulate {
+            self.emit(EnforcerEvent::Simulated(SimulatedAction::Limit { pid: process.pid, name: process.name, reason }));
+            report.cgroup_limited.push(process.pid);
+            return;
+        } else if self.dry_run {
+            None
+        } else {
+            match killer::limit_process_cgroup(process.pid, cpu_percent, memory_high) {
+                Ok(placement) => Some(placement),
+                Err(e) => {
+                    self.emit(EnforcerEvent::CgroupLimitFailed { pid: process.pid, name: process.name, error: e.to_string() });
+                    return;
+                }
+            }
+        };
+        self.emit(EnforcerEvent::ProcessLimited {
+            pid: process.pid,
+            name: process.name.clone(),
+            reason,
+            cpu_percent,
+            memory_high_bytes: memory_high,
+        });
+        if let Some(placement) = placement {
+            self.cgroup_limited.insert(process.pid, placement);
+        }
+        report.cgroup_limited.push(process.pid);
+    }
+
+    // Usage is comfortably back under the profile limits
+    fn usage_normalized(&self, stats: &SystemStats) -> bool {
+        let limits = &self.current_profile.limits;
+        stats.cpu_usage < limits.effective_cpu_percent(stats.cpu_capacity()) * CGROUP_RELEASE_RATIO
+            && stats.memory_percentage < limits.effective_ram_percent(stats.total_memory_gb) * CGROUP_RELEASE_RATIO
+            && stats
+                .mem_pressure
+                .zip(limits.max_mem_pressure)
+                .filter(|(pressure, max)| *pressure >= max * CGROUP_RELEASE_RATIO)
+                .is_none()
+    }
+
+    // Move every cgroup-limited process back to where it came from
+    fn release_cgroup_limits(&mut self) {
+        for (pid, placement) in std::mem::take(&mut self.cgroup_limited) {
+            match killer::release_process_cgroup(pid, &placement) {
+                Ok(()) => self.emit(EnforcerEvent::CgroupLimitReleased { pid }),
+                Err(e) => self.emit(EnforcerEvent::CgroupReleaseFailed { pid, error: e.to_string() }),
+            }
+        }
+    }
+
+    // Kill the heaviest process for the violated resource (excluding protected/critical)
+    fn kill_heaviest_process(
+        &mut self,
+        stats: &SystemStats,
+        reason: KillReason,
+        strategy: KillStrategy,
+        weights: ScoreWeights,
+        report: &mut EnforcementReport,
+    ) -> anyhow::Result<bool> {
+        if !self.current_profile.enforce {
+            self.emit(EnforcerEvent::Skipped(Skip::MonitorOnly { profile: self.current_profile.name.clone(), reason }));
+            return Ok(false);
+        }
+        if self.kill_limit_reached() {
+            return Ok(false);
+        }
+
+        let mut skipped = SkipCounts::default();
+        for process in self.candidates(stats, strategy, weights) {
+            // Skip protected processes
+            if let Some(skip) = self.skip_reason(process) {
+                skipped.add(skip);
+                continue;
+            }
+
+            let mut record = KillRecord {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason,
+                strategy,
+                success: false,
+            };
+            let description = record.describe();
+
+            // Kill this process
+            let restart = self.restart_command(process);
+            match self.kill(process.pid) {
+                Ok(_) => {
+                    record.success = true;
+                    self.emit_killed(record.clone());
+                    if !self.simulate {
+                        self.remember_kill(process);
+                    }
+                    if let Some(command) = restart {
+                        self.restarts.push(command);
+                    }
+                    self.count_kill();
+                    self.log_kill(process.pid, &process.name, true, &description);
+                    if !self.simulate {
+                        let _ = self.notification_manager.notify_process_killed(process.pid, &process.name, 1, &description);
+                    }
+                    report.kills.push(record);
+                    report.skipped.merge(&skipped);
+                    return Ok(true);
+                }
+                Err(e) => {
+                    self.emit(EnforcerEvent::KillFailed { record: record.clone(), error: e.to_string() });
+                    let _ = self.notification_manager.notify_kill_failed(process.pid, &process.name, &e.to_string());
+                    self.remember_unkillable(process, &e);
+                    self.log_kill(process.pid, &process.name, false, &description);
+                    report.kills.push(record);
+                    skipped.add(if e.permanent { SkipReason::PermissionDenied } else { SkipReason::KillFailed });
+                    // Continue to try the next process
+                }
+            }
+        }
+
+        self.report_nothing_killable(reason, skipped, report);
+        Ok(false)
+    }
+
+    // Get the current emergency status
+    pub fn is_emergency_mode(&self) -> bool {
+        self.emergency_mode
+    }
+
+    /// Whether memory emergency mode (swap_emergency) is active
+    pub fn is_swap_emergency(&self) -> bool {
+        self.swap_emergency
+    }
+
+    // Get time in emergency mode (if active)
+    pub fn emergency_duration(&self) -> Option<Duration> {
+        self.emergency_since.map(|since| since.elapsed())
+    }
+
+    /// One-line state for the service manager, e.g. "Enforcing 'coding' - emergency mode (12s)"
+    pub fn status_line(&self) -> String {
+        let profile = self.profile();
+        let mut line = format!(
+            "{} '{}'",
+            match (profile.enforce, self.is_alert_only()) {
+                (false, _) => "Monitoring",
+                (true, true) => "Alerting on",
+                (true, false) => "Enforcing",
+            },
+            profile.name
+        );
+        if self.profile_locked {
+            line.push_str(" (locked)");
+        }
+        if self.simulate {
+            line.push_str(" (simulated)");
+        }
+        match self.emergency_duration() {
+            Some(duration) => line.push_str(&format!(" - emergency mode ({}s)", duration.as_secs())),
+            None => line.push_str(" - normal"),
+        }
+        line
+    }
+
+    /// Alert-only mode (kern.yaml's or the active profile's enforcement.mode):
+    /// limits are evaluated and notified, but no process is killed, limited or throttled
+    pub fn is_alert_only(&self) -> bool {
+        self.alert_only_for(&self.current_profile)
+    }
+
+    // Alert wins: kern.yaml's alert mode applies to every profile
+    fn alert_only_for(&self, profile: &Profile) -> bool {
+        self.config.enforcement.mode == EnforcementMode::Alert || profile.enforcement.mode == EnforcementMode::Alert
+    }
+
+    /// Simulate mode: the full enforcement loop runs and keeps its state
+    /// (emergency mode, kill counts, cooldowns), but no process is signalled,
+    /// limited, throttled or relaunched. Each of those steps is reported as an
+    /// `EnforcerEvent::Simulated` instead, and nothing reaches the kill log.
+    pub fn set_simulate(&mut self, simulate: bool) {
+        self.simulate = simulate;
+    }
+
+    pub fn is_simulating(&self) -> bool {
+        self.simulate
+    }
+
+    /// Keep the current profile: while locked, switch_profile does nothing.
+    /// Limits are still enforced, and update_profile still applies edits.
+    pub fn set_profile_lock(&mut self, locked: bool) {
+        self.profile_locked = locked;
+    }
+
+    pub fn profile_locked(&self) -> bool {
+        self.profile_locked
+    }
+
+    // Switch to a new profile
+    pub fn switch_profile(&mut self, new_profile: Profile) -> anyhow::Result<()> {
+        if self.profile_locked {
+            self.log(
+                LogLevel::Debug,
+                &format!("Profile locked to '{}', not switching to '{}'", self.current_profile.name, new_profile.name),
+            );
+            return Ok(());
+        }
+        let old_name = self.current_profile.name.clone();
+        self.emit(EnforcerEvent::ProfileSwitched { from: old_name.clone(), to: new_profile.name.clone() });
+        self.kill_on_activate(&new_profile, &mut EnforcementReport::default());
+
+        self.restore_cpu_frequency();
+        self.release_cgroup_limits();
+        self.current_profile = new_profile;
+        self.capacity_checked = false;
+        self.emergency_mode = false;
+        self.emergency_since = None;
+        self.emergency_kills = 0;
+        self.critical_samples = 0;
+        self.cool_samples = 0;
+        
+        let _ = self.notification_manager.notify_profile_switched(&old_name, &self.current_profile.name);
+        
+        Ok(())
+    }
+
+    // Kill processes marked for killing on activate (only if not protected/critical).
+    // Monitor-only profiles never kill, so kill_on_activate is ignored for them.
+    fn kill_on_activate(&mut self, profile: &Profile, report: &mut EnforcementReport) {
+        let kill_on_activate = if profile.enforce { profile.kill_on_activate.as_slice() } else { &[] };
+        for proc_name in kill_on_activate {
+            let pids: Vec<u32> = killer::find_processes_by_name(proc_name)
+                .into_iter()
+                .filter(|pid| self.owns(monitor::read_process_uid(*pid)))
+                .collect();
+
+
+            for pid in pids {
+                if self.kill_limit_reached() {
+                    break;
+                }
+                if self.config.is_critical_process(proc_name) {
+                    self.emit(EnforcerEvent::Skipped(Skip::Critical { name: proc_name.clone() }));
+                    continue;
+                }
+
+                if killer::is_protected(proc_name, &profile.protected)
+                    || killer::is_protected(proc_name, &self.config.protected_processes)
+                    || self.protected_by_ancestry(pid, profile)
+                {
+                    self.emit(EnforcerEvent::Skipped(Skip::Protected { name: proc_name.clone() }));
+                    continue;
+                }
+
+                let reason = KillReason::ProfileActivation;
+                let mut record = KillRecord {
+                    pid,
+                    name: proc_name.clone(),
+                    reason,
+                    strategy: reason.default_strategy(),
+                    success: false,
+                };
+                if self.alert_only_for(profile) {
+                    self.hold_back_kill(0, record, report);
+                    continue;
+                }
+                match self.kill(pid) {
+                    Ok(_) => {
+                        record.success = true;
+                        self.emit_killed(record.clone());
+                        self.count_kill();
+                        self.log_kill(pid, proc_name, true, "profile activation");
+                    }
+                    Err(e) => {
+                        self.emit(EnforcerEvent::KillFailed { record: record.clone(), error: e.to_string() });
+                        let _ = self.notification_manager.notify_kill_failed(pid, proc_name, &e.to_string());
+                    }
+                }
+                report.kills.push(record);
+            }
+        }
+    }
+
+    /// What the current profile would do to the machine right now: its
+    /// kill_on_activate list, then one enforcement cycle against `stats`.
+    ///
+    /// Decisions go through the same code as a real run, but this enforcer is
+    /// switched to a dry run for good: nothing is signalled, limited, throttled,
+    /// logged to the kill log or notified. Every kill in the report is one that
+    /// would have been attempted; an alert-only profile reports its would-be
+    /// kills and limits in `would_kill` and `would_limit` instead.
+    pub fn preview(&mut self, stats: &SystemStats) -> anyhow::Result<EnforcementReport> {
+        self.dry_run = true;
+        self.notification_manager.set_enabled(false);
+
+        let mut report = EnforcementReport::default();
+        let profile = self.current_profile.clone();
+        self.kill_on_activate(&profile, &mut report);
+        // Gone by the time the first cycle runs, so never picked twice
+        let activated: HashSet<u32> = report.kills.iter().chain(&report.would_kill).map(|kill| kill.pid).collect();
+        for process in stats.top_processes.iter().filter(|p| activated.contains(&p.pid)) {
+            self.unkillable.insert((process.pid, process.start_time));
+        }
+
+        let cycle = self.enforce_stats(stats)?;
+        report.kills.extend(cycle.kills);
+        report.would_kill.extend(cycle.would_kill);
+        report.would_limit = cycle.would_limit;
+        report.cgroup_limited = cycle.cgroup_limited;
+        report.cpu_throttled = cycle.cpu_throttled;
+        report.critical_samples = cycle.critical_samples;
+        report.cool_samples = cycle.cool_samples;
+        Ok(report)
+    }
+
+    /// Apply edits to the current profile (e.g. limits changed at runtime)
+    /// without switch_profile's side effects: kill_on_activate doesn't run
+    /// and emergency state is kept
+    pub fn update_profile(&mut self, profile: Profile) {
+        self.current_profile = profile;
+        self.capacity_checked = false;
+    }
+
+    /// Get current profile
+    pub fn profile(&self) -> &Profile {
+        &self.current_profile
+    }
+
+    /// Processes killed during this run (see kill_count_limit)
+    pub fn total_kills(&self) -> usize {
+        self.total_kills
+    }
+
+    /// Get system stats at the time of last enforcement
+    pub fn last_enforcement_time(&self) -> Instant {
+        self.last_enforcement
+    }
+}
+
+// Window for EnforcerStatus::actions_last_hour
+const ACTION_WINDOW: Duration = Duration::from_secs(3600);
+
+/// The most recent action the enforcer took
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastAction {
+    pub timestamp: u64, // Unix epoch seconds
+    pub kind: &'static str,
+    pub process: Option<String>,
+}
+
+/// The last time a kill was called for but every candidate was skipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastSkip {
+    pub timestamp: u64, // Unix epoch seconds
+    pub reason: KillReason,
+    pub skipped: SkipCounts,
+}
+
+/// What a long-running enforcer is doing, for status queries from another
+/// thread (the DBus service). Fed by `record` from the enforcer's observer
+/// and `sync` after each cycle.
+#[derive(Debug, Clone, Default)]
+pub struct EnforcerStatus {
+    pub running: bool,
+    pub paused: bool, // The active profile is monitor-only
+    pub alert_only: bool, // Limits are only alerted on (see Enforcer::is_alert_only)
+    pub simulate: bool,   // See Enforcer::set_simulate
+    pub emergency_since: Option<Instant>,
+    pub swap_emergency: bool,
+    pub active_profile: String,
+    pub last_action: Option<LastAction>,
+    pub last_nothing_killable: Option<LastSkip>,
+    actions: VecDeque<Instant>, // Actions within ACTION_WINDOW, oldest first
+}
+
+/// `EnforcerStatus` shared between the enforcer thread and its readers
+pub type SharedEnforcerStatus = Arc<Mutex<EnforcerStatus>>;
+
+impl EnforcerStatus {
+    /// Note an event; kills, cgroup limits and CPU throttling count as actions,
+    /// and so do the kills and limits alert-only mode held back
+    pub fn record(&mut self, event: &EnforcerEvent) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        if let EnforcerEvent::NothingKillable { reason, skipped } = event {
+            self.last_nothing_killable = Some(LastSkip { timestamp, reason: *reason, skipped: skipped.clone() });
+            return;
+        }
+        let process = match event {
+            EnforcerEvent::ProcessKilled(record) | EnforcerEvent::WouldKill(record) => Some(record.name.clone()),
+            EnforcerEvent::ProcessLimited { name, .. }
+            | EnforcerEvent::WouldLimit { name, .. }
+            | EnforcerEvent::Simulated(SimulatedAction::Limit { name, .. }) => Some(name.clone()),
+            EnforcerEvent::Simulated(SimulatedAction::Kill(record)) => Some(record.name.clone()),
+            EnforcerEvent::CpuThrottled { .. } | EnforcerEvent::Simulated(SimulatedAction::ThrottleCpu) => None,
+            _ => return,
+        };
+        self.last_action = Some(LastAction { timestamp, kind: event.kind(), process });
+
+        let now = Instant::now();
+        self.actions.push_back(now);
+        while self.actions.front().is_some_and(|at| now.duration_since(*at) > ACTION_WINDOW) {
+            self.actions.pop_front();
+        }
+    }
+
+    /// Copy the enforcer's profile and emergency state
+    pub fn sync(&mut self, enforcer: &Enforcer) {
+        self.running = true;
+        self.paused = !enforcer.profile().enforce;
+        self.alert_only = enforcer.is_alert_only();
+        self.simulate = enforcer.is_simulating();
+        self.active_profile = enforcer.profile().name.clone();
+        self.emergency_since = enforcer.emergency_duration().and_then(|elapsed| Instant::now().checked_sub(elapsed));
+        self.swap_emergency = enforcer.is_swap_emergency();
+    }
+
+    pub fn emergency_mode(&self) -> bool {
+        self.emergency_since.is_some()
+    }
+
+    /// Actions taken during the last hour
+    pub fn actions_last_hour(&self) -> usize {
+        self.actions.iter().filter(|at| at.elapsed() <= ACTION_WINDOW).count()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "running": self.running,
+            "paused": self.paused,
+            "mode": if self.alert_only { EnforcementMode::Alert } else { EnforcementMode::Enforce },
+            "simulate": self.simulate,
+            "emergency_mode": self.emergency_mode(),
+            "emergency_duration_secs": self.emergency_since.map(|since| since.elapsed().as_secs()),
+            "swap_emergency": self.swap_emergency,
+            "active_profile": self.active_profile,
+            "last_action": self.last_action.as_ref().map(|action| json!({
+                "timestamp": action.timestamp,
+                "kind": action.kind,
+                "process": action.process,
+            })),
+            "actions_last_hour": self.actions_last_hour(),
+            "last_nothing_killable": self.last_nothing_killable.as_ref().map(|skip| json!({
+                "timestamp": skip.timestamp,
+                "reason": skip.reason,
+                "skipped": skip.skipped,
+            })),
+        })
+    }
+}
+
+/// What ended an enforcer run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Signal,   // The `RunLimit::stop` flag was set, e.g. by a SIGINT/SIGTERM handler
+    Duration, // `RunLimit::duration` elapsed
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Signal => write!(f, "signal"),
+            StopReason::Duration => write!(f, "duration reached"),
+        }
+    }
+}
+
+/// How `run_enforcer_loop` sets up its enforcer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    pub profile_locked: bool, // Start with the profile locked (see Enforcer::set_profile_lock)
+    pub simulate: bool,       // Act on nothing (see Enforcer::set_simulate)
+}
+
+/// When `run_enforcer_loop` returns. The default runs until the process is killed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunLimit {
+    pub duration: Option<Duration>,         // Stop once this much time has passed
+    pub stop: Option<&'static AtomicBool>, // Stop once this is set (checked between cycles and while waiting)
+}
+
+impl RunLimit {
+    fn stopped(&self) -> bool {
+        self.stop.is_some_and(|stop| stop.load(Ordering::SeqCst))
+    }
+
+    // Why the run is over at `now`, if it is
+    fn stop_reason(&self, started: Instant, now: Instant) -> Option<StopReason> {
+        if self.stopped() {
+            Some(StopReason::Signal)
+        } else if self.duration.is_some_and(|duration| now.duration_since(started) >= duration) {
+            Some(StopReason::Duration)
+        } else {
+            None
+        }
+    }
+}
+
+/// Run the enforcer in a continuous loop (blocking)
+/// Periodically checks system stats and enforces resource limits.
+/// With an `observer`, every event is handed to it; otherwise
+/// human-readable messages go to `logger`. `options` can lock the profile
+/// or make the run a simulation; `idle_switcher` switches profiles on session idle time. It returns once
+/// `limit` says the run is over, after undoing any CPU throttle and cgroup
+/// limits and reporting a `SessionEnded` summary.
+pub fn run_enforcer_loop(
+    config: KernConfig,
+    initial_profile: Profile,
+    options: RunOptions,
+    idle_switcher: IdleSwitcher,
+    observer: Option<EventObserver>,
+    logger: SharedLogger,
+    limit: RunLimit,
+) -> anyhow::Result<()> {
+    let observed = observer.is_some();
+    let mut enforcer = Enforcer::new(config.clone(), initial_profile);
+    enforcer.set_profile_lock(options.profile_locked);
+    enforcer.set_simulate(options.simulate);
+    enforcer.set_idle_switcher(idle_switcher);
+    enforcer.observer = observer.map(Observer);
+    enforcer.set_logger(logger.clone());
+    let interval = Duration::from_millis(config.monitor_interval);
+    let systemd = crate::systemd::Notifier::from_env();
+    enforcer.set_watchdog(systemd.clone());
+
+    // Undo a throttle left behind by a previous run that crashed mid-emergency
+    enforcer.restore_cpu_frequency();
+
+    if !observed {
+        if let Ok(mut logger) = logger.lock() {
+            logger.log(&format!("Starting enforcer loop (interval: {:?})", interval));
+            let profile = enforcer.profile();
+            logger.log(&format!(
+                "Enforcing profile '{}': CPU {}%, RAM {}%{}{}",
+                profile.name,
+                profile.limits.max_cpu_percent,
+                profile.limits.max_ram_percent,
+                match (profile.enforce, enforcer.is_alert_only()) {
+                    (false, _) => " (monitor-only)",
+                    (true, true) => " (alert-only: nothing is killed)",
+                    (true, false) => "",
+                },
+                if options.profile_locked { " (locked)" } else { "" }
+            ));
+            if options.simulate {
+                logger.log("Simulating: no process is killed, limited or throttled; [SIMULATE] lines show what would be");
+            }
+            match limit.duration {
+                Some(duration) => logger.log(&format!(
+                    "Stopping after {} (or Ctrl+C)",
+                    humantime::format_duration(duration)
+                )),
+                None => logger.log("Press Ctrl+C to stop"),
+            }
+            if !config.enforcement.all_users {
+                let user = monitor::user_name(enforcer.uid).unwrap_or_else(|| format!("UID {}", enforcer.uid));
+                logger.log(&format!("Only acting on processes owned by {} (enforcement.all_users: false)", user));
+            }
+        }
+    }
+    if config.enforcement.all_users && !killer::can_kill_any_user() {
+        if let Ok(mut logger) = logger.lock() {
+            logger.warn("⚠️  enforcement.all_users without root or CAP_KILL: other users' processes can't be killed");
+        }
+    }
+    systemd.ready();
+
+    let started = Instant::now();
+    let mut cycles = 0u64;
+    let stop = loop {
+        if let Some(stop) = limit.stop_reason(started, Instant::now()) {
+            break stop;
+        }
+        cycles += 1;
+        match enforcer.enforce_once() {
+            Ok(report) => {
+                if let Ok(mut logger) = logger.lock() {
+                    if report.action_taken() && enforcer.is_emergency_mode() && !observed {
+                        if let Some(duration) = enforcer.emergency_duration() {
+                            logger.warn(&format!("[Emergency mode - {:.1}s]", duration.as_secs_f64()));
+                        }
                     }
+                    logger.debug(&format!(
+                        "Cycle complete: {} kill attempts, critical samples {}, cool samples {}",
+                        report.kills.len(),
+                        report.critical_samples,
+                        report.cool_samples
+                    ));
                 }
             }
             Err(e) => {
-                eprintln!("Enforcer error: {}", e);
+                enforcer.emit(EnforcerEvent::Error { message: e.to_string() });
                 // Continue on error instead of crashing
             }
         }
+        systemd.status(&enforcer.status_line());
+
+        // Pings the systemd watchdog while waiting; a cycle that hangs stops the pings
+        let wait = match limit.duration {
+            Some(duration) => interval.min(duration.saturating_sub(started.elapsed())),
+            None => interval,
+        };
+        systemd.sleep_unless(wait, || limit.stopped());
+    };
+
+    systemd.stopping();
+    enforcer.restore_cpu_frequency();
+    enforcer.release_cgroup_limits();
+    enforcer.emit(EnforcerEvent::SessionEnded {
+        stop,
+        duration_secs: started.elapsed().as_secs(),
+        cycles,
+        kills: enforcer.total_kills(),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enforcer_creation() {
+        let config = KernConfig::default();
+        let profile = Profile::default();
+        let enforcer = Enforcer::new(config, profile);
+
+        assert!(!enforcer.is_emergency_mode());
+        assert!(enforcer.emergency_duration().is_none());
+    }
+
+    #[test]
+    fn test_status_line() {
+        let profile = Profile { name: "coding".to_string(), ..Default::default() };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        assert_eq!(enforcer.status_line(), "Enforcing 'coding' - normal");
+
+        enforcer.set_profile_lock(true);
+        enforcer.emergency_mode = true;
+        enforcer.emergency_since = Some(Instant::now());
+        assert_eq!(enforcer.status_line(), "Enforcing 'coding' (locked) - emergency mode (0s)");
+    }
+
+    #[test]
+    fn test_emergency_mode_activation() {
+        let mut config = KernConfig::default();
+        config.temperature.critical = 80.0;
+        
+        let profile = Profile::default();
+        let mut enforcer = Enforcer::new(config, profile);
+
+        assert!(!enforcer.is_emergency_mode());
+
+        // In real usage, emergency_since would be set when temp exceeds critical
+        enforcer.emergency_mode = true;
+        enforcer.emergency_since = Some(Instant::now());
+
+        assert!(enforcer.is_emergency_mode());
+        assert!(enforcer.emergency_duration().is_some());
+    }
+
+    #[test]
+    fn test_profile_switching() {
+        let config = KernConfig::default();
+        let profile1 = Profile {
+            name: "profile1".to_string(),
+            ..Default::default()
+        };
+        let profile2 = Profile {
+            name: "profile2".to_string(),
+            ..Default::default()
+        };
+
+        let mut enforcer = Enforcer::new(config, profile1);
+        assert_eq!(enforcer.profile().name, "profile1");
+
+        enforcer.switch_profile(profile2).ok();
+        assert_eq!(enforcer.profile().name, "profile2");
+    }
+
+    fn process(pid: u32, name: &str, memory_gb: f64, cpu_percentage: f64, start_time: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            memory_gb,
+            cpu_percentage,
+            start_time,
+            uid: Some(nix::unistd::getuid().as_raw()),
+            ..Default::default()
+        }
+    }
+
+    fn sample_processes() -> Vec<ProcessInfo> {
+        // Memory-sorted, like SystemStats::top_processes
+        vec![
+            process(1, "browser", 4.0, 1.0, 200),
+            process(2, "compiler", 1.0, 95.0, 300),
+            process(3, "editor", 0.5, 10.0, 100),
+        ]
+    }
+
+    #[test]
+    fn test_monitor_only_profile_never_kills() {
+        let mut config = KernConfig::default();
+        config.emergency.trigger_samples = 1;
+        let profile = Profile {
+            name: "watch".to_string(),
+            enforce: false,
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(config, profile);
+
+        // Synthetic PIDs above pid_max: nothing real could ever be signalled
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 99.0,
+            temperature_max: 70.0,
+            top_processes: vec![
+                process(10_000_001, "hog", 8.0, 99.0, 100),
+                process(10_000_002, "leak", 6.0, 50.0, 200),
+            ],
+            ..Default::default()
+        };
+
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert!(report.kills.is_empty());
+
+        // Emergency state is still tracked, but nothing is killed
+        let hot = SystemStats { temperature_max: 110.0, ..stats };
+        let report = enforcer.enforce_stats(&hot).unwrap();
+        assert!(enforcer.is_emergency_mode());
+        assert!(report.kills.is_empty());
+    }
+
+    #[test]
+    fn test_throttle_before_kill_on_emergency() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cpufreq = temp_dir.path().join("cpu/cpu0/cpufreq");
+        std::fs::create_dir_all(&cpufreq).unwrap();
+        std::fs::write(cpufreq.join("scaling_max_freq"), "3600000").unwrap();
+        std::fs::write(cpufreq.join("cpuinfo_min_freq"), "800000").unwrap();
+
+        let mut config = KernConfig::default();
+        config.emergency.trigger_samples = 1;
+        config.emergency.exit_samples = 1;
+        config.emergency.actions = vec![EmergencyAction::ThrottleCpu, EmergencyAction::Kill];
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        enforcer.cpu_throttle =
+            CpuThrottle::with_paths(temp_dir.path().join("cpu"), temp_dir.path().join("throttle.json"));
+
+        // Synthetic PID above pid_max: nothing real could ever be signalled
+        let hot = SystemStats {
+            cpu_usage: 100.0,
+            temperature_max: 110.0,
+            top_processes: vec![process(10_000_001, "hog", 8.0, 99.0, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.enforce_stats(&hot).unwrap();
+        assert!(enforcer.is_emergency_mode());
+        assert!(report.cpu_throttled);
+        assert!(report.kills.is_empty()); // Throttling gets the first cycle
+        assert_eq!(std::fs::read_to_string(cpufreq.join("scaling_max_freq")).unwrap(), "800000");
+
+        let cool = SystemStats { temperature_max: 40.0, ..Default::default() };
+        enforcer.enforce_stats(&cool).unwrap();
+        assert!(!enforcer.is_emergency_mode());
+        assert!(!enforcer.cpu_throttle.is_throttled());
+        assert_eq!(std::fs::read_to_string(cpufreq.join("scaling_max_freq")).unwrap(), "3600000");
+    }
+
+    #[test]
+    fn test_eperm_processes_are_skipped_in_later_cycles() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let owned_by_root = process(10_000_001, "daemon", 1.0, 90.0, 100);
+        let transient = killer::KillError { message: "EAGAIN".to_string(), permanent: false };
+        let eperm = killer::KillError { message: "EPERM".to_string(), permanent: true };
+
+        enforcer.remember_unkillable(&owned_by_root, &transient);
+        assert!(enforcer.is_killable(&owned_by_root));
+
+        enforcer.remember_unkillable(&owned_by_root, &eperm);
+        assert!(!enforcer.is_killable(&owned_by_root));
+
+        // Same PID reused by a different process
+        let recycled = process(10_000_001, "daemon", 1.0, 90.0, 200);
+        assert!(enforcer.is_killable(&recycled));
+    }
+
+    #[test]
+    fn test_processes_surviving_a_kill_are_written_off() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        // Synthetic PIDs above pid_max: nothing real could ever be signalled
+        let stuck = process(10_000_001, "stuck", 1.0, 1.0, 100);
+        let exited = process(10_000_002, "exited", 1.0, 1.0, 100);
+        enforcer.remember_kill(&stuck);
+        enforcer.remember_kill(&exited);
+
+        // Well within limits, so nothing is killed along the way
+        let stats = SystemStats {
+            cpu_usage: 10.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![stuck.clone()],
+            ..Default::default()
+        };
+        for _ in 1..UNKILLABLE_AFTER_CYCLES {
+            enforcer.enforce_stats(&stats).unwrap();
+            // A repeated kill keeps the original count
+            enforcer.remember_kill(&stuck);
+            assert!(enforcer.is_killable(&stuck));
+        }
+        assert!(!enforcer.recent_kills.contains_key(&(exited.pid, exited.start_time)));
+
+        enforcer.enforce_stats(&stats).unwrap();
+        assert!(!enforcer.is_killable(&stuck));
+        assert!(enforcer.recent_kills.is_empty());
+
+        // Forgotten once the process is finally gone
+        let gone = SystemStats { top_processes: Vec::new(), ..stats };
+        enforcer.enforce_stats(&gone).unwrap();
+        assert!(enforcer.is_killable(&stuck));
+    }
+
+    #[test]
+    fn test_children_of_protected_processes_are_spared() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        // The test binary is the child's parent; protect it by name
+        let own_name = std::fs::read_to_string("/proc/self/status")
+            .unwrap()
+            .lines()
+            .find_map(|line| line.strip_prefix("Name:").map(|name| name.trim().to_string()))
+            .unwrap();
+        let config = KernConfig { protected_processes: vec![own_name], ..Default::default() };
+        let sleeper = process(child.id(), "sleep", 0.1, 50.0, 100);
+
+        let enforcer = Enforcer::new(config.clone(), Profile::default());
+        assert!(!enforcer.is_killable(&sleeper));
+        let enforcer = Enforcer::new(KernConfig { ancestry_depth: 0, ..config }, Profile::default());
+        assert!(enforcer.is_killable(&sleeper));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_preview_decides_without_acting() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let profile = Profile {
+            name: "strict".to_string(),
+            kill_on_activate: vec!["sleep".to_string()],
+            protected: vec!["editor".to_string()],
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let events = collect_events(&mut enforcer);
+
+        // The protected editor is heaviest, so the CPU limit picks the compiler
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            top_processes: vec![
+                process(10_000_001, "editor", 3.0, 99.0, 100),
+                process(10_000_002, "compiler", 1.0, 90.0, 100),
+            ],
+            ..Default::default()
+        };
+        let report = enforcer.preview(&stats).unwrap();
+
+        let activation = report.kills.iter().find(|kill| kill.pid == child.id()).unwrap();
+        assert_eq!(activation.reason, KillReason::ProfileActivation);
+        let limit: Vec<&str> = report.kills.iter()
+            .filter(|kill| kill.reason == KillReason::CpuLimit)
+            .map(|kill| kill.name.as_str())
+            .collect();
+        assert_eq!(limit, vec!["compiler"]);
+        assert!(events.lock().unwrap().iter().any(|e| matches!(e, EnforcerEvent::LimitExceeded { .. })));
+
+        // Nothing was actually signalled
+        assert!(child.try_wait().unwrap().is_none());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_preview_reports_cgroup_limits() {
+        let profile = Profile {
+            name: "coding".to_string(),
+            limits: crate::profiles::ProfileResourceLimits {
+                action: LimitAction::CgroupLimit,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let _events = collect_events(&mut enforcer);
+
+        // Limiting this synthetic PID would fail for real; a preview never tries
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            top_processes: vec![process(10_000_001, "code", 2.0, 95.0, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.preview(&stats).unwrap();
+        assert!(report.kills.is_empty());
+        assert_eq!(report.cgroup_limited, vec![10_000_001]);
+        assert!(enforcer.cgroup_limited.is_empty());
+    }
+
+    #[test]
+    fn test_cgroup_limit_failure_never_kills() {
+        let profile = Profile {
+            name: "coding".to_string(),
+            limits: crate::profiles::ProfileResourceLimits {
+                action: LimitAction::CgroupLimit,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+
+        // Synthetic PID above pid_max: it has no /proc entry, so limiting fails
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            top_processes: vec![process(10_000_001, "code", 2.0, 95.0, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert!(report.kills.is_empty());
+        assert!(report.cgroup_limited.is_empty());
+    }
+
+    #[test]
+    fn test_absolute_limits_breach_alongside_percentages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("enforcer.log");
+        let profile = Profile {
+            name: "build".to_string(),
+            enforce: false, // Monitor-only: report the breach without killing anything
+            limits: crate::profiles::ProfileResourceLimits {
+                max_cpu_cores: Some(2.0),
+                max_ram_gb: Some(64.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        enforcer.set_logger(Logger::file(&log_path, LogLevel::Info, crate::log::DEFAULT_MAX_LOG_BYTES).unwrap().shared());
+
+        // 50% of 8 CPUs is 4 cores: under max_cpu_percent (90) but over max_cpu_cores (2)
+        let stats = SystemStats {
+            cpu_usage: 50.0,
+            cpu_count: 8,
+            total_memory_gb: 16.0,
+            used_memory_gb: 4.0,
+            memory_percentage: 25.0,
+            top_processes: vec![process(10_000_001, "make", 1.0, 50.0, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        enforcer.enforce_stats(&stats).unwrap();
+        assert!(report.kills.is_empty());
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("CPU limit exceeded: 50.0% > 25.0% (4.0 cores > 2 cores)"));
+        assert!(!log.contains("RAM limit exceeded"));
+        // max_ram_gb is larger than the machine: warned about once
+        assert_eq!(log.matches("max_ram_gb 64 exceeds").count(), 1);
+    }
+
+    #[test]
+    fn test_cgroup_limits_release_with_hysteresis() {
+        let enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let limits = &enforcer.profile().limits;
+        let just_under = SystemStats {
+            cpu_usage: limits.max_cpu_percent - 1.0,
+            memory_percentage: 10.0,
+            ..Default::default()
+        };
+        assert!(!enforcer.usage_normalized(&just_under));
+
+        let calm = SystemStats { cpu_usage: 10.0, memory_percentage: 10.0, ..Default::default() };
+        assert!(enforcer.usage_normalized(&calm));
+    }
+
+    #[test]
+    fn test_throttle_precedes_kill() {
+        let mut config = KernConfig::default();
+        assert!(!Enforcer::new(config.clone(), Profile::default()).throttle_precedes_kill());
+
+        config.emergency.actions = vec![EmergencyAction::Kill, EmergencyAction::ThrottleCpu];
+        assert!(!Enforcer::new(config.clone(), Profile::default()).throttle_precedes_kill());
+
+        config.emergency.actions = vec![EmergencyAction::ThrottleCpu, EmergencyAction::Kill];
+        assert!(Enforcer::new(config, Profile::default()).throttle_precedes_kill());
+    }
+
+    fn names(candidates: &[&ProcessInfo]) -> Vec<String> {
+        candidates.iter().map(|p| p.name.clone()).collect()
+    }
+
+    // A busy-looping child and an idle one, as get_system_stats sampled them
+    // (spinner's PID first). Both are gone before any assertion can fail, so
+    // a broken test never leaves the loop running.
+    fn spinning_and_idle() -> (u32, Vec<ProcessInfo>) {
+        let spinner = std::process::Command::new("sh").args(["-c", "while :; do :; done"]).spawn().unwrap();
+        let idle = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pids = [spinner.id(), idle.id()];
+        // A process with no CPU time yet at sysinfo's first reading gets no
+        // CPU%, so give the loop a few ticks (5s at most) before sampling
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline && !has_cpu_time(pids[0]) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let stats = get_system_stats(crate::config::MemoryMetric::Rss, None);
+        for mut child in [spinner, idle] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+        let processes = stats.unwrap().top_processes.into_iter().filter(|p| pids.contains(&p.pid)).collect();
+        (pids[0], processes)
+    }
+
+    // utime or stime (/proc/<pid>/stat fields 14 and 15) is non-zero
+    fn has_cpu_time(pid: u32) -> bool {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+        let after_comm = stat.rsplit_once(')').map_or("", |(_, rest)| rest);
+        after_comm.split_whitespace().skip(11).take(2).any(|ticks| ticks != "0")
+    }
+
+    #[test]
+    fn test_spinning_process_is_heaviest_cpu() {
+        let (spinner, processes) = spinning_and_idle();
+
+        let by_cpu = order_candidates(&processes, KillStrategy::HeaviestCpu, ScoreWeights::EQUAL);
+        assert_eq!(by_cpu.len(), 2);
+        assert_eq!(by_cpu[0].pid, spinner);
+        assert!(by_cpu[0].cpu_percentage > 0.0);
+    }
+
+    #[test]
+    fn test_combined_score_counts_sampled_cpu() {
+        let (spinner, processes) = spinning_and_idle();
+        let usage: Vec<(f64, f64)> = processes.iter().map(|p| (p.cpu_percentage, p.memory_gb)).collect();
+        let score = combined_scorer(&usage, ScoreWeights::EQUAL);
+
+        // Memory alone tops out at 0.5: anything above is the spinner's CPU share
+        let spinning = processes.iter().find(|p| p.pid == spinner).unwrap();
+        assert!(score(spinning.cpu_percentage, spinning.memory_gb) > 0.5);
+        let combined = order_candidates(&processes, KillStrategy::HeaviestCpuPlusMemory, ScoreWeights::EQUAL);
+        assert_eq!(combined[0].pid, spinner);
+    }
+
+    #[test]
+    fn test_weighted_cpu_share_uses_sampled_cpu() {
+        let (spinner, processes) = spinning_and_idle();
+
+        // With no weight on memory, only a real CPU reading gives a score
+        let cpu_only = ScoreWeights { cpu: 1.0, memory: 0.0 };
+        let usage: Vec<(f64, f64)> = processes.iter().map(|p| (p.cpu_percentage, p.memory_gb)).collect();
+        let score = combined_scorer(&usage, cpu_only);
+        let spinning = processes.iter().find(|p| p.pid == spinner).unwrap();
+        assert_eq!(score(spinning.cpu_percentage, spinning.memory_gb), 1.0);
+
+        let weights = VictimWeights::default();
+        assert_eq!(order_candidates(&processes, KillStrategy::Weighted, cpu_only)[0].pid, spinner);
+        assert_eq!(order_candidates(&processes, KillStrategy::Weighted, weights.cpu)[0].pid, spinner);
+    }
+
+    #[test]
+    fn test_order_candidates_by_strategy() {
+        let processes = sample_processes();
+
+        let by_cpu = order_candidates(&processes, KillStrategy::HeaviestCpu, ScoreWeights::EQUAL);
+        assert_eq!(names(&by_cpu), vec!["compiler", "editor", "browser"]);
+
+        let by_memory = order_candidates(&processes, KillStrategy::HeaviestMemory, ScoreWeights::EQUAL);
+        assert_eq!(names(&by_memory), vec!["browser", "compiler", "editor"]);
+
+        // compiler: 1.0 CPU share + 0.25 memory share beats browser's ~0.01 + 1.0
+        let combined = order_candidates(&processes, KillStrategy::HeaviestCpuPlusMemory, ScoreWeights::EQUAL);
+        assert_eq!(names(&combined), vec!["compiler", "browser", "editor"]);
+
+        let oldest = order_candidates(&processes, KillStrategy::Oldest, ScoreWeights::EQUAL);
+        assert_eq!(names(&oldest), vec!["editor", "browser", "compiler"]);
+
+        let newest = order_candidates(&processes, KillStrategy::Newest, ScoreWeights::EQUAL);
+        assert_eq!(names(&newest), vec!["compiler", "browser", "editor"]);
+    }
+
+    #[test]
+    fn test_weighted_order_follows_victim_weights() {
+        let weights = VictimWeights::default();
+
+        let processes = sample_processes();
+        let by_cpu = order_candidates(&processes, KillStrategy::Weighted, weights.cpu);
+        assert_eq!(names(&by_cpu), vec!["compiler", "browser", "editor"]);
+        let by_memory = order_candidates(&processes, KillStrategy::Weighted, weights.memory);
+        assert_eq!(names(&by_memory), vec!["browser", "compiler", "editor"]);
+
+        // Slightly less CPU but far more memory: killing "heavy" relieves more,
+        // even though "hog" tops the CPU column
+        let processes = vec![process(1, "hog", 0.2, 60.0, 100), process(2, "heavy", 4.0, 50.0, 100)];
+        assert_eq!(order_candidates(&processes, KillStrategy::HeaviestCpu, weights.cpu)[0].name, "hog");
+        assert_eq!(order_candidates(&processes, KillStrategy::Weighted, weights.cpu)[0].name, "heavy");
+        let cpu_only = ScoreWeights { cpu: 1.0, memory: 0.0 };
+        assert_eq!(order_candidates(&processes, KillStrategy::Weighted, cpu_only)[0].name, "hog");
+    }
+
+    #[test]
+    fn test_weighted_strategy_scores_per_exceeded_limit() {
+        // Synthetic PIDs above pid_max: the signal finds nothing (ESRCH), which counts as killed
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 50.0,
+            top_processes: vec![
+                process(10_000_001, "hog", 0.2, 60.0, 100),
+                process(10_000_002, "heavy", 4.0, 50.0, 100),
+            ],
+            ..Default::default()
+        };
+        let profile = Profile { kill_strategy: Some(KillStrategy::Weighted), ..Default::default() };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile.clone());
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(report.kills.len(), 1);
+        assert_eq!(report.kills[0].name, "heavy");
+        assert_eq!(report.kills[0].strategy, KillStrategy::Weighted);
+        assert_eq!(report.kills[0].describe(), "CPU limit, highest weighted CPU and memory score");
+
+        // Weights come from kern.yaml
+        let mut config = KernConfig::default();
+        config.victim_weights.cpu = ScoreWeights { cpu: 1.0, memory: 0.0 };
+        let mut enforcer = Enforcer::new(config, profile);
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(report.kills[0].name, "hog");
+
+        // Several limits at once: their weights, each scaled to 1, averaged
+        let enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let both = enforcer.weights_for_all(&[KillReason::CpuLimit, KillReason::RamLimit]);
+        assert!((both.cpu - 0.5).abs() < 1e-9 && (both.memory - 0.5).abs() < 1e-9);
+        let hot = enforcer.weights_for_all(&[KillReason::Emergency]);
+        assert!((hot.cpu - 0.9).abs() < 1e-9 && (hot.memory - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_io_limit_acts_on_process_over_it() {
+        let io_process = |pid, name: &str, cpu, read, write| ProcessInfo {
+            io_read_mbps: read,
+            io_write_mbps: write,
+            ..process(pid, name, 0.5, cpu, 100)
+        };
+        // CPU and RAM are fine; only the backup's disk I/O is over the limit
+        let stats = SystemStats {
+            cpu_usage: 20.0,
+            memory_percentage: 30.0,
+            top_processes: vec![
+                io_process(10_000_001, "compiler", 90.0, 5.0, 1.0),
+                io_process(10_000_002, "backup", 1.0, 80.0, 40.0),
+                io_process(10_000_003, "indexer", 2.0, 30.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        let mut profile = Profile::default();
+        profile.limits.max_io_mbps = Some(50.0);
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let events = collect_events(&mut enforcer);
+        let report = enforcer.enforce_stats(&stats).unwrap();
+
+        assert_eq!(report.kills.len(), 1);
+        assert_eq!(report.kills[0].name, "backup");
+        assert_eq!(report.kills[0].describe(), "disk I/O limit, highest disk I/O consumer");
+        let events = events.lock().unwrap();
+        let exceeded = events.iter().find(|e| e.kind() == "io_limit_exceeded").unwrap();
+        assert_eq!(exceeded.to_json()["read_mbps"], 80.0);
+        assert!(exceeded.to_string().contains("backup (PID: 10000002) 120.0 MB/s > 50.0 MB/s"), "{}", exceeded);
+
+        // Another strategy only reorders the processes over the limit
+        let mut profile = Profile { kill_strategy: Some(KillStrategy::HeaviestCpu), ..Default::default() };
+        profile.limits.max_io_mbps = Some(20.0);
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(report.kills[0].name, "indexer");
+    }
+
+    #[test]
+    fn test_order_candidates_grouped() {
+        // 3 small chrome processes outweigh one large editor together
+        let processes = vec![
+            process(1, "code", 1.0, 40.0, 100),
+            process(2, "chrome", 0.5, 10.0, 300),
+            process(3, "chrome", 0.6, 5.0, 200),
+            process(4, "chrome", 0.4, 30.0, 400),
+        ];
+        let pids = |candidates: Vec<&ProcessInfo>| candidates.iter().map(|p| p.pid).collect::<Vec<_>>();
+
+        // Heaviest child of the heaviest application first
+        assert_eq!(pids(order_candidates_grouped(&processes, KillStrategy::HeaviestMemory, ScoreWeights::EQUAL)), vec![3, 2, 4, 1]);
+        assert_eq!(pids(order_candidates_grouped(&processes, KillStrategy::HeaviestCpu, ScoreWeights::EQUAL)), vec![4, 2, 3, 1]);
+        assert_eq!(pids(order_candidates_grouped(&processes, KillStrategy::HeaviestCpuPlusMemory, ScoreWeights::EQUAL)), vec![4, 2, 3, 1]);
+        assert_eq!(pids(order_candidates_grouped(&processes, KillStrategy::Oldest, ScoreWeights::EQUAL)), vec![1, 3, 2, 4]);
+        assert_eq!(pids(order_candidates_grouped(&processes, KillStrategy::Newest, ScoreWeights::EQUAL)), vec![4, 2, 3, 1]);
+
+        // Ungrouped, the editor is the heaviest single process
+        assert_eq!(order_candidates(&processes, KillStrategy::HeaviestMemory, ScoreWeights::EQUAL)[0].pid, 1);
+    }
+
+    #[test]
+    fn test_strategy_follows_violated_limit() {
+        let enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+
+        assert_eq!(enforcer.strategy_for(KillReason::CpuLimit), KillStrategy::HeaviestCpu);
+        assert_eq!(enforcer.strategy_for(KillReason::TemperatureWarning), KillStrategy::HeaviestCpu);
+        assert_eq!(enforcer.strategy_for(KillReason::RamLimit), KillStrategy::HeaviestMemory);
+    }
+
+    #[test]
+    fn test_profile_strategy_override() {
+        let profile = Profile {
+            name: "override".to_string(),
+            kill_strategy: Some(KillStrategy::Oldest),
+            ..Default::default()
+        };
+        let enforcer = Enforcer::new(KernConfig::default(), profile);
+
+        assert_eq!(enforcer.strategy_for(KillReason::CpuLimit), KillStrategy::Oldest);
+        assert_eq!(enforcer.strategy_for(KillReason::RamLimit), KillStrategy::Oldest);
+    }
+
+    #[test]
+    fn test_kill_record_describe() {
+        let record = KillRecord {
+            pid: 42,
+            name: "compiler".to_string(),
+            reason: KillReason::CpuLimit,
+            strategy: KillStrategy::HeaviestCpu,
+            success: true,
+        };
+        assert_eq!(record.describe(), "CPU limit, highest CPU consumer");
+
+        let report = EnforcementReport { kills: vec![record], ..Default::default() };
+        assert!(report.action_taken());
+        assert!(!EnforcementReport::default().action_taken());
+    }
+
+    #[test]
+    fn test_json_event_fields() {
+        let event = json_event("limit_exceeded", json!({ "resource": "cpu", "value": 95.0, "limit": 90.0 }));
+
+        assert_eq!(event["event"], "limit_exceeded");
+        assert_eq!(event["resource"], "cpu");
+        assert_eq!(event["limit"], 90.0);
+        assert!(event["timestamp"].as_str().is_some());
+    }
+
+    // Record every event the enforcer reports, in order
+    fn collect_events(enforcer: &mut Enforcer) -> Arc<std::sync::Mutex<Vec<EnforcerEvent>>> {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        enforcer.set_observer(move |event: &EnforcerEvent| sink.lock().unwrap().push(event.clone()));
+        events
+    }
+
+    #[test]
+    fn test_permission_skips_are_reported_once_per_episode() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let events = collect_events(&mut enforcer);
+        let eperm = killer::KillError { message: "EPERM".to_string(), permanent: true };
+        let (first, second) = (process(10_000_001, "root-a", 1.0, 1.0, 100), process(10_000_002, "root-b", 1.0, 1.0, 100));
+        // Well within limits, so nothing is killed along the way
+        let calm = |processes: Vec<ProcessInfo>| SystemStats {
+            cpu_usage: 10.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: processes,
+            ..Default::default()
+        };
+        let reports = |events: &Arc<std::sync::Mutex<Vec<EnforcerEvent>>>| {
+            events.lock().unwrap().iter().filter(|event| event.kind() == "permission_denied").cloned().collect::<Vec<_>>()
+        };
+
+        enforcer.remember_unkillable(&first, &eperm);
+        enforcer.remember_unkillable(&second, &eperm);
+        for _ in 0..3 {
+            enforcer.enforce_stats(&calm(vec![first.clone(), second.clone()])).unwrap();
+        }
+        assert_eq!(reports(&events), vec![EnforcerEvent::PermissionDenied { processes: 2 }]);
+        assert!(reports(&events)[0].to_string().contains("2 processes skipped due to permissions"));
 
-        std::thread::sleep(interval);
+        // Both exit: the episode ends, and the next EPERM starts a new one
+        enforcer.enforce_stats(&calm(Vec::new())).unwrap();
+        enforcer.remember_unkillable(&first, &eperm);
+        enforcer.enforce_stats(&calm(vec![first.clone()])).unwrap();
+        assert_eq!(reports(&events).len(), 2);
+        assert_eq!(reports(&events)[1].to_json()["processes"], 1);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // One candidate per skip reason the enforcer can work out on its own
+    fn unkillable_candidates(enforcer: &mut Enforcer) -> Vec<ProcessInfo> {
+        let eperm = killer::KillError { message: "EPERM".to_string(), permanent: true };
+        let mut processes = vec![
+            process(10_000_001, "guard", 4.0, 90.0, 100),
+            process(10_000_002, "guard", 3.0, 80.0, 100),
+            process(10_000_003, "precious", 2.0, 70.0, 100),
+            process(10_000_004, "root-owned", 1.5, 60.0, 100),
+            process(10_000_005, "stuck", 1.0, 50.0, 100),
+            process(10_000_006, "defunct", 0.0, 0.0, 100),
+            process(10_000_007, "kworker/0:1", 0.0, 40.0, 100),
+        ];
+        processes[5].state = 'Z';
+        processes[6].kernel_thread = true;
+        enforcer.remember_unkillable(&processes[3], &eperm);
+        enforcer.unkillable.insert((processes[4].pid, processes[4].start_time));
+        processes
+    }
 
     #[test]
-    fn test_enforcer_creation() {
-        let config = KernConfig::default();
-        let profile = Profile::default();
-        let enforcer = Enforcer::new(config, profile);
+    fn test_nothing_killable_reports_skip_breakdown() {
+        let config = KernConfig {
+            protected_processes: vec!["guard".to_string()],
+            critical_processes: vec!["precious".to_string()],
+            ancestry_depth: 0,
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+        let stats = SystemStats {
+            cpu_usage: 10.0,
+            memory_percentage: 99.0,
+            temperature_max: 40.0,
+            top_processes: unkillable_candidates(&mut enforcer),
+            ..Default::default()
+        };
 
-        assert!(!enforcer.is_emergency_mode());
-        assert!(enforcer.emergency_duration().is_none());
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert!(report.kills.is_empty());
+        assert_eq!(report.skipped.total(), 7);
+        assert_eq!(report.skipped.get(SkipReason::Protected), 2);
+        assert_eq!(report.skipped.get(SkipReason::PermissionDenied), 1);
+        assert_eq!(
+            report.skipped.to_string(),
+            "2 protected, 1 critical, 1 permission denied, 1 unkillable, 1 zombie, 1 kernel thread"
+        );
+
+        let events = events.lock().unwrap();
+        let nothing: Vec<&EnforcerEvent> = events.iter().filter(|e| e.kind() == "nothing_killable").collect();
+        assert_eq!(nothing.len(), 1);
+        assert_eq!(
+            nothing[0].to_string(),
+            "⚠️  No killable process for RAM limit: 2 protected, 1 critical, 1 permission denied, 1 unkillable, 1 zombie, 1 kernel thread"
+        );
+        let json = nothing[0].to_json();
+        assert_eq!(json["reason"], "ram_limit");
+        assert_eq!(json["candidates"], 7);
+        assert_eq!(json["skipped"], json!({
+            "protected": 2, "critical": 1, "permission_denied": 1, "unkillable": 1, "zombie": 1, "kernel_thread": 1,
+        }));
+
+        let mut status = EnforcerStatus::default();
+        status.record(nothing[0]);
+        assert!(status.last_action.is_none());
+        assert_eq!(status.to_json()["last_nothing_killable"]["skipped"]["protected"], 2);
     }
 
     #[test]
-    fn test_emergency_mode_activation() {
+    fn test_other_users_processes_are_only_targeted_with_all_users() {
+        let own = nix::unistd::getuid().as_raw();
+        let owned_by = |pid, name: &str, memory_gb, uid| ProcessInfo { uid, ..process(pid, name, memory_gb, 10.0, 100) };
+        // Another user's job is the heaviest
+        let stats = SystemStats {
+            cpu_usage: 10.0,
+            memory_percentage: 99.0,
+            temperature_max: 40.0,
+            top_processes: vec![
+                owned_by(10_000_001, "batch-job", 8.0, Some(own + 1)),
+                owned_by(10_000_002, "mystery", 6.0, None),
+                owned_by(10_000_003, "build", 2.0, Some(own)),
+            ],
+            ..Default::default()
+        };
+        let victims = |config: KernConfig| -> Vec<String> {
+            let mut enforcer = Enforcer::new(config, Profile::default());
+            enforcer.set_observer(|_: &EnforcerEvent| {});
+            enforcer.preview(&stats).unwrap().kills.into_iter().map(|kill| kill.name).collect()
+        };
+
+        assert_eq!(victims(KernConfig::default()), vec!["build"]);
+        let mut all_users = KernConfig::default();
+        all_users.enforcement.all_users = true;
+        assert_eq!(victims(all_users), vec!["batch-job"]);
+
+        // Nothing of kern's own user to kill
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        enforcer.set_observer(|_: &EnforcerEvent| {});
+        let others = SystemStats { top_processes: stats.top_processes[..2].to_vec(), ..stats.clone() };
+        let report = enforcer.enforce_stats(&others).unwrap();
+        assert!(report.kills.is_empty());
+        assert_eq!(report.skipped.get(SkipReason::OtherUser), 2);
+        assert_eq!(report.skipped.to_string(), "2 other user");
+    }
+
+    #[test]
+    fn test_emergency_reports_nothing_killable() {
+        let mut config = KernConfig {
+            protected_processes: vec!["guard".to_string()],
+            critical_processes: vec!["precious".to_string()],
+            ancestry_depth: 0,
+            ..Default::default()
+        };
+        config.emergency.trigger_samples = 1;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+        let hot = SystemStats {
+            cpu_usage: 100.0,
+            temperature_max: 110.0,
+            top_processes: unkillable_candidates(&mut enforcer),
+            ..Default::default()
+        };
+
+        let report = enforcer.enforce_stats(&hot).unwrap();
+        assert!(enforcer.is_emergency_mode());
+        assert!(report.kills.is_empty());
+        assert_eq!(report.skipped.total(), 7);
+        assert!(events.lock().unwrap().contains(&EnforcerEvent::NothingKillable {
+            reason: KillReason::Emergency,
+            skipped: report.skipped.clone(),
+        }));
+
+        // Below the CPU floor nothing is meant to be killed, so there is nothing to explain
+        let events_before = events.lock().unwrap().len();
+        let idle = SystemStats { cpu_usage: 1.0, ..hot };
+        let report = enforcer.enforce_stats(&idle).unwrap();
+        assert!(report.skipped.is_empty());
+        assert!(events.lock().unwrap()[events_before..].iter().all(|e| e.kind() != "nothing_killable"));
+    }
+
+    #[test]
+    fn test_status_report_fires_every_interval() {
+        let config = KernConfig { report_interval_secs: Some(60), ..Default::default() };
+        let profile = Profile { name: "coding".to_string(), ..Default::default() };
+        let mut enforcer = Enforcer::new(config, profile);
+        let events = collect_events(&mut enforcer);
+        let stats = SystemStats { cpu_usage: 45.2, memory_percentage: 61.8, temperature_max: 71.0, ..Default::default() };
+        let reports = |events: &Arc<std::sync::Mutex<Vec<EnforcerEvent>>>| {
+            events.lock().unwrap().iter().filter(|event| event.kind() == "status_report").cloned().collect::<Vec<_>>()
+        };
+
+        // Not yet due right after startup
+        enforcer.enforce_stats(&stats).unwrap();
+        assert!(reports(&events).is_empty());
+
+        let Some(a_minute_ago) = Instant::now().checked_sub(Duration::from_secs(60)) else { return };
+        enforcer.last_report = a_minute_ago;
+        enforcer.enforce_stats(&stats).unwrap();
+        enforcer.enforce_stats(&stats).unwrap();
+        let fired = reports(&events);
+        assert_eq!(fired.len(), 1);
+        let line = fired[0].to_string();
+        assert!(line.ends_with("] CPU: 45% | RAM: 62% | Temp: 71°C | Mode: coding | Kills: 0"), "{}", line);
+        assert_eq!(fired[0].to_json()["profile"], "coding");
+
+        // Due again one interval later, never when disabled
+        enforcer.last_report = a_minute_ago;
+        enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(reports(&events).len(), 2);
+        enforcer.config.report_interval_secs = None;
+        enforcer.last_report = a_minute_ago;
+        enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(reports(&events).len(), 2);
+    }
+
+    #[test]
+    fn test_permission_denied_cooldown_expires() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let owned_by_root = process(10_000_001, "daemon", 1.0, 1.0, 100);
+        let stats = SystemStats { temperature_max: 40.0, top_processes: vec![owned_by_root.clone()], ..Default::default() };
+
+        let Some(long_ago) = Instant::now().checked_sub(PERMISSION_DENIED_COOLDOWN) else { return };
+        enforcer.permission_denied.insert((owned_by_root.pid, owned_by_root.start_time), long_ago);
+        assert!(!enforcer.is_killable(&owned_by_root));
+        enforcer.reap_recent_kills(&stats);
+        assert!(enforcer.is_killable(&owned_by_root));
+    }
+
+    #[test]
+    fn test_monitor_only_event_sequence() {
         let mut config = KernConfig::default();
-        config.temperature.critical = 80.0;
-        
-        let profile = Profile::default();
+        config.emergency.trigger_samples = 1;
+        config.multi_breach = MultiBreach::KillEach; // One event pair per exceeded limit
+        let profile = Profile {
+            name: "watch".to_string(),
+            enforce: false,
+            ..Default::default()
+        };
+        let mut enforcer = Enforcer::new(config, profile);
+        let events = collect_events(&mut enforcer);
+
+        // Synthetic PID above pid_max: nothing real could ever be signalled
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 99.0,
+            temperature_max: 70.0,
+            top_processes: vec![process(10_000_001, "hog", 8.0, 99.0, 100)],
+            ..Default::default()
+        };
+        enforcer.enforce_stats(&stats).unwrap();
+        let hot = SystemStats { temperature_max: 110.0, ..stats };
+        enforcer.enforce_stats(&hot).unwrap();
+
+        let limits = &enforcer.profile().limits;
+        let (cpu_limit, ram_limit) = (limits.max_cpu_percent, limits.max_ram_percent);
+        let exceeded = |resource, value, limit| EnforcerEvent::LimitExceeded { resource, value, limit, used: 0.0, cap: None };
+        let skipped = |reason| EnforcerEvent::Skipped(Skip::MonitorOnly { profile: "watch".to_string(), reason });
+        let (warning, critical) = enforcer.temperature_thresholds();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                exceeded(LimitResource::Cpu, 100.0, cpu_limit),
+                skipped(KillReason::CpuLimit),
+                exceeded(LimitResource::Ram, 99.0, ram_limit),
+                skipped(KillReason::RamLimit),
+                EnforcerEvent::EmergencyEntered { temperature: 110.0, critical, warning },
+                EnforcerEvent::EmergencyMonitorOnly { temperature: 110.0, critical },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limit_kill_event_sequence() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let events = collect_events(&mut enforcer);
+
+        // Synthetic PIDs above pid_max: the signal finds nothing (ESRCH), which counts as killed
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![
+                process(10_000_001, "hog", 1.0, 99.0, 100),
+                process(10_000_002, "spinner", 1.0, 50.0, 100),
+            ],
+            ..Default::default()
+        };
+        enforcer.enforce_stats(&stats).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind(), "limit_exceeded");
+        assert_eq!(
+            events[1],
+            EnforcerEvent::ProcessKilled(KillRecord {
+                pid: 10_000_001,
+                name: "hog".to_string(),
+                reason: KillReason::CpuLimit,
+                strategy: KillStrategy::HeaviestCpu,
+                success: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cpu_limit_checks_percentile_of_recent_samples() {
+        let mut config = KernConfig::default();
+        config.cpu_percentile.samples = 5;
+        config.cpu_percentile.percentile = 50.0;
+        config.notifications.enabled = false;
+        let profile = Profile { enforce: false, ..Default::default() };
         let mut enforcer = Enforcer::new(config, profile);
+        let events = collect_events(&mut enforcer);
+        let mut cpu_breaches = |cpu: f64| {
+            let stats = SystemStats { cpu_usage: cpu, memory_percentage: 10.0, temperature_max: 40.0, ..Default::default() };
+            enforcer.enforce_stats(&stats).unwrap();
+            let events: Vec<EnforcerEvent> = events.lock().unwrap().drain(..).collect();
+            events
+                .into_iter()
+                .filter_map(|event| match event {
+                    EnforcerEvent::LimitExceeded { resource: LimitResource::Cpu, value, .. } => Some(value),
+                    _ => None,
+                })
+                .collect::<Vec<f64>>()
+        };
+
+        // A lone spike doesn't move the median of the window
+        assert!(cpu_breaches(20.0).is_empty());
+        assert!(cpu_breaches(20.0).is_empty());
+        assert!(cpu_breaches(99.0).is_empty());
+        // Sustained load does, and is reported as the median
+        assert!(cpu_breaches(95.0).is_empty());
+        assert_eq!(cpu_breaches(97.0), vec![95.0]);
+        // Unreadable samples are left out of the window
+        assert_eq!(cpu_breaches(f64::NAN), vec![95.0]);
+        assert_eq!(enforcer.cpu_samples.len(), 5);
+    }
+
+    #[test]
+    fn test_restart_after_conditions_settle() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = KernConfig::default();
+        config.restart.stabilization_secs = 0;
+        config.restart.max_per_hour = 1;
+        config.notifications.enabled = false;
+        config.kill_log_path = Some(temp_dir.path().join("kern.log"));
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+        let command = RestartCommand {
+            name: "kern-sync-test".to_string(),
+            cmdline: vec!["true".to_string()],
+            cwd: temp_dir.path().to_path_buf(),
+            uid: nix::unistd::getuid().as_raw(),
+            gid: nix::unistd::getgid().as_raw(),
+            env: Vec::new(),
+        };
+        let calm = SystemStats { cpu_usage: 10.0, memory_percentage: 20.0, temperature_max: 40.0, ..Default::default() };
+        let restart_events = |events: &Arc<std::sync::Mutex<Vec<EnforcerEvent>>>| {
+            let events: Vec<EnforcerEvent> = events.lock().unwrap().drain(..).collect();
+            events.into_iter().filter(|event| event.kind().starts_with("restart") || event.kind() == "process_restarted").collect::<Vec<_>>()
+        };
+
+        // Still under pressure: stays queued
+        enforcer.restarts.push(command.clone());
+        enforcer.enforce_stats(&SystemStats { cpu_usage: 100.0, ..calm.clone() }).unwrap();
+        assert!(restart_events(&events).is_empty());
+        assert_eq!(enforcer.restarts.pending().len(), 1);
+
+        // Back under the limits, but it came back on its own
+        let running = SystemStats { top_processes: vec![process(10_000_001, "kern-sync-test", 0.1, 1.0, 100)], ..calm.clone() };
+        enforcer.enforce_stats(&running).unwrap();
+        assert_eq!(
+            restart_events(&events),
+            vec![EnforcerEvent::RestartSkipped { name: "kern-sync-test".to_string(), reason: RestartSkip::AlreadyRunning }]
+        );
+
+        enforcer.restarts.push(command.clone());
+        enforcer.enforce_stats(&calm).unwrap();
+        let restarted = restart_events(&events);
+        assert!(matches!(&restarted[..], [EnforcerEvent::ProcessRestarted { name, command, .. }] if name == "kern-sync-test" && command == "true"));
+        let log = std::fs::read_to_string(temp_dir.path().join("kern.log")).unwrap();
+        assert!(log.contains("RESTART [PID: ") && log.contains("name=\"kern-sync-test\" status=ok"));
+
+        // max_per_hour: 1 is used up
+        enforcer.restarts.push(command);
+        enforcer.enforce_stats(&calm).unwrap();
+        assert_eq!(
+            restart_events(&events),
+            vec![EnforcerEvent::RestartSkipped {
+                name: "kern-sync-test".to_string(),
+                reason: RestartSkip::RateLimited { max_per_hour: 1 },
+            }]
+        );
+        assert!(enforcer.restarts.is_empty());
+    }
+
+    #[test]
+    fn test_alert_only_reports_instead_of_acting() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = KernConfig::default();
+        config.enforcement.mode = EnforcementMode::Alert;
+        config.emergency.trigger_samples = 1;
+        config.notifications.enabled = false;
+        config.kill_log_path = Some(temp_dir.path().join("kern.log"));
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+        assert!(enforcer.status_line().starts_with("Alerting on"));
+
+        // Synthetic PID above pid_max; alert-only mode wouldn't signal it anyway
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![process(10_000_001, "hog", 1.0, 99.0, 100)],
+            ..Default::default()
+        };
+        let held_back = KillRecord {
+            pid: 10_000_001,
+            name: "hog".to_string(),
+            reason: KillReason::CpuLimit,
+            strategy: KillStrategy::HeaviestCpu,
+            success: false,
+        };
+        for _ in 0..2 {
+            let report = enforcer.enforce_stats(&stats).unwrap();
+            assert!(report.kills.is_empty());
+            assert_eq!(report.would_kill, vec![held_back.clone()]);
+        }
+        assert_eq!(events.lock().unwrap()[1], EnforcerEvent::WouldKill(held_back));
+        assert_eq!(enforcer.total_kills(), 0);
+        // Logged once, not on every cycle the process stays over the limit
+        let log = std::fs::read_to_string(temp_dir.path().join("kern.log")).unwrap();
+        assert_eq!(log.matches("ALERT [PID: 10000001]").count(), 1);
+
+        // Emergency: no throttle, no kill, an alert naming the would-be victim
+        events.lock().unwrap().clear();
+        let hot = SystemStats { temperature_max: 110.0, ..stats.clone() };
+        let report = enforcer.enforce_stats(&hot).unwrap();
+        assert!(!report.cpu_throttled && report.kills.is_empty());
+        assert_eq!(report.would_kill.len(), 1);
+        let critical = enforcer.temperature_thresholds().1;
+        let events = events.lock().unwrap();
+        assert_eq!(events.iter().map(EnforcerEvent::kind).collect::<Vec<_>>(), ["emergency_activated", "would_kill", "emergency_alert"]);
+        assert_eq!(
+            events[2],
+            EnforcerEvent::EmergencyAlert { temperature: 110.0, critical, would_kill: vec!["hog".to_string()] }
+        );
+    }
+
+    #[test]
+    fn test_alert_only_profile_would_limit() {
+        let mut profile = Profile { name: "watch".to_string(), ..Default::default() };
+        profile.enforcement.mode = EnforcementMode::Alert;
+        profile.limits.action = LimitAction::CgroupLimit;
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let events = collect_events(&mut enforcer);
+        assert!(enforcer.is_alert_only());
+
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![process(10_000_001, "hog", 1.0, 99.0, 100)],
+            ..Default::default()
+        };
+        // A preview keeps the kill log and cgroups out of it; the decision is the same
+        let report = enforcer.preview(&stats).unwrap();
+        assert!(report.cgroup_limited.is_empty() && report.kills.is_empty());
+        assert_eq!(report.would_limit, vec![10_000_001]);
+        assert_eq!(
+            events.lock().unwrap()[1],
+            EnforcerEvent::WouldLimit { pid: 10_000_001, name: "hog".to_string(), reason: KillReason::CpuLimit }
+        );
+    }
+
+    #[test]
+    fn test_multi_breach_kills_once_or_per_limit() {
+        // CPU, RAM and temperature warning all exceeded at once. Synthetic PIDs
+        // above pid_max: the signal finds nothing (ESRCH), which counts as killed
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 99.0,
+            temperature_max: 80.0,
+            top_processes: vec![
+                process(10_000_001, "spinner", 0.5, 99.0, 100),
+                process(10_000_002, "leak", 9.0, 10.0, 100),
+                process(10_000_003, "both", 8.0, 90.0, 100),
+            ],
+            ..Default::default()
+        };
+
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(report.kills.len(), 1);
+        assert_eq!(report.kills[0].name, "both");
+        assert_eq!(report.kills[0].reason, KillReason::CpuLimit);
+        assert_eq!(report.kills[0].strategy, KillStrategy::HeaviestCpuPlusMemory);
+
+        let config = KernConfig { multi_breach: MultiBreach::KillEach, ..Default::default() };
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        let reasons: Vec<KillReason> = report.kills.iter().map(|k| k.reason).collect();
+        assert_eq!(reasons, vec![KillReason::CpuLimit, KillReason::RamLimit, KillReason::TemperatureWarning]);
+        assert_eq!((report.kills[0].name.as_str(), report.kills[1].name.as_str()), ("spinner", "leak"));
+
+        // A single breach keeps its own strategy
+        let enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        assert_eq!(enforcer.strategy_for_all(&[KillReason::RamLimit]), KillStrategy::HeaviestMemory);
+        assert_eq!(
+            enforcer.strategy_for_all(&[KillReason::CpuLimit, KillReason::TemperatureWarning]),
+            KillStrategy::HeaviestCpu
+        );
+    }
+
+    #[test]
+    fn test_profile_lock_prevents_switching() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile { name: "debug".to_string(), ..Default::default() });
+        let events = collect_events(&mut enforcer);
+        let other = Profile { name: "gaming".to_string(), enforce: false, ..Default::default() };
+
+        enforcer.set_profile_lock(true);
+        assert!(enforcer.profile_locked());
+        enforcer.switch_profile(other.clone()).unwrap();
+        assert_eq!(enforcer.profile().name, "debug");
+        assert!(events.lock().unwrap().is_empty());
+
+        // Limits are still enforced while locked
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            top_processes: vec![process(10_000_001, "hog", 1.0, 99.0, 100)],
+            ..Default::default()
+        };
+        enforcer.enforce_stats(&stats).unwrap();
+        assert_eq!(events.lock().unwrap()[0].kind(), "limit_exceeded");
+
+        enforcer.set_profile_lock(false);
+        enforcer.switch_profile(other).unwrap();
+        assert_eq!(enforcer.profile().name, "gaming");
+    }
+
+    #[test]
+    fn test_memory_pressure_limit() {
+        let profile = Profile {
+            name: "watch".to_string(),
+            enforce: false,
+            limits: crate::profiles::ProfileResourceLimits { max_mem_pressure: Some(20.0), ..Default::default() },
+            ..Default::default()
+        };
+        let stats = SystemStats {
+            cpu_usage: 10.0,
+            cpu_count: 4,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![process(10_000_001, "hog", 8.0, 1.0, 100)],
+            mem_pressure: Some(35.0),
+            ..Default::default()
+        };
+
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile.clone());
+        let events = collect_events(&mut enforcer);
+        enforcer.enforce_stats(&stats).unwrap();
+        let calm = SystemStats { mem_pressure: Some(5.0), ..Default::default() };
+        enforcer.enforce_stats(&calm).unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                EnforcerEvent::LimitExceeded {
+                    resource: LimitResource::MemoryPressure,
+                    value: 35.0,
+                    limit: 20.0,
+                    used: 35.0,
+                    cap: None,
+                },
+                EnforcerEvent::Skipped(Skip::MonitorOnly {
+                    profile: "watch".to_string(),
+                    reason: KillReason::MemoryPressure,
+                }),
+            ]
+        );
+
+        // Without PSI the rule is skipped, with one warning
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
+        let events = collect_events(&mut enforcer);
+        let no_psi = SystemStats { mem_pressure: None, ..stats };
+        enforcer.enforce_stats(&no_psi).unwrap();
+        enforcer.enforce_stats(&no_psi).unwrap();
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].to_string().contains("max_mem_pressure is ignored"), "{}", events[0]);
+    }
+
+    #[test]
+    fn test_swap_emergency_kills_heaviest_memory_user() {
+        let mut config = KernConfig::default();
+        config.swap_emergency.enabled = true;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+
+        // RAM well under the profile limit: only swap is in trouble
+        let thrashing = SystemStats {
+            cpu_usage: 10.0,
+            memory_percentage: 40.0,
+            swap_percentage: 95.0,
+            temperature_max: 40.0,
+            top_processes: vec![
+                process(10_000_001, "browser", 2.0, 5.0, 100),
+                process(10_000_002, "leaky", 6.0, 1.0, 100),
+            ],
+            ..Default::default()
+        };
+        enforcer.enforce_stats(&thrashing).unwrap();
+        assert!(enforcer.is_swap_emergency());
+        assert_eq!(
+            events.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            vec![
+                EnforcerEvent::SwapEmergencyEntered { swap_percentage: 95.0, threshold: 80.0 },
+                EnforcerEvent::ProcessKilled(KillRecord {
+                    pid: 10_000_002,
+                    name: "leaky".to_string(),
+                    reason: KillReason::SwapEmergency,
+                    strategy: KillStrategy::HeaviestMemory,
+                    success: true,
+                }),
+            ]
+        );
+
+        // Between exit_percent and the threshold: still in the mode, nothing killed
+        enforcer.enforce_stats(&SystemStats { swap_percentage: 60.0, ..thrashing.clone() }).unwrap();
+        assert!(enforcer.is_swap_emergency());
+        assert!(events.lock().unwrap().is_empty());
+
+        enforcer.enforce_stats(&SystemStats { swap_percentage: 40.0, ..thrashing.clone() }).unwrap();
+        assert!(!enforcer.is_swap_emergency());
+        assert_eq!(*events.lock().unwrap(), vec![EnforcerEvent::SwapEmergencyExited { swap_percentage: 40.0 }]);
+
+        // Disabled (the default): swap alone never kills
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let events = collect_events(&mut enforcer);
+        enforcer.enforce_stats(&thrashing).unwrap();
+        assert!(!enforcer.is_swap_emergency());
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_simulate_decides_without_killing() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let mut config = KernConfig::default();
+        config.emergency.trigger_samples = 1;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        enforcer.set_simulate(true);
+        let events = collect_events(&mut enforcer);
+
+        let stats = SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![process(pid, "sleep", 1.0, 99.0, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        let record = KillRecord {
+            pid,
+            name: "sleep".to_string(),
+            reason: KillReason::CpuLimit,
+            strategy: KillStrategy::HeaviestCpu,
+            success: true,
+        };
+        assert_eq!(report.kills, vec![record.clone()]);
+        assert_eq!(events.lock().unwrap()[1], EnforcerEvent::Simulated(SimulatedAction::Kill(record)));
+        assert_eq!(
+            events.lock().unwrap()[1].to_string(),
+            format!("[SIMULATE] would kill pid={} name=sleep (CPU limit, highest CPU consumer)", pid)
+        );
+        assert_eq!(enforcer.total_kills(), 1);
+        assert!(child.try_wait().unwrap().is_none(), "simulate must not signal the process");
 
+        // Emergency mode is still entered and left
+        enforcer.enforce_stats(&SystemStats { cpu_usage: 10.0, temperature_max: 99.0, ..stats.clone() }).unwrap();
+        assert!(enforcer.is_emergency_mode());
+        assert!(enforcer.status_line().contains("(simulated)"));
+        enforcer.enforce_stats(&SystemStats { cpu_usage: 10.0, ..stats.clone() }).unwrap();
+        enforcer.enforce_stats(&SystemStats { cpu_usage: 10.0, ..stats }).unwrap();
         assert!(!enforcer.is_emergency_mode());
+        assert!(child.try_wait().unwrap().is_none());
 
-        // In real usage, emergency_since would be set when temp exceeds critical
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_event_messages_and_json() {
+        let event = EnforcerEvent::LimitExceeded {
+            resource: LimitResource::Cpu,
+            value: 95.0,
+            limit: 50.0,
+            used: 7.6,
+            cap: Some(4.0),
+        };
+        assert_eq!(event.to_string(), "⚠️  CPU limit exceeded: 95.0% > 50.0% (7.6 cores > 4 cores)");
+        assert_eq!(event.level(), LogLevel::Warn);
+        let json = event.to_json();
+        assert_eq!(json["event"], "limit_exceeded");
+        assert_eq!(json["resource"], "cpu");
+        assert_eq!(json["cores"], 7.6);
+        assert_eq!(json["max_cpu_cores"], 4.0);
+
+        let record = KillRecord {
+            pid: 42,
+            name: "hog".to_string(),
+            reason: KillReason::RamLimit,
+            strategy: KillStrategy::HeaviestMemory,
+            success: false,
+        };
+        let failed = EnforcerEvent::KillFailed { record, error: "EPERM".to_string() };
+        assert_eq!(failed.to_string(), "  Failed to kill hog (PID: 42): EPERM");
+        assert_eq!(failed.level(), LogLevel::Error);
+        assert_eq!(failed.to_json()["error"], "EPERM");
+        assert_eq!(failed.to_json()["pid"], 42);
+    }
+
+    #[test]
+    fn test_enforcer_status_tracks_actions() {
+        let mut status = EnforcerStatus::default();
+        let record = KillRecord {
+            pid: 10_000_001,
+            name: "hog".to_string(),
+            reason: KillReason::CpuLimit,
+            strategy: KillStrategy::HeaviestCpu,
+            success: true,
+        };
+
+        // Failures and warnings are not actions
+        status.record(&EnforcerEvent::KillFailed { record: record.clone(), error: "EPERM".to_string() });
+        status.record(&EnforcerEvent::EmergencyKillCapReached { kills: 3 });
+        assert!(status.last_action.is_none());
+
+        status.record(&EnforcerEvent::ProcessKilled(record));
+        status.record(&EnforcerEvent::CpuThrottled { cpus: 8 });
+        assert_eq!(status.actions_last_hour(), 2);
+        let last = status.last_action.clone().unwrap();
+        assert_eq!((last.kind, last.process), ("cpu_throttled", None));
+
+        let profile = Profile { name: "watch".to_string(), enforce: false, ..Default::default() };
+        let mut enforcer = Enforcer::new(KernConfig::default(), profile);
         enforcer.emergency_mode = true;
         enforcer.emergency_since = Some(Instant::now());
+        status.sync(&enforcer);
 
-        assert!(enforcer.is_emergency_mode());
-        assert!(enforcer.emergency_duration().is_some());
+        let json = status.to_json();
+        assert_eq!(json["running"], true);
+        assert_eq!(json["paused"], true);
+        assert_eq!(json["mode"], "enforce");
+        assert_eq!(json["emergency_mode"], true);
+        assert_eq!(json["emergency_duration_secs"], 0);
+        assert_eq!(json["active_profile"], "watch");
+        assert_eq!(json["last_action"]["kind"], "cpu_throttled");
+        assert_eq!(json["actions_last_hour"], 2);
     }
 
     #[test]
-    fn test_profile_switching() {
-        let config = KernConfig::default();
-        let profile1 = Profile {
-            name: "profile1".to_string(),
+    fn test_kill_count_limit_stops_killing() {
+        let config = KernConfig { kill_count_limit: 2, ..Default::default() };
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let events = collect_events(&mut enforcer);
+
+        // A new synthetic PID above pid_max each cycle: the signal finds nothing (ESRCH), which counts as killed
+        let over_cpu_limit = |pid| SystemStats {
+            cpu_usage: 100.0,
+            memory_percentage: 10.0,
+            temperature_max: 40.0,
+            top_processes: vec![process(pid, "hog", 1.0, 99.0, 100)],
             ..Default::default()
         };
-        let profile2 = Profile {
-            name: "profile2".to_string(),
+        assert_eq!(enforcer.enforce_stats(&over_cpu_limit(10_000_001)).unwrap().kills.len(), 1);
+        assert_eq!(enforcer.enforce_stats(&over_cpu_limit(10_000_002)).unwrap().kills.len(), 1);
+        assert_eq!(enforcer.total_kills(), 2);
+
+        // Still monitoring, but no more kills
+        for pid in [10_000_003, 10_000_004] {
+            assert!(enforcer.enforce_stats(&over_cpu_limit(pid)).unwrap().kills.is_empty());
+        }
+        assert_eq!(enforcer.total_kills(), 2);
+
+        let events = events.lock().unwrap();
+        let kinds: Vec<&str> = events.iter().map(|event| event.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "limit_exceeded", "process_killed",
+                "limit_exceeded", "process_killed", "kill_limit_reached",
+                "limit_exceeded",
+                "limit_exceeded",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kill_record_json() {
+        let record = KillRecord {
+            pid: 42,
+            name: "compiler".to_string(),
+            reason: KillReason::CpuLimit,
+            strategy: KillStrategy::HeaviestCpu,
+            success: true,
+        };
+        let value = serde_json::to_value(&record).unwrap();
+
+        assert_eq!(value["pid"], 42);
+        assert_eq!(value["reason"], "cpu_limit");
+        assert_eq!(value["strategy"], "heaviest_cpu");
+        assert_eq!(value["success"], true);
+    }
+
+    fn stats_with_cpu(cpu_usage: f64) -> SystemStats {
+        SystemStats {
+            cpu_usage,
+            top_processes: sample_processes(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_emergency_budget_per_cycle() {
+        let enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+
+        // Default config: at most 3 kills per cycle while CPU is high
+        assert_eq!(enforcer.emergency_kill_budget(&stats_with_cpu(95.0)), 3);
+    }
+
+    #[test]
+    fn test_emergency_budget_stops_below_cpu_floor() {
+        let mut config = KernConfig::default();
+        config.emergency.cpu_floor_percent = 40.0;
+        let enforcer = Enforcer::new(config, Profile::default());
+
+        // Load is addressed even though the temperature may still be high
+        assert_eq!(enforcer.emergency_kill_budget(&stats_with_cpu(35.0)), 0);
+        assert_eq!(enforcer.emergency_kill_budget(&stats_with_cpu(45.0)), 3);
+    }
+
+    #[test]
+    fn test_nan_readings_skip_checks() {
+        let mut config = KernConfig::default();
+        config.emergency.trigger_samples = 2;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+
+        // Synthetic PIDs above pid_max: nothing real could ever be signalled
+        let stats = SystemStats {
+            cpu_usage: f64::NAN,
+            memory_percentage: f64::NAN,
+            temperature_max: f64::NAN,
+            top_processes: vec![process(10_000_001, "hog", 8.0, f64::NAN, 100)],
+            ..Default::default()
+        };
+        let report = enforcer.enforce_stats(&stats).unwrap();
+        assert!(report.kills.is_empty());
+        assert!(!enforcer.is_emergency_mode());
+        assert_eq!(enforcer.emergency_kill_budget(&stats), 0);
+
+        // A NaN sample between two hot ones doesn't reset the streak
+        enforcer.observe_temperature(110.0);
+        assert_eq!(enforcer.observe_temperature(f64::NAN), None);
+        assert_eq!(enforcer.observe_temperature(110.0), Some(EmergencyTransition::Entered));
+    }
+
+    #[test]
+    fn test_disabled_temperature_is_ignored() {
+        let mut config = KernConfig::default();
+        config.temperature.enabled = false;
+        config.emergency.trigger_samples = 1;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+
+        // Synthetic PID above pid_max: nothing real could ever be signalled
+        let hog = vec![process(10_000_001, "hog", 8.0, 1.0, 100)];
+        let warm = SystemStats { temperature_max: 80.0, top_processes: hog.clone(), ..Default::default() };
+        assert!(enforcer.enforce_stats(&warm).unwrap().kills.is_empty());
+
+        let hot = SystemStats { temperature_max: 110.0, top_processes: hog, ..Default::default() };
+        let report = enforcer.enforce_stats(&hot).unwrap();
+        assert!(report.kills.is_empty());
+        assert!(!enforcer.is_emergency_mode());
+        assert_eq!(report.critical_samples, 0);
+    }
+
+    #[test]
+    fn test_emergency_budget_respects_episode_cap() {
+        let mut config = KernConfig::default();
+        config.emergency.max_kills_per_cycle = 3;
+        config.emergency.max_total_kills = 5;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+        let stats = stats_with_cpu(95.0);
+
+        enforcer.emergency_kills = 3;
+        assert_eq!(enforcer.emergency_kill_budget(&stats), 2);
+
+        enforcer.emergency_kills = 5;
+        assert_eq!(enforcer.emergency_kill_budget(&stats), 0);
+    }
+
+    #[test]
+    fn test_profile_switch_resets_emergency_kills() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        enforcer.emergency_kills = 7;
+
+        enforcer.switch_profile(Profile { name: "next".to_string(), ..Default::default() }).ok();
+        assert_eq!(enforcer.emergency_kills, 0);
+    }
+
+    #[test]
+    fn test_temperature_thresholds_fallback_and_override() {
+        let mut config = KernConfig::default();
+        config.temperature.warning = 70.0;
+        config.temperature.critical = 80.0;
+
+        let enforcer = Enforcer::new(config.clone(), Profile::default());
+        assert_eq!(enforcer.temperature_thresholds(), (70.0, 80.0));
+
+        let mut gaming = Profile {
+            name: "gaming".to_string(),
             ..Default::default()
         };
+        gaming.limits.temp_critical = Some(95.0);
+        let enforcer = Enforcer::new(config, gaming);
+        // Only critical overridden; warning comes from the global config
+        assert_eq!(enforcer.temperature_thresholds(), (70.0, 95.0));
+    }
 
-        let mut enforcer = Enforcer::new(config, profile1);
-        assert_eq!(enforcer.profile().name, "profile1");
+    // Feed a sequence of temperatures and collect the resulting transitions
+    fn run_samples(enforcer: &mut Enforcer, samples: &[f64]) -> Vec<Option<EmergencyTransition>> {
+        samples.iter().map(|t| enforcer.observe_temperature(*t)).collect()
+    }
 
-        enforcer.switch_profile(profile2).ok();
-        assert_eq!(enforcer.profile().name, "profile2");
+    #[test]
+    fn test_single_spike_does_not_trigger_emergency() {
+        // Defaults: warning 75, critical 85, 2 samples to enter/exit
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+
+        let transitions = run_samples(&mut enforcer, &[60.0, 90.0, 60.0, 90.0, 60.0]);
+        assert!(transitions.iter().all(|t| t.is_none()));
+        assert!(!enforcer.is_emergency_mode());
+    }
+
+    #[test]
+    fn test_consecutive_samples_trigger_emergency() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+
+        let transitions = run_samples(&mut enforcer, &[90.0, 91.0]);
+        assert_eq!(transitions, vec![None, Some(EmergencyTransition::Entered)]);
+        assert!(enforcer.is_emergency_mode());
+        assert!(enforcer.emergency_duration().is_some());
+    }
+
+    #[test]
+    fn test_emergency_exit_requires_consecutive_cool_samples() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        run_samples(&mut enforcer, &[90.0, 90.0]);
+        assert!(enforcer.is_emergency_mode());
+
+        // Between warning and critical doesn't count as cooled: streak resets
+        let transitions = run_samples(&mut enforcer, &[70.0, 80.0, 70.0]);
+        assert!(transitions.iter().all(|t| t.is_none()));
+        assert!(enforcer.is_emergency_mode());
+        assert_eq!(enforcer.cool_samples, 1);
+
+        // Second consecutive cool sample leaves emergency mode
+        let transitions = run_samples(&mut enforcer, &[70.0]);
+        assert_eq!(transitions, vec![Some(EmergencyTransition::Exited)]);
+        assert!(!enforcer.is_emergency_mode());
+        assert!(enforcer.emergency_duration().is_none());
+    }
+
+    #[test]
+    fn test_enforce_once_uses_stats_provider() {
+        let mut enforcer = Enforcer::new(KernConfig::default(), Profile::default());
+        let events = collect_events(&mut enforcer);
+        let mut temperatures = VecDeque::from([90.0, 91.0, 70.0, 70.0]);
+        enforcer.set_stats_provider(move || match temperatures.pop_front() {
+            Some(temperature) => Ok(SystemStats { temperature_max: temperature, ..Default::default() }),
+            None => Err(anyhow::anyhow!("no more samples")),
+        });
+
+        enforcer.enforce_once().unwrap();
+        assert!(!enforcer.is_emergency_mode());
+        enforcer.enforce_once().unwrap();
+        assert!(enforcer.is_emergency_mode());
+        enforcer.enforce_once().unwrap();
+        enforcer.enforce_once().unwrap();
+        assert!(!enforcer.is_emergency_mode());
+        let kinds: Vec<_> = events.lock().unwrap().iter().map(EnforcerEvent::kind).collect();
+        assert!(kinds.contains(&"emergency_activated") && kinds.contains(&"emergency_resolved"));
+
+        // A failing provider fails the cycle, as a failed read of the system would
+        assert!(enforcer.enforce_once().is_err());
+    }
+
+    #[test]
+    fn test_trigger_samples_configurable() {
+        let mut config = KernConfig::default();
+        config.emergency.trigger_samples = 1;
+        config.emergency.exit_samples = 1;
+        let mut enforcer = Enforcer::new(config, Profile::default());
+
+        assert_eq!(enforcer.observe_temperature(90.0), Some(EmergencyTransition::Entered));
+        assert_eq!(enforcer.observe_temperature(70.0), Some(EmergencyTransition::Exited));
     }
 
     #[test]
@@ -0,0 +1,304 @@
+use crate::profiles::{Profile, ProfileManager};
+use std::fmt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const INPUT_DIR: &str = "/dev/input";
+
+/// Consecutive samples that must agree before switching into or out of an idle profile
+pub const IDLE_DEBOUNCE_SAMPLES: u32 = 2;
+
+/// How long the user session has been idle, or None where that can't be
+/// determined (headless machines, no session bus, no readable input devices).
+///
+/// Asks the session's screensaver over DBus first (`org.freedesktop.ScreenSaver`,
+/// or Mutter's idle monitor on GNOME, which doesn't implement GetSessionIdleTime),
+/// then falls back to the last activity on `/dev/input` event devices.
+pub fn session_idle_time() -> Option<Duration> {
+    screensaver_idle_time().or_else(|| input_idle_time(Path::new(INPUT_DIR)))
+}
+
+// Idle time from the session bus; both services report milliseconds
+fn screensaver_idle_time() -> Option<Duration> {
+    let calls: [&[&str]; 2] = [
+        &["org.freedesktop.ScreenSaver", "/org/freedesktop/ScreenSaver", "org.freedesktop.ScreenSaver", "GetSessionIdleTime"],
+        &["org.gnome.Mutter.IdleMonitor", "/org/gnome/Mutter/IdleMonitor/Core", "org.gnome.Mutter.IdleMonitor", "GetIdletime"],
+    ];
+    calls.iter().find_map(|call| {
+        let output = Command::new("busctl").arg("--user").arg("call").args(*call).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_busctl_idle(&String::from_utf8_lossy(&output.stdout))
+    })
+}
+
+// Parse busctl's reply to an idle time call, e.g. "u 61000" or "t 61000" (milliseconds)
+fn parse_busctl_idle(output: &str) -> Option<Duration> {
+    let mut fields = output.split_whitespace();
+    match fields.next()? {
+        "u" | "t" => fields.next()?.parse().ok().map(Duration::from_millis),
+        _ => None,
+    }
+}
+
+/// Time since the newest activity on the `event*` devices in `dir`.
+///
+/// Only devices whose access or modification time moved past their change
+/// time count: on systems where input doesn't touch the device node's
+/// timestamps there is nothing to go by, and this returns None.
+pub fn input_idle_time(dir: &Path) -> Option<Duration> {
+    let last_activity = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("event"))
+        .filter_map(|entry| entry.metadata().ok())
+        .filter_map(|metadata| {
+            let activity = metadata.atime().max(metadata.mtime());
+            (activity > metadata.ctime()).then_some(activity)
+        })
+        .max()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some(Duration::from_secs(now.saturating_sub(last_activity).max(0) as u64))
+}
+
+/// A profile activated by an `idle` auto-activation trigger
+#[derive(Debug, Clone)]
+struct IdleTrigger {
+    threshold: Duration,
+    key: String, // ProfileManager key (file name), which can differ from Profile::name
+    profile: Profile,
+}
+
+/// A switch decided by [`IdleSwitcher::update`]
+#[derive(Debug, Clone)]
+pub struct IdleSwitch {
+    pub key: String,
+    pub profile: Profile,
+    pub idle_for: Option<Duration>, // Set when entering an idle profile, None when switching back
+}
+
+impl fmt::Display for IdleSwitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.idle_for {
+            Some(idle) => write!(
+                f,
+                "Session idle for {}m, switching to profile '{}'",
+                idle.as_secs() / 60,
+                self.profile.name
+            ),
+            None => write!(f, "Activity resumed, switching back to profile '{}'", self.profile.name),
+        }
+    }
+}
+
+/// Switches to the profile of the deepest `idle` trigger the session has been
+/// idle for, and back to the previous profile once there is activity again.
+///
+/// Both directions need [`IDLE_DEBOUNCE_SAMPLES`] consecutive samples that
+/// agree. A profile switch made by hand while idle is kept: there is nothing
+/// to switch back to afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct IdleSwitcher {
+    triggers: Vec<IdleTrigger>,            // Ascending by threshold
+    resume: Option<(String, Profile)>,     // Active before the first idle switch
+    switched_to: Option<String>,           // Name of the idle profile this switcher activated
+    pending: Option<(Option<usize>, u32)>, // Trigger index (None = resume) and its streak
+}
+
+impl IdleSwitcher {
+    /// Collect the `idle` triggers of profiles with auto-activation enabled
+    pub fn from_profiles(manager: &ProfileManager) -> Self {
+        let mut triggers: Vec<IdleTrigger> = manager
+            .list_all()
+            .into_iter()
+            .filter(|(_, profile)| profile.auto_activate.enabled)
+            .flat_map(|(key, profile)| {
+                profile.auto_activate.triggers.iter().filter_map(move |trigger| {
+                    trigger.idle_threshold().map(|threshold| IdleTrigger {
+                        threshold,
+                        key: key.to_string(),
+                        profile: profile.clone(),
+                    })
+                })
+            })
+            .collect();
+        triggers.sort_by_key(|trigger| trigger.threshold);
+        Self { triggers, ..Default::default() }
+    }
+
+    /// No profile has an idle trigger, so there is nothing to poll
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    /// Feed one idle time sample. `current_key`/`current` are the active
+    /// profile. Returns the profile to switch to, if any; None idle time
+    /// (undeterminable) changes nothing.
+    pub fn update(&mut self, idle: Option<Duration>, current_key: &str, current: &Profile) -> Option<IdleSwitch> {
+        let Some(idle) = idle else {
+            self.pending = None;
+            return None;
+        };
+        if self.switched_to.as_ref().is_some_and(|name| *name != current.name) {
+            // Switched by hand since: keep that profile, don't switch back later
+            self.resume = None;
+            self.switched_to = None;
+        }
+
+        let target = self.triggers.iter().rposition(|trigger| idle >= trigger.threshold);
+        let target_name = match target {
+            Some(index) => &self.triggers[index].profile.name,
+            None => match &self.resume {
+                Some((_, profile)) => &profile.name,
+                None => {
+                    self.pending = None;
+                    return None;
+                }
+            },
+        };
+        if *target_name == current.name {
+            self.pending = None;
+            return None;
+        }
+
+        let streak = match self.pending {
+            Some((pending, streak)) if pending == target => streak + 1,
+            _ => 1,
+        };
+        if streak < IDLE_DEBOUNCE_SAMPLES {
+            self.pending = Some((target, streak));
+            return None;
+        }
+        self.pending = None;
+
+        match target {
+            Some(index) => {
+                let trigger = &self.triggers[index];
+                if self.resume.is_none() {
+                    self.resume = Some((current_key.to_string(), current.clone()));
+                }
+                self.switched_to = Some(trigger.profile.name.clone());
+                Some(IdleSwitch { key: trigger.key.clone(), profile: trigger.profile.clone(), idle_for: Some(idle) })
+            }
+            None => {
+                self.switched_to = None;
+                let (key, profile) = self.resume.take()?;
+                Some(IdleSwitch { key, profile, idle_for: None })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::{AutoActivateConfig, AutoActivateTrigger};
+
+    fn idle_profile(name: &str, minutes: u64) -> Profile {
+        Profile {
+            name: name.to_string(),
+            auto_activate: AutoActivateConfig {
+                enabled: true,
+                triggers: vec![AutoActivateTrigger {
+                    trigger_type: Some("idle".to_string()),
+                    command_contains: None,
+                    idle_minutes: Some(minutes),
+                }],
+            },
+            ..Default::default()
+        }
+    }
+
+    fn switcher(profiles: &[Profile]) -> IdleSwitcher {
+        let mut triggers: Vec<IdleTrigger> = profiles
+            .iter()
+            .map(|profile| IdleTrigger {
+                threshold: profile.auto_activate.triggers[0].idle_threshold().unwrap(),
+                key: profile.name.clone(),
+                profile: profile.clone(),
+            })
+            .collect();
+        triggers.sort_by_key(|trigger| trigger.threshold);
+        IdleSwitcher { triggers, ..Default::default() }
+    }
+
+    fn minutes(m: u64) -> Option<Duration> {
+        Some(Duration::from_secs(m * 60))
+    }
+
+    #[test]
+    fn test_parse_busctl_idle() {
+        assert_eq!(parse_busctl_idle("u 61000\n"), Some(Duration::from_secs(61)));
+        assert_eq!(parse_busctl_idle("t 1500"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_busctl_idle("s \"oops\""), None);
+        assert_eq!(parse_busctl_idle(""), None);
+    }
+
+    #[test]
+    fn test_idle_switch_is_debounced_and_switches_back() {
+        let coding = Profile { name: "coding".to_string(), ..Default::default() };
+        let saving = idle_profile("power-saving", 10);
+        let mut switcher = switcher(std::slice::from_ref(&saving));
+
+        assert!(switcher.update(minutes(5), "coding", &coding).is_none());
+        // First sample over the threshold only starts the streak
+        assert!(switcher.update(minutes(10), "coding", &coding).is_none());
+        let switch = switcher.update(minutes(11), "coding", &coding).unwrap();
+        assert_eq!(switch.key, "power-saving");
+        assert_eq!(switch.to_string(), "Session idle for 11m, switching to profile 'power-saving'");
+
+        // A single active sample doesn't switch back, two do
+        assert!(switcher.update(Some(Duration::ZERO), "power-saving", &saving).is_none());
+        assert!(switcher.update(minutes(12), "power-saving", &saving).is_none());
+        assert!(switcher.update(Some(Duration::ZERO), "power-saving", &saving).is_none());
+        let back = switcher.update(Some(Duration::from_secs(2)), "power-saving", &saving).unwrap();
+        assert_eq!(back.key, "coding");
+        assert!(back.idle_for.is_none());
+        assert!(switcher.update(Some(Duration::ZERO), "coding", &coding).is_none());
+    }
+
+    #[test]
+    fn test_idle_switch_picks_deepest_threshold_and_resumes_original() {
+        let coding = Profile { name: "coding".to_string(), ..Default::default() };
+        let quiet = idle_profile("quiet", 5);
+        let saving = idle_profile("power-saving", 30);
+        let mut switcher = switcher(&[saving.clone(), quiet.clone()]);
+
+        switcher.update(minutes(6), "coding", &coding);
+        assert_eq!(switcher.update(minutes(6), "coding", &coding).unwrap().key, "quiet");
+        switcher.update(minutes(31), "quiet", &quiet);
+        assert_eq!(switcher.update(minutes(31), "quiet", &quiet).unwrap().key, "power-saving");
+        switcher.update(minutes(0), "power-saving", &saving);
+        assert_eq!(switcher.update(minutes(0), "power-saving", &saving).unwrap().key, "coding");
+    }
+
+    #[test]
+    fn test_idle_switcher_keeps_manual_switch_and_skips_unknown_idle() {
+        let coding = Profile { name: "coding".to_string(), ..Default::default() };
+        let gaming = Profile { name: "gaming".to_string(), ..Default::default() };
+        let mut switcher = switcher(&[idle_profile("power-saving", 10)]);
+
+        // Headless: no idle time, nothing happens
+        assert!(switcher.update(None, "coding", &coding).is_none());
+        assert!(switcher.update(None, "coding", &coding).is_none());
+
+        switcher.update(minutes(10), "coding", &coding);
+        assert!(switcher.update(minutes(10), "coding", &coding).is_some());
+        // The user picked another profile while idle: activity doesn't undo it
+        switcher.update(minutes(0), "gaming", &gaming);
+        assert!(switcher.update(minutes(0), "gaming", &gaming).is_none());
+    }
+
+    #[test]
+    fn test_input_idle_time_without_activity_is_unknown() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(input_idle_time(dir.path()).is_none());
+        std::fs::write(dir.path().join("event0"), "").unwrap();
+        // Never touched since it was created: nothing to go by
+        assert!(input_idle_time(dir.path()).is_none());
+        assert!(input_idle_time(&dir.path().join("missing")).is_none());
+    }
+}
@@ -1,66 +1,305 @@
-pub fn kill_process(pid: u32, graceful: bool) -> Result<(), String> {
+use crate::cgroups::{CgroupLimiter, CgroupPlacement};
+use crate::config::KernConfig;
+use crate::monitor::{descending, ProcessInfo};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// Retries for a signal that failed with a possibly transient error
+pub const KILL_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled on each further attempt
+pub const KILL_RETRY_BACKOFF_MS: u64 = 50;
+
+/// How long a process may take to disappear after SIGKILL before the kill counts as failed
+pub const KILL_VERIFY_TIMEOUT_MS: u64 = 1000;
+
+/// A failed kill
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillError {
+    pub message: String,
+    pub permanent: bool, // Retrying this process (now or in a later cycle) won't help
+}
+
+impl fmt::Display for KillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// How a failed `kill(2)` call should be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillErrorKind {
+    Gone,      // ESRCH: the process already exited, which counts as success
+    Permanent, // EPERM: not ours to kill, skip it
+    Transient, // Anything else: worth retrying
+}
+
+#[cfg(unix)]
+pub fn classify_kill_errno(errno: nix::errno::Errno) -> KillErrorKind {
+    use nix::errno::Errno;
+
+    match errno {
+        Errno::ESRCH => KillErrorKind::Gone,
+        Errno::EPERM => KillErrorKind::Permanent,
+        _ => KillErrorKind::Transient,
+    }
+}
+
+/// Run `send` until it succeeds, retrying transient errors with exponential backoff.
+/// Returns Ok(false) if the process is gone.
+#[cfg(unix)]
+fn send_with_retry<F>(mut send: F, retries: u32, backoff: std::time::Duration) -> Result<bool, (nix::errno::Errno, KillErrorKind)>
+where
+    F: FnMut() -> nix::Result<()>,
+{
+    let mut attempt = 0;
+    loop {
+        let errno = match send() {
+            Ok(()) => return Ok(true),
+            Err(errno) => errno,
+        };
+        match classify_kill_errno(errno) {
+            KillErrorKind::Gone => return Ok(false),
+            KillErrorKind::Transient if attempt < retries => {
+                std::thread::sleep(backoff * 2u32.pow(attempt));
+                attempt += 1;
+            }
+            kind => return Err((errno, kind)),
+        }
+    }
+}
+
+// Send `signal` to `pid`, retrying transient failures. Ok(false) if the process is gone.
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: nix::sys::signal::Signal) -> Result<bool, KillError> {
+    use nix::sys::signal::kill;
+    use nix::unistd::Pid;
+    use std::time::Duration;
+
+    send_with_retry(
+        || kill(Pid::from_raw(pid as i32), signal),
+        KILL_RETRIES,
+        Duration::from_millis(KILL_RETRY_BACKOFF_MS),
+    )
+    .map_err(|(errno, kind)| KillError {
+        message: format!("Failed to send {} to {}: {}", signal, pid, errno),
+        permanent: kind == KillErrorKind::Permanent,
+    })
+}
+
+/// How long a graceful kill waits between SIGTERM and SIGKILL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GracefulWait<'a> {
+    pub timeout: std::time::Duration, // SIGKILL once it's still running after this (kill_graceful_timeout_secs)
+    pub poll: std::time::Duration,    // Check whether it exited this often (kill_graceful_poll_ms)
+    pub watchdog: Option<&'a crate::systemd::Notifier>, // Kept fed while waiting, so a long wait can't trip it
+}
+
+/// Kill `pid`. Graceful: SIGTERM once, then a liveness check (signal 0) every
+/// `wait.poll`, and SIGKILL if it is still running after `wait.timeout`.
+/// Otherwise SIGKILL right away.
+pub fn kill_process(pid: u32, graceful: bool, wait: GracefulWait<'_>) -> Result<(), KillError> {
     #[cfg(unix)]
     {
-        use nix::sys::signal::{kill, Signal};
-        use nix::unistd::Pid;
-        use std::time::Duration;
+        use nix::sys::signal::Signal;
+        use std::time::Instant;
         use std::thread;
 
         if graceful {
-            // 1. Send SIGTERM to process
-            match kill(Pid::from_raw(pid as i32), Signal::SIGTERM) {
-                Ok(_) => {},
-                Err(e) => {
-                    // If process doesn't exist, it's already dead
-                    if e.to_string().contains("No such process") {
-                        return Ok(());
-                    }
-                    return Err(format!("Failed to send SIGTERM to {}: {}", pid, e));
-                }
+            // 1. Send SIGTERM to process (if it doesn't exist, it's already dead)
+            if !send_signal(pid, Signal::SIGTERM)? {
+                return Ok(());
             }
 
-            // 2. Wait 5 seconds for graceful shutdown
-            for _ in 0..50 {
-                thread::sleep(Duration::from_millis(100));
-
-                // Check if process still alive by sending signal 0 (no-op)
-                match kill(Pid::from_raw(pid as i32), Signal::SIGTERM) {
-                    Err(e) if e.to_string().contains("No such process") => {
-                        return Ok(()); // Process died gracefully
-                    }
-                    _ => continue,
+            // 2. Wait up to `wait.timeout` for graceful shutdown, probing without re-signalling
+            let deadline = Instant::now() + wait.timeout;
+            let mut next_ping = Instant::now();
+            while Instant::now() < deadline {
+                if let Some(watchdog) = wait.watchdog {
+                    watchdog.watchdog_due(&mut next_ping);
+                }
+                thread::sleep(wait.poll.min(deadline.saturating_duration_since(Instant::now())));
+                if has_exited(pid) {
+                    return Ok(()); // Process died gracefully
                 }
             }
 
-            // 3. If still alive after 5 seconds, send SIGKILL
-            kill(Pid::from_raw(pid as i32), Signal::SIGKILL)
-                .map_err(|e| format!("Failed to force kill process {}: {}", pid, e))?;
+            // 3. If still alive after the timeout, send SIGKILL
+            if send_signal(pid, Signal::SIGKILL)? {
+                verify_exited(pid)?;
+            }
             Ok(())
         } else {
             // Force kill immediately
-            kill(Pid::from_raw(pid as i32), Signal::SIGKILL)
-                .map_err(|e| format!("Failed to kill process {}: {}", pid, e))?;
+            if send_signal(pid, Signal::SIGKILL)? {
+                verify_exited(pid)?;
+            }
             Ok(())
         }
     }
 
     #[cfg(not(unix))]
     {
-        Err("Process killing is not supported on this platform.".to_string())
+        Err(KillError {
+            message: "Process killing is not supported on this platform.".to_string(),
+            permanent: true,
+        })
     }
 }
 
-pub fn kill_processes(pids: &[u32], graceful: bool) -> Result<(), String> {
+// Wait for `pid` to exit after SIGKILL. A process stuck in uninterruptible
+// sleep can outlive it; that's a failed kill, worth retrying next cycle.
+#[cfg(unix)]
+fn verify_exited(pid: u32) -> Result<(), KillError> {
+    use std::time::{Duration, Instant};
+
+    let deadline = Instant::now() + Duration::from_millis(KILL_VERIFY_TIMEOUT_MS);
+    while !has_exited(pid) {
+        if Instant::now() >= deadline {
+            return Err(KillError {
+                message: format!("Process {} is still running after SIGKILL", pid),
+                permanent: false,
+            });
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    Ok(())
+}
+
+// Gone, or a zombie waiting for its parent to reap it
+#[cfg(unix)]
+fn has_exited(pid: u32) -> bool {
+    can_signal(pid).is_none()
+        || crate::monitor::read_proc_stat(pid).is_some_and(|stat| stat.state == 'Z')
+}
+
+// CAP_KILL from include/uapi/linux/capability.h
+const CAP_KILL: u32 = 5;
+
+/// Whether kern may signal `pid` (checked with signal 0, which sends nothing);
+/// None if the process is gone
+#[cfg(unix)]
+pub fn can_signal(pid: u32) -> Option<bool> {
+    use nix::sys::signal::kill;
+    use nix::unistd::Pid;
+
+    match kill(Pid::from_raw(pid as i32), None) {
+        Ok(()) => Some(true),
+        Err(errno) => match classify_kill_errno(errno) {
+            KillErrorKind::Gone => None,
+            KillErrorKind::Permanent => Some(false),
+            KillErrorKind::Transient => Some(true),
+        },
+    }
+}
+
+/// Whether kern may signal every user's processes: root, or CAP_KILL in its effective set
+#[cfg(unix)]
+pub fn can_kill_any_user() -> bool {
+    nix::unistd::geteuid().is_root()
+        || std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| effective_capabilities(&status))
+            .is_some_and(|caps| caps & (1 << CAP_KILL) != 0)
+}
+
+/// The CapEff bitmask from /proc/<pid>/status content
+pub fn effective_capabilities(status: &str) -> Option<u64> {
+    let hex = status.lines().find_map(|line| line.strip_prefix("CapEff:"))?;
+    u64::from_str_radix(hex.trim(), 16).ok()
+}
+
+/// Move `pid` into kern's CPU/memory-limited cgroup instead of killing it.
+///
+/// `cpu_max_percent` is a share of the whole machine; `memory_high_bytes`
+/// sets memory.high (None leaves memory unlimited).
+pub fn limit_process_cgroup(
+    pid: u32,
+    cpu_max_percent: f64,
+    memory_high_bytes: Option<u64>,
+) -> anyhow::Result<CgroupPlacement> {
+    CgroupLimiter::new().limit(pid, cpu_max_percent, memory_high_bytes)
+}
+
+/// Undo `limit_process_cgroup`
+pub fn release_process_cgroup(pid: u32, placement: &CgroupPlacement) -> anyhow::Result<()> {
+    CgroupLimiter::new().release(pid, placement)
+}
+
+pub fn kill_processes(pids: &[u32], graceful: bool, wait: GracefulWait<'_>) -> Result<(), KillError> {
     for &pid in pids {
-        kill_process(pid, graceful)?;
+        kill_process(pid, graceful, wait)?;
     }
     Ok(())
 }
 
+/// Which one of several processes matching a name `kern kill` picks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillSelector {
+    Oldest,   // Earliest start time
+    Newest,   // Latest start time
+    Heaviest, // Most memory
+}
+
+impl fmt::Display for KillSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            KillSelector::Oldest => "oldest",
+            KillSelector::Newest => "newest",
+            KillSelector::Heaviest => "heaviest",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// The single process `selector` picks out of `processes`; None if empty.
+/// Ties go to the lower PID for oldest and heaviest, the higher for newest.
+pub fn select_kill_target(processes: &[ProcessInfo], selector: KillSelector) -> Option<&ProcessInfo> {
+    match selector {
+        KillSelector::Oldest => processes.iter().min_by_key(|p| (p.start_time, p.pid)),
+        KillSelector::Newest => processes.iter().max_by_key(|p| (p.start_time, p.pid)),
+        KillSelector::Heaviest => processes.iter().min_by(|a, b| descending(a.memory_gb, b.memory_gb).then(a.pid.cmp(&b.pid))),
+    }
+}
+
+/// How long `process` has been running; `now` in seconds since the Unix epoch
+pub fn process_age(process: &ProcessInfo, now: u64) -> std::time::Duration {
+    std::time::Duration::from_secs(now.saturating_sub(process.start_time))
+}
+
+/// Split `processes` into those running for longer than `min_age` and the rest
+/// (`kern kill --older-than`); `now` in seconds since the Unix epoch
+pub fn partition_by_age(
+    processes: Vec<ProcessInfo>,
+    min_age: std::time::Duration,
+    now: u64,
+) -> (Vec<ProcessInfo>, Vec<ProcessInfo>) {
+    processes.into_iter().partition(|p| process_age(p, now) > min_age)
+}
+
+/// Why `selector` picked `process`, e.g. "newest, started 2m 5s ago" or
+/// "heaviest, 1.25 GB"; `now` in seconds since the Unix epoch
+pub fn describe_selection(process: &ProcessInfo, selector: KillSelector, now: u64) -> String {
+    match selector {
+        KillSelector::Oldest | KillSelector::Newest => {
+            format!("{}, started {} ago", selector, humantime::format_duration(process_age(process, now)))
+        }
+        KillSelector::Heaviest => format!("{}, {:.2} GB", selector, process.memory_gb),
+    }
+}
+
 /// Get the path to the kill log file
-pub fn get_kill_log_path() -> std::path::PathBuf {
+///
+/// Prefers `kill_log_path` from the config when set, otherwise falls back
+/// to ~/.config/kern/kern.log
+pub fn get_kill_log_path(config: Option<&KernConfig>) -> std::path::PathBuf {
     use std::path::PathBuf;
 
+    if let Some(path) = config.and_then(|c| c.kill_log_path.as_ref()) {
+        return path.clone();
+    }
+
     if let Ok(config_home) = std::env::var("XDG_CONFIG_HOME") {
         PathBuf::from(config_home).join("kern").join("kern.log")
     } else if let Ok(home) = std::env::var("HOME") {
@@ -70,14 +309,19 @@ pub fn get_kill_log_path() -> std::path::PathBuf {
     }
 }
 
-/// Log a kill action to ~/.config/kern/kern.log
-pub fn log_kill_action(pid: u32, name: &str, success: bool, graceful: bool) {
+/// Log a kill action to the configured kill log (~/.config/kern/kern.log by default)
+pub fn log_kill_action(
+    config: Option<&KernConfig>,
+    pid: u32,
+    name: &str,
+    success: bool,
+    graceful: bool,
+    reason: &str,
+) {
     use chrono::Local;
-    use std::fs::OpenOptions;
-    use std::io::Write;
 
     // Get log file path
-    let log_path = get_kill_log_path();
+    let log_path = get_kill_log_path(config);
 
     // Ensure directory exists
     if let Some(parent) = log_path.parent() {
@@ -89,32 +333,345 @@ pub fn log_kill_action(pid: u32, name: &str, success: bool, graceful: bool) {
     let status = if success { "ok" } else { "failed" };
     
     let log_entry = format!(
-        "[{}] KILL [PID: {}] name=\"{}\" graceful={} status={}\n",
-        timestamp, pid, name, graceful, status
+        "[{}] KILL [PID: {}] name=\"{}\" graceful={} status={} reason=\"{}\"\n",
+        timestamp, pid, name, graceful, status, reason
     );
+    append_log_entry(&log_path, &log_entry);
+}
+
+/// Log a renice/ionice action to the kill log, e.g. action "RENICE", change "0 -> 15"
+pub fn log_priority_action(
+    config: Option<&KernConfig>,
+    action: &str,
+    pid: u32,
+    name: &str,
+    success: bool,
+    change: &str,
+) {
+    let log_path = get_kill_log_path(config);
+    if let Some(parent) = log_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let status = if success { "ok" } else { "failed" };
+    let log_entry = format!(
+        "[{}] {} [PID: {}] name=\"{}\" status={} change=\"{}\"\n",
+        timestamp, action, pid, name, status, change
+    );
+    append_log_entry(&log_path, &log_entry);
+}
+
+/// Log a kill alert-only mode held back to the kill log, e.g. action "kill" or "limit"
+pub fn log_alert_action(config: Option<&KernConfig>, pid: u32, name: &str, action: &str, reason: &str) {
+    let log_path = get_kill_log_path(config);
+    if let Some(parent) = log_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let log_entry = format!(
+        "[{}] ALERT [PID: {}] name=\"{}\" would={} reason=\"{}\"\n",
+        timestamp, pid, name, action, reason
+    );
+    append_log_entry(&log_path, &log_entry);
+}
+
+/// Log a match left alone at `kern kill`'s confirmation prompt to the kill log
+pub fn log_deselected_action(config: Option<&KernConfig>, pid: u32, name: &str) {
+    let log_path = get_kill_log_path(config);
+    if let Some(parent) = log_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let log_entry = format!("[{}] SKIP [PID: {}] name=\"{}\" reason=\"deselected at confirmation\"\n", timestamp, pid, name);
+    append_log_entry(&log_path, &log_entry);
+}
+
+/// Log the relaunch of a killed restart_after_kill process to the kill log
+pub fn log_restart_action(config: Option<&KernConfig>, pid: Option<u32>, name: &str, success: bool, command: &str) {
+    let log_path = get_kill_log_path(config);
+    if let Some(parent) = log_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let status = if success { "ok" } else { "failed" };
+    let pid = pid.map_or_else(|| "-".to_string(), |pid| pid.to_string());
+    let log_entry = format!(
+        "[{}] RESTART [PID: {}] name=\"{}\" status={} command=\"{}\"\n",
+        timestamp, pid, name, status, command
+    );
+    append_log_entry(&log_path, &log_entry);
+}
+
+fn append_log_entry(log_path: &std::path::Path, log_entry: &str) {
+    use std::fs::OpenOptions;
+    use std::io::Write;
 
     // Write to log file
     if let Ok(mut file) = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&log_path)
+        .open(log_path)
     {
         let _ = file.write_all(log_entry.as_bytes());
     }
 }
 
+/// Nice value of a process before and after `renice_process`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NiceChange {
+    pub old: i32,
+    pub new: i32,
+}
+
+/// I/O scheduling class (see ionice(1))
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    None,
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+/// I/O priority: a class plus a level (0 = highest, 7 = lowest) for realtime and best-effort
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            IoClass::None => write!(f, "none"),
+            IoClass::Idle => write!(f, "idle"),
+            IoClass::Realtime => write!(f, "realtime/{}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort/{}", self.level),
+        }
+    }
+}
+
+/// I/O priority before and after `ionice_process`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IoPriorityChange {
+    pub old: IoPriority,
+    pub new: IoPriority,
+}
+
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+/// Kernel ioprio value for a class and level
+pub fn encode_ioprio(priority: IoPriority) -> i32 {
+    let class = match priority.class {
+        IoClass::None => 0,
+        IoClass::Realtime => 1,
+        IoClass::BestEffort => 2,
+        IoClass::Idle => 3,
+    };
+    (class << IOPRIO_CLASS_SHIFT) | i32::from(priority.level.min(7))
+}
+
+/// Class and level from a kernel ioprio value
+pub fn decode_ioprio(value: i32) -> IoPriority {
+    let class = match value >> IOPRIO_CLASS_SHIFT {
+        1 => IoClass::Realtime,
+        2 => IoClass::BestEffort,
+        3 => IoClass::Idle,
+        _ => IoClass::None,
+    };
+    IoPriority { class, level: (value & 0x7) as u8 }
+}
+
+/// Human-readable message for a failed priority change, with a hint for permission errors
+#[cfg(unix)]
+pub fn priority_error_message(errno: nix::errno::Errno, pid: u32, action: &str) -> String {
+    use nix::errno::Errno;
+
+    match errno {
+        Errno::ESRCH => format!("Cannot {} PID {}: no such process", action, pid),
+        Errno::EPERM | Errno::EACCES => format!(
+            "Cannot {} PID {}: permission denied. Raising priority (lower nice, realtime I/O) \
+             or changing another user's process requires root or CAP_SYS_NICE - try sudo",
+            action, pid
+        ),
+        Errno::EINVAL => format!("Cannot {} PID {}: invalid priority", action, pid),
+        _ => format!("Cannot {} PID {}: {}", action, pid, errno),
+    }
+}
+
+/// Current nice value of `pid`
+#[cfg(target_os = "linux")]
+pub fn get_nice(pid: u32) -> Result<i32, String> {
+    use nix::errno::Errno;
+
+    // -1 is a valid nice value, so errors are detected through errno
+    Errno::clear();
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+    match Errno::last_raw() {
+        0 => Ok(nice),
+        _ => Err(priority_error_message(Errno::last(), pid, "read nice value of")),
+    }
+}
+
+/// Set the nice value (-20..19) of `pid`
+#[cfg(target_os = "linux")]
+pub fn renice_process(pid: u32, nice: i32) -> Result<NiceChange, String> {
+    use nix::errno::Errno;
+
+    let old = get_nice(pid)?;
+    let new = nice.clamp(-20, 19);
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, new) } != 0 {
+        return Err(priority_error_message(Errno::last(), pid, "renice"));
+    }
+    Ok(NiceChange { old, new })
+}
+
+/// Refuse nice values the kernel would reject up front: out of range, or negative without root
+pub fn check_nice_value(nice: i32) -> Result<(), String> {
+    if !(-20..=19).contains(&nice) {
+        return Err(format!("Invalid nice value {} (must be -20..19)", nice));
+    }
+    if nice < 0 && !nix::unistd::geteuid().is_root() {
+        return Err(format!(
+            "Cannot set nice value {}: negative nice values (higher priority) require root - try sudo",
+            nice
+        ));
+    }
+    Ok(())
+}
+
+/// Outcome of renicing each PID
+pub type ReniceResults = Vec<(u32, Result<NiceChange, String>)>;
+
+/// Renice every PID in `pids`. Err if the value itself is refused; otherwise one
+/// result per PID (a process may still refuse, e.g. one owned by another user).
+#[cfg(target_os = "linux")]
+pub fn renice_processes(pids: &[u32], nice: i32) -> Result<ReniceResults, String> {
+    check_nice_value(nice)?;
+    Ok(pids.iter().map(|&pid| (pid, renice_process(pid, nice))).collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn renice_processes(_pids: &[u32], _nice: i32) -> Result<ReniceResults, String> {
+    Err("Renicing processes is not supported on this platform".to_string())
+}
+
+/// Current I/O priority of `pid`
+#[cfg(target_os = "linux")]
+pub fn get_io_priority(pid: u32) -> Result<IoPriority, String> {
+    use nix::errno::Errno;
+
+    let value = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid as libc::c_int) };
+    if value < 0 {
+        return Err(priority_error_message(Errno::last(), pid, "read I/O priority of"));
+    }
+    Ok(decode_ioprio(value as i32))
+}
+
+/// Set the I/O scheduling class and level of `pid`
+#[cfg(target_os = "linux")]
+pub fn ionice_process(pid: u32, priority: IoPriority) -> Result<IoPriorityChange, String> {
+    use nix::errno::Errno;
+
+    let old = get_io_priority(pid)?;
+    let value = encode_ioprio(priority);
+    let result = unsafe {
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid as libc::c_int, value)
+    };
+    if result < 0 {
+        return Err(priority_error_message(Errno::last(), pid, "ionice"));
+    }
+    Ok(IoPriorityChange { old, new: decode_ioprio(value) })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn ionice_process(_pid: u32, _priority: IoPriority) -> Result<IoPriorityChange, String> {
+    Err("I/O priorities are not supported on this platform".to_string())
+}
+
+/// Process name (comm) for a PID
+pub fn process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|name| name.trim().to_string())
+}
+
 pub fn is_protected(name: &str, protected_list: &[String]) -> bool {
     protected_list.iter().any(|protected_name| protected_name == name)
 }
 
-pub fn is_critical_process(name: &str) -> bool {
-    let critical_processes = vec![
-        "systemd", "gnome-shell", "Xwayland", "X", "Xvfb",
-        "dbus-daemon", "bluetoothd", "wpa_supplicant",
-        "NetworkManager", "ModemManager", "upowerd",
-        "systemd-logind", "login", "sshd", "sudo"
-    ];
-    critical_processes.iter().any(|critical| *critical == name)
+/// Most ancestor levels `ancestry_depth` may ask for
+pub const MAX_ANCESTRY_DEPTH: u8 = 5;
+
+/// Whether one of the first `depth` ancestors of `pid` (1 = its parent) is in
+/// `protected`, so e.g. an `sh` spawned by gnome-shell is spared with it
+pub fn is_protected_by_ancestry(pid: u32, protected: &[String], depth: u8) -> bool {
+    protected_ancestor(Path::new("/proc"), pid, depth, |name| is_protected(name, protected)).is_some()
+}
+
+/// The first of `pid`'s ancestors, up to `depth` levels (at most
+/// MAX_ANCESTRY_DEPTH), whose name satisfies `protected`, read from
+/// `<proc_root>/<pid>/status`. PID 1 never counts: it adopts every orphan.
+pub fn protected_ancestor(
+    proc_root: &Path,
+    pid: u32,
+    depth: u8,
+    protected: impl Fn(&str) -> bool,
+) -> Option<(u32, String)> {
+    let mut current = pid;
+    for _ in 0..depth.min(MAX_ANCESTRY_DEPTH) {
+        let (_, parent) = read_name_and_ppid(proc_root, current)?;
+        if parent <= 1 {
+            return None;
+        }
+        let (name, _) = read_name_and_ppid(proc_root, parent)?;
+        if protected(&name) {
+            return Some((parent, name));
+        }
+        current = parent;
+    }
+    None
+}
+
+// "Name:" and "PPid:" from /proc/<pid>/status
+fn read_name_and_ppid(proc_root: &Path, pid: u32) -> Option<(String, u32)> {
+    let status = std::fs::read_to_string(proc_root.join(pid.to_string()).join("status")).ok()?;
+    let field = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(|value| value.trim().to_string())
+    };
+    Some((field("Name:")?, field("PPid:")?.parse().ok()?))
+}
+
+/// Processes that are critical by default; `critical_processes` in the config adds to these
+/// and `critical_override_allow` removes names explicitly
+#[cfg(not(target_os = "macos"))]
+pub const BUILTIN_CRITICAL_PROCESSES: &[&str] = &[
+    "systemd", "gnome-shell", "Xwayland", "X", "Xvfb",
+    "dbus-daemon", "bluetoothd", "wpa_supplicant",
+    "NetworkManager", "ModemManager", "upowerd",
+    "systemd-logind", "login", "sshd", "sudo"
+];
+
+#[cfg(target_os = "macos")]
+pub const BUILTIN_CRITICAL_PROCESSES: &[&str] = &[
+    "launchd", "kernel_task", "WindowServer", "loginwindow",
+    "login", "sshd", "sudo"
+];
+
+/// Whether `name` is critical: `(built-in ∪ extra) \ allow`, with `extra` and `allow`
+/// coming from the config's `critical_processes` and `critical_override_allow`
+pub fn is_critical_process(name: &str, extra: &[String], allow: &[String]) -> bool {
+    (BUILTIN_CRITICAL_PROCESSES.contains(&name) || extra.iter().any(|critical| critical == name))
+        && !allow.iter().any(|allowed| allowed == name)
 }
 
 pub fn find_processes_by_name(name: &str) -> Vec<u32> {
@@ -149,14 +706,70 @@ pub fn find_processes_by_name(name: &str) -> Vec<u32> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_select_kill_target() {
+        let process = |pid, start_time, memory_gb| ProcessInfo {
+            pid,
+            name: "python3".to_string(),
+            start_time,
+            memory_gb,
+            ..Default::default()
+        };
+        let matches = vec![
+            process(4100, 1_000, 0.5),
+            process(4200, 3_000, 2.0),
+            process(4300, 2_000, 2.0),
+            process(4050, 1_000, 0.1),
+        ];
+        let pick = |selector| select_kill_target(&matches, selector).map(|p| p.pid);
+        assert_eq!(pick(KillSelector::Oldest), Some(4050));
+        assert_eq!(pick(KillSelector::Newest), Some(4200));
+        assert_eq!(pick(KillSelector::Heaviest), Some(4200));
+        assert!(select_kill_target(&[], KillSelector::Newest).is_none());
+
+        // NaN memory never wins
+        let unreadable = vec![process(1, 0, f64::NAN), process(2, 0, 0.3)];
+        assert_eq!(select_kill_target(&unreadable, KillSelector::Heaviest).map(|p| p.pid), Some(2));
+
+        assert_eq!(describe_selection(&matches[1], KillSelector::Newest, 3_125), "newest, started 2m 5s ago");
+        assert_eq!(describe_selection(&matches[1], KillSelector::Heaviest, 3_125), "heaviest, 2.00 GB");
+
+        // --older-than 30m at t=3000: only the processes started at t=1000 (33m 20s ago)
+        let (old, recent) = partition_by_age(matches, std::time::Duration::from_secs(1_800), 3_000);
+        assert_eq!(old.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![4100, 4050]);
+        assert_eq!(recent.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![4200, 4300]);
+        assert_eq!(process_age(&old[0], 999), std::time::Duration::ZERO);
+    }
+
     #[test]
     fn test_is_critical_process() {
-        assert!(is_critical_process("systemd"));
-        assert!(is_critical_process("gnome-shell"));
-        assert!(is_critical_process("dbus-daemon"));
-        assert!(is_critical_process("sshd"));
-        assert!(!is_critical_process("firefox"));
-        assert!(!is_critical_process("code"));
+        assert!(is_critical_process("systemd", &[], &[]));
+        assert!(is_critical_process("gnome-shell", &[], &[]));
+        assert!(is_critical_process("dbus-daemon", &[], &[]));
+        assert!(is_critical_process("sshd", &[], &[]));
+        assert!(!is_critical_process("firefox", &[], &[]));
+        assert!(!is_critical_process("code", &[], &[]));
+    }
+
+    #[test]
+    fn test_is_critical_process_with_config_list() {
+        let extra = vec!["sway".to_string(), "pipewire".to_string()];
+        assert!(is_critical_process("sway", &extra, &[]));
+        assert!(is_critical_process("pipewire", &extra, &[]));
+        // Built-ins stay critical unless critical_override_allow names them
+        assert!(is_critical_process("systemd", &extra, &[]));
+        assert!(!is_critical_process("firefox", &extra, &[]));
+    }
+
+    #[test]
+    fn test_is_critical_process_with_override() {
+        let extra = vec!["sway".to_string()];
+        let allow = vec!["sshd".to_string(), "sway".to_string()];
+        assert!(!is_critical_process("sshd", &extra, &allow));
+        assert!(!is_critical_process("sway", &extra, &allow));
+        assert!(is_critical_process("systemd", &extra, &allow));
+        // Exact names only
+        assert!(is_critical_process("sshd", &[], &["ssh".to_string()]));
     }
 
     #[test]
@@ -173,6 +786,28 @@ mod tests {
         assert!(!is_protected("systemd", &protected_list));
     }
 
+    #[test]
+    fn test_protected_ancestor_walks_the_tree() {
+        // gnome-shell (100) -> sh (200) -> python3 (300), all under PID 1
+        let proc_root = tempfile::TempDir::new().unwrap();
+        for (pid, name, ppid) in [(100, "gnome-shell", 1), (200, "sh", 100), (300, "python3", 200)] {
+            let dir = proc_root.path().join(pid.to_string());
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("status"), format!("Name:\t{}\nState:\tS (sleeping)\nPPid:\t{}\n", name, ppid)).unwrap();
+        }
+        let protected = vec!["gnome-shell".to_string()];
+        let check = |pid, depth| protected_ancestor(proc_root.path(), pid, depth, |name| is_protected(name, &protected));
+
+        assert_eq!(check(200, 1), Some((100, "gnome-shell".to_string())));
+        assert_eq!(check(300, 1), None);
+        assert_eq!(check(300, 2), Some((100, "gnome-shell".to_string())));
+        // 0 keeps the name-only behavior
+        assert_eq!(check(200, 0), None);
+        // PID 1 and missing processes end the walk
+        assert_eq!(check(100, 5), None);
+        assert_eq!(check(999, 5), None);
+    }
+
     #[test]
     fn test_is_protected_empty_list() {
         let protected_list: Vec<String> = vec![];
@@ -194,11 +829,243 @@ mod tests {
         assert!(pids.is_empty(), "nonexistent process should return empty vec");
     }
 
+    #[test]
+    fn test_kill_log_path_prefers_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("logs").join("kern.log");
+
+        let config = KernConfig {
+            kill_log_path: Some(log_path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(get_kill_log_path(Some(&config)), log_path);
+
+        // Without an override the default location is used
+        assert_eq!(get_kill_log_path(Some(&KernConfig::default())), get_kill_log_path(None));
+        assert_ne!(get_kill_log_path(None), log_path);
+    }
+
+    #[test]
+    fn test_log_kill_action_writes_to_configured_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // Parent directory doesn't exist yet and must be created
+        let log_path = temp_dir.path().join("nested").join("kern.log");
+
+        let config = KernConfig {
+            kill_log_path: Some(log_path.clone()),
+            ..Default::default()
+        };
+        log_kill_action(Some(&config), 4242, "test-proc", true, true, "manual");
+
+        let contents = std::fs::read_to_string(&log_path).expect("log file should exist");
+        assert!(contents.contains("[PID: 4242]"));
+        assert!(contents.contains("name=\"test-proc\""));
+    }
+
+    #[test]
+    fn test_classify_kill_errno() {
+        use nix::errno::Errno;
+
+        assert_eq!(classify_kill_errno(Errno::ESRCH), KillErrorKind::Gone);
+        assert_eq!(classify_kill_errno(Errno::EPERM), KillErrorKind::Permanent);
+        assert_eq!(classify_kill_errno(Errno::EAGAIN), KillErrorKind::Transient);
+        assert_eq!(classify_kill_errno(Errno::EINTR), KillErrorKind::Transient);
+    }
+
+    #[test]
+    fn test_signal_permissions() {
+        // Our own process is always ours; a synthetic PID above pid_max never exists
+        assert_eq!(can_signal(std::process::id()), Some(true));
+        assert_eq!(can_signal(10_000_001), None);
+
+        let status = "Name:\tkern\nCapInh:\t0000000000000000\nCapEff:\t0000000000000020\n";
+        assert_eq!(effective_capabilities(status), Some(1 << CAP_KILL));
+        assert_eq!(effective_capabilities("CapEff:\t000001ffffffffff\n"), Some(0x1ff_ffff_ffff));
+        assert_eq!(effective_capabilities("Name:\tkern\n"), None);
+    }
+
+    #[test]
+    fn test_send_with_retry_recovers_from_transient_errors() {
+        use nix::errno::Errno;
+        use std::time::Duration;
+
+        let mut calls = 0;
+        let result = send_with_retry(
+            || {
+                calls += 1;
+                if calls < 3 { Err(Errno::EAGAIN) } else { Ok(()) }
+            },
+            KILL_RETRIES,
+            Duration::ZERO,
+        );
+        assert_eq!(result, Ok(true));
+        assert_eq!(calls, 3);
+
+        // Gives up after the retry budget
+        let mut calls = 0;
+        let result = send_with_retry(|| { calls += 1; Err(Errno::EAGAIN) }, 2, Duration::ZERO);
+        assert_eq!(result, Err((Errno::EAGAIN, KillErrorKind::Transient)));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_send_with_retry_does_not_retry_eperm_or_esrch() {
+        use nix::errno::Errno;
+        use std::time::Duration;
+
+        let mut calls = 0;
+        let result = send_with_retry(|| { calls += 1; Err(Errno::EPERM) }, KILL_RETRIES, Duration::ZERO);
+        assert_eq!(result, Err((Errno::EPERM, KillErrorKind::Permanent)));
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result = send_with_retry(|| { calls += 1; Err(Errno::ESRCH) }, KILL_RETRIES, Duration::ZERO);
+        assert_eq!(result, Ok(false));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_ioprio_encoding_round_trip() {
+        let idle = IoPriority { class: IoClass::Idle, level: 0 };
+        assert_eq!(encode_ioprio(idle), 3 << 13);
+        assert_eq!(decode_ioprio(encode_ioprio(idle)), idle);
+
+        let best_effort = IoPriority { class: IoClass::BestEffort, level: 7 };
+        assert_eq!(encode_ioprio(best_effort), (2 << 13) | 7);
+        assert_eq!(decode_ioprio(encode_ioprio(best_effort)), best_effort);
+        assert_eq!(best_effort.to_string(), "best-effort/7");
+
+        // Levels above 7 are clamped
+        let clamped = IoPriority { class: IoClass::Realtime, level: 12 };
+        assert_eq!(decode_ioprio(encode_ioprio(clamped)).level, 7);
+        assert_eq!(decode_ioprio(0).class, IoClass::None);
+    }
+
+    #[test]
+    fn test_priority_error_message_is_actionable() {
+        use nix::errno::Errno;
+
+        let message = priority_error_message(Errno::EACCES, 42, "renice");
+        assert!(message.contains("CAP_SYS_NICE"));
+        assert!(priority_error_message(Errno::EPERM, 42, "ionice").contains("sudo"));
+        assert!(priority_error_message(Errno::ESRCH, 42, "renice").contains("no such process"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_renice_own_process() {
+        // Raising our own nice value never needs privileges; use a child so
+        // the test runner itself isn't slowed down
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+
+        let change = renice_process(pid, 10).unwrap();
+        assert_eq!(change.new, 10);
+        assert_eq!(get_nice(pid).unwrap(), 10);
+
+        let change = ionice_process(pid, IoPriority { class: IoClass::Idle, level: 0 }).unwrap();
+        assert_eq!(change.new.class, IoClass::Idle);
+        assert_eq!(get_io_priority(pid).unwrap().class, IoClass::Idle);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_check_nice_value() {
+        assert!(check_nice_value(20).unwrap_err().contains("-20..19"));
+        assert!(check_nice_value(-21).is_err());
+        assert!(check_nice_value(0).is_ok());
+        assert!(check_nice_value(19).is_ok());
+        if !nix::unistd::geteuid().is_root() {
+            assert!(check_nice_value(-5).unwrap_err().contains("require root"));
+        }
+    }
+
+    #[test]
+    fn test_renice_processes_reports_each_pid() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+
+        // Synthetic PID above pid_max, so it can never be a real process
+        let results = renice_processes(&[pid, 10_000_001], 15).unwrap();
+        assert_eq!(results[0].1.as_ref().unwrap().new, 15);
+        assert!(results[1].1.is_err());
+        assert_eq!(crate::monitor::read_proc_stat(pid).unwrap().nice, Some(15));
+
+        assert!(renice_processes(&[pid], 25).is_err());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_log_priority_action() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("kern.log");
+        let config = KernConfig {
+            kill_log_path: Some(log_path.clone()),
+            ..Default::default()
+        };
+        log_priority_action(Some(&config), "RENICE", 4242, "make", true, "0 -> 15");
+
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        assert!(contents.contains("RENICE [PID: 4242]"));
+        assert!(contents.contains("change=\"0 -> 15\""));
+
+        log_alert_action(Some(&config), 4243, "rustc", "kill", "CPU limit, highest CPU consumer");
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        assert!(contents.contains("ALERT [PID: 4243] name=\"rustc\" would=kill"));
+
+        log_deselected_action(Some(&config), 4244, "python3");
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        assert!(contents.contains("SKIP [PID: 4244] name=\"python3\" reason=\"deselected at confirmation\""));
+    }
+
+    #[test]
+    fn test_kill_process_verifies_exit() {
+        // Our own child stays a zombie until we reap it, which already counts as exited
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        assert!(!has_exited(child.id()));
+
+        assert_eq!(kill_process(child.id(), false, KernConfig::default().kill_graceful_wait()), Ok(()));
+        assert!(has_exited(child.id()));
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_graceful_kill_returns_once_the_process_exits() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::time::{Duration, Instant};
+
+        // sleep exits on SIGTERM, long before the 10s timeout would send SIGKILL
+        let wait = GracefulWait { timeout: Duration::from_secs(10), poll: Duration::from_millis(100), watchdog: None };
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let started = Instant::now();
+        assert_eq!(kill_process(child.id(), true, wait), Ok(()));
+        assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGTERM));
+
+        // A process that ignores SIGTERM gets it once, then SIGKILL after the timeout;
+        // a short poll interval doesn't resend SIGTERM
+        let child = std::process::Command::new("sh")
+            .args(["-c", "trap 'echo term' TERM; while true; do sleep 0.05; done"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200)); // Let the trap be installed
+        let wait = GracefulWait { timeout: Duration::from_millis(600), poll: Duration::from_millis(10), watchdog: None };
+        assert_eq!(kill_process(child.id(), true, wait), Ok(()));
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(libc::SIGKILL));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).matches("term").count(), 1);
+    }
+
     #[test]
     fn test_kill_nonexistent_process() {
         // Trying to kill a non-existent PID returns Ok() gracefully 
         // because the process is already dead
-        let result = kill_process(99999, true);
+        let result = kill_process(99999, true, KernConfig::default().kill_graceful_wait());
         // Should either be Ok (already dead) or Err (permission/other issue)
         // We just verify it doesn't panic
         let _ = result;
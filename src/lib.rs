@@ -0,0 +1,30 @@
+//! kern's monitoring and enforcement, usable without the `kern` CLI.
+//!
+//! Library code never prints: the enforcer reports through a [`log::Logger`]
+//! or an event callback, and other warnings (a stuck /proc read, a profile
+//! that failed to load) go to [`log::set_warning_handler`] when one is set.
+//!
+//! ```no_run
+//! use kern::config::KernConfig;
+//! use kern::monitor;
+//!
+//! let config = KernConfig::load()?;
+//! let stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+//! println!("CPU {:.1}%, RAM {:.1}%", stats.cpu_usage, stats.memory_percentage);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod cgroups;
+pub mod config;
+pub mod enforcer;
+pub mod idle;
+pub mod killer;
+pub mod log;
+pub mod monitor;
+pub mod notify;
+pub mod output;
+pub mod profiles;
+pub mod restart;
+pub mod stats;
+pub mod systemd;
+pub mod throttle;
@@ -0,0 +1,216 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Rotate the enforcer log once it grows past this size
+pub const DEFAULT_MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Logger shared between the enforcer and its loop
+pub type SharedLogger = Arc<Mutex<Logger>>;
+
+/// Receives warnings from code that has no logger of its own
+pub type WarningHandler = fn(&str);
+
+static WARNING_HANDLER: Mutex<Option<WarningHandler>> = Mutex::new(None);
+
+/// Route library warnings (a stuck /proc read, a profile that failed to load,
+/// ...) to `handler`. Without one they are dropped, so embedders see no output.
+///
+/// ```
+/// kern::log::set_warning_handler(|message| eprintln!("kern: {}", message));
+/// ```
+pub fn set_warning_handler(handler: WarningHandler) {
+    *WARNING_HANDLER.lock().unwrap_or_else(|e| e.into_inner()) = Some(handler);
+}
+
+pub(crate) fn warning(message: &str) {
+    let handler = *WARNING_HANDLER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(handler) = handler {
+        handler(message);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Sink {
+    Stderr,
+    File {
+        writer: BufWriter<File>,
+        path: PathBuf,
+        written: u64,
+        max_bytes: u64,
+    },
+}
+
+/// Enforcer log output: stderr (the journal under systemd) or a size-rotated file
+#[derive(Debug)]
+pub struct Logger {
+    sink: Sink,
+    level: LogLevel,
+}
+
+impl Logger {
+    pub fn stderr(level: LogLevel) -> Self {
+        Self { sink: Sink::Stderr, level }
+    }
+
+    /// Append to `path`, moving it to `<path>.1` once it exceeds `max_bytes`
+    pub fn file(path: &Path, level: LogLevel, max_bytes: u64) -> Result<Self> {
+        let (writer, written) = open_log(path)?;
+        Ok(Self {
+            sink: Sink::File {
+                writer,
+                path: path.to_path_buf(),
+                written,
+                max_bytes,
+            },
+            level,
+        })
+    }
+
+    pub fn shared(self) -> SharedLogger {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn debug(&mut self, msg: &str) {
+        self.write(LogLevel::Debug, msg);
+    }
+
+    pub fn log(&mut self, msg: &str) {
+        self.write(LogLevel::Info, msg);
+    }
+
+    pub fn warn(&mut self, msg: &str) {
+        self.write(LogLevel::Warn, msg);
+    }
+
+    pub fn write(&mut self, level: LogLevel, msg: &str) {
+        if level < self.level {
+            return;
+        }
+
+        match &mut self.sink {
+            Sink::Stderr => eprintln!("{}", msg),
+            Sink::File { writer, path, written, max_bytes } => {
+                if *written >= *max_bytes {
+                    if let Ok((new_writer, new_written)) = rotate(writer, path) {
+                        *writer = new_writer;
+                        *written = new_written;
+                    }
+                }
+
+                let line = format!(
+                    "[{}] {} {}\n",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    level.label(),
+                    msg
+                );
+                // Flush every line so `tail -f` sees events as they happen
+                if writer.write_all(line.as_bytes()).and_then(|_| writer.flush()).is_ok() {
+                    *written += line.len() as u64;
+                }
+            }
+        }
+    }
+}
+
+fn open_log(path: &Path) -> Result<(BufWriter<File>, u64)> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    let written = file.metadata()?.len();
+    Ok((BufWriter::new(file), written))
+}
+
+// Keep one previous generation: kern.log -> kern.log.1
+fn rotate(writer: &mut BufWriter<File>, path: &Path) -> Result<(BufWriter<File>, u64)> {
+    writer.flush()?;
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    std::fs::rename(path, &rotated)?;
+    open_log(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_logger_filters_by_level() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("logs/enforcer.log");
+        let mut logger = Logger::file(&path, LogLevel::Warn, DEFAULT_MAX_LOG_BYTES).unwrap();
+
+        logger.debug("cycle done");
+        logger.log("profile switched");
+        logger.warn("CPU limit exceeded");
+        logger.write(LogLevel::Error, "kill failed");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("cycle done"));
+        assert!(!content.contains("profile switched"));
+        assert!(content.contains("WARN CPU limit exceeded"));
+        assert!(content.contains("ERROR kill failed"));
+    }
+
+    #[test]
+    fn test_file_logger_rotates() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("enforcer.log");
+        let mut logger = Logger::file(&path, LogLevel::Info, 64).unwrap();
+
+        // Each line is ~50 bytes: the third write goes to a fresh file
+        for i in 0..3 {
+            logger.log(&format!("message number {}", i));
+        }
+
+        let rotated = std::fs::read_to_string(temp_dir.path().join("enforcer.log.1")).unwrap();
+        let current = std::fs::read_to_string(&path).unwrap();
+        assert!(rotated.contains("message number 0"));
+        assert!(rotated.contains("message number 1"));
+        assert!(current.contains("message number 2"));
+        assert!(!current.contains("message number 0"));
+    }
+
+    #[test]
+    fn test_warning_handler_receives_warnings() {
+        static SEEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        set_warning_handler(|message| SEEN.lock().unwrap().push(message.to_string()));
+
+        warning("profile failed to load");
+        assert!(SEEN.lock().unwrap().iter().any(|message| message == "profile failed to load"));
+    }
+
+    #[test]
+    fn test_log_level_order() {
+        assert!(LogLevel::Debug < LogLevel::Info);
+        assert!(LogLevel::Warn < LogLevel::Error);
+    }
+}
@@ -1,15 +1,17 @@
-mod monitor;
-mod config;
-mod profiles;
-mod killer;
-mod enforcer;
-mod stats;
 mod dbus_server;
-mod notify;
+mod dbus_client;
+mod control_socket;
+mod color;
+
+use kern::{config, enforcer, idle, killer, log, monitor, notify, output, profiles, stats, systemd, throttle};
 
 use anyhow::Result;
 use clap::{Parser, Subcommand, CommandFactory};
-use std::io::{self, Write};
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal, Write};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 
 #[derive(Debug, Parser)]
@@ -18,230 +20,2746 @@ struct Cli { // kern --monitor
     /// Start monitoring loop (updates every 2 seconds)
     #[arg(long, default_value_t = false)]
     monitor: bool,
+    /// With --monitor: refresh every MS milliseconds (100-3600000) instead of monitor_interval
+    #[arg(long, value_name = "MS", requires = "monitor", value_parser = clap::value_parser!(u64).range(100..=3_600_000))]
+    interval_ms: Option<u64>,
+    /// With --monitor: stop after this long (e.g. 90s, 10m, 1h 30m)
+    #[arg(long, value_name = "DURATION", requires = "monitor", value_parser = parse_run_duration)]
+    duration: Option<Duration>,
+    /// With --monitor: leave out the CPU and temperature sparklines
+    #[arg(long, requires = "monitor", default_value_t = false)]
+    no_sparkline: bool,
+    /// Suppress the config summary and decorative output (implied by --json)
+    #[arg(short, long, global = true, default_value_t = false)]
+    quiet: bool,
+    /// Color values over their thresholds: auto (terminal only, honors NO_COLOR), always or never
+    #[arg(long, value_enum, global = true, default_value_t = color::ColorChoice::Auto)]
+    color: color::ColorChoice,
+    /// Work locally even when a kern daemon (`kern dbus`) is running; by default
+    /// `status` and `mode` go through it
+    #[arg(long, global = true, default_value_t = false)]
+    no_daemon: bool,
+    /// Show per-process CPU as a share of all CPUs (0-100%) instead of per core
+    /// (100% = one core); same as normalize_cpu in kern.yaml
+    #[arg(long, global = true, default_value_t = false)]
+    normalize_cpu: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Output of `kern status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum StatusFormat {
+    Text,
+    Json,
+    Html, // Self-contained snippet for dashboards and emails
+    Line, // One line for status bars: "CPU 42% | RAM 61% | TEMP 54C | top:firefox"
+}
+
+/// Output of `kern list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ListFormat {
+    Table,
+    Json,
+    Ndjson, // One JSON object per line, streamed with --sort none
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands { // kern status , kern list , kern kill [process_name] , kern mode [profile_name]
     Status {
+        /// Same as --format json
         #[arg(long, default_value_t = false)]
         json: bool,
+        /// Same as --format line
+        #[arg(long, default_value_t = false, conflicts_with = "json")]
+        compact: bool,
+        #[arg(long, value_enum, default_value_t = StatusFormat::Text, conflicts_with_all = ["json", "compact"])]
+        format: StatusFormat,
+        /// With --format line: text between the fields
+        #[arg(long, value_name = "TEXT")]
+        separator: Option<String>,
+        /// With --format html: a complete HTML document instead of an embeddable snippet
+        #[arg(long, default_value_t = false)]
+        full_page: bool,
+        /// Flip the top process and application lists (lightest first instead of heaviest)
+        #[arg(short = 'r', long, default_value_t = false)]
+        sort_reverse: bool,
+        /// Only CPU, RAM and temperature: skips scanning processes, so it's faster
+        #[arg(long, default_value_t = false, conflicts_with = "sort_reverse")]
+        no_processes: bool,
+        /// Preview what this profile would kill or limit right now, without doing it.
+        /// Exits with status 3 if it would kill anything.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["compact", "full_page", "sort_reverse", "no_processes"])]
+        profile: Option<String>,
+        /// Only kern's own processes (daemon, enforcer, monitor): their memory and CPU use
+        #[arg(long = "self", conflicts_with_all = ["compact", "full_page", "sort_reverse", "no_processes", "profile"])]
+        own: bool,
+        /// Compare against a snapshot saved with `kern status --json > FILE`: new and gone
+        /// processes, memory changes over 10%, and global CPU/RAM/temperature
+        #[arg(long, value_name = "SNAPSHOT", conflicts_with_all = ["compact", "full_page", "sort_reverse", "no_processes", "profile", "own"])]
+        diff: Option<std::path::PathBuf>,
+        /// With --json: write the JSON to PATH instead of stdout, replacing the file
+        /// atomically so readers never see half of it. With --monitor it is rewritten
+        /// every interval (file format in the README)
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["compact", "full_page", "no_processes", "profile", "own", "diff"])]
+        output_file: Option<std::path::PathBuf>,
     },
     List {
+        /// Same as --format json
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        /// With ndjson and --sort none, processes are written as they are read
+        /// instead of being collected and sorted first
+        #[arg(long, value_enum, default_value_t = ListFormat::Table, conflicts_with = "json")]
+        format: ListFormat,
+        /// Number of rows to show: 1-100000, or "all" (or 0) for every process
+        #[arg(short, long, default_value = "20", value_parser = parse_count)]
+        count: ListCount,
+        /// Column to order processes by
+        #[arg(long, value_enum, default_value_t = monitor::ProcessSort::Memory)]
+        sort: monitor::ProcessSort,
+        /// Flip the --sort order (e.g. lightest memory first); applied before --count
+        #[arg(short = 'r', long, default_value_t = false)]
+        sort_reverse: bool,
+        /// Only show processes whose name contains this text (case-insensitive)
+        #[arg(long)]
+        filter_name: Option<String>,
+        /// Add a NICE column (scheduling priority, -20 = highest)
+        #[arg(long, default_value_t = false)]
+        show_nice: bool,
+        /// Add a column with each process's value of environment variable KEY
+        /// (e.g. VIRTUAL_ENV); "-" when it is unset or can't be read
+        #[arg(long, value_name = "KEY", conflicts_with = "group")]
+        show_env: Option<String>,
+        /// Add a CGROUP column (e.g. /system.slice/docker-<id>.scope)
+        #[arg(long, default_value_t = false, conflicts_with = "group")]
+        show_cgroup: bool,
+        /// Only show processes whose cgroup path contains this text (e.g. docker)
+        #[arg(long, value_name = "PATTERN")]
+        filter_cgroup: Option<String>,
+        /// Add disk read/write columns (MB/s, from /proc/<pid>/io); a single
+        /// list samples for a second, --watch compares refreshes
+        #[arg(long, default_value_t = false, conflicts_with = "group")]
+        wide: bool,
+        /// Include kernel threads (kworker, ksoftirqd, ...), hidden by default
+        #[arg(long, default_value_t = false)]
+        kernel_threads: bool,
+        /// Also list every process's threads, marked "[T]" and showing their whole
+        /// process's memory, with a TGID column naming the process each belongs to
+        #[arg(long, default_value_t = false, conflicts_with = "group")]
+        show_threads: bool,
+        /// Combine processes sharing a name into one row with an instance count
+        #[arg(long, default_value_t = false)]
+        group: bool,
+        /// Each user's N heaviest processes by memory, under a header per user
+        /// (replaces --count)
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+            conflicts_with_all = ["group", "sort", "sort_reverse"]
+        )]
+        top_n_per_user: Option<usize>,
+        /// Redraw the list in place every SECS seconds until Ctrl+C
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        watch: Option<u64>,
+        /// With --json: write the list to PATH instead of stdout, replacing the file
+        /// atomically; with --watch it is rewritten every refresh
+        #[arg(long, value_name = "PATH")]
+        output_file: Option<std::path::PathBuf>,
+    },
+    /// Explain why the enforcer would or wouldn't kill processes named NAME (exact name)
+    Explain {
+        name: String,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// List processes whose name contains QUERY (case-insensitive) and whether kern may kill them
+    Find {
+        query: String,
         #[arg(long, default_value_t = false)]
         json: bool,
-        #[arg(short, long, default_value_t = 20)]
-        count: usize,
     },
     Kill {
         name: String,
+        /// Ask before killing each matching process (overrides kill_confirmation_threshold)
+        #[arg(long, default_value_t = false)]
+        confirm_each: bool,
+        /// Let the running kern daemon do the kill (e.g. a root daemon killing
+        /// another user's process, with --all-users); kills locally if no daemon is running
+        #[arg(long, default_value_t = false, conflicts_with = "confirm_each")]
+        via_daemon: bool,
+        /// Seconds to wait after SIGTERM before sending SIGKILL (0-60);
+        /// overrides kill_graceful_timeout_secs (default 5)
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(0..=60), conflicts_with = "via_daemon")]
+        timeout: Option<u64>,
+        /// Also kill other users' matching processes (enforcement.all_users;
+        /// needs root, CAP_KILL or a root daemon with --via-daemon)
+        #[arg(long, default_value_t = false)]
+        all_users: bool,
+        /// When several processes match, kill only the one started first
+        #[arg(long, group = "selector", conflicts_with = "via_daemon")]
+        oldest: bool,
+        /// When several processes match, kill only the one started last
+        #[arg(long, group = "selector", conflicts_with = "via_daemon")]
+        newest: bool,
+        /// When several processes match, kill only the one using the most memory
+        #[arg(long, group = "selector", conflicts_with = "via_daemon")]
+        heaviest: bool,
+        /// Kill every matching process (the default without --oldest, --newest or --heaviest)
+        #[arg(long, group = "selector")]
+        all: bool,
+        /// Don't ask for confirmation, however many processes match (needed to kill
+        /// more than kill_confirmation_threshold when stdin is not a terminal)
+        #[arg(short, long, default_value_t = false, conflicts_with = "confirm_each")]
+        yes: bool,
+        /// Only kill matching processes that have been running longer than this
+        /// (e.g. 30m, 1h, 2h 30m)
+        #[arg(long, value_name = "DURATION", value_parser = parse_run_duration, conflicts_with = "via_daemon")]
+        older_than: Option<Duration>,
     },
     Mode {
         profile: String,
     },
+    /// Change the CPU priority (nice value) of processes by name or PID
+    Renice {
+        name: Option<String>,
+        /// New nice value, as an alternative to --nice (`kern renice firefox 10`)
+        #[arg(allow_negative_numbers = true, value_parser = clap::value_parser!(i32).range(-20..=19))]
+        value: Option<i32>,
+        #[arg(long, conflicts_with = "name", required_unless_present = "name")]
+        pid: Option<u32>,
+        /// New nice value (-20 = highest priority, 19 = lowest; negative needs root)
+        #[arg(
+            long,
+            allow_hyphen_values = true,
+            conflicts_with = "value",
+            required_unless_present = "value",
+            value_parser = clap::value_parser!(i32).range(-20..=19)
+        )]
+        nice: Option<i32>,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Change the I/O scheduling class of processes by name or PID
+    Ionice {
+        name: Option<String>,
+        #[arg(long, conflicts_with = "name", required_unless_present = "name")]
+        pid: Option<u32>,
+        #[arg(long, value_enum)]
+        class: killer::IoClass,
+        /// Level within realtime/best-effort (0 = highest, 7 = lowest)
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(0..=7))]
+        level: u8,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
     /// Start enforcer loop (monitors and enforces resource limits)
-    Enforce,
+    Enforce {
+        /// Emit one JSON event per action on stdout instead of human-readable logs
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        /// Write logs to this file instead of stderr (rotated at 10 MB)
+        #[arg(long, alias = "output-file")]
+        log_file: Option<std::path::PathBuf>,
+        /// Minimum level of messages to log
+        #[arg(long, value_enum, default_value_t = log::LogLevel::Info)]
+        log_level: log::LogLevel,
+        /// Read temperature from this file only (e.g. /sys/class/hwmon/hwmon2/temp1_input)
+        /// instead of scanning thermal zones; overrides temperature_sensor_path
+        #[arg(long, value_name = "PATH")]
+        temperature_sensor: Option<std::path::PathBuf>,
+        /// Start with this profile instead of the saved one (or default_profile)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Stop killing after this many kills in this run (0 = unlimited);
+        /// overrides kill_count_limit
+        #[arg(long, value_name = "N")]
+        kill_count_limit: Option<usize>,
+        /// Keep the starting profile: profile switches are ignored while enforcing
+        #[arg(long, default_value_t = false)]
+        profile_lock: bool,
+        /// Check limits every MS milliseconds (100-3600000); overrides monitor_interval
+        #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(100..=3_600_000))]
+        interval_ms: Option<u64>,
+        /// Log a one-line status summary every SECS seconds, even when nothing
+        /// happens (0 = never); overrides report_interval_secs (default 60)
+        #[arg(long, value_name = "SECS")]
+        report_interval: Option<u64>,
+        /// Evaluate limits and notify, but never kill, limit or throttle anything
+        /// (enforcement.mode: alert for every profile)
+        #[arg(long, default_value_t = false)]
+        alert_only: bool,
+        /// Run the full enforcement loop, but log what would be killed, limited or
+        /// throttled ("[SIMULATE] would kill ...") instead of doing it
+        #[arg(long, default_value_t = false)]
+        simulate: bool,
+        /// Stop after this long (e.g. 60s, 45m, 2h) and print the session summary,
+        /// as Ctrl+C or SIGTERM would
+        #[arg(long, value_name = "DURATION", value_parser = parse_run_duration)]
+        duration: Option<Duration>,
+        /// Act on every user's processes, not only your own (enforcement.all_users;
+        /// needs root or CAP_KILL)
+        #[arg(long, default_value_t = false)]
+        all_users: bool,
+    },
+    /// Check whether kern can act on this machine (e.g. signal the heaviest processes)
+    Doctor {
+        /// Number of top processes (by memory) to check
+        #[arg(short, long, default_value_t = 10)]
+        count: usize,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
     /// Debug thermal zones (shows all available temperature sensors)
-    Thermal,
+    Thermal {
+        /// Briefly throttle the CPU to verify frequency control works, then restore it
+        #[arg(long, default_value_t = false)]
+        throttle_test: bool,
+        /// Keep watching every zone and send a desktop notification when one is above TEMP °C
+        #[arg(long, value_name = "TEMP", conflicts_with = "throttle_test")]
+        alert: Option<f64>,
+        /// Seconds between readings with --alert
+        #[arg(long, default_value_t = 5, requires = "alert", value_parser = clap::value_parser!(u64).range(1..))]
+        interval_secs: u64,
+    },
     /// Start DBus server for GNOME Shell integration
-    Dbus,
+    Dbus {
+        /// Also run the enforcer, reporting its state through GetEnforcerStatus
+        #[arg(long)]
+        enforce: bool,
+        /// Also write GetStatus's JSON to PATH every monitor_interval, replaced
+        /// atomically, for clients that would rather read a file than call DBus
+        #[arg(long, value_name = "PATH")]
+        status_file: Option<std::path::PathBuf>,
+    },
+    /// Control a running `kern dbus` through its control socket (control_socket in kern.yaml)
+    Ctl {
+        /// Socket to talk to instead of the configured one
+        #[arg(long, value_name = "PATH", global = true)]
+        socket: Option<std::path::PathBuf>,
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Inspect the loaded configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Manage profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+}
+
+/// `kern ctl` requests; each prints the daemon's JSON answer
+#[derive(Debug, Subcommand)]
+enum CtlCommand {
+    /// System status (GetStatus)
+    Status,
+    /// What the daemon's enforcer is doing (GetEnforcerStatus)
+    Enforcer,
+    /// Heaviest processes by memory
+    List {
+        /// Number of processes, 0 for all
+        #[arg(short, long, default_value_t = 20)]
+        count: u32,
+    },
+    /// Switch the daemon's profile
+    Mode { profile: String },
+    /// Kill every process whose name contains NAME
+    Kill { name: String },
+    /// Most recent kill log lines, newest first
+    KillLog {
+        /// Number of lines (default: the whole log)
+        #[arg(short, long)]
+        limit: Option<u16>,
+    },
+    /// Keep monitoring but stop acting on limits
+    Pause,
+    /// Undo pause
+    Resume,
 }
 
-fn print_status(json: bool) -> Result<()> {
-    let stats = monitor::get_system_stats()?;
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Show only the settings that differ from the compiled-in defaults
+    CheckDefaults {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
 
-    if json {
-        let top: Vec<serde_json::Value> = stats
-            .top_processes
+#[derive(Debug, Subcommand)]
+enum ProfileCommand {
+    /// Create NEW_NAME as a copy of SOURCE, differing only in its name
+    Duplicate { source: String, new_name: String },
+}
+
+/// Thresholds `kern status` and `kern list` color values against
+#[derive(Debug, Clone)]
+struct Highlight {
+    painter: color::Painter,
+    limits: profiles::ProfileResourceLimits,
+    temp_warning: f64,
+    temp_critical: f64,
+}
+
+impl Highlight {
+    // Active profile's limits, or kern.yaml's when no profile can be loaded.
+    // Profiles are only read when something will actually be colored.
+    fn load(config: &config::KernConfig, painter: color::Painter) -> Self {
+        let profile = painter.enabled().then(|| {
+            let mut manager = profiles::ProfileManager::new(None).ok()?;
+            manager.load_state().ok()?;
+            manager.current().ok().cloned()
+        });
+        let limits = profile.flatten().map(|p| p.limits).unwrap_or_else(|| profiles::ProfileResourceLimits {
+            max_cpu_percent: config.limits.max_cpu_percent,
+            max_ram_percent: config.limits.max_ram_percent,
+            ..Default::default()
+        });
+        Self {
+            painter,
+            temp_warning: limits.temp_warning.unwrap_or(config.temperature.warning),
+            temp_critical: limits.temp_critical.unwrap_or(config.temperature.critical),
+            limits,
+        }
+    }
+
+    fn temperature(&self, celsius: f64) -> String {
+        let color = color::threshold_color(celsius, self.temp_warning, self.temp_critical);
+        self.painter.paint(&format!("{:.2}", celsius), color)
+    }
+}
+
+// Local time of a Unix timestamp, e.g. "2026-10-13 09:41"
+fn format_timestamp(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Where `kern status` got its numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatsSource {
+    Local,
+    Daemon,
+}
+
+// From the running daemon unless told otherwise, else read locally. A daemon
+// that fails to answer is reported and skipped. Per-process CPU follows this
+// side's normalize_cpu, whatever the daemon's is.
+fn load_status(config: &config::KernConfig, daemon: Option<&dbus_client::Daemon>) -> Result<(monitor::SystemStats, StatsSource)> {
+    let (mut stats, source) = match daemon.map(|daemon| daemon.status()) {
+        Some(Ok(stats)) => (stats, StatsSource::Daemon),
+        other => {
+            if let Some(Err(e)) = other {
+                eprintln!("ℹ️  kern daemon did not answer ({}); reading stats locally", e);
+            }
+            let stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+            (stats, StatsSource::Local)
+        }
+    };
+    if config.normalize_cpu {
+        stats.normalize_process_cpu();
+    }
+    Ok((stats, source))
+}
+
+// Where `print_status` writes
+enum StatusTarget<'a> {
+    Text(Option<&'a mut MonitorTrends>), // With trends: sparklines after CPU and temperature
+    Json(Option<&'a std::path::Path>),   // To this file (--output-file) rather than stdout
+}
+
+// Readings kept across `kern --monitor` refreshes for the sparklines
+struct MonitorTrends {
+    ascii: bool,
+    cpu: VecDeque<f32>,
+    temperature: VecDeque<f32>,
+}
+
+impl MonitorTrends {
+    const SAMPLES: usize = 30;
+
+    // Plain ASCII unless the locale says the terminal speaks UTF-8
+    fn new() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
             .iter()
-            .map(|p| {
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        Self {
+            ascii: !(locale.contains("utf-8") || locale.contains("utf8")),
+            cpu: VecDeque::with_capacity(Self::SAMPLES),
+            temperature: VecDeque::with_capacity(Self::SAMPLES),
+        }
+    }
+
+    fn record(&mut self, stats: &monitor::SystemStats) {
+        for (samples, value) in [(&mut self.cpu, stats.cpu_usage), (&mut self.temperature, stats.temperature_max)] {
+            if samples.len() == Self::SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(value as f32);
+        }
+    }
+
+    // " ▁▂▃▅▇ ↑": the recorded readings and where they are heading
+    fn suffix(&self, samples: &VecDeque<f32>) -> String {
+        let samples: Vec<f32> = samples.iter().copied().collect();
+        format!(
+            " {} {}",
+            output::render_sparkline(&samples, self.ascii),
+            output::trend_arrow(&stats::detect_trend(samples), self.ascii)
+        )
+    }
+}
+
+fn print_status(
+    target: StatusTarget,
+    quiet: bool,
+    reverse: bool,
+    config: &config::KernConfig,
+    highlight: &Highlight,
+    daemon: Option<&dbus_client::Daemon>,
+) -> Result<()> {
+    let metric = config.memory_metric;
+    let (stats, source) = load_status(config, daemon)?;
+
+    // --sort-reverse: lightest first, so the lists below show the bottom of each ranking
+    let mut groups = monitor::group_processes(&stats.top_processes);
+    let mut top_memory: Vec<&monitor::ProcessInfo> = stats.top_processes.iter().collect();
+    let mut top_cpu = stats.top_cpu_processes();
+    if reverse {
+        groups.reverse();
+        top_memory.reverse();
+        top_cpu.reverse();
+    }
+
+    if let StatusTarget::Json(output_file) = target {
+        let groups: Vec<serde_json::Value> = groups
+            .iter()
+            .take(10)
+            .map(|g| {
                 serde_json::json!({
-                    "pid": p.pid,
-                    "name": p.name,
-                    "memory_gb": p.memory_gb,
-                    "cpu_percentage": p.cpu_percentage,
+                    "name": g.name,
+                    "count": g.count(),
+                    "memory_gb": g.memory_gb,
+                    "cpu_percentage": g.cpu_percentage,
                 })
             })
             .collect();
+        let summary = |p: &&monitor::ProcessInfo| {
+            serde_json::json!({
+                "pid": p.pid,
+                "name": p.name,
+                "memory_gb": p.memory_gb,
+                "cpu_percentage": p.cpu_percentage,
+                "uid": p.uid,
+                "user": p.uid.and_then(monitor::user_name),
+            })
+        };
+        let top: Vec<serde_json::Value> = top_memory.iter().map(summary).collect();
+        let top_cpu: Vec<serde_json::Value> = top_cpu.iter().take(10).map(summary).collect();
 
         let jsonout = serde_json::json!({
             "cpu_usage": stats.cpu_usage,
             "total_memory_gb": stats.total_memory_gb,
             "used_memory_gb": stats.used_memory_gb,
             "memory_percentage": stats.memory_percentage,
+            "swap_percentage": stats.swap_percentage,
             "temperature": stats.temperature,
+            "temperature_avg": stats.temperature_avg,
+            "temperature_max": stats.temperature_max,
+            "cpu_pressure": stats.cpu_pressure,
+            "mem_pressure": stats.mem_pressure,
+            "memory_metric": metric,
+            "cpu_normalized": config.normalize_cpu,
+            "cgroup_limited": stats.cgroup.is_some(),
+            "memory_source": stats.memory_source().as_str(),
+            "cgroup_memory_max_gb": stats.cgroup.as_ref().and_then(|c| c.memory_max_bytes).map(|_| stats.total_memory_gb),
+            "cgroup_cpu_max_cores": stats.cgroup.as_ref().and_then(|c| c.cpu_max_cores),
+            "uptime_secs": stats.uptime_secs,
+            "boot_time": stats.boot_time,
+            "self": stats.footprint,
             "top_processes": top,
+            "top_cpu_processes": top_cpu,
+            "top_groups": groups,
+            "source": match source {
+                StatsSource::Local => "local",
+                StatsSource::Daemon => "daemon",
+            },
         });
-        println!("{}", serde_json::to_string_pretty(&jsonout)?);
+        match output_file {
+            Some(path) => output::write_output_file(path, jsonout)?,
+            None => println!("{}", serde_json::to_string_pretty(&jsonout)?),
+        }
         return Ok(());
     }
 
-    println!("📊 KERN - System Status");
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("CPU: {:.2}%", stats.cpu_usage);
-    println!("RAM: {:.2} GB / {:.2} GB ({:.2}%)", 
-        stats.used_memory_gb, stats.total_memory_gb, stats.memory_percentage);
-    println!("Temp: {:.2} °C", stats.temperature);
+    if !quiet {
+        println!("📊 KERN - System Status");
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        if let Some(line) = daemon.filter(|_| source == StatsSource::Daemon).and_then(daemon_status_line) {
+            println!("{}", line);
+        }
+    }
+    let paint = |value: f64, limit: f64| highlight.painter.paint(&format!("{:.2}", value), color::limit_color(value, limit));
+    let cpu_limit = highlight.limits.effective_cpu_percent(stats.cpu_capacity());
+    let ram_limit = highlight.limits.effective_ram_percent(stats.total_memory_gb);
+    let trends = match target {
+        StatusTarget::Text(Some(trends)) => {
+            trends.record(&stats);
+            Some(&*trends)
+        }
+        _ => None,
+    };
+    let trend = |samples: fn(&MonitorTrends) -> &VecDeque<f32>| trends.map(|t| t.suffix(samples(t))).unwrap_or_default();
+    println!("CPU: {}%{}", paint(stats.cpu_usage, cpu_limit), trend(|t| &t.cpu));
+    println!("RAM: {:.2} GB / {:.2} GB ({}%)", 
+        stats.used_memory_gb, stats.total_memory_gb, paint(stats.memory_percentage, ram_limit));
+    if let Some(cgroup) = &stats.cgroup {
+        // Rounded, so 4 GiB prints as "4 GiB" rather than "4.00 GiB"
+        let round = |value: f64| (value * 100.0).round() / 100.0;
+        let mut parts = Vec::new();
+        if let Some(max) = cgroup.memory_max_bytes {
+            parts.push(format!("{} GiB", round(max as f64 / 1_073_741_824.0)));
+        }
+        if let Some(cores) = cgroup.cpu_max_cores {
+            parts.push(format!("{} CPUs", round(cores)));
+        }
+        println!("cgroup limit: {} (usage above is relative to it)", parts.join(", "));
+    }
+    println!("Temp: {} °C (max), {} °C (avg){}",
+        highlight.temperature(stats.temperature_max), highlight.temperature(stats.temperature_avg), trend(|t| &t.temperature));
+    // Kernels without PSI have no pressure files; leave the line out rather than print zeros
+    if let (Some(cpu), Some(memory)) = (stats.cpu_pressure, stats.mem_pressure) {
+        let memory = match highlight.limits.max_mem_pressure {
+            Some(max) => paint(memory, max),
+            None => format!("{:.2}", memory),
+        };
+        println!("Pressure: CPU {:.2}%, memory {}% (stalled, last 10s)", cpu, memory);
+    }
+    if stats.uptime_secs > 0 {
+        println!("Uptime: {} (booted {})", output::format_uptime(stats.uptime_secs), format_timestamp(stats.boot_time));
+    }
+    if let Some(footprint) = stats.footprint {
+        println!(
+            "{}: {:.1} MB, {:.2}% CPU (PID {})",
+            if source == StatsSource::Daemon { "kern daemon" } else { "kern" },
+            footprint.memory_mb,
+            footprint.cpu_percent,
+            footprint.pid
+        );
+    }
     println!();
 
-    println!("Top processes by memory:");
-    for (idx, p) in stats.top_processes.iter().take(5).enumerate() {
+    println!("Top processes by memory ({:?}):", metric);
+    for (idx, p) in top_memory.iter().take(5).enumerate() {
         println!("  {}. {} (PID: {}) - {:.2} GB - {:.2}% CPU", 
             idx + 1, p.name, p.pid, p.memory_gb, p.cpu_percentage);
     }
+    println!();
+
+    println!("Top processes by CPU:");
+    for (idx, p) in top_cpu.iter().take(5).enumerate() {
+        println!("  {}. {} (PID: {}) - {:.2}% CPU - {:.2} GB", 
+            idx + 1, p.name, p.pid, p.cpu_percentage, p.memory_gb);
+    }
+    println!();
+
+    println!("Top applications by memory:");
+    for (idx, g) in groups.iter().take(5).enumerate() {
+        println!("  {}. {} ×{} - {:.2} GB - {:.2}% CPU",
+            idx + 1, g.name, g.count(), g.memory_gb, g.cpu_percentage);
+    }
 
     Ok(())
 }
 
-fn print_list(json: bool, count: usize) -> Result<()> {
-    let processes = monitor::get_all_processes()?;
+// `kern status --diff`: what changed since a saved `kern status --json`.
+// Rises are red and drops green.
+fn print_status_diff(
+    path: &std::path::Path,
+    json: bool,
+    quiet: bool,
+    config: &config::KernConfig,
+    highlight: &Highlight,
+    daemon: Option<&dbus_client::Daemon>,
+) -> Result<()> {
+    let snapshot = monitor::Snapshot::load(path)?;
+    let (stats, _) = load_status(config, daemon)?;
+    let diff = monitor::diff_stats(&snapshot, &stats);
+
     if json {
-        // For JSON mode, only output the JSON array without config summary
-        let arr: Vec<serde_json::Value> = processes
-            .iter()
-            .take(count)
-            .map(|p| {
-                serde_json::json!({
-                    "pid": p.pid,
-                    "name": p.name,
-                    "memory_gb": p.memory_gb,
-                    "cpu_percentage": p.cpu_percentage
-                })
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&arr)?);
+        println!("{}", serde_json::to_string_pretty(&diff)?);
         return Ok(());
     }
 
-    println!("{:<8} {:<8} {:<8} {}", "PID", "MEM(GB)", "CPU%", "NAME");
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    for p in processes.iter().take(count) {
-        println!("{:<8} {:<8.2} {:<8.2} {}", p.pid, p.memory_gb, p.cpu_percentage, p.name);
+    if !quiet {
+        println!("📊 KERN - Changes since {}", path.display());
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+    let painter = &highlight.painter;
+    let global = |label: &str, change: monitor::ValueChange, unit: &str| {
+        let delta = painter.paint(&format!("{:+.2}{}", change.delta(), unit), color::change_color(change.delta()));
+        println!("{}: {:.2}{} → {:.2}{} ({})", label, change.old, unit, change.new, unit, delta);
+    };
+    global("CPU", diff.cpu_usage, "%");
+    global("RAM", diff.memory_percentage, "%");
+    global("Temp", diff.temperature, " °C");
+
+    let process_line = |sign: &str, p: &monitor::SnapshotProcess, color: Option<color::Color>| {
+        let line = format!("  {} {} (PID: {}) - {:.2} GB", sign, p.name, p.pid, p.memory_gb);
+        println!("{}", painter.paint(&line, color));
+    };
+    let change_line = |sign: &str, c: &monitor::MemoryChange| {
+        let line = format!(
+            "  {} {} (PID: {}) - {:.2} GB → {:.2} GB ({:+.1}%)",
+            sign, c.name, c.pid, c.old_memory_gb, c.new_memory_gb, c.change_percent
+        );
+        println!("{}", painter.paint(&line, color::change_color(c.change_percent)));
+    };
+    if !diff.new_processes.is_empty() {
+        println!();
+        println!("New processes ({}):", diff.new_processes.len());
+        for p in &diff.new_processes {
+            process_line("+", p, Some(color::Color::Red));
+        }
+    }
+    if !diff.gone_processes.is_empty() {
+        println!();
+        println!("Gone processes ({}):", diff.gone_processes.len());
+        for p in &diff.gone_processes {
+            process_line("-", p, None);
+        }
+    }
+    if !diff.memory_increased.is_empty() {
+        println!();
+        println!("Memory up more than {}%:", monitor::MEMORY_CHANGE_PERCENT);
+        for c in &diff.memory_increased {
+            change_line("+", c);
+        }
+    }
+    if !diff.memory_decreased.is_empty() {
+        println!();
+        println!("Memory down more than {}%:", monitor::MEMORY_CHANGE_PERCENT);
+        for c in &diff.memory_decreased {
+            change_line("-", c);
+        }
     }
     Ok(())
 }
 
-fn monitor_loop(interval_secs: u64) -> Result<()> {
-    println!("Starting monitor loop (interval: {} seconds). Press Ctrl+C to exit.", interval_secs);
-    println!();
-    
-    loop {
-        print_status(false)?;
-        println!();
-        std::thread::sleep(std::time::Duration::from_secs(interval_secs));
+// What a running kern process is doing, from its command line
+fn kern_role(args: &[String]) -> &'static str {
+    let has_flag = |flag: &str| args.iter().skip(1).any(|a| a == flag);
+    match args.iter().skip(1).find(|a| !a.starts_with('-')).map(String::as_str) {
+        Some("dbus") if has_flag("--enforce") => "daemon + enforcer",
+        Some("dbus") => "daemon",
+        Some("enforce") => "enforcer",
+        _ if has_flag("--monitor") => "monitor",
+        _ => "command",
     }
 }
 
-fn kill_process_by_name(name: &str, config: &config::KernConfig) -> Result<()> {
-    // Find all processes matching the name
-    let pids = killer::find_processes_by_name(name);
-    
-    if pids.is_empty() {
-        println!("❌ No running process found matching '{}'", name);
+// `kern status --self`: every other kern process (found by executable name,
+// plus the daemon's PID from the bus) and what it costs
+fn print_own_status(json: bool, quiet: bool, normalize_cpu: bool, daemon: Option<&dbus_client::Daemon>) -> Result<()> {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, UpdateKind};
+
+    let own_pid = std::process::id();
+    let daemon_pid = daemon.and_then(|daemon| daemon.pid());
+    let own_name = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "kern".to_string());
+
+    let cpu_divisor = if normalize_cpu { monitor::cpu_count() as f64 } else { 1.0 };
+    let mut sys = System::new_with_specifics(RefreshKind::new());
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::new().with_cmd(UpdateKind::Always),
+    );
+    let mut processes: Vec<serde_json::Value> = Vec::new();
+    for (pid, process) in sys.processes() {
+        let pid = pid.as_u32();
+        let is_kern = process.name().to_string_lossy() == own_name.as_str() || Some(pid) == daemon_pid;
+        if pid == own_pid || process.thread_kind().is_some() || !is_kern {
+            continue;
+        }
+        let Some(mut footprint) = monitor::process_footprint(pid) else { continue };
+        footprint.cpu_percent /= cpu_divisor;
+        let args: Vec<String> = process.cmd().iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        processes.push(serde_json::json!({
+            "pid": pid,
+            "role": if Some(pid) == daemon_pid { "daemon" } else { kern_role(&args) },
+            "command": args.join(" "),
+            "memory_mb": footprint.memory_mb,
+            "cpu_percent": footprint.cpu_percent,
+            "cpu_time_secs": footprint.cpu_time_secs,
+        }));
+    }
+    processes.sort_by_key(|p| p["pid"].as_u64());
+
+    if json {
+        let jsonout = serde_json::json!({
+            "processes": processes,
+            "self": monitor::own_footprint().map(|mut footprint| {
+                footprint.cpu_percent /= cpu_divisor;
+                footprint
+            }),
+            "cpu_normalized": normalize_cpu,
+        });
+        println!("{}", serde_json::to_string_pretty(&jsonout)?);
         return Ok(());
     }
-    
-    println!("Found {} process(es) matching '{}'", pids.len(), name);
-    
-    // Check if process is critical
-    if killer::is_critical_process(name) {
-        println!("❌ Cannot kill '{}' - it is a critical system process", name);
+
+    if !quiet {
+        println!("📊 KERN - Own Processes");
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+    if processes.is_empty() {
+        println!("No other kern process is running");
         return Ok(());
     }
-    
-    // Check if process is protected
-    if killer::is_protected(name, &config.protected_processes) {
-        println!("❌ Cannot kill '{}' - it is in the protected process list", name);
+    println!("{:<8} {:<18} {:>10} {:>12} {:>10}", "PID", "ROLE", "MEMORY", "CPU (avg)", "CPU TIME");
+    for p in &processes {
+        println!(
+            "{:<8} {:<18} {:>7.1} MB {:>11.2}% {:>9.1}s",
+            p["pid"].as_u64().unwrap_or_default(),
+            p["role"].as_str().unwrap_or_default(),
+            p["memory_mb"].as_f64().unwrap_or_default(),
+            p["cpu_percent"].as_f64().unwrap_or_default(),
+            p["cpu_time_secs"].as_f64().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+// `kern status --no-processes`: always read locally, since that is already
+// cheaper than asking the daemon
+fn print_global_status(json: bool, quiet: bool, config: &config::KernConfig, highlight: &Highlight) -> Result<()> {
+    let stats = monitor::get_global_stats(config.temperature_sensor_path.as_deref())?;
+
+    if json {
+        let jsonout = serde_json::json!({
+            "cpu_usage": stats.cpu_usage,
+            "total_memory_gb": stats.total_memory_gb,
+            "used_memory_gb": stats.used_memory_gb,
+            "memory_percentage": stats.memory_percentage,
+            "memory_source": stats.memory_source.as_str(),
+            "temperature": stats.temperature,
+            "uptime_secs": stats.uptime_secs,
+        });
+        println!("{}", serde_json::to_string_pretty(&jsonout)?);
         return Ok(());
     }
-    
-    // If more than threshold, ask for confirmation
-    if pids.len() > config.kill_confirmation_threshold {
-        println!("\n⚠️  This will kill {} processes. Are you sure? (yes/no)", pids.len());
-        print!("Please confirm: ");
-        io::stdout().flush()?;
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if !input.trim().eq_ignore_ascii_case("yes") && !input.trim().eq_ignore_ascii_case("y") {
-            println!("Cancelled.");
-            return Ok(());
-        }
+
+    if !quiet {
+        println!("📊 KERN - System Status");
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     }
-    
-    // Kill the processes
-    match killer::kill_processes(&pids, config.kill_graceful) {
-        Ok(_) => {
-            let kill_type = if config.kill_graceful { "gracefully" } else { "forcefully" };
-            println!("✅ Killed {} process(es) {} (PID: {})", 
-                pids.len(), 
-                kill_type,
-                pids.iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
-            
-            // Log the action for each PID
-            for pid in &pids {
-                killer::log_kill_action(*pid, name, true, config.kill_graceful);
-            }
-        }
-        Err(e) => {
-            println!("❌ Error killing processes: {}", e);
-            // Log failed attempt
-            for pid in &pids {
-                killer::log_kill_action(*pid, name, false, config.kill_graceful);
-            }
+    let paint = |value: f64, limit: f64| highlight.painter.paint(&format!("{:.2}", value), color::limit_color(value, limit));
+    let cpu_limit = highlight.limits.effective_cpu_percent(stats.cpu_capacity);
+    let ram_limit = highlight.limits.effective_ram_percent(stats.total_memory_gb);
+    println!("CPU: {}%", paint(stats.cpu_usage, cpu_limit));
+    println!("RAM: {:.2} GB / {:.2} GB ({}%){}",
+        stats.used_memory_gb, stats.total_memory_gb, paint(stats.memory_percentage, ram_limit),
+        if stats.memory_source == monitor::MemorySource::Cgroup { " of the cgroup limit" } else { "" });
+    println!("Temp: {} °C (max)", highlight.temperature(stats.temperature));
+    if stats.uptime_secs > 0 {
+        println!("Uptime: {}", output::format_uptime(stats.uptime_secs));
+    }
+    Ok(())
+}
+
+// Report whether kern may signal the heaviest processes. Without root or
+// CAP_KILL the enforcer can only act on the current user's processes.
+fn run_doctor(config: &config::KernConfig, count: usize, json: bool) -> Result<()> {
+    let stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+    let uid = nix::unistd::geteuid();
+    let privileged = killer::can_kill_any_user();
+    let checked: Vec<(&monitor::ProcessInfo, bool)> = stats
+        .top_processes
+        .iter()
+        .take(count)
+        .filter_map(|p| killer::can_signal(p.pid).map(|allowed| (p, allowed)))
+        .collect();
+    let denied = checked.iter().filter(|(_, allowed)| !allowed).count();
+
+    if json {
+        let processes: Vec<serde_json::Value> = checked
+            .iter()
+            .map(|(p, allowed)| serde_json::json!({ "pid": p.pid, "name": p.name, "can_signal": allowed }))
+            .collect();
+        let jsonout = serde_json::json!({
+            "uid": uid.as_raw(),
+            "root": uid.is_root(),
+            "can_kill_any_user": privileged,
+            "processes": processes,
+            "cannot_signal": denied,
+        });
+        println!("{}", serde_json::to_string_pretty(&jsonout)?);
+        return Ok(());
+    }
+
+    println!("🩺 KERN - Doctor");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!(
+        "Running as uid {}{}",
+        uid,
+        match (uid.is_root(), privileged) {
+            (true, _) => " (root)",
+            (false, true) => " with CAP_KILL",
+            (false, false) => " without CAP_KILL",
         }
+    );
+    println!("Top {} processes: {} can be signalled, {} cannot", checked.len(), checked.len() - denied, denied);
+    for (process, allowed) in &checked {
+        println!("  {} {} (PID {})", if *allowed { "✓" } else { "✗" }, process.name, process.pid);
+    }
+    if denied > 0 {
+        println!(
+            "⚠️  The enforcer will skip {} of them (EPERM); run it as root or grant CAP_KILL \
+             (e.g. AmbientCapabilities=CAP_KILL in kern.service)",
+            denied
+        );
+    } else {
+        println!("✅ kern can act on all of them");
     }
-    
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    
-    // Load configuration at startup
-    let config = config::KernConfig::load()?;
-    
-    // Suppress config summary in JSON mode
-    let is_json_mode = match &cli.command {
-        Some(Commands::Status { json }) => *json,
-        Some(Commands::List { json, .. }) => *json,
-        _ => false,
+// "Daemon: enforcing 'strict'" or "Daemon: running, profile 'strict' (not enforcing)"
+fn daemon_status_line(daemon: &dbus_client::Daemon) -> Option<String> {
+    let status = daemon.enforcer_status().ok()?;
+    let profile = status["active_profile"].as_str().unwrap_or("?");
+    Some(if status["running"] == true {
+        format!("Daemon: enforcing '{}'", profile)
+    } else {
+        format!("Daemon: running, profile '{}' (not enforcing)", profile)
+    })
+}
+
+// `kern status --compact`: exactly one line, whatever --quiet says
+fn print_status_line(
+    config: &config::KernConfig,
+    separator: &str,
+    reverse: bool,
+    no_processes: bool,
+    daemon: Option<&dbus_client::Daemon>,
+) -> Result<()> {
+    let mut stats = if no_processes {
+        monitor::get_system_summary(config.temperature_sensor_path.as_deref())?
+    } else {
+        load_status(config, daemon)?.0
     };
-    
-    if !is_json_mode {
-        config.print_summary();
-        println!();
+    if reverse {
+        stats.top_processes.reverse();
     }
+    println!("{}", output::render_status_line(&stats, separator));
+    Ok(())
+}
 
-    if cli.monitor {
-        return monitor_loop(config.monitor_interval);
-    }
+// `kern ctl`: one request to the daemon's control socket
+fn run_ctl(config: &config::KernConfig, socket: Option<std::path::PathBuf>, command: CtlCommand) -> Result<()> {
+    use control_socket::ControlRequest;
 
-    match cli.command {
-        Some(Commands::Status { json }) => print_status(json)?,
-        Some(Commands::List { json, count }) => print_list(json, count)?,
-        Some(Commands::Kill { name }) => kill_process_by_name(&name, &config)?,
-        Some(Commands::Mode { profile }) => {
-            println!("Mode switching to '{}' (not yet implemented)", profile);
+    let path = socket.or_else(|| config.control_socket.socket_path()).ok_or_else(|| {
+        anyhow::anyhow!("No control socket: pass --socket, or set control_socket.path or XDG_RUNTIME_DIR")
+    })?;
+    let request = match command {
+        CtlCommand::Status => ControlRequest::GetStatus,
+        CtlCommand::Enforcer => ControlRequest::GetEnforcerStatus,
+        CtlCommand::List { count } => ControlRequest::ListProcesses { count },
+        CtlCommand::Mode { profile } => ControlRequest::SetMode { profile },
+        CtlCommand::Kill { name } => ControlRequest::Kill { name },
+        CtlCommand::KillLog { limit } => ControlRequest::GetKillLog { limit: limit.map(i32::from) },
+        CtlCommand::Pause => ControlRequest::Pause,
+        CtlCommand::Resume => ControlRequest::Resume,
+    };
+    let result = control_socket::request(&path, &request)?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+fn print_status_html(config: &config::KernConfig, full_page: bool) -> Result<()> {
+    let mut stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+    if config.normalize_cpu {
+        stats.normalize_process_cpu();
+    }
+    let snippet = output::render_status_html(&stats, &config.temperature);
+    if full_page {
+        print!("{}", output::full_page(&snippet));
+    } else {
+        print!("{}", snippet);
+    }
+    Ok(())
+}
+
+/// Rows `kern list` shows; None means every process. An alias rather than a
+/// bare Option so clap keeps the default of 20 instead of treating it as optional.
+type ListCount = Option<usize>;
+
+const MAX_LIST_COUNT: usize = 100_000;
+
+// --duration: "60s", "10m", "1h 30m"; never zero
+fn parse_run_duration(value: &str) -> std::result::Result<Duration, String> {
+    match humantime::parse_duration(value) {
+        Ok(duration) if duration.is_zero() => Err("must be longer than 0s".to_string()),
+        Ok(duration) => Ok(duration),
+        Err(e) => Err(format!("{} (expected e.g. 60s, 10m or 1h 30m)", e)),
+    }
+}
+
+fn parse_count(value: &str) -> std::result::Result<ListCount, String> {
+    if value.eq_ignore_ascii_case("all") {
+        return Ok(None);
+    }
+    match value.parse::<usize>() {
+        Ok(0) => Ok(None),
+        Ok(count) if count <= MAX_LIST_COUNT => Ok(Some(count)),
+        Ok(_) => Err(format!("must be at most {} (use \"all\" for every process)", MAX_LIST_COUNT)),
+        Err(_) => Err(format!("expected a number or \"all\", got '{}'", value)),
+    }
+}
+
+/// Flags shared by `kern list` and `kern list --watch`
+#[derive(Debug, Clone)]
+struct ListOptions {
+    json: bool,
+    count: ListCount,
+    sort: monitor::ProcessSort,
+    sort_reverse: bool,
+    filter_name: Option<String>,
+    group: bool,
+    top_n_per_user: Option<usize>,
+    kernel_threads: bool,
+    show_threads: bool,
+    show_nice: bool,
+    show_env: Option<String>,
+    show_cgroup: bool,
+    filter_cgroup: Option<String>,
+    wide: bool,
+    normalize_cpu: bool, // Per-process CPU as a share of all CPUs rather than per core
+    quiet: bool,
+    highlight: Highlight,
+    output_file: Option<std::path::PathBuf>, // --output-file: JSON goes there instead of stdout
+}
+
+impl ListOptions {
+    fn include_threads(&self) -> monitor::IncludeThreads {
+        if self.show_threads { monitor::IncludeThreads::Yes } else { monitor::IncludeThreads::No }
+    }
+}
+
+/// One refresh of `kern list`: individual processes or, with --group, applications,
+/// or with --top-n-per-user, processes by owner
+enum ListRows {
+    Processes(Vec<monitor::ProcessInfo>),
+    Groups(Vec<monitor::ProcessGroup>),
+    Users(std::collections::BTreeMap<String, Vec<monitor::ProcessInfo>>),
+}
+
+impl ListRows {
+    // Every process shown, in display order (none for groups)
+    fn processes(&self) -> Vec<&monitor::ProcessInfo> {
+        match self {
+            ListRows::Processes(processes) => processes.iter().collect(),
+            ListRows::Groups(_) => Vec::new(),
+            ListRows::Users(users) => users.values().flatten().collect(),
+        }
+    }
+}
+
+// Window between the two /proc/<pid>/io reads of a one-off `kern list --wide`
+const IO_SAMPLE_WINDOW: Duration = Duration::from_secs(1);
+
+// With --wide, `io` fills in disk I/O rates. A sampler without a previous
+// refresh to compare to reads twice, IO_SAMPLE_WINDOW apart.
+fn list_processes(opts: &ListOptions, metric: config::MemoryMetric, io: &mut monitor::IoSampler) -> Result<ListRows> {
+    let mut processes = monitor::get_all_processes(metric, opts.include_threads())?;
+    if opts.normalize_cpu {
+        monitor::normalize_process_cpu(&mut processes, monitor::cpu_count());
+    }
+    if opts.wide {
+        if !io.has_baseline() {
+            io.update(&mut processes);
+            std::thread::sleep(IO_SAMPLE_WINDOW);
+        }
+        io.update(&mut processes);
+    }
+    Ok(arrange_rows(processes, opts))
+}
+
+/// Filter, order and cut `processes` the way `opts` asks
+fn arrange_rows(mut processes: Vec<monitor::ProcessInfo>, opts: &ListOptions) -> ListRows {
+    if !opts.kernel_threads {
+        processes.retain(|p| !p.kernel_thread);
+    }
+    if let Some(pattern) = opts.filter_cgroup.as_deref() {
+        processes.retain(|p| in_cgroup(p, pattern));
+    }
+    let mut selected = monitor::select_processes(processes, opts.sort, opts.filter_name.as_deref());
+    if let Some(n) = opts.top_n_per_user {
+        return ListRows::Users(monitor::top_processes_per_user(selected, n));
+    }
+    if opts.group {
+        let mut groups = monitor::group_processes(&selected);
+        monitor::sort_groups(&mut groups, opts.sort);
+        if opts.sort_reverse {
+            groups.reverse();
+        }
+        groups.truncate(opts.count.unwrap_or(usize::MAX));
+        return ListRows::Groups(groups);
+    }
+    if opts.sort_reverse {
+        selected.reverse();
+    }
+    if opts.show_threads {
+        selected = monitor::threads_after_processes(selected);
+    }
+    selected.truncate(opts.count.unwrap_or(usize::MAX));
+    ListRows::Processes(selected)
+}
+
+// --filter-cgroup: a process whose cgroup can't be read never matches
+fn in_cgroup(p: &monitor::ProcessInfo, pattern: &str) -> bool {
+    p.cgroup_path.as_deref().is_some_and(|path| path.contains(pattern))
+}
+
+// With `show_env`, "env" maps that variable to its value (null when unset or unreadable);
+// `wide` adds the disk I/O rates
+fn process_json(p: &monitor::ProcessInfo, show_env: Option<&str>, wide: bool) -> serde_json::Value {
+    let mut row = serde_json::json!({
+        "pid": p.pid,
+        "tgid": p.tgid,
+        "thread": p.is_thread(),
+        "name": p.name,
+        "memory_gb": p.memory_gb,
+        "cpu_percentage": p.cpu_percentage,
+        "state": p.state.to_string(),
+        "ppid": p.ppid,
+        "nice": p.nice,
+        "cgroup": p.cgroup_path,
+        "uid": p.uid,
+        "user": p.uid.and_then(monitor::user_name)
+    });
+    if let Some(key) = show_env {
+        row["env"] = serde_json::json!({ key: monitor::read_process_env_var(p.pid, key) });
+    }
+    if wide {
+        row["io_read_mbps"] = serde_json::json!(p.io_read_mbps);
+        row["io_write_mbps"] = serde_json::json!(p.io_write_mbps);
+    }
+    row
+}
+
+fn list_json(rows: &ListRows, show_env: Option<&str>, wide: bool) -> Vec<serde_json::Value> {
+    match rows {
+        ListRows::Processes(processes) => processes.iter().map(|p| process_json(p, show_env, wide)).collect(),
+        ListRows::Groups(groups) => groups
+            .iter()
+            .map(|g| {
+                serde_json::json!({
+                    "name": g.name,
+                    "count": g.count(),
+                    "pids": g.pids,
+                    "memory_gb": g.memory_gb,
+                    "cpu_percentage": g.cpu_percentage
+                })
+            })
+            .collect(),
+        ListRows::Users(users) => users
+            .iter()
+            .map(|(user, processes)| {
+                serde_json::json!({
+                    "user": user,
+                    "memory_gb": processes.iter().map(|p| p.memory_gb).sum::<f64>(),
+                    "processes": processes.iter().map(|p| process_json(p, show_env, wide)).collect::<Vec<_>>()
+                })
+            })
+            .collect(),
+    }
+}
+
+fn list_table(rows: &ListRows, opts: &ListOptions) -> Vec<String> {
+    // Per-process CPU% is per core unless normalized: red once a process alone
+    // uses more of the machine than the active profile allows
+    let cpus = monitor::cpu_count() as f64;
+    let cpu_limit = opts.highlight.limits.effective_cpu_percent(cpus) * if opts.normalize_cpu { 1.0 } else { cpus };
+    let cpu_column = |cpu: f64| opts.highlight.painter.paint(&format!("{:<8.2}", cpu), color::limit_color(cpu, cpu_limit));
+    let nice_column = |value: String| if opts.show_nice { format!("{:<5} ", value) } else { String::new() };
+    let tgid_column = |value: String| if opts.show_threads { format!("{:<8} ", value) } else { String::new() };
+    let io_columns = |read: String, write: String| {
+        if opts.wide { format!("{:<10} {:<11} ", read, write) } else { String::new() }
+    };
+    // --show-env values, read once per row; the column is as wide as its longest value
+    let processes = rows.processes();
+    let env_values: Vec<String> = match opts.show_env.as_deref() {
+        Some(key) => processes
+            .iter()
+            .map(|p| monitor::read_process_env_var(p.pid, key).unwrap_or_else(|| "-".to_string()))
+            .collect(),
+        None => Vec::new(),
+    };
+    let env_width = env_values.iter().chain(opts.show_env.as_ref()).map(|value| value.chars().count()).max().unwrap_or(0);
+    let env_column = |value: &str| if opts.show_env.is_some() { format!("{:<1$} ", value, env_width) } else { String::new() };
+    let cgroup_width = if opts.show_cgroup {
+        processes.iter().filter_map(|p| p.cgroup_path.as_ref()).map(|path| path.chars().count()).max().unwrap_or(0).max("CGROUP".len())
+    } else {
+        0
+    };
+    let cgroup_column = |value: &str| if opts.show_cgroup { format!("{:<1$} ", value, cgroup_width) } else { String::new() };
+    let mut lines = match rows {
+        ListRows::Processes(_) | ListRows::Users(_) => vec![format!(
+            "{:<8} {}{:<8} {:<8} {}{}{:<3} {}{}{}",
+            "PID",
+            tgid_column("TGID".to_string()),
+            "MEM(GB)",
+            "CPU%",
+            io_columns("READ MB/s".to_string(), "WRITE MB/s".to_string()),
+            nice_column("NICE".to_string()),
+            "S",
+            env_column(opts.show_env.as_deref().unwrap_or_default()),
+            cgroup_column("CGROUP"),
+            "NAME"
+        )],
+        ListRows::Groups(_) => vec![format!("{:<8} {:<8} {:<8} {}", "COUNT", "MEM(GB)", "CPU%", "NAME")],
+    };
+    if !opts.quiet {
+        lines.push("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string());
+    }
+    // The i-th of `processes`
+    let process_line = |i: usize, p: &monitor::ProcessInfo| {
+        // Zombies are already dead; only their parent can make them go away
+        let zombie = if p.is_zombie() { format!(" <defunct, parent PID {}>", p.ppid) } else { String::new() };
+        let nice = nice_column(p.nice.map_or_else(|| "-".to_string(), |nice| nice.to_string()));
+        let env = env_column(env_values.get(i).map_or("-", String::as_str));
+        let cgroup = cgroup_column(p.cgroup_path.as_deref().unwrap_or("-"));
+        let io = io_columns(format!("{:.1}", p.io_read_mbps), format!("{:.1}", p.io_write_mbps));
+        let thread = if p.is_thread() { "[T] " } else { "" };
+        format!(
+            "{:<8} {}{:<8.2} {} {}{}{:<3} {}{}{}{}{}",
+            p.pid, tgid_column(p.tgid.to_string()), p.memory_gb, cpu_column(p.cpu_percentage), io, nice, p.state, env, cgroup,
+            thread, p.name, zombie
+        )
+    };
+    match rows {
+        ListRows::Processes(processes) => {
+            lines.extend(processes.iter().enumerate().map(|(i, p)| process_line(i, p)));
+        }
+        ListRows::Users(users) => {
+            let mut i = 0;
+            for (user, processes) in users {
+                let memory_gb: f64 = processes.iter().map(|p| p.memory_gb).sum();
+                lines.push(format!("── {} ({:.2} GB) ──", user, memory_gb));
+                for p in processes {
+                    lines.push(process_line(i, p));
+                    i += 1;
+                }
+            }
+        }
+        ListRows::Groups(groups) => {
+            for g in groups {
+                lines.push(format!("{:<8} {:<8.2} {} {}", g.count(), g.memory_gb, cpu_column(g.cpu_percentage), g.name));
+            }
+        }
+    }
+    lines
+}
+
+fn print_list(opts: &ListOptions, metric: config::MemoryMetric) -> Result<()> {
+    let rows = list_processes(opts, metric, &mut monitor::IoSampler::new())?;
+    if opts.json {
+        // For JSON mode, only output the JSON array without config summary
+        let processes = list_json(&rows, opts.show_env.as_deref(), opts.wide);
+        match &opts.output_file {
+            Some(path) => output::write_output_file(path, serde_json::json!(processes))?,
+            None => println!("{}", serde_json::to_string_pretty(&processes)?),
+        }
+        return Ok(());
+    }
+
+    for line in list_table(&rows, opts) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// `kern list --format ndjson`: one compact JSON object per line, up to --count
+struct NdjsonWriter<W: Write> {
+    out: W,
+    remaining: ListCount, // None: no --count limit
+    error: Option<io::Error>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    fn new(out: W, count: ListCount) -> Self {
+        Self { out, remaining: count, error: None }
+    }
+
+    // Break once --count rows are written or the output fails
+    fn write(&mut self, row: &serde_json::Value) -> ControlFlow<()> {
+        if self.remaining == Some(0) {
+            return ControlFlow::Break(());
+        }
+        if let Err(e) = writeln!(self.out, "{}", row) {
+            self.error = Some(e);
+            return ControlFlow::Break(());
+        }
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        if self.remaining == Some(0) { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }
+
+    // A reader that went away early (`| head`) is not an error
+    fn finish(mut self) -> io::Result<()> {
+        let result = match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        };
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        }
+    }
+}
+
+fn print_list_ndjson(opts: &ListOptions, metric: config::MemoryMetric) -> Result<()> {
+    let stdout = io::stdout();
+    let mut writer = NdjsonWriter::new(io::BufWriter::new(stdout.lock()), opts.count);
+
+    if opts.sort == monitor::ProcessSort::Unsorted && !opts.group && !opts.sort_reverse && !opts.wide {
+        // Nothing to order: write each process as it is read, never holding the whole table
+        let filter = opts.filter_name.as_deref().map(str::to_lowercase);
+        let cpus = if opts.normalize_cpu { monitor::cpu_count() } else { 1 };
+        monitor::for_each_process(metric, opts.include_threads(), |mut p| {
+            let hidden = (p.kernel_thread && !opts.kernel_threads)
+                || filter.as_ref().is_some_and(|filter| !p.name.to_lowercase().contains(filter))
+                || opts.filter_cgroup.as_deref().is_some_and(|pattern| !in_cgroup(&p, pattern));
+            if hidden {
+                return ControlFlow::Continue(());
+            }
+            monitor::normalize_process_cpu(std::slice::from_mut(&mut p), cpus);
+            writer.write(&process_json(&p, opts.show_env.as_deref(), false))
+        })?;
+    } else {
+        let rows = list_processes(opts, metric, &mut monitor::IoSampler::new())?;
+        for row in list_json(&rows, opts.show_env.as_deref(), opts.wide) {
+            if writer.write(&row).is_break() {
+                break;
+            }
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+// Ctrl+C sets INTERRUPTED instead of killing kern, so loops can clean up
+fn catch_sigint() -> Result<()> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    let action = SigAction::new(SigHandler::Handler(on_sigint), SaFlags::empty(), SigSet::empty());
+    // SAFETY: the handler only stores to an atomic
+    unsafe { sigaction(Signal::SIGINT, &action) }?;
+    Ok(())
+}
+
+// `kern enforce` also stops cleanly on SIGTERM (systemctl stop), restoring what it changed
+fn catch_shutdown_signals() -> Result<()> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    catch_sigint()?;
+    let action = SigAction::new(SigHandler::Handler(on_sigint), SaFlags::empty(), SigSet::empty());
+    // SAFETY: the handler only stores to an atomic
+    unsafe { sigaction(Signal::SIGTERM, &action) }?;
+    Ok(())
+}
+
+// Sleep in short steps so Ctrl+C exits promptly
+fn sleep_unless_interrupted(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline && !INTERRUPTED.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Redraw `kern list` in place every `interval` seconds until Ctrl+C.
+/// With --json, prints one compact JSON object per refresh instead.
+fn watch_list_loop(config: &config::KernConfig, opts: ListOptions, interval: u64) -> Result<()> {
+    catch_sigint()?;
+
+    let mut stdout = io::stdout();
+    if !opts.json && opts.output_file.is_none() {
+        // Clear once and hide the cursor; later frames overwrite in place
+        write!(stdout, "\x1b[2J\x1b[?25l")?;
+    }
+
+    // --wide: I/O rates over the time since the previous refresh
+    let mut io = monitor::IoSampler::new();
+    let result = (|| -> Result<()> {
+        while !INTERRUPTED.load(Ordering::SeqCst) {
+            let rows = list_processes(&opts, config.memory_metric, &mut io)?;
+            let refreshed = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+
+            if let Some(path) = &opts.output_file {
+                output::write_output_file(path, serde_json::json!(list_json(&rows, opts.show_env.as_deref(), opts.wide)))?;
+            } else if opts.json {
+                let frame = serde_json::json!({
+                    "refreshed": refreshed.to_string(),
+                    "processes": list_json(&rows, opts.show_env.as_deref(), opts.wide),
+                });
+                writeln!(stdout, "{}", frame)?;
+            } else {
+                // Home the cursor and clear each line's tail rather than the whole screen
+                let mut frame = String::from("\x1b[H");
+                let header = format!("Last refresh: {} (every {}s, Ctrl+C to exit)", refreshed, interval);
+                for line in std::iter::once(header).chain(list_table(&rows, &opts)) {
+                    frame.push_str(&line);
+                    frame.push_str("\x1b[K\n");
+                }
+                frame.push_str("\x1b[J");
+                write!(stdout, "{}", frame)?;
+            }
+            stdout.flush()?;
+
+            sleep_unless_interrupted(Duration::from_secs(interval));
+        }
+        Ok(())
+    })();
+
+    if !opts.json && opts.output_file.is_none() {
+        writeln!(stdout, "\x1b[?25h")?;
+    }
+    result
+}
+
+fn print_config_diff(config: &config::KernConfig, json: bool) -> Result<()> {
+    let diffs = config::diff_from_defaults(config);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diffs)?);
+        return Ok(());
+    }
+
+    if diffs.is_empty() {
+        println!("All settings match the compiled-in defaults");
+    }
+    for diff in &diffs {
+        println!("{}: {} (default) → {} (current)", diff.path, diff.default_value, diff.current_value);
+    }
+    Ok(())
+}
+
+// With `output_file` (`kern --monitor status --json --output-file PATH`) each
+// refresh replaces the file instead of printing. `duration` (--duration) ends
+// the loop like Ctrl+C. With `sparklines`, CPU and temperature are followed by
+// their last 30 readings and trend.
+fn monitor_loop(
+    config: &config::KernConfig,
+    quiet: bool,
+    highlight: &Highlight,
+    output_file: Option<&std::path::Path>,
+    duration: Option<Duration>,
+    sparklines: bool,
+) -> Result<()> {
+    if !quiet {
+        let until = match duration {
+            Some(duration) => format!("Stopping after {} (or Ctrl+C).", humantime::format_duration(duration)),
+            None => "Press Ctrl+C to exit.".to_string(),
+        };
+        println!(
+            "Starting monitor loop (interval: {}). {}",
+            config::format_interval(config.monitor_interval),
+            until
+        );
+        println!();
+    }
+    
+    catch_sigint()?;
+    let started = Instant::now();
+    let over = || duration.is_some_and(|duration| started.elapsed() >= duration);
+    let mut refreshes = 0u64;
+    let mut trends = sparklines.then(MonitorTrends::new);
+    while !INTERRUPTED.load(Ordering::SeqCst) && !over() {
+        match output_file {
+            Some(path) => print_status(StatusTarget::Json(Some(path)), quiet, false, config, highlight, None)?,
+            None => {
+                print_status(StatusTarget::Text(trends.as_mut()), quiet, false, config, highlight, None)?;
+                println!();
+            }
+        }
+        refreshes += 1;
+        let interval = Duration::from_millis(config.monitor_interval);
+        sleep_unless_interrupted(duration.map_or(interval, |duration| interval.min(duration.saturating_sub(started.elapsed()))));
+    }
+
+    // What the refreshes cost, to catch regressions in sampling
+    if let Some(footprint) = monitor::own_footprint() {
+        eprintln!(
+            "Monitor used {:.2}s of CPU time over {} refresh(es) ({:.2}% of one CPU)",
+            footprint.cpu_time_secs, refreshes, footprint.cpu_percent
+        );
+    }
+    Ok(())
+}
+
+/// Source of answers to interactive prompts, so `kern kill --confirm-each`
+/// can be driven by a script in tests
+trait ConfirmationReader {
+    /// Show `prompt` and return the answer, or None once input is exhausted
+    fn read_answer(&mut self, prompt: &str) -> Result<Option<String>>;
+
+    /// Whether someone is there to answer; confirmation prompts abort otherwise
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+struct StdinReader;
+
+impl ConfirmationReader for StdinReader {
+    fn read_answer(&mut self, prompt: &str) -> Result<Option<String>> {
+        print!("{}", prompt);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(input.trim().to_string()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        io::stdin().is_terminal()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EachAnswer {
+    Yes,
+    No,
+    All,  // Kill this and every remaining process without asking again
+    Quit, // Skip this and every remaining process
+}
+
+fn parse_each_answer(input: &str) -> Option<EachAnswer> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(EachAnswer::Yes),
+        "n" | "no" => Some(EachAnswer::No),
+        "a" | "all" => Some(EachAnswer::All),
+        "q" | "quit" => Some(EachAnswer::Quit),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct EachSummary {
+    killed: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+/// Ask about each process in turn and call `kill` on the accepted ones.
+/// End of input is treated as quit so a closed stdin never kills anything.
+fn confirm_each<F>(
+    processes: &[monitor::ProcessInfo],
+    reader: &mut dyn ConfirmationReader,
+    mut kill: F,
+) -> Result<EachSummary>
+where
+    F: FnMut(&monitor::ProcessInfo) -> bool,
+{
+    let mut summary = EachSummary::default();
+    let mut kill_rest = false;
+
+    for (i, process) in processes.iter().enumerate() {
+        if !kill_rest {
+            println!(
+                "\n{} (PID {}) - memory: {:.2} GB, CPU: {:.1}%",
+                process.name, process.pid, process.memory_gb, process.cpu_percentage
+            );
+            let answer = loop {
+                let Some(input) = reader.read_answer("Kill this process? (y/n/a[ll]/q[uit]) ")? else {
+                    break EachAnswer::Quit;
+                };
+                match parse_each_answer(&input) {
+                    Some(answer) => break answer,
+                    None => println!("Please answer y, n, a or q."),
+                }
+            };
+
+            match answer {
+                EachAnswer::Yes => {}
+                EachAnswer::No => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                EachAnswer::All => kill_rest = true,
+                EachAnswer::Quit => {
+                    summary.skipped += processes.len() - i;
+                    break;
+                }
+            }
+        }
+
+        if kill(process) {
+            summary.killed += 1;
+        } else {
+            summary.failed += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// When `kern kill` asks before killing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KillConfirmation {
+    Threshold,   // Above kill_confirmation_threshold matches, pick which ones to kill
+    EachProcess, // --confirm-each: ask about every match in turn
+    Skip,        // --yes
+}
+
+/// Which of `count` numbered entries an answer picks, as indices from 0:
+/// "all" (or "y"), "none" (or "n"), or numbers and ranges like "1,3,5" or
+/// "2-4 7". None for anything else, including numbers outside 1..=count.
+fn parse_selection(input: &str, count: usize) -> Option<Vec<usize>> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "a" | "all" | "y" | "yes" => return Some((0..count).collect()),
+        "n" | "none" | "no" => return Some(Vec::new()),
+        "" => return None,
+        _ => {}
+    }
+    let mut chosen = std::collections::BTreeSet::new();
+    for part in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (first.trim().parse::<usize>().ok()?, last.trim().parse::<usize>().ok()?),
+            None => {
+                let number = part.parse::<usize>().ok()?;
+                (number, number)
+            }
+        };
+        if first == 0 || first > last || last > count {
+            return None;
+        }
+        chosen.extend(first - 1..last);
+    }
+    Some(chosen.into_iter().collect())
+}
+
+/// Ask which of `count` numbered entries to kill until the answer parses;
+/// None (cancel) at end of input
+fn confirm_selection(count: usize, reader: &mut dyn ConfirmationReader) -> Result<Option<Vec<usize>>> {
+    loop {
+        let Some(input) = reader.read_answer("Kill which? (all, none, or numbers like 1,3,5): ")? else {
+            return Ok(None);
+        };
+        match parse_selection(&input, count) {
+            Some(chosen) => return Ok(Some(chosen)),
+            None => println!("Please answer all, none, or numbers from 1 to {}.", count),
+        }
+    }
+}
+
+/// Which of the processes matching a name `kern kill` targets
+#[derive(Debug, Clone, Copy, Default)]
+struct KillFilter {
+    selector: Option<killer::KillSelector>, // --oldest, --newest or --heaviest
+    older_than: Option<Duration>,           // --older-than
+}
+
+fn kill_process_by_name(
+    name: &str,
+    filter: KillFilter,
+    confirmation: KillConfirmation,
+    config: &config::KernConfig,
+    reader: &mut dyn ConfirmationReader,
+    daemon: Option<&dbus_client::Daemon>,
+) -> Result<()> {
+    // Find all processes matching the name
+    let pids = killer::find_processes_by_name(name);
+    
+    if pids.is_empty() {
+        println!("❌ No running process found matching '{}'", name);
+        return Ok(());
+    }
+
+    // Only your own processes, unless enforcement.all_users (--all-users)
+    let uid = nix::unistd::getuid().as_raw();
+    let (pids, others): (Vec<u32>, Vec<u32>) = pids
+        .into_iter()
+        .partition(|pid| config.enforcement.all_users || monitor::read_process_uid(*pid) == Some(uid));
+    if pids.is_empty() {
+        println!(
+            "❌ No process of yours matches '{}' ({} belong to other users; use --all-users to include them)",
+            name,
+            others.len()
+        );
+        return Ok(());
+    }
+    // The daemon kills every match by name, other users' included
+    let daemon = match daemon {
+        Some(_) if !others.is_empty() => {
+            eprintln!("ℹ️  Other users' '{}' processes match too; killing only yours, locally", name);
+            None
+        }
+        daemon => daemon,
+    };
+
+    // Zombies have already exited: signals do nothing until the parent reaps them
+    let (zombies, pids): (Vec<u32>, Vec<u32>) = pids.into_iter().partition(|pid| {
+        monitor::read_proc_stat(*pid).is_some_and(|stat| stat.state == 'Z')
+    });
+    for pid in &zombies {
+        let parent = monitor::read_proc_stat(*pid).map_or(0, |stat| stat.ppid);
+        println!(
+            "❌ Cannot kill PID {} - it is a zombie (already exited). It goes away once its parent (PID {}) reaps it or exits.",
+            pid, parent
+        );
+    }
+    if pids.is_empty() {
+        return Ok(());
+    }
+    
+    println!("Found {} process(es) matching '{}'", pids.len(), name);
+
+    // Full details of the matches; ones that exited since the name lookup drop out
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let matches: Vec<monitor::ProcessInfo> = monitor::get_all_processes(config.memory_metric, monitor::IncludeThreads::No)?
+        .into_iter()
+        .filter(|p| pids.contains(&p.pid))
+        .collect();
+    if matches.is_empty() {
+        println!("❌ The processes matching '{}' exited", name);
+        return Ok(());
+    }
+
+    // --older-than: keep the ones running longer than that, showing every match's age
+    let matches = match filter.older_than {
+        Some(min_age) => {
+            let (old, recent) = killer::partition_by_age(matches, min_age, now);
+            let min_age = humantime::format_duration(min_age);
+            for (process, selected) in old.iter().map(|p| (p, true)).chain(recent.iter().map(|p| (p, false))) {
+                println!(
+                    "  PID {:<8} running {}{}",
+                    process.pid,
+                    humantime::format_duration(killer::process_age(process, now)),
+                    if selected { "" } else { " (too recent, kept)" }
+                );
+            }
+            if old.is_empty() {
+                println!("❌ No process matching '{}' has been running longer than {}", name, min_age);
+                return Ok(());
+            }
+            println!("{} process(es) running longer than {}", old.len(), min_age);
+            old
+        }
+        None => matches,
+    };
+
+    // --oldest, --newest or --heaviest: narrow the matches down to one
+    let mut matches = match filter.selector {
+        Some(selector) if matches.len() > 1 => match killer::select_kill_target(&matches, selector) {
+            Some(target) => {
+                println!("Selected PID {} ({})", target.pid, killer::describe_selection(target, selector, now));
+                vec![target.clone()]
+            }
+            None => matches,
+        },
+        _ => matches,
+    };
+
+    // Check if process is critical
+    if config.is_critical_process(name) {
+        println!("❌ Cannot kill '{}' - it is a critical system process", name);
+        return Ok(());
+    }
+
+    // Check if process is protected
+    if killer::is_protected(name, &config.protected_processes) {
+        println!("❌ Cannot kill '{}' - it is in the protected process list", name);
+        return Ok(());
+    }
+
+    let mut deselected = Vec::new();
+    match confirmation {
+        KillConfirmation::EachProcess => return kill_each_confirmed(name, &matches, config, reader),
+        KillConfirmation::Skip => {}
+        // Over the threshold (or always, with a threshold of 0): show the matches and ask which to kill
+        KillConfirmation::Threshold if config.needs_kill_confirmation(matches.len()) => {
+            if !reader.is_interactive() {
+                return Err(anyhow::anyhow!(
+                    "Not killing {} processes matching '{}' without confirmation: stdin is not a terminal (pass --yes)",
+                    matches.len(),
+                    name
+                ));
+            }
+            println!("\n⚠️  This will kill {} processes:", matches.len());
+            let command = |p: &monitor::ProcessInfo| monitor::read_process_cmdline(p.pid).unwrap_or_else(|| p.name.clone());
+            for line in output::render_process_table(&matches, command) {
+                println!("{}", line);
+            }
+            let Some(chosen) = confirm_selection(matches.len(), reader)? else {
+                println!("Cancelled.");
+                return Ok(());
+            };
+            let (kept, left): (Vec<_>, Vec<_>) =
+                matches.into_iter().enumerate().partition(|(i, _)| chosen.contains(i));
+            matches = kept.into_iter().map(|(_, p)| p).collect();
+            deselected = left.into_iter().map(|(_, p)| p).collect();
+            if matches.is_empty() {
+                println!("Cancelled.");
+                return Ok(());
+            }
+        }
+        KillConfirmation::Threshold => {}
+    }
+    if !deselected.is_empty() {
+        println!(
+            "Leaving {} deselected process(es) alone (PID: {})",
+            deselected.len(),
+            deselected.iter().map(|p| p.pid.to_string()).collect::<Vec<_>>().join(", ")
+        );
+        for process in &deselected {
+            killer::log_deselected_action(Some(config), process.pid, &process.name);
+        }
+    }
+    let pids: Vec<u32> = matches.iter().map(|p| p.pid).collect();
+
+    // The daemon kills every match by name, deselected ones included
+    let daemon = match daemon {
+        Some(_) if !deselected.is_empty() => {
+            eprintln!("ℹ️  Killing only the selected '{}' processes, locally", name);
+            None
+        }
+        daemon => daemon,
+    };
+
+    if let Some(daemon) = daemon {
+        match daemon.kill_by_name(name) {
+            Ok(result) => {
+                print_daemon_kill(&result);
+                return Ok(());
+            }
+            Err(e) if dbus_client::is_method_error(&e) => {
+                println!("❌ kern daemon refused to kill '{}': {}", name, e);
+                return Ok(());
+            }
+            Err(e) => eprintln!("ℹ️  kern daemon did not answer ({}); killing locally", e),
+        }
+    }
+
+    // Kill the processes
+    match killer::kill_processes(&pids, config.kill_graceful, config.kill_graceful_wait()) {
+        Ok(_) => {
+            let kill_type = if config.kill_graceful { "gracefully" } else { "forcefully" };
+            println!("✅ Killed {} process(es) {} (PID: {})", 
+                pids.len(), 
+                kill_type,
+                pids.iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            
+            // Log the action for each PID
+            for pid in &pids {
+                killer::log_kill_action(Some(config), *pid, name, true, config.kill_graceful, "manual");
+            }
+        }
+        Err(e) => {
+            println!("❌ Error killing processes: {}", e);
+            // Log failed attempt
+            for pid in &pids {
+                killer::log_kill_action(Some(config), *pid, name, false, config.kill_graceful, "manual");
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+// Summarize a KillProcessesByName reply
+fn print_daemon_kill(result: &serde_json::Value) {
+    let pids = |key: &str| -> Vec<String> {
+        result[key].as_array().into_iter().flatten().map(|pid| pid.to_string()).collect()
+    };
+    let (killed, failed, zombies) = (pids("killed"), pids("failed"), pids("zombies"));
+    if !killed.is_empty() {
+        println!("✅ kern daemon killed {} process(es) (PID: {})", killed.len(), killed.join(", "));
+    }
+    if !failed.is_empty() {
+        println!("❌ kern daemon failed to kill PID {}", failed.join(", "));
+    }
+    if !zombies.is_empty() {
+        println!("❌ Skipped zombie PID {} (already exited, waiting for their parent)", zombies.join(", "));
+    }
+    if killed.is_empty() && failed.is_empty() && zombies.is_empty() {
+        println!("❌ kern daemon found no running process matching that name");
+    }
+}
+
+// Read-only companion to `kern kill`: what matches, and what kill would refuse
+// `kern status --profile` exits with this when the preview includes kills
+const PREVIEW_KILLS_EXIT_CODE: i32 = 3;
+
+// What a profile would kill or limit on this machine right now, decided by the
+// enforcer itself in a dry run so the preview can't drift from a real switch
+fn preview_profile(name: &str, json: bool, config: &config::KernConfig) -> Result<()> {
+    let manager = profiles::ProfileManager::new(None)?;
+    let profile = manager.get(name).cloned().ok_or_else(|| {
+        anyhow::anyhow!("Profile '{}' not found. Available: {}", name, manager.list_names().join(", "))
+    })?;
+    let stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+
+    let mut enforcer = enforcer::Enforcer::new(config.clone(), profile.clone());
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = events.clone();
+    enforcer.set_observer(move |event: &enforcer::EnforcerEvent| {
+        if let Ok(mut events) = sink.lock() {
+            events.push(event.clone());
+        }
+    });
+    let report = enforcer.preview(&stats)?;
+    let events = events.lock().map(|events| events.clone()).unwrap_or_default();
+
+    // kill_on_activate names its victims, no strategy picks them
+    let describe = |kill: &enforcer::KillRecord| match kill.reason {
+        enforcer::KillReason::ProfileActivation => kill.reason.to_string(),
+        _ => kill.describe(),
+    };
+    let find = |pids: &[u32]| -> Vec<&monitor::ProcessInfo> {
+        pids.iter().filter_map(|pid| stats.top_processes.iter().find(|p| p.pid == *pid)).collect()
+    };
+    let limited = find(&report.cgroup_limited);
+    // An alert-only profile decides the same way, then only alerts
+    let alert_limited = find(&report.would_limit);
+    // Why: exceeded limits and skipped kill_on_activate entries, not the actions themselves
+    let findings: Vec<&enforcer::EnforcerEvent> = events
+        .iter()
+        .filter(|event| {
+            !matches!(
+                event,
+                enforcer::EnforcerEvent::ProcessKilled(_)
+                    | enforcer::EnforcerEvent::ProcessLimited { .. }
+                    | enforcer::EnforcerEvent::WouldKill(_)
+                    | enforcer::EnforcerEvent::WouldLimit { .. }
+                    | enforcer::EnforcerEvent::StatusReport { .. }
+            )
+        })
+        .collect();
+
+    if json {
+        let kill_json = |kills: &[enforcer::KillRecord]| -> Vec<serde_json::Value> {
+            kills
+                .iter()
+                .map(|kill| {
+                    serde_json::json!({
+                        "pid": kill.pid,
+                        "name": kill.name,
+                        "reason": kill.reason,
+                        "strategy": kill.strategy,
+                        "description": describe(kill),
+                    })
+                })
+                .collect()
+        };
+        let limit_json = |processes: &[&monitor::ProcessInfo]| -> Vec<serde_json::Value> {
+            processes.iter().map(|p| serde_json::json!({ "pid": p.pid, "name": p.name })).collect()
+        };
+        let kills = kill_json(&report.kills);
+        let limits = limit_json(&limited);
+        let output = serde_json::json!({
+            "profile": profile.name,
+            "enforce": profile.enforce,
+            "alert_only": enforcer.is_alert_only(),
+            "alert_would_kill": kill_json(&report.would_kill),
+            "alert_would_limit": limit_json(&alert_limited),
+            "cpu_usage": stats.cpu_usage,
+            "memory_percentage": stats.memory_percentage,
+            "temperature": stats.temperature,
+            "would_kill": kills,
+            "would_limit": limits,
+            "would_throttle_cpu": report.cpu_throttled,
+            "events": findings.iter().map(|event| event.to_json()).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("🔍 Preview of profile '{}' - nothing is changed", profile.name);
+        println!(
+            "   CPU {:.1}% | RAM {:.1}% | Temp {:.1}°C",
+            stats.cpu_usage, stats.memory_percentage, stats.temperature
+        );
+        for event in &findings {
+            println!("   {}", event.to_string().trim_start());
+        }
+        if !report.kills.is_empty() {
+            println!();
+            println!("Would kill ({}):", report.kills.len());
+            for kill in &report.kills {
+                println!("  {:<8} {:<20} {}", kill.pid, kill.name, describe(kill));
+            }
+        }
+        if !limited.is_empty() {
+            println!();
+            println!("Would limit ({}):", limited.len());
+            for p in &limited {
+                println!("  {:<8} {:<20} to {}% CPU", p.pid, p.name, profile.limits.cgroup_cpu_percent);
+            }
+        }
+        if report.cpu_throttled {
+            println!();
+            println!("Would throttle CPU frequency");
+        }
+        if !report.would_kill.is_empty() || !alert_limited.is_empty() {
+            println!();
+            println!("Alert-only: would report, without acting on them ({}):", report.would_kill.len() + alert_limited.len());
+            for kill in &report.would_kill {
+                println!("  {:<8} {:<20} kill - {}", kill.pid, kill.name, describe(kill));
+            }
+            for p in &alert_limited {
+                println!("  {:<8} {:<20} cgroup limit", p.pid, p.name);
+            }
+        }
+        if report.kills.is_empty() && limited.is_empty() && !report.cpu_throttled {
+            println!("✅ Nothing would be killed or limited");
+        }
+    }
+
+    if !report.kills.is_empty() {
+        std::process::exit(PREVIEW_KILLS_EXIT_CODE);
+    }
+    Ok(())
+}
+
+// Why the enforcer spares or targets processes named `name`: the critical and
+// protected rules, their live usage, and a dry run of the active profile
+fn explain_process(name: &str, json: bool, config: &config::KernConfig) -> Result<()> {
+    let profile = enforce_profile(None, config)?;
+    let stats = monitor::get_system_stats(config.memory_metric, config.temperature_sensor_path.as_deref())?;
+    let running: Vec<&monitor::ProcessInfo> = stats.top_processes.iter().filter(|p| p.name == name).collect();
+    // The enforcer below works on per-core values, so normalize_cpu only applies to what is shown
+    let cpu_divisor = if config.normalize_cpu { stats.cpu_count.max(1) as f64 } else { 1.0 };
+    let cpu: f64 = running.iter().map(|p| p.cpu_percentage).sum::<f64>() / cpu_divisor;
+    let memory_gb: f64 = running.iter().map(|p| p.memory_gb).sum();
+
+    let builtin = killer::BUILTIN_CRITICAL_PROCESSES.contains(&name);
+    let configured = config.critical_processes.iter().any(|critical| critical == name);
+    let overridden = (builtin || configured) && config.critical_override_allow.iter().any(|allowed| allowed == name);
+    let critical = config.is_critical_process(name);
+    let protected_global = killer::is_protected(name, &config.protected_processes);
+    let protected_profile = killer::is_protected(name, &profile.protected);
+    // (pid, protected ancestor pid, its name) for the first of them that has one
+    let ancestor = running.iter().find_map(|p| {
+        killer::protected_ancestor(std::path::Path::new("/proc"), p.pid, config.ancestry_depth, |ancestor| {
+            killer::is_protected(ancestor, &config.protected_processes) || killer::is_protected(ancestor, &profile.protected)
+        })
+        .map(|(parent, parent_name)| (p.pid, parent, parent_name))
+    });
+
+    let uid = nix::unistd::getuid().as_raw();
+    let other_users_only =
+        !config.enforcement.all_users && !running.is_empty() && running.iter().all(|p| p.uid != Some(uid));
+
+    let cpu_limit = profile.limits.effective_cpu_percent(stats.cpu_capacity());
+    let ram_limit = profile.limits.effective_ram_percent(stats.total_memory_gb);
+    let cpu_exceeded = stats.cpu_usage.is_finite() && stats.cpu_usage > cpu_limit;
+    let ram_exceeded = stats.memory_percentage.is_finite() && stats.memory_percentage > ram_limit;
+
+    // The enforcer's own decision for this sample, without acting on it
+    let mut enforcer = enforcer::Enforcer::new(config.clone(), profile.clone());
+    enforcer.set_observer(|_: &enforcer::EnforcerEvent| {});
+    let report = enforcer.preview(&stats)?;
+    let targeted: Vec<&enforcer::KillRecord> = report
+        .kills
+        .iter()
+        .filter(|kill| kill.name == name && kill.reason != enforcer::KillReason::ProfileActivation)
+        .collect();
+    let limited = running.iter().any(|p| report.cgroup_limited.contains(&p.pid));
+    // Alert-only mode picks the same victims but holds back
+    let held_back = report.would_kill.iter().find(|kill| kill.name == name && kill.reason != enforcer::KillReason::ProfileActivation);
+    let held_back_limit = running.iter().any(|p| report.would_limit.contains(&p.pid));
+
+    let verdict = if critical {
+        "never killed: critical process".to_string()
+    } else if protected_global {
+        "never killed: in protected_processes (kern.yaml)".to_string()
+    } else if protected_profile {
+        format!("never killed: protected by profile '{}'", profile.name)
+    } else if let Some((_, parent, parent_name)) = &ancestor {
+        format!("spared: descends from protected '{}' (PID {})", parent_name, parent)
+    } else if other_users_only {
+        "never killed: owned by another user (enforcement.all_users is off)".to_string()
+    } else if !profile.enforce {
+        format!("not killed: profile '{}' is monitor-only", profile.name)
+    } else if let Some(kill) = held_back {
+        format!("not killed: alert-only mode, but would be killed now ({})", kill.describe())
+    } else if held_back_limit {
+        "not limited: alert-only mode, but would be cgroup-limited now".to_string()
+    } else if let Some(kill) = targeted.first() {
+        format!("would be killed now ({})", kill.describe())
+    } else if limited {
+        "would be cgroup-limited now".to_string()
+    } else if running.is_empty() {
+        "not running".to_string()
+    } else if cpu_exceeded || ram_exceeded {
+        "not targeted now: heavier processes come first".to_string()
+    } else {
+        "not targeted now: no limit is exceeded".to_string()
+    };
+
+    if json {
+        let processes: Vec<serde_json::Value> = running
+            .iter()
+            .map(|p| serde_json::json!({ "pid": p.pid, "cpu_percentage": p.cpu_percentage / cpu_divisor, "memory_gb": p.memory_gb }))
+            .collect();
+        let critical_reason = match (builtin, configured) {
+            (true, _) => Some("builtin"),
+            (false, true) => Some("critical_processes"),
+            (false, false) => None,
+        };
+        let output = serde_json::json!({
+            "name": name,
+            "processes": processes,
+            "cpu_percentage": cpu,
+            "memory_gb": memory_gb,
+            "critical": critical,
+            "critical_reason": critical_reason,
+            "critical_overridden": overridden,
+            "protected_global": protected_global,
+            "protected_profile": protected_profile,
+            "protected_ancestor": ancestor.as_ref().map(|(pid, parent, parent_name)| {
+                serde_json::json!({ "pid": pid, "ancestor_pid": parent, "ancestor_name": parent_name })
+            }),
+            "profile": profile.name,
+            "enforce": profile.enforce,
+            "alert_only": enforcer.is_alert_only(),
+            "limits": {
+                "cpu": { "usage": stats.cpu_usage, "limit": cpu_limit, "exceeded": cpu_exceeded },
+                "ram": { "usage": stats.memory_percentage, "limit": ram_limit, "exceeded": ram_exceeded },
+            },
+            "would_kill": !targeted.is_empty(),
+            "would_limit": limited,
+            "verdict": verdict,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    println!("🔎 KERN - Explain '{}'", name);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if running.is_empty() {
+        println!("Running: no");
+    } else {
+        let pids: Vec<String> = running.iter().map(|p| p.pid.to_string()).collect();
+        println!("Running: {} process(es), PID {}", running.len(), pids.join(", "));
+        println!("Usage: {:.1}% CPU, {:.2} GB RAM", cpu, memory_gb);
+    }
+    let critical_why = match (builtin, configured, overridden) {
+        (_, _, true) => " (listed in critical_override_allow, so not critical)",
+        (true, _, false) => " (built-in critical process)",
+        (false, true, false) => " (listed in critical_processes)",
+        _ => "",
+    };
+    println!("Critical: {}{}", yes_no(critical), critical_why);
+    println!("Protected in kern.yaml: {}", yes_no(protected_global));
+    println!("Protected by profile '{}': {}", profile.name, yes_no(protected_profile));
+    if let Some((pid, parent, parent_name)) = &ancestor {
+        println!("Protected ancestor: PID {} descends from '{}' (PID {})", pid, parent_name, parent);
+    }
+    println!(
+        "Profile limits: CPU {:.1}% of {:.1}%{}, RAM {:.1}% of {:.1}%{}",
+        stats.cpu_usage,
+        cpu_limit,
+        if cpu_exceeded { " (exceeded)" } else { "" },
+        stats.memory_percentage,
+        ram_limit,
+        if ram_exceeded { " (exceeded)" } else { "" },
+    );
+    println!("Verdict: {}", verdict);
+    Ok(())
+}
+
+fn find_processes(query: &str, json: bool, quiet: bool, config: &config::KernConfig) -> Result<()> {
+    let mut processes: Vec<monitor::ProcessInfo> = monitor::get_all_processes(config.memory_metric, monitor::IncludeThreads::No)?
+        .into_iter()
+        .filter(|p| !p.kernel_thread)
+        .collect();
+    if config.normalize_cpu {
+        monitor::normalize_process_cpu(&mut processes, monitor::cpu_count());
+    }
+    let matches = monitor::select_processes(processes, monitor::ProcessSort::Name, Some(query));
+    let flags = |p: &monitor::ProcessInfo| {
+        (killer::is_protected(&p.name, &config.protected_processes), config.is_critical_process(&p.name))
+    };
+
+    if json {
+        let entries: Vec<serde_json::Value> = matches
+            .iter()
+            .map(|p| {
+                let (protected, critical) = flags(p);
+                serde_json::json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "memory_gb": p.memory_gb,
+                    "cpu_percentage": p.cpu_percentage,
+                    "protected": protected,
+                    "critical": critical,
+                })
+            })
+            .collect();
+        let output = serde_json::json!({ "query": query, "matches": entries });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if matches.is_empty() {
+        println!("No running process matches '{}'", query);
+        return Ok(());
+    }
+
+    println!("{:<8} {:<8} {:<8} {:<10} NAME", "PID", "MEM(GB)", "CPU%", "STATUS");
+    if !quiet {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+    for p in &matches {
+        let status = match flags(p) {
+            (_, true) => "critical",
+            (true, false) => "protected",
+            (false, false) => "-",
+        };
+        println!("{:<8} {:<8.2} {:<8.2} {:<10} {}", p.pid, p.memory_gb, p.cpu_percentage, status, p.name);
+    }
+    Ok(())
+}
+
+fn kill_each_confirmed(
+    name: &str,
+    processes: &[monitor::ProcessInfo],
+    config: &config::KernConfig,
+    reader: &mut dyn ConfirmationReader,
+) -> Result<()> {
+    let summary = confirm_each(processes, reader, |process| {
+        match killer::kill_processes(&[process.pid], config.kill_graceful, config.kill_graceful_wait()) {
+            Ok(_) => {
+                println!("✅ Killed PID {}", process.pid);
+                killer::log_kill_action(Some(config), process.pid, name, true, config.kill_graceful, "manual");
+                true
+            }
+            Err(e) => {
+                println!("❌ Error killing PID {}: {}", process.pid, e);
+                killer::log_kill_action(Some(config), process.pid, name, false, config.kill_graceful, "manual");
+                false
+            }
+        }
+    })?;
+
+    print!("\nKilled {}, skipped {}", summary.killed, summary.skipped);
+    if summary.failed > 0 {
+        print!(", failed {}", summary.failed);
+    }
+    println!();
+    Ok(())
+}
+
+// Name and PIDs targeted by `kern renice` / `kern ionice`, with the same
+// critical/protected refusals as `kern kill`
+fn priority_targets(
+    name: Option<&str>,
+    pid: Option<u32>,
+    config: &config::KernConfig,
+    verb: &str,
+) -> std::result::Result<(String, Vec<u32>), String> {
+    let (name, pids) = match (name, pid) {
+        (_, Some(pid)) => match killer::process_name(pid) {
+            Some(name) => (name, vec![pid]),
+            None => return Err(format!("No running process with PID {}", pid)),
+        },
+        (Some(name), None) => (name.to_string(), killer::find_processes_by_name(name)),
+        (None, None) => return Err("Specify a process name or --pid".to_string()),
+    };
+
+    if pids.is_empty() {
+        return Err(format!("No running process found matching '{}'", name));
+    }
+    if config.is_critical_process(&name) {
+        return Err(format!("Cannot {} '{}' - it is a critical system process", verb, name));
+    }
+    if killer::is_protected(&name, &config.protected_processes) {
+        return Err(format!("Cannot {} '{}' - it is in the protected process list", verb, name));
+    }
+    Ok((name, pids))
+}
+
+// Print per-PID priority results as JSON or human-readable lines
+fn print_priority_results(results: &[serde_json::Value], json: bool, label: &str) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(results)?);
+        return Ok(());
+    }
+
+    let text = |value: &serde_json::Value| value.as_str().map_or_else(|| value.to_string(), str::to_string);
+    for result in results {
+        match result.get("error") {
+            Some(error) => println!("❌ {}", text(error)),
+            None => println!(
+                "✅ PID {} ({}): {} {} → {}",
+                result["pid"], text(&result["name"]), label, text(&result["old"]), text(&result["new"])
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn print_priority_refusal(message: &str, json: bool) {
+    if json {
+        println!("{}", serde_json::json!({ "error": message }));
+    } else {
+        println!("❌ {}", message);
+    }
+}
+
+fn renice_processes(name: Option<&str>, pid: Option<u32>, nice: i32, json: bool, config: &config::KernConfig) -> Result<()> {
+    let (name, pids) = match priority_targets(name, pid, config, "renice") {
+        Ok(targets) => targets,
+        Err(message) => {
+            print_priority_refusal(&message, json);
+            return Ok(());
+        }
+    };
+
+    let outcomes = match killer::renice_processes(&pids, nice) {
+        Ok(outcomes) => outcomes,
+        Err(message) => {
+            print_priority_refusal(&message, json);
+            return Ok(());
         }
-        Some(Commands::Enforce) => {
-            let default_profile = profiles::Profile {
+    };
+
+    let results: Vec<serde_json::Value> = outcomes
+        .into_iter()
+        .map(|(pid, outcome)| match outcome {
+            Ok(change) => {
+                killer::log_priority_action(Some(config), "RENICE", pid, &name, true, &format!("{} -> {}", change.old, change.new));
+                serde_json::json!({ "pid": pid, "name": name, "old": change.old, "new": change.new, "success": true })
+            }
+            Err(e) => {
+                killer::log_priority_action(Some(config), "RENICE", pid, &name, false, &format!("-> {}", nice));
+                serde_json::json!({ "pid": pid, "name": name, "success": false, "error": e })
+            }
+        })
+        .collect();
+
+    print_priority_results(&results, json, "nice")
+}
+
+fn ionice_processes(
+    name: Option<&str>,
+    pid: Option<u32>,
+    priority: killer::IoPriority,
+    json: bool,
+    config: &config::KernConfig,
+) -> Result<()> {
+    let (name, pids) = match priority_targets(name, pid, config, "ionice") {
+        Ok(targets) => targets,
+        Err(message) => {
+            print_priority_refusal(&message, json);
+            return Ok(());
+        }
+    };
+
+    let results: Vec<serde_json::Value> = pids
+        .iter()
+        .map(|&pid| match killer::ionice_process(pid, priority) {
+            Ok(change) => {
+                killer::log_priority_action(Some(config), "IONICE", pid, &name, true, &format!("{} -> {}", change.old, change.new));
+                serde_json::json!({
+                    "pid": pid, "name": name,
+                    "old": change.old.to_string(), "new": change.new.to_string(),
+                    "success": true,
+                })
+            }
+            Err(e) => {
+                killer::log_priority_action(Some(config), "IONICE", pid, &name, false, &format!("-> {}", priority));
+                serde_json::json!({ "pid": pid, "name": name, "success": false, "error": e })
+            }
+        })
+        .collect();
+
+    print_priority_results(&results, json, "I/O priority")
+}
+
+// The installed profile to enforce (see ProfileManager::startup_profile).
+// Built-in default limits only when no profiles are installed and none was asked for.
+fn enforce_profile(requested: Option<&str>, config: &config::KernConfig) -> Result<profiles::Profile> {
+    match profiles::ProfileManager::new(None) {
+        Ok(manager) => Ok(manager.startup_profile(requested, &config.default_profile)?.clone()),
+        Err(e) if requested.is_none() => {
+            eprintln!("⚠️  Warning: {} - enforcing built-in default limits", e);
+            Ok(profiles::Profile {
                 name: config.default_profile.clone(),
                 ..Default::default()
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn switch_mode(profile_name: &str, config: &config::KernConfig, daemon: Option<&dbus_client::Daemon>) -> Result<()> {
+    let mut manager = profiles::ProfileManager::new(None)?;
+    manager.load_state()?;
+
+    let Some(new_profile) = manager.get(profile_name).cloned() else {
+        eprintln!(
+            "❌ Profile '{}' not found. Available: {}",
+            profile_name,
+            manager.list_names().join(", ")
+        );
+        std::process::exit(2);
+    };
+
+    // A running daemon saves the choice and runs kill_on_activate itself, so
+    // the two never disagree and nothing is killed twice
+    let mut via_daemon = false;
+    if let Some(daemon) = daemon {
+        match daemon.set_mode(profile_name) {
+            Ok(_) => via_daemon = true,
+            Err(e) if dbus_client::is_method_error(&e) => {
+                return Err(anyhow::anyhow!("kern daemon refused to switch to '{}': {}", profile_name, e));
+            }
+            Err(e) => eprintln!("ℹ️  kern daemon did not answer ({}); switching locally", e),
+        }
+    }
+    if !via_daemon {
+        let old_profile = manager.current()?.clone();
+        manager.switch_to(profile_name)?;
+
+        // Run kill_on_activate with the same protections as the enforcer
+        let mut enforcer = enforcer::Enforcer::new(config.clone(), old_profile);
+        enforcer.switch_profile(new_profile.clone())?;
+    }
+
+    let through = if via_daemon { " through the running kern daemon" } else { "" };
+    println!("✅ Switched to '{}' ({}){}", profile_name, new_profile.name, through);
+    println!(
+        "   Limits: CPU {}%, RAM {}%, Temp {}°C",
+        new_profile.limits.max_cpu_percent,
+        new_profile.limits.max_ram_percent,
+        new_profile.limits.max_temp
+    );
+    if !new_profile.enforce {
+        println!("   Monitor-only: limits are reported, no processes are killed");
+    }
+
+    Ok(())
+}
+
+fn print_cpu_frequencies(states: &[throttle::CpuFreqState]) {
+    for state in states {
+        println!(
+            "  {}: max_freq={} governor={}",
+            state.cpu,
+            state.scaling_max_freq.as_deref().unwrap_or("-"),
+            state.scaling_governor.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+// Throttle for a few seconds and restore, so the emergency action can be verified by hand.
+// Ctrl+C cuts the wait short; the CPUs are restored however the test ends.
+fn run_throttle_test() -> Result<()> {
+    let cpu_throttle = throttle::CpuThrottle::new();
+    println!();
+    println!("CPU throttle test");
+
+    if let Err(e) = cpu_throttle.check_writable() {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+
+    println!("Current settings:");
+    print_cpu_frequencies(&cpu_throttle.snapshot());
+
+    catch_sigint()?;
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let cpus = cpu_throttle.throttle()?;
+    let throttled = (|| -> Result<()> {
+        println!("Throttled {} CPUs (saved to {}):", cpus, cpu_throttle.state_path().display());
+        print_cpu_frequencies(&cpu_throttle.snapshot());
+        io::stdout().flush()?;
+        sleep_unless_interrupted(Duration::from_secs(5));
+        Ok(())
+    })();
+
+    let restored = cpu_throttle.restore()?;
+    println!("✅ Restored {} CPUs:", restored);
+    print_cpu_frequencies(&cpu_throttle.snapshot());
+    throttled
+}
+
+// `kern thermal --alert`: notify until Ctrl+C whenever a zone runs hotter than `threshold`
+fn run_thermal_alert(config: &config::KernConfig, threshold: f64, interval: Duration) -> Result<()> {
+    let mut notifier = notify::NotificationManager::new(&config.notifications);
+    if !notifier.is_enabled() {
+        eprintln!("⚠️  Warning: notifications are disabled in kern.yaml; nothing will be shown");
+    }
+
+    println!();
+    println!(
+        "Alerting when any zone exceeds {:.1}°C (every {}, at most once per {}s per zone; Ctrl+C to stop)",
+        threshold,
+        humantime::format_duration(interval),
+        config.notifications.cooldown_per_category_secs
+    );
+    catch_sigint()?;
+    monitor::thermal_alert_loop(threshold, interval, &mut notifier, &INTERRUPTED)
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    log::set_warning_handler(|message| eprintln!("⚠️  Warning: {}", message));
+    
+    // Load configuration at startup
+    let mut config = config::KernConfig::load()?;
+
+    // Checked before the summary so it shows the sensor actually in use
+    if let Some(Commands::Enforce { temperature_sensor: Some(path), .. }) = &cli.command {
+        config.temperature_sensor_path = Some(path.clone());
+        config.check_temperature_sensor()?;
+    }
+    if let Some(Commands::Enforce { kill_count_limit: Some(limit), .. }) = &cli.command {
+        config.kill_count_limit = *limit;
+    }
+    if let Some(interval) = cli.interval_ms {
+        config.monitor_interval = interval;
+    }
+    if cli.normalize_cpu {
+        config.normalize_cpu = true;
+    }
+    if let Some(Commands::Enforce { interval_ms: Some(interval), .. }) = &cli.command {
+        config.monitor_interval = *interval;
+    }
+    if let Some(Commands::Enforce { report_interval: Some(secs), .. }) = &cli.command {
+        config.report_interval_secs = (*secs > 0).then_some(*secs);
+    }
+    if let Some(Commands::Enforce { alert_only: true, .. }) = &cli.command {
+        config.enforcement.mode = config::EnforcementMode::Alert;
+    }
+    if let Some(Commands::Kill { timeout: Some(secs), .. }) = &cli.command {
+        config.kill_graceful_timeout_secs = *secs;
+    }
+    if let Some(Commands::Enforce { all_users: true, .. } | Commands::Kill { all_users: true, .. }) = &cli.command {
+        config.enforcement.all_users = true;
+    }
+    
+    // --output-file only writes JSON
+    match &cli.command {
+        Some(Commands::Status { json: false, format, output_file: Some(_), .. }) if *format != StatusFormat::Json => {
+            return Err(anyhow::anyhow!("--output-file needs --json"));
+        }
+        Some(Commands::List { json: false, format, output_file: Some(_), .. }) if *format != ListFormat::Json => {
+            return Err(anyhow::anyhow!("--output-file needs --json"));
+        }
+        _ => {}
+    }
+
+    // Suppress config summary in JSON mode
+    let is_json_mode = match &cli.command {
+        Some(Commands::Status { json, compact, format, .. }) => *json || *compact || *format != StatusFormat::Text,
+        Some(Commands::List { json, format, .. }) => *json || *format != ListFormat::Table,
+        Some(Commands::Find { json, .. }) => *json,
+        Some(Commands::Explain { json, .. }) => *json,
+        Some(Commands::Enforce { json, .. }) => *json,
+        Some(Commands::Renice { json, .. }) => *json,
+        Some(Commands::Ionice { json, .. }) => *json,
+        Some(Commands::Config { action: ConfigCommand::CheckDefaults { json } }) => *json,
+        Some(Commands::Ctl { .. }) => true,
+        Some(Commands::Doctor { json, .. }) => *json,
+        _ => false,
+    };
+    
+    // JSON output is always quiet: nothing but the JSON itself on stdout
+    let quiet = cli.quiet || is_json_mode;
+    if !quiet {
+        for line in config.summary_lines() {
+            println!("{}", line);
+        }
+        println!();
+    }
+
+    // JSON never carries escape codes, whatever --color says
+    let painter = if is_json_mode { color::Painter::default() } else { color::Painter::from_env(cli.color) };
+    let highlight = Highlight::load(&config, painter);
+
+    if cli.monitor {
+        let output_file = match &cli.command {
+            Some(Commands::Status { output_file, .. }) => output_file.as_deref(),
+            _ => None,
+        };
+        return monitor_loop(&config, quiet, &highlight, output_file, cli.duration, !cli.no_sparkline);
+    }
+
+    match cli.command {
+        Some(Commands::Status { json, format, own: true, .. }) => match format {
+            StatusFormat::Text | StatusFormat::Json => {
+                let daemon = (!cli.no_daemon).then(dbus_client::Daemon::find).flatten();
+                print_own_status(json || format == StatusFormat::Json, quiet, config.normalize_cpu, daemon.as_ref())?
+            }
+            _ => return Err(anyhow::anyhow!("--self only supports text and --json output")),
+        },
+        Some(Commands::Status { json, format, profile: Some(profile), .. }) => match format {
+            StatusFormat::Text | StatusFormat::Json => preview_profile(&profile, json || format == StatusFormat::Json, &config)?,
+            _ => return Err(anyhow::anyhow!("--profile only supports text and --json output")),
+        },
+        Some(Commands::Status { json, format, diff: Some(path), .. }) => match format {
+            StatusFormat::Text | StatusFormat::Json => {
+                let daemon = (!cli.no_daemon).then(dbus_client::Daemon::find).flatten();
+                print_status_diff(&path, json || format == StatusFormat::Json, quiet, &config, &highlight, daemon.as_ref())?
+            }
+            _ => return Err(anyhow::anyhow!("--diff only supports text and --json output")),
+        },
+        Some(Commands::Status {
+            json, compact, format, separator, full_page, sort_reverse, no_processes, output_file, ..
+        }) => match format {
+            _ if separator.is_some() && !compact && format != StatusFormat::Line => {
+                return Err(anyhow::anyhow!("--separator only applies to --format line"))
+            }
+            _ if compact || format == StatusFormat::Line => {
+                if full_page {
+                    return Err(anyhow::anyhow!("--full-page only applies to --format html"));
+                }
+                let daemon = (!cli.no_daemon && !no_processes).then(dbus_client::Daemon::find).flatten();
+                let separator = separator.as_deref().unwrap_or(output::LINE_SEPARATOR);
+                print_status_line(&config, separator, sort_reverse, no_processes, daemon.as_ref())?
+            }
+            StatusFormat::Html if no_processes => return Err(anyhow::anyhow!("--no-processes doesn't apply to --format html")),
+            StatusFormat::Html => print_status_html(&config, full_page)?,
+            _ if full_page => return Err(anyhow::anyhow!("--full-page only applies to --format html")),
+            _ if no_processes => print_global_status(json || format == StatusFormat::Json, quiet, &config, &highlight)?,
+            _ => {
+                let daemon = (!cli.no_daemon).then(dbus_client::Daemon::find).flatten();
+                let target = if json || format == StatusFormat::Json {
+                    StatusTarget::Json(output_file.as_deref())
+                } else {
+                    StatusTarget::Text(None)
+                };
+                print_status(target, quiet, sort_reverse, &config, &highlight, daemon.as_ref())?
+            }
+        },
+        Some(Commands::List {
+            json, format, count, sort, sort_reverse, filter_name, show_nice, show_env, show_cgroup, filter_cgroup, wide,
+            kernel_threads, show_threads, group, top_n_per_user, watch, output_file,
+        }) => {
+            let json = json || format == ListFormat::Json;
+            let ndjson = format == ListFormat::Ndjson;
+            let normalize_cpu = config.normalize_cpu;
+            let opts = ListOptions {
+                json, count, sort, sort_reverse, filter_name, group, top_n_per_user, kernel_threads, show_threads, show_nice,
+                show_env, show_cgroup, filter_cgroup, wide, normalize_cpu, quiet, highlight, output_file,
+            };
+            match watch {
+                Some(_) if ndjson => return Err(anyhow::anyhow!("--format ndjson doesn't apply to --watch (use --json)")),
+                Some(interval) => watch_list_loop(&config, opts, interval)?,
+                None if ndjson => print_list_ndjson(&opts, config.memory_metric)?,
+                None => print_list(&opts, config.memory_metric)?,
+            }
+        }
+        Some(Commands::Find { query, json }) => find_processes(&query, json, quiet, &config)?,
+        Some(Commands::Explain { name, json }) => explain_process(&name, json, &config)?,
+        Some(Commands::Kill { name, confirm_each, via_daemon, oldest, newest, heaviest, older_than, yes, .. }) => {
+            let daemon = (via_daemon && !cli.no_daemon).then(dbus_client::Daemon::find).flatten();
+            if via_daemon && !cli.no_daemon && daemon.is_none() {
+                eprintln!("ℹ️  No kern daemon running; killing locally");
+            }
+            if config.enforcement.all_users && daemon.is_none() && !killer::can_kill_any_user() {
+                eprintln!("⚠️  --all-users without root or CAP_KILL: other users' processes can't be killed");
+            }
+            let selector = match (oldest, newest, heaviest) {
+                (true, _, _) => Some(killer::KillSelector::Oldest),
+                (_, true, _) => Some(killer::KillSelector::Newest),
+                (_, _, true) => Some(killer::KillSelector::Heaviest),
+                _ => None,
+            };
+            let filter = KillFilter { selector, older_than };
+            let confirmation = match (confirm_each, yes) {
+                (true, _) => KillConfirmation::EachProcess,
+                (_, true) => KillConfirmation::Skip,
+                _ => KillConfirmation::Threshold,
+            };
+            kill_process_by_name(&name, filter, confirmation, &config, &mut StdinReader, daemon.as_ref())?
+        }
+        Some(Commands::Mode { profile }) => {
+            let daemon = (!cli.no_daemon).then(dbus_client::Daemon::find).flatten();
+            switch_mode(&profile, &config, daemon.as_ref())?
+        }
+        Some(Commands::Renice { name, value, pid, nice, json }) => {
+            // clap guarantees exactly one of the positional value and --nice
+            let nice = value.or(nice).unwrap_or_default();
+            renice_processes(name.as_deref(), pid, nice, json, &config)?
+        }
+        Some(Commands::Ionice { name, pid, class, level, json }) => {
+            let priority = killer::IoPriority { class, level };
+            ionice_processes(name.as_deref(), pid, priority, json, &config)?
+        }
+        Some(Commands::Enforce { json, log_file, log_level, profile, profile_lock, simulate, duration, .. }) => {
+            let initial_profile = enforce_profile(profile.as_deref(), &config)?;
+            let logger = match log_file {
+                Some(path) => log::Logger::file(&path, log_level, log::DEFAULT_MAX_LOG_BYTES)?,
+                None => log::Logger::stderr(log_level),
             };
-            enforcer::run_enforcer_loop(config, default_profile)?;
+            // JSON mode: one event per line on stdout, for log pipelines
+            let observer: Option<enforcer::EventObserver> = if json {
+                Some(std::sync::Arc::new(|event: &enforcer::EnforcerEvent| println!("{}", event.to_json())))
+            } else {
+                None
+            };
+            let idle_switcher = profiles::ProfileManager::new(None)
+                .map(|manager| idle::IdleSwitcher::from_profiles(&manager))
+                .unwrap_or_default();
+            catch_shutdown_signals()?;
+            let limit = enforcer::RunLimit { duration, stop: Some(&INTERRUPTED) };
+            let options = enforcer::RunOptions { profile_locked: profile_lock, simulate };
+            enforcer::run_enforcer_loop(config, initial_profile, options, idle_switcher, observer, logger.shared(), limit)?;
+        }
+        Some(Commands::Doctor { count, json }) => run_doctor(&config, count, json)?,
+        Some(Commands::Thermal { throttle_test, alert, interval_secs }) => {
+            for line in monitor::describe_thermal_zones() {
+                println!("{}", line);
+            }
+            if throttle_test {
+                run_throttle_test()?;
+            }
+            if let Some(threshold) = alert {
+                run_thermal_alert(&config, threshold, Duration::from_secs(interval_secs))?;
+            }
         }
-        Some(Commands::Thermal) => monitor::debug_thermal_zones()?,
-        Some(Commands::Dbus) => {
+        Some(Commands::Dbus { enforce, status_file }) => {
             let profile_manager = profiles::ProfileManager::new(None)?;
             tokio::runtime::Runtime::new()?
-                .block_on(dbus_server::start_dbus_server(profile_manager, config))?;
+                .block_on(dbus_server::start_dbus_server(profile_manager, config, enforce, status_file))?;
+        }
+        Some(Commands::Config { action: ConfigCommand::CheckDefaults { json } }) => {
+            print_config_diff(&config, json)?
+        }
+        Some(Commands::Ctl { socket, command }) => run_ctl(&config, socket, command)?,
+        Some(Commands::Profile { action: ProfileCommand::Duplicate { source, new_name } }) => {
+            let mut manager = profiles::ProfileManager::new(None)?;
+            let path = manager.duplicate(&source, &new_name)?;
+            println!("✅ Created profile '{}' from '{}': {}", new_name, source, path.display());
         }
         None => {
             Cli::command().print_help()?;
@@ -251,3 +2769,296 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScriptedReader(std::collections::VecDeque<&'static str>);
+
+    impl ConfirmationReader for ScriptedReader {
+        fn read_answer(&mut self, _prompt: &str) -> Result<Option<String>> {
+            Ok(self.0.pop_front().map(str::to_string))
+        }
+    }
+
+    fn fake_processes(count: u32) -> Vec<monitor::ProcessInfo> {
+        // Synthetic PIDs: the kill closure below never signals anything
+        (0..count)
+            .map(|i| monitor::ProcessInfo {
+                pid: 10_000_001 + i,
+                name: "python".to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn run(answers: &[&'static str], count: u32) -> (EachSummary, Vec<u32>) {
+        let mut reader = ScriptedReader(answers.iter().copied().collect());
+        let mut killed = Vec::new();
+        let summary = confirm_each(&fake_processes(count), &mut reader, |p| {
+            killed.push(p.pid);
+            true
+        })
+        .unwrap();
+        (summary, killed)
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("20"), Ok(Some(20)));
+        assert_eq!(parse_count("all"), Ok(None));
+        assert_eq!(parse_count("ALL"), Ok(None));
+        assert_eq!(parse_count("0"), Ok(None));
+        assert_eq!(parse_count("100000"), Ok(Some(100_000)));
+        assert!(parse_count("100001").is_err());
+        assert!(parse_count("-3").is_err());
+        assert!(parse_count("some").is_err());
+    }
+
+    #[test]
+    fn test_parse_each_answer() {
+        assert_eq!(parse_each_answer("Y"), Some(EachAnswer::Yes));
+        assert_eq!(parse_each_answer(" no\n"), Some(EachAnswer::No));
+        assert_eq!(parse_each_answer("all"), Some(EachAnswer::All));
+        assert_eq!(parse_each_answer("q"), Some(EachAnswer::Quit));
+        assert_eq!(parse_each_answer("maybe"), None);
+    }
+
+    #[test]
+    fn test_confirm_each_yes_no_and_all() {
+        let (summary, killed) = run(&["y", "n", "what", "a"], 5);
+        assert_eq!(killed, vec![10_000_001, 10_000_003, 10_000_004, 10_000_005]);
+        assert_eq!(summary, EachSummary { killed: 4, skipped: 1, failed: 0 });
+    }
+
+    #[test]
+    fn test_confirm_each_quit_and_end_of_input_skip_the_rest() {
+        let (summary, killed) = run(&["y", "q"], 4);
+        assert_eq!(killed, vec![10_000_001]);
+        assert_eq!(summary, EachSummary { killed: 1, skipped: 3, failed: 0 });
+
+        let (summary, killed) = run(&[], 2);
+        assert!(killed.is_empty());
+        assert_eq!(summary, EachSummary { killed: 0, skipped: 2, failed: 0 });
+    }
+
+    #[test]
+    fn test_confirm_each_counts_failed_kills() {
+        let mut reader = ScriptedReader(["y", "y"].into_iter().collect());
+        let summary = confirm_each(&fake_processes(2), &mut reader, |p| p.pid == 10_000_001).unwrap();
+        assert_eq!(summary, EachSummary { killed: 1, skipped: 0, failed: 1 });
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("all", 3), Some(vec![0, 1, 2]));
+        assert_eq!(parse_selection("Y", 2), Some(vec![0, 1]));
+        assert_eq!(parse_selection("none", 3), Some(vec![]));
+        assert_eq!(parse_selection("1,3", 3), Some(vec![0, 2]));
+        assert_eq!(parse_selection(" 3, 1 1 ", 3), Some(vec![0, 2]));
+        assert_eq!(parse_selection("2-4,7", 7), Some(vec![1, 2, 3, 6]));
+        assert_eq!(parse_selection("0", 3), None);
+        assert_eq!(parse_selection("4", 3), None);
+        assert_eq!(parse_selection("3-2", 3), None);
+        assert_eq!(parse_selection("1,x", 3), None);
+        assert_eq!(parse_selection("", 3), None);
+    }
+
+    #[test]
+    fn test_confirm_selection_reprompts_and_cancels() {
+        let mut reader = ScriptedReader(["", "9", "1,3"].into_iter().collect());
+        assert_eq!(confirm_selection(3, &mut reader).unwrap(), Some(vec![0, 2]));
+
+        let mut reader = ScriptedReader(["maybe"].into_iter().collect());
+        assert_eq!(confirm_selection(3, &mut reader).unwrap(), None);
+    }
+
+    fn list_options(sort: monitor::ProcessSort, sort_reverse: bool) -> ListOptions {
+        ListOptions {
+            json: false,
+            count: Some(20),
+            sort,
+            sort_reverse,
+            filter_name: None,
+            group: false,
+            top_n_per_user: None,
+            kernel_threads: false,
+            show_threads: false,
+            show_nice: false,
+            show_env: None,
+            show_cgroup: false,
+            filter_cgroup: None,
+            wide: false,
+            normalize_cpu: false,
+            quiet: true,
+            highlight: Highlight::load(&config::KernConfig::default(), color::Painter::default()),
+            output_file: None,
+        }
+    }
+
+    fn sample_processes() -> Vec<monitor::ProcessInfo> {
+        let process = |pid, name: &str, memory_gb, cpu_percentage| monitor::ProcessInfo {
+            pid,
+            name: name.to_string(),
+            memory_gb,
+            cpu_percentage,
+            ..Default::default()
+        };
+        vec![
+            process(10_000_003, "firefox", 1.5, 2.0),
+            process(10_000_001, "code", 0.8, 40.0),
+            process(10_000_002, "bash", 0.2, 9.0),
+        ]
+    }
+
+    fn row_pids(rows: ListRows) -> Vec<u32> {
+        match rows {
+            ListRows::Processes(processes) => processes.iter().map(|p| p.pid).collect(),
+            ListRows::Groups(groups) => groups.iter().map(|g| g.pids[0]).collect(),
+            ListRows::Users(users) => users.values().flatten().map(|p| p.pid).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sort_reverse_flips_every_sort_order() {
+        use monitor::ProcessSort::*;
+        let cases = [
+            (Memory, vec![10_000_003, 10_000_001, 10_000_002]),
+            (Cpu, vec![10_000_001, 10_000_002, 10_000_003]),
+            (Pid, vec![10_000_001, 10_000_002, 10_000_003]),
+            (Name, vec![10_000_002, 10_000_001, 10_000_003]),
+        ];
+        for (sort, forward) in cases {
+            let mut backward = forward.clone();
+            backward.reverse();
+            assert_eq!(row_pids(arrange_rows(sample_processes(), &list_options(sort, false))), forward, "{:?}", sort);
+            assert_eq!(row_pids(arrange_rows(sample_processes(), &list_options(sort, true))), backward, "{:?}", sort);
+
+            let grouped = ListOptions { group: true, ..list_options(sort, true) };
+            assert_eq!(row_pids(arrange_rows(sample_processes(), &grouped)), backward, "{:?} grouped", sort);
+        }
+    }
+
+    #[test]
+    fn test_filter_cgroup_and_column() {
+        let mut processes = sample_processes();
+        processes[0].cgroup_path = Some("/system.slice/docker-4f1e2a.scope".to_string());
+        processes[1].cgroup_path = Some("/user.slice/user-1000.slice/session-2.scope".to_string());
+        let opts = ListOptions {
+            filter_cgroup: Some("docker".to_string()),
+            show_cgroup: true,
+            ..list_options(monitor::ProcessSort::Memory, false)
+        };
+        let rows = arrange_rows(processes.clone(), &opts);
+        let lines = list_table(&rows, &opts);
+        assert!(lines[0].contains("CGROUP"));
+        assert!(lines.last().unwrap().contains("/system.slice/docker-4f1e2a.scope firefox"));
+        assert_eq!(row_pids(rows), vec![10_000_003]);
+
+        // Unreadable cgroups never match a filter, and show as "-"
+        let opts = ListOptions { filter_cgroup: None, ..opts };
+        let lines = list_table(&arrange_rows(processes, &opts), &opts);
+        assert!(lines.last().unwrap().contains(" -"));
+        assert!(lines.last().unwrap().ends_with("bash"));
+    }
+
+    #[test]
+    fn test_show_threads_rows() {
+        let mut processes = sample_processes();
+        for p in &mut processes {
+            p.tgid = p.pid;
+        }
+        // A thread of code, with its process's memory
+        processes.push(monitor::ProcessInfo {
+            pid: 10_000_005,
+            tgid: 10_000_001,
+            name: "code-worker".to_string(),
+            memory_gb: 0.8,
+            ..Default::default()
+        });
+        let opts = ListOptions { show_threads: true, ..list_options(monitor::ProcessSort::Cpu, false) };
+        let rows = arrange_rows(processes, &opts);
+        let lines = list_table(&rows, &opts);
+        assert!(lines[0].starts_with("PID      TGID     MEM(GB)"));
+        assert!(lines[1].ends_with(" code"));
+        assert!(lines[2].starts_with("10000005 10000001 "));
+        assert!(lines[2].ends_with("[T] code-worker"));
+        assert_eq!(row_pids(rows), vec![10_000_001, 10_000_005, 10_000_002, 10_000_003]);
+    }
+
+    #[test]
+    fn test_top_n_per_user_rows() {
+        let mut processes = sample_processes();
+        for p in &mut processes {
+            p.uid = Some(4_000_000_001);
+        }
+        processes[0].uid = Some(0);
+        let opts = ListOptions { top_n_per_user: Some(1), ..list_options(monitor::ProcessSort::Memory, false) };
+        let rows = arrange_rows(processes, &opts);
+
+        let lines = list_table(&rows, &opts);
+        assert!(lines[0].starts_with("PID"));
+        assert_eq!(lines[1], "── root (1.50 GB) ──");
+        assert!(lines[2].ends_with("firefox"));
+        assert_eq!(lines[3], "── uid 4000000001 (0.80 GB) ──"); // bash is cut
+        assert!(lines[4].ends_with("code"));
+        assert_eq!(lines.len(), 5);
+
+        let json = list_json(&rows, None, false);
+        assert_eq!(json[0]["user"], "root");
+        assert_eq!(json[1]["memory_gb"], 0.8);
+        assert_eq!(json[1]["processes"][0]["name"], "code");
+    }
+
+    #[test]
+    fn test_process_json_includes_owner() {
+        let mut processes = sample_processes();
+        processes[0].uid = Some(0);
+        let rows = list_json(&ListRows::Processes(processes), None, false);
+        assert_eq!(rows[0]["uid"], 0);
+        assert_eq!(rows[0]["user"], "root");
+        // Unknown owner
+        assert!(rows[1]["uid"].is_null() && rows[1]["user"].is_null());
+    }
+
+    #[test]
+    fn test_ndjson_writer_stops_at_count() {
+        let rows = list_json(&ListRows::Processes(sample_processes()), None, false);
+        let mut writer = NdjsonWriter::new(Vec::new(), Some(2));
+        assert!(writer.write(&rows[0]).is_continue());
+        assert!(writer.write(&rows[1]).is_break());
+        assert!(writer.write(&rows[2]).is_break());
+
+        let output = String::from_utf8(writer.out).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines, rows[..2]);
+
+        let mut unlimited = NdjsonWriter::new(Vec::new(), None);
+        assert!(rows.iter().all(|row| unlimited.write(row).is_continue()));
+        assert!(unlimited.finish().is_ok());
+    }
+
+    #[test]
+    fn test_sort_reverse_applies_before_count() {
+        let opts = ListOptions { count: Some(1), ..list_options(monitor::ProcessSort::Memory, true) };
+        let rows = arrange_rows(sample_processes(), &opts);
+        assert_eq!(row_pids(rows), vec![10_000_002]);
+
+        let rows = arrange_rows(sample_processes(), &opts);
+        let json = list_json(&rows, None, false);
+        assert_eq!(json.len(), 1);
+        assert_eq!(json[0]["name"], "bash");
+    }
+
+    #[test]
+    fn test_kern_role_from_command_line() {
+        let role = |args: &[&str]| kern_role(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>());
+        assert_eq!(role(&["kern", "dbus"]), "daemon");
+        assert_eq!(role(&["kern", "dbus", "--enforce"]), "daemon + enforcer");
+        assert_eq!(role(&["/usr/bin/kern", "enforce", "--profile", "coding"]), "enforcer");
+        assert_eq!(role(&["kern", "-q", "--monitor"]), "monitor");
+        assert_eq!(role(&["kern", "kill", "enforce"]), "command");
+        assert_eq!(role(&["kern", "status"]), "command");
+    }
+}
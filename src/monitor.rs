@@ -1,139 +1,1012 @@
-use anyhow::Result;
-use sysinfo::System;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{
+    CpuRefreshKind, MemoryRefreshKind, Pid, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, ThreadKind,
+    UpdateKind,
+};
 
-#[derive(Debug, Clone)]
+use crate::cgroups::{CgroupLimiter, CgroupLimits};
+use crate::config::{MemoryMetric, TemperatureStrategy};
+use crate::notify::NotificationManager;
+
+#[derive(Debug, Clone, Default)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
     pub memory_gb: f64,
     pub cpu_percentage: f64,
+    pub start_time: u64, // Seconds since the Unix epoch
+    pub state: char,     // R/S/D/Z/T... from /proc/<pid>/stat ('?' if unreadable)
+    pub ppid: u32,
+    pub kernel_thread: bool,
+    pub nice: Option<i32>, // -20 (highest priority) to 19
+    pub io_read_mbps: f64,  // Disk reads in MB/s, set by an IoSampler (0 if /proc/<pid>/io is unreadable)
+    pub io_write_mbps: f64, // Disk writes in MB/s, likewise
+    pub cgroup_path: Option<String>, // e.g. "/system.slice/docker-<id>.scope" (None if /proc/<pid>/cgroup is unreadable)
+    pub uid: Option<u32>,            // Real UID of the owner (None if unknown)
+    pub tgid: u32,                   // Thread group (process) it belongs to: its own PID unless it is a thread
+}
+
+impl ProcessInfo {
+    /// Exited but not yet reaped by its parent: it can't be killed, only reaped
+    pub fn is_zombie(&self) -> bool {
+        self.state == 'Z'
+    }
+
+    /// Disk reads plus writes, in MB/s
+    pub fn io_mbps(&self) -> f64 {
+        self.io_read_mbps + self.io_write_mbps
+    }
+
+    /// A thread of process `tgid` rather than a process (only listed with `IncludeThreads::Yes`)
+    pub fn is_thread(&self) -> bool {
+        self.tgid != 0 && self.tgid != self.pid
+    }
+}
+
+/// Whether process listings also include each process's threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeThreads {
+    No,
+    Yes,
+}
+
+/// Bytes a process has read from and written to storage since it started
+/// (read_bytes / write_bytes in /proc/<pid>/io)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoCounters {
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// Read /proc/<pid>/io; None for other users' processes unless running as root
+pub fn read_io_counters(pid: u32) -> Option<IoCounters> {
+    let contents = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    parse_io_counters(&contents)
 }
 
-#[derive(Debug)]
+fn parse_io_counters(contents: &str) -> Option<IoCounters> {
+    let field = |name: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    Some(IoCounters { read_bytes: field("read_bytes")?, write_bytes: field("write_bytes")? })
+}
+
+/// Per-process disk I/O rates. /proc/<pid>/io only holds running totals, so a
+/// rate needs two reads spaced in time: keep one sampler across the cycles of
+/// a loop (the enforcer, `kern list --watch`) and update it every cycle.
+#[derive(Debug, Clone, Default)]
+pub struct IoSampler {
+    previous: HashMap<(u32, u64), IoCounters>, // (pid, start_time) -> totals at the last update
+    sampled_at: Option<Instant>,
+}
+
+impl IoSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `io_read_mbps`/`io_write_mbps` of `processes` from what each read
+    /// and wrote since the previous update. Processes seen for the first time,
+    /// and every process on the first update, get 0.
+    pub fn update(&mut self, processes: &mut [ProcessInfo]) {
+        self.update_with(processes, Instant::now(), read_io_counters);
+    }
+
+    /// True once an update has run, so the next one yields rates
+    pub fn has_baseline(&self) -> bool {
+        self.sampled_at.is_some()
+    }
+
+    fn update_with(&mut self, processes: &mut [ProcessInfo], now: Instant, read: impl Fn(u32) -> Option<IoCounters>) {
+        let elapsed = self.sampled_at.map(|at| now.duration_since(at).as_secs_f64()).unwrap_or(0.0);
+        let mut current = HashMap::with_capacity(processes.len());
+        for process in processes.iter_mut() {
+            let key = (process.pid, process.start_time);
+            let counters = read(process.pid);
+            let rate = |bytes: u64, before: u64| bytes.saturating_sub(before) as f64 / elapsed / 1_048_576.0;
+            match (counters, self.previous.get(&key)) {
+                (Some(now), Some(before)) if elapsed > 0.0 => {
+                    process.io_read_mbps = finite_or_zero(rate(now.read_bytes, before.read_bytes));
+                    process.io_write_mbps = finite_or_zero(rate(now.write_bytes, before.write_bytes));
+                }
+                _ => {
+                    process.io_read_mbps = 0.0;
+                    process.io_write_mbps = 0.0;
+                }
+            }
+            if let Some(counters) = counters {
+                current.insert(key, counters);
+            }
+        }
+        self.previous = current;
+        self.sampled_at = Some(now);
+    }
+}
+
+// PF_KTHREAD from include/linux/sched.h
+const PF_KTHREAD: u64 = 0x0020_0000;
+
+/// The fields kern needs from /proc/<pid>/stat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcStat {
+    pub state: char,
+    pub ppid: u32,
+    pub flags: u64,
+    pub nice: Option<i32>,
+}
+
+impl ProcStat {
+    pub fn is_kernel_thread(&self) -> bool {
+        self.flags & PF_KTHREAD != 0
+    }
+}
+
+/// Read /proc/<pid>/stat for a live process
+pub fn read_proc_stat(pid: u32) -> Option<ProcStat> {
+    read_proc_stat_in(Path::new("/proc"), pid)
+}
+
+fn read_proc_stat_in(proc_root: &Path, pid: u32) -> Option<ProcStat> {
+    let contents = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    parse_proc_stat(&contents)
+}
+
+// "pid (comm) state ppid pgrp session tty_nr tpgid flags ... nice (field 19) ...";
+// comm may contain spaces and ')'
+fn parse_proc_stat(contents: &str) -> Option<ProcStat> {
+    let (_, rest) = contents.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    Some(ProcStat {
+        state: fields.first()?.chars().next()?,
+        ppid: fields.get(1)?.parse().ok()?,
+        flags: fields.get(6)?.parse().ok()?,
+        nice: fields.get(16).and_then(|nice| nice.parse().ok()),
+    })
+}
+
+/// Order for "heaviest first" sorts. NaN (e.g. a process sampled as it exited)
+/// sorts last rather than panicking or outranking real values.
+pub fn descending(a: f64, b: f64) -> std::cmp::Ordering {
+    let key = |value: f64| if value.is_nan() { f64::NEG_INFINITY } else { value };
+    key(b).total_cmp(&key(a))
+}
+
+/// Where `total_memory_gb` (and so `memory_percentage`) comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemorySource {
+    #[default]
+    Host,   // The machine's RAM as sysinfo reports it
+    Cgroup, // A cgroup memory limit tighter than the machine (container, MemoryMax=)
+}
+
+impl MemorySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemorySource::Host => "host",
+            MemorySource::Cgroup => "cgroup",
+        }
+    }
+}
+
+impl std::fmt::Display for MemorySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct SystemStats {
     pub cpu_usage: f64,
+    pub cpu_count: usize,
     pub total_memory_gb: f64,
     pub used_memory_gb: f64,
     pub memory_percentage: f64,
-    pub temperature: f64,
+    pub swap_percentage: f64, // Used swap as a share of all swap; 0 without swap
+    pub temperature: f64, // Same as temperature_max, kept for existing consumers
+    pub temperature_avg: f64,
+    pub temperature_max: f64,
     pub top_processes: Vec<ProcessInfo>,
+    pub cgroup: Option<CgroupLimits>, // Set when kern's cgroup limits replaced the machine totals
+    pub cpu_pressure: Option<f64>, // PSI "some" avg10 (% of time stalled), None without PSI
+    pub mem_pressure: Option<f64>,
+    pub uptime_secs: u64,
+    pub boot_time: u64, // Unix timestamp, derived from uptime_secs
+    pub footprint: Option<ProcessFootprint>, // kern's own process (the daemon's, when read over DBus)
 }
 
-fn get_process_memory_from_proc(pid: u32) -> Option<u64> {
-    let status_path = format!("/proc/{}/status", pid);
-    let contents = std::fs::read_to_string(status_path).ok()?;
-    
-    for line in contents.lines() {
-        if line.starts_with("VmRSS:") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                if let Ok(kb) = parts[1].parse::<u64>() {
-                    return Some(kb * 1024);
+impl SystemStats {
+    /// `top_processes` reordered by CPU usage, heaviest first (ties keep memory order)
+    pub fn top_cpu_processes(&self) -> Vec<&ProcessInfo> {
+        let mut processes: Vec<&ProcessInfo> = self.top_processes.iter().collect();
+        processes.sort_by(|a, b| descending(a.cpu_percentage, b.cpu_percentage));
+        processes
+    }
+
+    /// Set `uptime_secs` and the `boot_time` it implies
+    pub fn set_uptime(&mut self, uptime_secs: u64) {
+        self.uptime_secs = uptime_secs;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.boot_time = now.saturating_sub(uptime_secs);
+    }
+
+    /// Turn per-process CPU% (the process list and kern's own footprint) from
+    /// per core into a share of all CPUs, see [`normalize_process_cpu`]
+    pub fn normalize_process_cpu(&mut self) {
+        normalize_process_cpu(&mut self.top_processes, self.cpu_count);
+        if let Some(footprint) = self.footprint.as_mut() {
+            footprint.cpu_percent /= self.cpu_count.max(1) as f64;
+        }
+    }
+
+    /// Global CPU usage as a number of fully busy cores
+    pub fn cpu_cores_used(&self) -> f64 {
+        self.cpu_usage / 100.0 * self.cpu_capacity()
+    }
+
+    /// Whether memory figures are relative to the machine or a cgroup limit
+    pub fn memory_source(&self) -> MemorySource {
+        match self.cgroup.as_ref().and_then(|limits| limits.memory_max_bytes) {
+            Some(_) => MemorySource::Cgroup,
+            None => MemorySource::Host,
+        }
+    }
+
+    /// CPUs kern may use: the cgroup's cpu.max quota when limited, else every CPU
+    pub fn cpu_capacity(&self) -> f64 {
+        self.cgroup
+            .as_ref()
+            .and_then(|limits| limits.cpu_max_cores)
+            .unwrap_or(self.cpu_count as f64)
+    }
+
+    /// Rebase memory and CPU usage on the cgroup's limits where they are tighter
+    /// than the machine, so percentages mean "of what kern may use".
+    /// `cpu_time` is the cgroup's CPU time over a wall-clock sample window.
+    pub fn apply_cgroup_limits(&mut self, mut limits: CgroupLimits, cpu_time: Option<(Duration, Duration)>) {
+        let host_memory = self.total_memory_gb;
+        limits.memory_max_bytes = limits
+            .memory_max_bytes
+            .filter(|max| host_memory <= 0.0 || (*max as f64 / 1_073_741_824.0) < host_memory);
+        limits.cpu_max_cores = limits.cpu_max_cores.filter(|cores| *cores < self.cpu_count as f64);
+
+        if let Some(max) = limits.memory_max_bytes {
+            self.total_memory_gb = max as f64 / 1_073_741_824.0;
+            if let Some(current) = limits.memory_current_bytes {
+                self.used_memory_gb = current as f64 / 1_073_741_824.0;
+            }
+            self.memory_percentage = memory_percentage(self.used_memory_gb, self.total_memory_gb);
+        }
+
+        if let (Some(cores), Some((busy, wall))) = (limits.cpu_max_cores, cpu_time) {
+            let percent = busy.as_secs_f64() / (wall.as_secs_f64() * cores) * 100.0;
+            self.cpu_usage = finite_or_zero(percent).clamp(0.0, 100.0);
+        }
+
+        if limits.memory_max_bytes.is_some() || limits.cpu_max_cores.is_some() {
+            self.cgroup = Some(limits);
+        }
+    }
+
+    /// Temperature according to the configured aggregation strategy
+    pub fn effective_temperature(&self, strategy: TemperatureStrategy) -> f64 {
+        match strategy {
+            TemperatureStrategy::Max => self.temperature_max,
+            TemperatureStrategy::Avg => self.temperature_avg,
+        }
+    }
+}
+
+// Per-process memory in bytes using the configured metric. Each collection
+// reads every process once, and that one sample feeds status, list and the
+// enforcer's victim ordering for the whole cycle.
+fn read_process_memory(proc_root: &Path, pid: u32, metric: MemoryMetric) -> Option<u64> {
+    let pid_dir = proc_root.join(pid.to_string());
+    let rss = || {
+        let contents = std::fs::read_to_string(pid_dir.join("status")).ok()?;
+        parse_status_rss(&contents)
+    };
+
+    match metric {
+        MemoryMetric::Rss => rss(),
+        // smaps_rollup needs the same user or root; fall back to RSS otherwise
+        MemoryMetric::Pss => std::fs::read_to_string(pid_dir.join("smaps_rollup"))
+            .ok()
+            .and_then(|contents| parse_smaps_rollup_pss(&contents))
+            .or_else(rss),
+    }
+}
+
+// "VmRSS:    123456 kB" from /proc/<pid>/status
+fn parse_status_rss(contents: &str) -> Option<u64> {
+    parse_kb_field(contents, "VmRSS:")
+}
+
+// "Pss:    123456 kB" from /proc/<pid>/smaps_rollup
+fn parse_smaps_rollup_pss(contents: &str) -> Option<u64> {
+    parse_kb_field(contents, "Pss:")
+}
+
+fn parse_kb_field(contents: &str, field: &str) -> Option<u64> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+// The thread group (process) of `pid`, from "Tgid:" in /proc/<pid>/status;
+// it differs from `pid` for threads
+fn read_tgid(proc_root: &Path, pid: u32) -> Option<u32> {
+    let contents = std::fs::read_to_string(proc_root.join(pid.to_string()).join("status")).ok()?;
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("Tgid:"))
+        .and_then(|rest| rest.trim().parse::<u32>().ok())
+}
+
+/// Wall-clock budget for one process's own /proc reads; a read taking longer is stuck
+const PROC_READ_BUDGET: Duration = Duration::from_secs(2);
+
+// Results the reader thread may get ahead of a slow `visit` by
+const READ_AHEAD: usize = 64;
+
+// Processes (PID, start time) whose /proc reads blew the budget. Later collections
+// don't read them again, so a wedged process can't strand a reader thread every cycle.
+static STUCK_PROCESSES: Mutex<Vec<(u32, u64)>> = Mutex::new(Vec::new());
+
+// What a process's own /proc files add to sysinfo's view of it
+#[derive(Debug, Clone)]
+struct ProcDetails {
+    tgid: Option<u32>,
+    memory_bytes: Option<u64>,
+    stat: Option<ProcStat>,
+    cgroup_path: Option<String>,
+}
+
+fn read_proc_details(pid: u32, metric: MemoryMetric) -> ProcDetails {
+    ProcDetails {
+        tgid: read_tgid(Path::new("/proc"), pid),
+        memory_bytes: read_process_memory(Path::new("/proc"), pid, metric),
+        stat: read_proc_stat(pid),
+        cgroup_path: read_process_cgroup(pid),
+    }
+}
+
+// Run `read` for each PID, in order, on a helper thread and hand each result to
+// `visit` as soon as it arrives, so at most READ_AHEAD results are held. Once
+// one PID's read has taken `budget` (timed from when that read started, so a
+// slow `visit` never counts), it and the remaining PIDs get None and that PID
+// is returned. A stuck reader is left behind and exits if its read ever
+// returns. Stops early when `visit` breaks.
+fn stream_with_budget<T, F>(
+    pids: Vec<u32>,
+    budget: Duration,
+    read: F,
+    mut visit: impl FnMut(u32, Option<T>) -> ControlFlow<()>,
+) -> Option<u32>
+where
+    T: Send + 'static,
+    F: Fn(u32) -> T + Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::sync_channel(READ_AHEAD);
+    let order = pids.clone();
+    // Index (in `pids`) of the read in progress, and when it started
+    let reading: Arc<Mutex<Option<(usize, Instant)>>> = Arc::new(Mutex::new(None));
+    let progress = Arc::clone(&reading);
+    std::thread::spawn(move || {
+        for (index, pid) in pids.into_iter().enumerate() {
+            *progress.lock().unwrap_or_else(|e| e.into_inner()) = Some((index, Instant::now()));
+            let value = read(pid);
+            if tx.send((pid, value)).is_err() {
+                break; // The caller gave up waiting
+            }
+        }
+    });
+    // When the read of result `index` started, if it is the one in progress
+    let started = |index: usize| match *reading.lock().unwrap_or_else(|e| e.into_inner()) {
+        Some((reading, started)) if reading == index => Some(started),
+        _ => None,
+    };
+
+    // Results arrive in order, so everything before `next` has been visited
+    let mut next = 0;
+    loop {
+        // Result `next` is already queued, or still being read (or about to be)
+        let wait = started(next).map_or(budget, |started| budget.saturating_sub(started.elapsed()));
+        match rx.recv_timeout(wait) {
+            Ok((pid, value)) => {
+                next += 1;
+                if visit(pid, Some(value)).is_break() {
+                    return None;
                 }
             }
+            Err(RecvTimeoutError::Disconnected) => return None,
+            Err(RecvTimeoutError::Timeout) => {
+                if !started(next).is_some_and(|started| started.elapsed() >= budget) {
+                    continue;
+                }
+                for pid in &order[next..] {
+                    if visit(*pid, None).is_break() {
+                        break;
+                    }
+                }
+                return order.get(next).copied();
+            }
         }
     }
-    None
 }
 
-fn is_thread(pid: u32) -> bool {
-    if let Ok(contents) = std::fs::read_to_string(format!("/proc/{}/status", pid)) {
-        let mut tgid = None;
-        let mut pid_val = None;
-        
-        for line in contents.lines() {
-            if line.starts_with("Tgid:") {
-                tgid = line.split_whitespace().nth(1).and_then(|s| s.parse::<u32>().ok());
-            } else if line.starts_with("Pid:") {
-                pid_val = line.split_whitespace().nth(1).and_then(|s| s.parse::<u32>().ok());
+// One ProcessInfo per process (threads only with `threads`), including kernel threads.
+fn collect_processes(sys: &System, metric: MemoryMetric, threads: IncludeThreads) -> Vec<ProcessInfo> {
+    let mut processes = Vec::with_capacity(sys.processes().len());
+    visit_processes(sys, metric, threads, |process| {
+        processes.push(process);
+        ControlFlow::Continue(())
+    });
+    processes
+}
+
+// Hand each process (threads only with `threads`, kernel threads included) to
+// `visit` as soon as its /proc files are read, in sysinfo's table order.
+//
+// A process in uninterruptible sleep (D state) can block reads of its /proc
+// files. sysinfo's pass (`sys`) skips the files that wait on it (see
+// process_refresh_kind), and kern's own reads run under PROC_READ_BUDGET: once
+// one process's take longer, it and the processes not yet read fall back to
+// sysinfo's values (RSS memory, its thread flags, state '?'), and the process
+// that blocked is never read again, so one stuck process can't freeze
+// `kern status` or the enforcer.
+fn visit_processes(
+    sys: &System,
+    metric: MemoryMetric,
+    threads: IncludeThreads,
+    mut visit: impl FnMut(ProcessInfo) -> ControlFlow<()>,
+) {
+    let mut pids = Vec::new();
+    let mut skipped = Vec::new();
+    {
+        let mut stuck = STUCK_PROCESSES.lock().unwrap_or_else(|e| e.into_inner());
+        stuck.retain(|(pid, start)| sys.process(Pid::from_u32(*pid)).is_some_and(|p| p.start_time() == *start));
+        for (pid, process) in sys.processes() {
+            if stuck.contains(&(pid.as_u32(), process.start_time())) {
+                skipped.push(pid.as_u32());
+            } else {
+                pids.push(pid.as_u32());
             }
         }
-        
-        if let (Some(tgid), Some(pid_val)) = (tgid, pid_val) {
-            return tgid != pid_val;
+    }
+
+    let mut stopped = false;
+    let mut hand_over = |pid: u32, details: Option<ProcDetails>| {
+        if stopped {
+            return ControlFlow::Break(());
+        }
+        let info = sys.process(Pid::from_u32(pid)).and_then(|process| process_info(pid, process, details, threads));
+        if info.is_some_and(|info| visit(info).is_break()) {
+            stopped = true;
+            return ControlFlow::Break(());
         }
+        ControlFlow::Continue(())
+    };
+    let stuck_pid = stream_with_budget(pids, PROC_READ_BUDGET, move |pid| read_proc_details(pid, metric), &mut hand_over);
+    if let Some(process) = stuck_pid.and_then(|pid| sys.process(Pid::from_u32(pid))) {
+        crate::log::warning(&format!(
+            "reading /proc/{} took over {}s (stuck in D state?); using sysinfo's values for it",
+            process.pid(),
+            PROC_READ_BUDGET.as_secs()
+        ));
+        let mut stuck = STUCK_PROCESSES.lock().unwrap_or_else(|e| e.into_inner());
+        stuck.push((process.pid().as_u32(), process.start_time()));
     }
-    false
+
+    // Processes known to block are never read, only reported from sysinfo
+    for pid in skipped {
+        if hand_over(pid, None).is_break() {
+            break;
+        }
+    }
+}
+
+// A process as sysinfo sees it, completed by its own /proc files when they
+// could be read (`details`). None for threads, unless `threads` includes them.
+fn process_info(
+    pid: u32,
+    process: &sysinfo::Process,
+    details: Option<ProcDetails>,
+    threads: IncludeThreads,
+) -> Option<ProcessInfo> {
+    let details = details.unwrap_or(ProcDetails {
+        tgid: None,
+        memory_bytes: None,
+        stat: None,
+        cgroup_path: None,
+    });
+
+    // sysinfo lists a thread with its process as parent
+    let tgid = details.tgid.unwrap_or_else(|| match process.thread_kind() {
+        Some(ThreadKind::Userland) => process.parent().map_or(pid, |parent| parent.as_u32()),
+        _ => pid,
+    });
+    if tgid != pid && threads == IncludeThreads::No {
+        return None;
+    }
+
+    let memory_bytes = details.memory_bytes.unwrap_or_else(|| process.memory());
+    let stat = details.stat;
+
+    Some(ProcessInfo {
+        pid,
+        name: process.name().to_string_lossy().to_string(),
+        memory_gb: memory_bytes as f64 / 1_073_741_824.0,
+        cpu_percentage: finite_or_zero(process.cpu_usage() as f64),
+        start_time: process.start_time(),
+        state: stat.map_or('?', |stat| stat.state),
+        ppid: stat.map_or(0, |stat| stat.ppid),
+        kernel_thread: stat.map_or(process.thread_kind() == Some(ThreadKind::Kernel), |stat| stat.is_kernel_thread()),
+        nice: stat.and_then(|stat| stat.nice),
+        io_read_mbps: 0.0,
+        io_write_mbps: 0.0,
+        cgroup_path: details.cgroup_path,
+        uid: process.user_id().map(|uid| **uid),
+        tgid,
+    })
+}
+
+// A NaN or infinite reading would poison JSON (serialized as null) and limit checks
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+// used / total as a percentage in 0..=100; 0.0 when the total is unknown
+fn memory_percentage(used_gb: f64, total_gb: f64) -> f64 {
+    if total_gb > 0.0 {
+        finite_or_zero(used_gb / total_gb * 100.0).clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+// Heaviest first; a NaN reading sorts last instead of panicking
+fn sort_by_memory(processes: &mut [ProcessInfo]) {
+    processes.sort_by(|a, b| descending(a.memory_gb, b.memory_gb));
+}
+
+/// `sensor` replaces thermal zone discovery with a single configured file
+pub fn get_system_stats(metric: MemoryMetric, sensor: Option<&Path>) -> Result<SystemStats> {
+    sample_system(Some(metric), sensor)
+}
+
+/// System-wide CPU, memory and temperature only: skips per-process
+/// collection, so it is cheap enough for a panel indicator to poll
+pub fn get_system_summary(sensor: Option<&Path>) -> Result<SystemStats> {
+    sample_system(None, sensor)
+}
+
+/// The system-wide numbers `kern status --no-processes` prints
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlobalStats {
+    pub cpu_usage: f64,
+    pub total_memory_gb: f64,
+    pub used_memory_gb: f64,
+    pub memory_percentage: f64,
+    pub temperature: f64, // Hottest zone, like SystemStats::temperature
+    pub cpu_capacity: f64, // CPUs cpu_usage is relative to, for core-based limits
+    pub memory_source: MemorySource,
+    pub uptime_secs: u64,
+}
+
+/// CPU, memory and temperature without enumerating processes: much faster
+/// than `get_system_stats` on machines running thousands of them
+pub fn get_global_stats(sensor: Option<&Path>) -> Result<GlobalStats> {
+    let stats = get_system_summary(sensor)?;
+    Ok(GlobalStats {
+        cpu_usage: stats.cpu_usage,
+        total_memory_gb: stats.total_memory_gb,
+        used_memory_gb: stats.used_memory_gb,
+        memory_percentage: stats.memory_percentage,
+        temperature: stats.temperature,
+        cpu_capacity: stats.cpu_capacity(),
+        memory_source: stats.memory_source(),
+        uptime_secs: stats.uptime_secs,
+    })
 }
 
-pub fn get_system_stats() -> Result<SystemStats> {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+// How long CPU usage is sampled over: sysinfo works out CPU% (global and per
+// process) from the CPU time spent between two refreshes
+const CPU_SAMPLE_WINDOW: Duration = Duration::from_millis(200);
 
-    std::thread::sleep(std::time::Duration::from_millis(200));
+// All sysinfo reads of a process: stat, statm and status (CPU time, memory,
+// owner). cmdline, environ, cwd and exe are left out: reading those waits on
+// the process's memory map, which blocks for as long as a process in D state
+// holds it, and only visit_processes' own reads run under PROC_READ_BUDGET.
+fn process_refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::new().with_cpu().with_memory().with_user(UpdateKind::OnlyIfNotSet)
+}
+
+// CPU, memory and the processes, each process read as process_refresh_kind says
+fn system_with_processes() -> System {
+    System::new_with_specifics(
+        RefreshKind::new()
+            .with_cpu(CpuRefreshKind::new().with_cpu_usage())
+            .with_memory(MemoryRefreshKind::new().with_ram().with_swap())
+            .with_processes(process_refresh_kind()),
+    )
+}
+
+// Read every process's CPU time again at the end of the sample window:
+// without this second reading sysinfo reports 0% for every process
+fn refresh_process_cpu(sys: &mut System) {
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, process_refresh_kind());
+}
+
+// Every process, read twice CPU_SAMPLE_WINDOW apart so each has a CPU%
+fn sample_processes() -> System {
+    let mut sys = system_with_processes();
+    std::thread::sleep(CPU_SAMPLE_WINDOW);
     sys.refresh_cpu_all();
+    refresh_process_cpu(&mut sys);
+    sys
+}
+
+// Without a `metric` no processes are read and `top_processes` stays empty
+fn sample_system(metric: Option<MemoryMetric>, sensor: Option<&Path>) -> Result<SystemStats> {
+    let mut sys = match metric {
+        Some(_) => system_with_processes(),
+        None => System::new_with_specifics(
+            RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new().with_cpu_usage())
+                .with_memory(MemoryRefreshKind::new().with_ram().with_swap()),
+        ),
+    };
 
-    let cpu_usage = sys.global_cpu_usage() as f64;
+    // Sample the cgroup's CPU time over the same window as the global CPU usage
+    let cgroup = CgroupLimiter::new().own_limits();
+    let cgroup_cpu_before = cgroup.as_ref().and_then(|limits| limits.cpu_usage_usec());
+    let sample_start = Instant::now();
+
+    std::thread::sleep(CPU_SAMPLE_WINDOW);
+    sys.refresh_cpu_all();
+    if metric.is_some() {
+        refresh_process_cpu(&mut sys);
+    }
+
+    let cpu_usage = finite_or_zero(sys.global_cpu_usage() as f64);
+    let cpu_count = sys.cpus().len();
 
     let total_memory = sys.total_memory() as f64 / 1_073_741_824.0;
     let used_memory = sys.used_memory() as f64 / 1_073_741_824.0;
-    let memory_percentage = (used_memory / total_memory) * 100.0;
+    let swap_percentage = memory_percentage(sys.used_swap() as f64, sys.total_swap() as f64);
+    let memory_percentage = memory_percentage(used_memory, total_memory);
 
-    let temperature = get_cpu_temperature().unwrap_or(0.0);
+    let (temperature_avg, temperature_max) = match sensor {
+        // A sensor that disappears after startup reads as 0, like a machine without zones
+        Some(path) => {
+            let temp = read_temperature_sensor(path).unwrap_or(0.0);
+            (temp, temp)
+        }
+        None => aggregate_temperatures(&read_all_thermal_zones()),
+    };
 
-    let mut processes: Vec<ProcessInfo> = sys
-        .processes()
-        .iter()
-        .filter_map(|(pid, process)| {
-            let pid_val = pid.as_u32();
-            
-            if is_thread(pid_val) {
-                return None;
-            }
-            
-            let memory_bytes = get_process_memory_from_proc(pid_val)
-                .unwrap_or_else(|| process.memory());
-            
-            Some(ProcessInfo {
-                pid: pid_val,
-                name: process.name().to_string_lossy().to_string(),
-                memory_gb: memory_bytes as f64 / 1_073_741_824.0,
-                cpu_percentage: process.cpu_usage() as f64,
-            })
-        })
+    // Kernel threads can't be killed and own no user memory
+    let mut processes: Vec<ProcessInfo> = metric
+        .map(|metric| collect_processes(&sys, metric, IncludeThreads::No))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| !p.kernel_thread)
         .collect();
 
-    processes.sort_by(|a, b| b.memory_gb.partial_cmp(&a.memory_gb).unwrap());
+    sort_by_memory(&mut processes);
 
-    Ok(SystemStats {
+    let mut stats = SystemStats {
         cpu_usage,
+        cpu_count,
         total_memory_gb: total_memory,
         used_memory_gb: used_memory,
         memory_percentage,
-        temperature,
+        swap_percentage,
+        temperature: temperature_max,
+        temperature_avg,
+        temperature_max,
         top_processes: processes,
-    })
+        cgroup: None,
+        cpu_pressure: read_pressure("cpu"),
+        mem_pressure: read_pressure("memory"),
+        uptime_secs: 0,
+        boot_time: 0,
+        footprint: own_footprint(),
+    };
+    stats.set_uptime(read_uptime());
+
+    if let Some(limits) = cgroup {
+        let cpu_time = cgroup_cpu_before
+            .zip(limits.cpu_usage_usec())
+            .map(|(before, after)| (Duration::from_micros(after.saturating_sub(before)), sample_start.elapsed()));
+        stats.apply_cgroup_limits(limits, cpu_time);
+    }
+
+    if stats.total_memory_gb <= 0.0 {
+        static WARN_NO_MEMORY: std::sync::Once = std::sync::Once::new();
+        WARN_NO_MEMORY.call_once(|| {
+            crate::log::warning("total memory reported as 0 (container without memory info?); RAM usage shows as 0% and RAM limits are not checked");
+        });
+    }
+
+    Ok(stats)
+}
+
+/// Hand every process (kernel threads included, and threads with
+/// `IncludeThreads::Yes`) to `visit` as soon as it is read, without collecting
+/// or sorting them, so even a huge process table is streamed in constant
+/// memory. Return `ControlFlow::Break` to stop early.
+pub fn for_each_process(
+    metric: MemoryMetric,
+    threads: IncludeThreads,
+    visit: impl FnMut(ProcessInfo) -> ControlFlow<()>,
+) -> Result<()> {
+    let sys = sample_processes();
+    visit_processes(&sys, metric, threads, visit);
+    Ok(())
+}
+
+/// Every process, heaviest memory first. With `IncludeThreads::Yes` each
+/// thread is listed too, carrying its whole process's memory.
+pub fn get_all_processes(metric: MemoryMetric, threads: IncludeThreads) -> Result<Vec<ProcessInfo>> {
+    let sys = sample_processes();
+
+    let mut processes = collect_processes(&sys, metric, threads);
+
+    sort_by_memory(&mut processes);
+
+    Ok(processes)
+}
+
+/// Number of logical CPUs, for scaling per-process CPU% (100% = one core)
+pub fn cpu_count() -> usize {
+    let sys = System::new_with_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::new()));
+    sys.cpus().len().max(1)
+}
+
+/// Divide per-process CPU% by `cpu_count`, so 100% means every CPU busy rather
+/// than one core (the `normalize_cpu` setting)
+pub fn normalize_process_cpu(processes: &mut [ProcessInfo], cpu_count: usize) {
+    let cpus = cpu_count.max(1) as f64;
+    for process in processes {
+        process.cpu_percentage /= cpus;
+    }
 }
 
-pub fn get_all_processes() -> Result<Vec<ProcessInfo>> {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+/// Column a process listing is ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProcessSort {
+    Memory,
+    Cpu,
+    Pid,
+    Name,
+    /// As read from /proc, unsorted
+    #[value(name = "none")]
+    Unsorted,
+}
 
-    let mut processes: Vec<ProcessInfo> = sys
-        .processes()
+/// Keep processes whose name contains `filter` (case-insensitive), ordered by `sort`
+pub fn select_processes(
+    mut processes: Vec<ProcessInfo>,
+    sort: ProcessSort,
+    filter: Option<&str>,
+) -> Vec<ProcessInfo> {
+    if let Some(filter) = filter {
+        let filter = filter.to_lowercase();
+        processes.retain(|p| p.name.to_lowercase().contains(&filter));
+    }
+
+    match sort {
+        ProcessSort::Memory => processes.sort_by(|a, b| descending(a.memory_gb, b.memory_gb)),
+        ProcessSort::Cpu => processes.sort_by(|a, b| descending(a.cpu_percentage, b.cpu_percentage)),
+        ProcessSort::Pid => processes.sort_by_key(|p| p.pid),
+        ProcessSort::Name => processes.sort_by(|a, b| a.name.cmp(&b.name).then(a.pid.cmp(&b.pid))),
+        ProcessSort::Unsorted => {}
+    }
+    processes
+}
+
+/// Move each thread right after its process, keeping everything else in
+/// order; a thread whose process isn't in `processes` stays where it is
+pub fn threads_after_processes(processes: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+    let positions: HashMap<u32, usize> = processes
         .iter()
-        .filter_map(|(pid, process)| {
-            let pid_val = pid.as_u32();
-            
-            if is_thread(pid_val) {
-                return None;
-            }
-            
-            let memory_bytes = get_process_memory_from_proc(pid_val)
-                .unwrap_or_else(|| process.memory());
-            
-            Some(ProcessInfo {
-                pid: pid_val,
-                name: process.name().to_string_lossy().to_string(),
-                memory_gb: memory_bytes as f64 / 1_073_741_824.0,
-                cpu_percentage: process.cpu_usage() as f64,
-            })
+        .enumerate()
+        .filter(|(_, p)| !p.is_thread())
+        .map(|(i, p)| (p.pid, i))
+        .collect();
+    let mut keyed: Vec<(usize, bool, ProcessInfo)> = processes
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let position = if p.is_thread() { positions.get(&p.tgid).copied().unwrap_or(i) } else { i };
+            (position, p.is_thread(), p)
         })
         .collect();
+    keyed.sort_by_key(|(position, thread, _)| (*position, *thread));
+    keyed.into_iter().map(|(_, _, p)| p).collect()
+}
 
-    processes.sort_by(|a, b| b.memory_gb.partial_cmp(&a.memory_gb).unwrap());
+/// Processes sharing a name, summed (e.g. all of a browser's renderers)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessGroup {
+    pub name: String,
+    pub pids: Vec<u32>, // Ascending
+    pub memory_gb: f64,
+    pub cpu_percentage: f64,
+}
 
-    Ok(processes)
+impl ProcessGroup {
+    pub fn count(&self) -> usize {
+        self.pids.len()
+    }
+}
+
+/// Aggregate processes by name, heaviest memory first
+pub fn group_processes(processes: &[ProcessInfo]) -> Vec<ProcessGroup> {
+    let mut by_name: HashMap<&str, ProcessGroup> = HashMap::new();
+    for process in processes {
+        let group = by_name.entry(&process.name).or_insert_with(|| ProcessGroup {
+            name: process.name.clone(),
+            ..Default::default()
+        });
+        group.pids.push(process.pid);
+        group.memory_gb += process.memory_gb;
+        group.cpu_percentage += process.cpu_percentage;
+    }
+
+    let mut groups: Vec<ProcessGroup> = by_name.into_values().collect();
+    for group in &mut groups {
+        group.pids.sort_unstable();
+    }
+    sort_groups(&mut groups, ProcessSort::Memory);
+    groups
+}
+
+/// Order groups like `select_processes` orders processes (by lowest PID for `Pid`)
+pub fn sort_groups(groups: &mut [ProcessGroup], sort: ProcessSort) {
+    match sort {
+        ProcessSort::Memory => groups.sort_by(|a, b| descending(a.memory_gb, b.memory_gb).then(a.name.cmp(&b.name))),
+        ProcessSort::Cpu => groups.sort_by(|a, b| descending(a.cpu_percentage, b.cpu_percentage).then(a.name.cmp(&b.name))),
+        ProcessSort::Pid => groups.sort_by_key(|g| g.pids.first().copied()),
+        ProcessSort::Name => groups.sort_by(|a, b| a.name.cmp(&b.name)),
+        ProcessSort::Unsorted => {}
+    }
+}
+
+/// A saved `kern status --json`, read back by `kern status --diff`. Only the
+/// fields a diff needs; everything else in the file is ignored.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+pub struct Snapshot {
+    pub cpu_usage: f64,
+    pub memory_percentage: f64,
+    #[serde(default)]
+    pub temperature: f64,
+    #[serde(default)]
+    pub top_processes: Vec<SnapshotProcess>,
+}
+
+/// One entry of a snapshot's `top_processes`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotProcess {
+    pub pid: u32,
+    pub name: String,
+    pub memory_gb: f64,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read snapshot {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("{} is not a `kern status --json` snapshot: {}", path.display(), e))
+    }
+}
+
+/// How far a process's memory has to move, in percent of its snapshot value,
+/// to be reported by `diff_stats`
+pub const MEMORY_CHANGE_PERCENT: f64 = 10.0;
+
+/// What changed between a snapshot and the current stats
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct StatsDiff {
+    pub new_processes: Vec<SnapshotProcess>,
+    pub gone_processes: Vec<SnapshotProcess>,
+    pub memory_increased: Vec<MemoryChange>, // Largest growth first
+    pub memory_decreased: Vec<MemoryChange>, // Largest drop first
+    pub cpu_usage: ValueChange,
+    pub memory_percentage: ValueChange,
+    pub temperature: ValueChange,
+}
+
+/// A process in both the snapshot and now whose memory moved by more than
+/// `MEMORY_CHANGE_PERCENT`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct MemoryChange {
+    pub pid: u32,
+    pub name: String,
+    pub old_memory_gb: f64,
+    pub new_memory_gb: f64,
+    pub change_percent: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct ValueChange {
+    pub old: f64,
+    pub new: f64,
+}
+
+impl ValueChange {
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+/// Compare `new` against `old`. A process is the same one when both its PID
+/// and name match, so a reused PID shows up as one gone and one new process.
+/// Processes at 0 GB in the snapshot are never reported as grown.
+pub fn diff_stats(old: &Snapshot, new: &SystemStats) -> StatsDiff {
+    let before: HashMap<(u32, &str), &SnapshotProcess> =
+        old.top_processes.iter().map(|p| ((p.pid, p.name.as_str()), p)).collect();
+    let now: HashMap<(u32, &str), &ProcessInfo> =
+        new.top_processes.iter().map(|p| ((p.pid, p.name.as_str()), p)).collect();
+
+    let mut diff = StatsDiff {
+        cpu_usage: ValueChange { old: old.cpu_usage, new: new.cpu_usage },
+        memory_percentage: ValueChange { old: old.memory_percentage, new: new.memory_percentage },
+        temperature: ValueChange { old: old.temperature, new: new.temperature },
+        ..Default::default()
+    };
+
+    for process in &new.top_processes {
+        let Some(previous) = before.get(&(process.pid, process.name.as_str())) else {
+            diff.new_processes.push(SnapshotProcess {
+                pid: process.pid,
+                name: process.name.clone(),
+                memory_gb: process.memory_gb,
+            });
+            continue;
+        };
+        if previous.memory_gb <= 0.0 {
+            continue;
+        }
+        let change_percent = finite_or_zero((process.memory_gb - previous.memory_gb) / previous.memory_gb * 100.0);
+        let change = MemoryChange {
+            pid: process.pid,
+            name: process.name.clone(),
+            old_memory_gb: previous.memory_gb,
+            new_memory_gb: process.memory_gb,
+            change_percent,
+        };
+        if change_percent > MEMORY_CHANGE_PERCENT {
+            diff.memory_increased.push(change);
+        } else if change_percent < -MEMORY_CHANGE_PERCENT {
+            diff.memory_decreased.push(change);
+        }
+    }
+    diff.gone_processes = old
+        .top_processes
+        .iter()
+        .filter(|p| !now.contains_key(&(p.pid, p.name.as_str())))
+        .cloned()
+        .collect();
+
+    diff.memory_increased.sort_by(|a, b| descending(a.change_percent, b.change_percent));
+    diff.memory_decreased.sort_by(|a, b| a.change_percent.total_cmp(&b.change_percent));
+    diff
 }
 
 pub fn find_process_by_name(name: &str) -> Option<u32> {
@@ -149,29 +1022,379 @@ pub fn find_process_by_name(name: &str) -> Option<u32> {
     None
 }
 
-fn get_cpu_temperature() -> Result<f64> {
-    let thermal_zones = [
-        "/sys/class/thermal/thermal_zone4/temp",
-        "/sys/class/thermal/thermal_zone6/temp",
-        "/sys/class/thermal/thermal_zone1/temp",
-        "/sys/class/thermal/thermal_zone2/temp",
-        "/sys/class/thermal/thermal_zone0/temp",
-        "/sys/class/thermal/thermal_zone5/temp",
-        "/sys/class/thermal/thermal_zone3/temp",
-    ];
-
-    for path in &thermal_zones {
-        if let Ok(contents) = std::fs::read_to_string(path) {
-            if let Ok(temp) = contents.trim().parse::<f64>() {
-                return Ok(temp / 1000.0);
-            }
+/// Share of the last 10 seconds (0-100%) in which some task stalled waiting
+/// for `resource` ("cpu", "memory" or "io"), from /proc/pressure. None on
+/// kernels built without PSI or booted with psi=0.
+pub fn read_pressure(resource: &str) -> Option<f64> {
+    let contents = std::fs::read_to_string(Path::new("/proc/pressure").join(resource)).ok()?;
+    parse_pressure_avg10(&contents)
+}
+
+// "some avg10=1.53 avg60=0.87 avg300=0.40 total=123456\nfull avg10=..."
+fn parse_pressure_avg10(contents: &str) -> Option<f64> {
+    let line = contents.lines().find(|line| line.starts_with("some "))?;
+    line.split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+}
+
+/// Value of environment variable `key` in a process's /proc/<pid>/environ, as
+/// it was when the process started. None when unset, or when the file can't
+/// be read (another user's process, without root).
+pub fn read_process_env_var(pid: u32, key: &str) -> Option<String> {
+    let environ = std::fs::read(Path::new("/proc").join(pid.to_string()).join("environ")).ok()?;
+    parse_environ_var(&environ, key)
+}
+
+// NUL-separated KEY=VALUE entries; values may contain '=' and aren't always UTF-8
+fn parse_environ_var(environ: &[u8], key: &str) -> Option<String> {
+    environ.split(|byte| *byte == 0).find_map(|entry| {
+        let value = entry.strip_prefix(key.as_bytes())?.strip_prefix(b"=")?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    })
+}
+
+/// Cgroup a process belongs to, from /proc/<pid>/cgroup
+pub fn read_process_cgroup(pid: u32) -> Option<String> {
+    parse_cgroup(&std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?)
+}
+
+// One "hierarchy-ID:controllers:path" line per hierarchy. cgroup v2 has a single
+// "0::/path" line, which wins on hybrid systems; under v1 the first line's path is used
+fn parse_cgroup(contents: &str) -> Option<String> {
+    let paths: Vec<(&str, &str)> = contents
+        .lines()
+        .filter_map(|line| {
+            let (id, rest) = line.split_once(':')?;
+            let (_, path) = rest.split_once(':')?;
+            Some((id, path.trim()))
+        })
+        .filter(|(_, path)| path.starts_with('/'))
+        .collect();
+    let (_, path) = paths.iter().find(|(id, _)| *id == "0").or_else(|| paths.first())?;
+    Some(path.to_string())
+}
+
+/// Command line of a process, arguments joined by spaces; None for kernel
+/// threads (empty command line) and processes that are gone
+pub fn read_process_cmdline(pid: u32) -> Option<String> {
+    let contents = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = contents
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    (!args.is_empty()).then(|| args.join(" "))
+}
+
+/// Real UID of a process's owner, from /proc/<pid>/status
+pub fn read_process_uid(pid: u32) -> Option<u32> {
+    parse_status_uid(&std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?)
+}
+
+// "Uid:" lists the real, effective, saved and filesystem UIDs
+fn parse_status_uid(status: &str) -> Option<u32> {
+    status.lines().find_map(|line| line.strip_prefix("Uid:")?.split_whitespace().next()?.parse().ok())
+}
+
+/// Login name for `uid`, from the user database (each UID looked up once per run)
+pub fn user_name(uid: u32) -> Option<String> {
+    static NAMES: std::sync::OnceLock<Mutex<HashMap<u32, Option<String>>>> = std::sync::OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    names
+        .entry(uid)
+        .or_insert_with(|| {
+            nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(uid)).ok().flatten().map(|user| user.name)
+        })
+        .clone()
+}
+
+/// Who owns a process, for display: the login name, "uid N" without a user
+/// database entry, or "unknown" when the owner couldn't be read
+pub fn owner_name(uid: Option<u32>) -> String {
+    match uid {
+        Some(uid) => user_name(uid).unwrap_or_else(|| format!("uid {}", uid)),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Each user's `n` heaviest processes by memory, keyed by `owner_name`
+pub fn top_processes_per_user(processes: Vec<ProcessInfo>, n: usize) -> BTreeMap<String, Vec<ProcessInfo>> {
+    let mut users: BTreeMap<String, Vec<ProcessInfo>> = BTreeMap::new();
+    for process in processes {
+        users.entry(owner_name(process.uid)).or_default().push(process);
+    }
+    for processes in users.values_mut() {
+        processes.sort_by(|a, b| descending(a.memory_gb, b.memory_gb));
+        processes.truncate(n);
+    }
+    users
+}
+
+/// Seconds since boot, from /proc/uptime (sysinfo elsewhere)
+pub fn read_uptime() -> u64 {
+    proc_uptime().map_or_else(System::uptime, |seconds| seconds as u64)
+}
+
+fn proc_uptime() -> Option<f64> {
+    parse_uptime(&std::fs::read_to_string("/proc/uptime").ok()?)
+}
+
+// "350735.47 234388.90": seconds up, then seconds idle summed over all CPUs
+fn parse_uptime(contents: &str) -> Option<f64> {
+    let seconds: f64 = contents.split_whitespace().next()?.parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then_some(seconds)
+}
+
+/// How much a process costs: resident memory now, and CPU time averaged over
+/// its whole life, so a monitor that is cheap per refresh shows as cheap
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct ProcessFootprint {
+    pub pid: u32,
+    pub memory_mb: f64,
+    pub cpu_percent: f64, // 100 = one core busy since the process started
+    pub cpu_time_secs: f64, // user + system
+}
+
+/// kern's own footprint (None where /proc isn't available)
+pub fn own_footprint() -> Option<ProcessFootprint> {
+    process_footprint(std::process::id())
+}
+
+/// Footprint of a live process, from /proc/<pid>/stat and /proc/<pid>/status
+pub fn process_footprint(pid: u32) -> Option<ProcessFootprint> {
+    let dir = Path::new("/proc").join(pid.to_string());
+    let stat = std::fs::read_to_string(dir.join("stat")).ok()?;
+    let status = std::fs::read_to_string(dir.join("status")).ok()?;
+    let uptime = proc_uptime()?;
+    // SAFETY: sysconf only reads a system constant
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    parse_footprint(pid, &stat, &status, uptime, ticks_per_sec as f64)
+}
+
+// utime and stime are stat fields 14 and 15, starttime field 22, all in clock
+// ticks (starttime counted from boot); VmRSS in status is in kB
+fn parse_footprint(pid: u32, stat: &str, status: &str, uptime_secs: f64, ticks_per_sec: f64) -> Option<ProcessFootprint> {
+    if ticks_per_sec <= 0.0 {
+        return None;
+    }
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let ticks = |index: usize| fields.get(index).and_then(|field| field.parse::<u64>().ok());
+    let cpu_time_secs = (ticks(11)? + ticks(12)?) as f64 / ticks_per_sec;
+    let alive_secs = uptime_secs - ticks(19)? as f64 / ticks_per_sec;
+
+    let rss_kb: f64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0.0);
+
+    Some(ProcessFootprint {
+        pid,
+        memory_mb: rss_kb / 1024.0,
+        cpu_percent: if alive_secs > 0.0 { cpu_time_secs / alive_secs * 100.0 } else { 0.0 },
+        cpu_time_secs,
+    })
+}
+
+/// Read a sysfs temperature file (millidegrees Celsius) in °C
+pub fn read_temperature_sensor(path: &Path) -> Result<f64> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+    let millidegrees = contents
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| anyhow!("{} does not contain a numeric temperature", path.display()))?;
+    Ok(millidegrees / 1000.0)
+}
+
+/// Read every thermal zone as (zone_type, °C), ordered by zone number.
+/// macOS has no thermal zones: its SMC sensors are read through powermetrics.
+pub fn read_all_thermal_zones() -> Vec<(String, f64)> {
+    #[cfg(target_os = "macos")]
+    {
+        read_powermetrics_temperatures()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        read_thermal_zones_in(Path::new("/sys/class/thermal"))
+    }
+}
+
+// How often the background thread takes a new powermetrics sample
+#[cfg(target_os = "macos")]
+const POWERMETRICS_REFRESH: Duration = Duration::from_secs(5);
+
+// Whether powermetrics worked the first time, and its latest readings
+#[cfg(target_os = "macos")]
+static POWERMETRICS_AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+#[cfg(target_os = "macos")]
+static POWERMETRICS_LATEST: Mutex<Vec<(String, f64)>> = Mutex::new(Vec::new());
+
+// A powermetrics run takes too long for every sample_system call, so only the
+// first read waits for one; later reads get the latest sample, which a
+// background thread refreshes every POWERMETRICS_REFRESH. powermetrics only
+// runs as root: when the first run fails, that is remembered and it is never
+// run again, leaving no readings.
+#[cfg(target_os = "macos")]
+fn read_powermetrics_temperatures() -> Vec<(String, f64)> {
+    let available = *POWERMETRICS_AVAILABLE.get_or_init(|| match sample_powermetrics() {
+        Some(readings) => {
+            *POWERMETRICS_LATEST.lock().unwrap_or_else(|e| e.into_inner()) = readings;
+            std::thread::spawn(|| loop {
+                std::thread::sleep(POWERMETRICS_REFRESH);
+                let readings = sample_powermetrics().unwrap_or_default();
+                *POWERMETRICS_LATEST.lock().unwrap_or_else(|e| e.into_inner()) = readings;
+            });
+            true
+        }
+        None => false,
+    });
+    if available {
+        POWERMETRICS_LATEST.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    } else {
+        Vec::new()
+    }
+}
+
+// One `powermetrics --samplers smc` run; None when it fails (not root)
+#[cfg(target_os = "macos")]
+fn sample_powermetrics() -> Option<Vec<(String, f64)>> {
+    std::process::Command::new("powermetrics")
+        .args(["--samplers", "smc", "-i", "1", "-n", "1"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_powermetrics_temperatures(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// "CPU die temperature: 48.53 C" lines from `powermetrics --samplers smc`
+#[cfg(any(target_os = "macos", test))]
+fn parse_powermetrics_temperatures(output: &str) -> Vec<(String, f64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (sensor, value) = line.split_once("temperature:")?;
+            let celsius = value.trim().strip_suffix('C')?.trim().parse::<f64>().ok()?;
+            Some((sensor.trim().to_string(), celsius))
+        })
+        .collect()
+}
+
+#[cfg(any(not(target_os = "macos"), test))]
+fn read_thermal_zones_in(root: &Path) -> Vec<(String, f64)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    let mut zones: Vec<(u32, String, f64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let index = dir_name.strip_prefix("thermal_zone")?.parse::<u32>().ok()?;
+
+            let path = entry.path();
+            let temp = std::fs::read_to_string(path.join("temp")).ok()?;
+            let temp = temp.trim().parse::<f64>().ok()? / 1000.0;
+            let zone_type = std::fs::read_to_string(path.join("type"))
+                .map(|t| t.trim().to_string())
+                .unwrap_or_else(|_| dir_name.clone());
+
+            Some((index, zone_type, temp))
+        })
+        .collect();
+
+    zones.sort_by_key(|(index, _, _)| *index);
+    zones.into_iter().map(|(_, zone_type, temp)| (zone_type, temp)).collect()
+}
+
+// How often thermal_alert_loop checks `stop` while waiting out the interval
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Watch every thermal zone (every sensor on macOS) each `interval` and
+/// notify, rate limited per zone, when one is above `threshold` °C.
+/// Returns once `stop` is set, or at once if no sensor is readable.
+pub fn thermal_alert_loop(
+    threshold: f64,
+    interval: Duration,
+    notifier: &mut NotificationManager,
+    stop: &AtomicBool,
+) -> Result<()> {
+    watch_thermal_zones(read_all_thermal_zones, threshold, interval, stop, |zone, temp| {
+        notifier.notify_thermal_alert(zone, temp, threshold)
+    })
+}
+
+// The loop behind thermal_alert_loop, with the zone reader and the alert injectable
+fn watch_thermal_zones(
+    mut read_zones: impl FnMut() -> Vec<(String, f64)>,
+    threshold: f64,
+    interval: Duration,
+    stop: &AtomicBool,
+    mut on_alert: impl FnMut(&str, f64) -> Result<()>,
+) -> Result<()> {
+    let mut zones = read_zones();
+    if zones.is_empty() {
+        return Err(anyhow!("no temperature sensor is readable"));
+    }
+
+    while !stop.load(Ordering::SeqCst) {
+        for (zone, temp) in zones.iter().filter(|(_, temp)| *temp > threshold) {
+            on_alert(zone, *temp)?;
+        }
+
+        let deadline = Instant::now() + interval;
+        while !stop.load(Ordering::SeqCst) && Instant::now() < deadline {
+            std::thread::sleep(STOP_POLL.min(deadline.saturating_duration_since(Instant::now())));
         }
+        zones = read_zones();
     }
-    Ok(0.0)
+    Ok(())
+}
+
+/// (average, maximum) over the zones reporting a non-zero temperature.
+/// Both are 0.0 when no zone is readable.
+fn aggregate_temperatures(zones: &[(String, f64)]) -> (f64, f64) {
+    let readings: Vec<f64> = zones
+        .iter()
+        .map(|(_, temp)| *temp)
+        .filter(|temp| *temp > 0.0)
+        .collect();
+
+    if readings.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let avg = readings.iter().sum::<f64>() / readings.len() as f64;
+    let max = readings.iter().cloned().fold(f64::MIN, f64::max);
+    (avg, max)
 }
 
-pub fn debug_thermal_zones() -> Result<()> {
-    println!("Available thermal zones:");
+/// One line per temperature sensor, for `kern thermal`
+#[cfg(target_os = "macos")]
+pub fn describe_thermal_zones() -> Vec<String> {
+    let mut lines = vec!["Available temperature sensors (powermetrics, needs sudo):".to_string()];
+    let sensors = read_powermetrics_temperatures();
+    if sensors.is_empty() {
+        lines.push("  none readable - run with sudo, or set temperature.enabled: false".to_string());
+    }
+    for (sensor, temp) in sensors {
+        lines.push(format!("  {} - {:.2}°C", sensor, temp));
+    }
+    lines
+}
+
+/// One line per thermal zone, for `kern thermal`
+#[cfg(not(target_os = "macos"))]
+pub fn describe_thermal_zones() -> Vec<String> {
+    let mut lines = vec!["Available thermal zones:".to_string()];
     for i in 0..10 {
         let type_path = format!("/sys/class/thermal/thermal_zone{}/type", i);
         let temp_path = format!("/sys/class/thermal/thermal_zone{}/temp", i);
@@ -179,10 +1402,755 @@ pub fn debug_thermal_zones() -> Result<()> {
         if let Ok(zone_type) = std::fs::read_to_string(&type_path) {
             if let Ok(temp_str) = std::fs::read_to_string(&temp_path) {
                 if let Ok(temp) = temp_str.trim().parse::<f64>() {
-                    println!("  thermal_zone{}: {} - {:.2}°C", i, zone_type.trim(), temp / 1000.0);
+                    lines.push(format!("  thermal_zone{}: {} - {:.2}°C", i, zone_type.trim(), temp / 1000.0));
                 }
             }
         }
     }
-    Ok(())
-}
\ No newline at end of file
+    lines
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_zone(root: &Path, index: u32, zone_type: &str, millidegrees: &str) {
+        let zone = root.join(format!("thermal_zone{}", index));
+        std::fs::create_dir_all(&zone).unwrap();
+        std::fs::write(zone.join("type"), format!("{}\n", zone_type)).unwrap();
+        std::fs::write(zone.join("temp"), format!("{}\n", millidegrees)).unwrap();
+    }
+
+    #[test]
+    fn test_read_thermal_zones_in() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_zone(root, 10, "x86_pkg_temp", "62000");
+        write_zone(root, 2, "acpitz", "48500");
+        write_zone(root, 3, "broken", "not-a-number");
+        std::fs::create_dir_all(root.join("cooling_device0")).unwrap();
+
+        let zones = read_thermal_zones_in(root);
+        assert_eq!(
+            zones,
+            vec![("acpitz".to_string(), 48.5), ("x86_pkg_temp".to_string(), 62.0)]
+        );
+    }
+
+    #[test]
+    fn test_read_temperature_sensor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sensor = temp_dir.path().join("temp1_input");
+        std::fs::write(&sensor, "45500\n").unwrap();
+        assert_eq!(read_temperature_sensor(&sensor).unwrap(), 45.5);
+
+        std::fs::write(&sensor, "N/A\n").unwrap();
+        assert!(read_temperature_sensor(&sensor).is_err());
+        assert!(read_temperature_sensor(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_parse_powermetrics_temperatures() {
+        let output = "**** SMC sensors ****\n\nCPU Thermal level: 0\nFan: 1299.61 rpm\nCPU die temperature: 48.53 C\nGPU die temperature: 41.00 C\n";
+        assert_eq!(
+            parse_powermetrics_temperatures(output),
+            vec![("CPU die".to_string(), 48.53), ("GPU die".to_string(), 41.0)]
+        );
+        assert!(parse_powermetrics_temperatures("powermetrics must be invoked as the superuser").is_empty());
+    }
+
+    #[test]
+    fn test_read_thermal_zones_missing_dir() {
+        assert!(read_thermal_zones_in(Path::new("/nonexistent/thermal")).is_empty());
+    }
+
+    #[test]
+    fn test_watch_thermal_zones_alerts_on_crossing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_zone(root, 0, "acpitz", "45000");
+        write_zone(root, 1, "x86_pkg_temp", "70000");
+
+        // The package zone heats past the threshold on the third reading
+        let mut reads = 0;
+        let read_zones = || {
+            reads += 1;
+            if reads == 3 {
+                write_zone(root, 1, "x86_pkg_temp", "92500");
+            }
+            read_thermal_zones_in(root)
+        };
+        let stop = AtomicBool::new(false);
+        let mut alerts = Vec::new();
+        watch_thermal_zones(read_zones, 80.0, Duration::from_millis(1), &stop, |zone, temp| {
+            alerts.push((zone.to_string(), temp));
+            stop.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(alerts, vec![("x86_pkg_temp".to_string(), 92.5)]);
+    }
+
+    #[test]
+    fn test_watch_thermal_zones_needs_a_sensor() {
+        let stop = AtomicBool::new(false);
+        let result = watch_thermal_zones(Vec::new, 80.0, Duration::from_millis(1), &stop, |_, _| Ok(()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_aggregate_temperatures() {
+        let zones = vec![
+            ("a".to_string(), 40.0),
+            ("b".to_string(), 0.0), // Ignored: sensor not reporting
+            ("c".to_string(), 60.0),
+        ];
+        assert_eq!(aggregate_temperatures(&zones), (50.0, 60.0));
+        assert_eq!(aggregate_temperatures(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_effective_temperature() {
+        let stats = SystemStats {
+            temperature_avg: 50.0,
+            temperature_max: 70.0,
+            ..Default::default()
+        };
+        assert_eq!(stats.effective_temperature(TemperatureStrategy::Max), 70.0);
+        assert_eq!(stats.effective_temperature(TemperatureStrategy::Avg), 50.0);
+    }
+
+    fn process(pid: u32, name: &str, memory_gb: f64, cpu_percentage: f64) -> ProcessInfo {
+        ProcessInfo { pid, name: name.to_string(), memory_gb, cpu_percentage, ..Default::default() }
+    }
+
+    #[test]
+    fn test_select_processes() {
+        let processes = vec![
+            process(30, "firefox", 1.5, 2.0),
+            process(10, "Code", 0.8, 40.0),
+            process(20, "firefox", 0.2, 9.0),
+        ];
+        let pids = |selected: Vec<ProcessInfo>| selected.iter().map(|p| p.pid).collect::<Vec<_>>();
+
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Memory, None)), vec![30, 10, 20]);
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Cpu, None)), vec![10, 20, 30]);
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Pid, None)), vec![10, 20, 30]);
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Name, None)), vec![10, 20, 30]);
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Memory, Some("FIRE"))), vec![30, 20]);
+        assert!(select_processes(processes.clone(), ProcessSort::Memory, Some("chrome")).is_empty());
+        assert_eq!(pids(select_processes(processes.clone(), ProcessSort::Unsorted, None)), pids(processes));
+    }
+
+    #[test]
+    fn test_threads_after_processes() {
+        let thread = |pid, tgid| ProcessInfo { tgid, ..process(pid, "worker", 1.0, 0.0) };
+        let processes = vec![
+            thread(31, 30),
+            process(10, "Code", 0.8, 40.0),
+            thread(11, 10),
+            process(30, "firefox", 1.5, 2.0),
+            thread(41, 40), // Its process was filtered out
+            thread(32, 30),
+        ];
+        let pids: Vec<u32> = threads_after_processes(processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![10, 11, 30, 31, 32, 41]);
+    }
+
+    // The results of stream_with_budget, collected
+    fn read_with_budget<T, F>(pids: Vec<u32>, budget: Duration, read: F) -> (HashMap<u32, T>, Option<u32>)
+    where
+        T: Send + 'static,
+        F: Fn(u32) -> T + Send + 'static,
+    {
+        let mut results = HashMap::new();
+        let stuck = stream_with_budget(pids, budget, read, |pid, value| {
+            if let Some(value) = value {
+                results.insert(pid, value);
+            }
+            ControlFlow::Continue(())
+        });
+        (results, stuck)
+    }
+
+    #[test]
+    fn test_stream_with_budget_hands_over_results_before_the_table_is_read() {
+        // 10 reads of 30ms: collecting first would hold everything back for 300ms
+        let started = Instant::now();
+        let mut first_visit = None;
+        let mut visited = Vec::new();
+        let stuck = stream_with_budget((1..=10).collect(), Duration::from_secs(10), |pid| {
+            std::thread::sleep(Duration::from_millis(30));
+            pid
+        }, |pid, value| {
+            first_visit.get_or_insert_with(|| started.elapsed());
+            visited.push((pid, value));
+            ControlFlow::Continue(())
+        });
+        let total = started.elapsed();
+
+        assert_eq!(stuck, None);
+        assert_eq!(visited, (1..=10).map(|pid| (pid, Some(pid))).collect::<Vec<_>>());
+        assert!(total >= Duration::from_millis(300));
+        assert!(first_visit.unwrap() < total / 2, "first row after {:?} of {:?}", first_visit, total);
+
+        // Breaking stops the walk
+        let mut seen = 0;
+        stream_with_budget((1..=10).collect(), Duration::from_secs(10), |pid| pid, |_, _| {
+            seen += 1;
+            if seen == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(seen, 3);
+    }
+
+    #[test]
+    fn test_sysinfo_skips_files_a_stuck_process_blocks() {
+        let kind = process_refresh_kind();
+        for update in [kind.cmd(), kind.environ(), kind.cwd(), kind.exe(), kind.root()] {
+            assert_eq!(update, UpdateKind::Never);
+        }
+
+        let sys = system_with_processes();
+        let own = sys.process(Pid::from_u32(std::process::id())).unwrap();
+        assert!(own.cmd().is_empty());
+        assert!(own.environ().is_empty());
+        assert!(!own.name().is_empty());
+        assert!(own.memory() > 0);
+    }
+
+    #[test]
+    fn test_stream_with_budget_ignores_slow_visit() {
+        // Every read is instant, but handing the rows over takes 5x the budget
+        let mut visited = Vec::new();
+        let stuck = stream_with_budget((1..=10).collect(), Duration::from_millis(100), |pid| pid, |pid, value| {
+            std::thread::sleep(Duration::from_millis(50));
+            visited.push((pid, value));
+            ControlFlow::Continue(())
+        });
+
+        assert_eq!(stuck, None);
+        assert_eq!(visited, (1..=10).map(|pid| (pid, Some(pid))).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_read_with_budget_gives_up_on_a_stuck_read() {
+        // PID 2 "blocks" far longer than the budget
+        let started = Instant::now();
+        let (results, stuck) = read_with_budget(vec![1, 2, 3], Duration::from_millis(200), |pid| {
+            if pid == 2 {
+                std::thread::sleep(Duration::from_secs(3));
+            }
+            pid * 10
+        });
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(results.get(&1), Some(&10));
+        assert!(!results.contains_key(&3));
+        assert_eq!(stuck, Some(2));
+
+        let (results, stuck) = read_with_budget(vec![1, 2], Duration::from_secs(5), |pid| pid);
+        assert_eq!(results.len(), 2);
+        assert_eq!(stuck, None);
+    }
+
+    #[test]
+    fn test_parse_pressure_avg10() {
+        let memory = "some avg10=12.50 avg60=3.10 avg300=0.80 total=987654\n\
+                      full avg10=4.00 avg60=1.00 avg300=0.20 total=12345\n";
+        assert_eq!(parse_pressure_avg10(memory), Some(12.5));
+        assert_eq!(parse_pressure_avg10("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n"), Some(0.0));
+        assert_eq!(parse_pressure_avg10("full avg10=4.00 avg60=1.00 avg300=0.20 total=1\n"), None);
+        assert_eq!(parse_pressure_avg10("some avg10=NaN\n"), None);
+        assert_eq!(parse_pressure_avg10(""), None);
+    }
+
+    #[test]
+    fn test_io_sampler_rates_between_updates() {
+        let io = "rchar: 9000\nwchar: 9000\nsyscr: 3\nsyscw: 3\nread_bytes: 1048576\nwrite_bytes: 4096\ncancelled_write_bytes: 0\n";
+        assert_eq!(parse_io_counters(io), Some(IoCounters { read_bytes: 1_048_576, write_bytes: 4096 }));
+        assert_eq!(parse_io_counters("rchar: 1\n"), None);
+
+        let counters = |read_mb: u64, write_mb: u64| Some(IoCounters { read_bytes: read_mb << 20, write_bytes: write_mb << 20 });
+        let mut processes = vec![process(1, "db", 1.0, 5.0), process(2, "other-user", 1.0, 5.0)];
+        let mut sampler = IoSampler::new();
+        let start = Instant::now();
+        sampler.update_with(&mut processes, start, |pid| (pid == 1).then(|| counters(100, 10)).flatten());
+        assert!(sampler.has_baseline());
+        assert_eq!(processes[0].io_mbps(), 0.0);
+
+        // 2 seconds later: 40 MB read and 20 MB written
+        sampler.update_with(&mut processes, start + Duration::from_secs(2), |pid| (pid == 1).then(|| counters(140, 30)).flatten());
+        assert_eq!(processes[0].io_read_mbps, 20.0);
+        assert_eq!(processes[0].io_write_mbps, 10.0);
+        assert_eq!(processes[1].io_mbps(), 0.0);
+
+        // A new process behind a reused PID starts over
+        processes[0].start_time = 99;
+        sampler.update_with(&mut processes, start + Duration::from_secs(3), |_| counters(500, 500));
+        assert_eq!(processes[0].io_mbps(), 0.0);
+    }
+
+    #[test]
+    fn test_parse_environ_var() {
+        let environ = b"PATH=/usr/bin:/bin\0VIRTUAL_ENV=/home/dev/venvs/api\0QUERY=a=b\0VIRTUAL=no\0EMPTY=\0RAW=\xfe\0";
+        assert_eq!(parse_environ_var(environ, "VIRTUAL_ENV").as_deref(), Some("/home/dev/venvs/api"));
+        assert_eq!(parse_environ_var(environ, "VIRTUAL").as_deref(), Some("no"));
+        assert_eq!(parse_environ_var(environ, "QUERY").as_deref(), Some("a=b"));
+        assert_eq!(parse_environ_var(environ, "EMPTY").as_deref(), Some(""));
+        assert_eq!(parse_environ_var(environ, "PATH").as_deref(), Some("/usr/bin:/bin"));
+        assert_eq!(parse_environ_var(environ, "RAW").as_deref(), Some("\u{FFFD}"));
+        assert_eq!(parse_environ_var(environ, "HOME"), None);
+        assert_eq!(parse_environ_var(environ, "VIRTUAL_"), None);
+        assert_eq!(parse_environ_var(b"", "PATH"), None);
+
+        // This test process: PATH is inherited at startup and never changed
+        assert_eq!(read_process_env_var(std::process::id(), "PATH"), std::env::var("PATH").ok());
+        assert_eq!(read_process_env_var(std::process::id(), "KERN_NO_SUCH_VARIABLE"), None);
+    }
+
+    #[test]
+    fn test_parse_cgroup() {
+        // cgroup v2: a single unified line
+        let v2 = "0::/system.slice/docker-4f1e2a.scope\n";
+        assert_eq!(parse_cgroup(v2).as_deref(), Some("/system.slice/docker-4f1e2a.scope"));
+        assert_eq!(parse_cgroup("0::/\n").as_deref(), Some("/"));
+
+        // cgroup v1: one line per hierarchy, the first one's path is used
+        let v1 = "12:memory:/docker/4f1e2a\n11:cpu,cpuacct:/docker/4f1e2a\n1:name=systemd:/docker/4f1e2a\n";
+        assert_eq!(parse_cgroup(v1).as_deref(), Some("/docker/4f1e2a"));
+
+        // Hybrid: the unified hierarchy wins
+        let hybrid = "12:memory:/user.slice\n1:name=systemd:/user.slice/session-2.scope\n0::/user.slice/session-2.scope\n";
+        assert_eq!(parse_cgroup(hybrid).as_deref(), Some("/user.slice/session-2.scope"));
+
+        assert_eq!(parse_cgroup(""), None);
+        assert_eq!(parse_cgroup("garbage\n"), None);
+
+        // This test process is in some cgroup
+        assert!(read_process_cgroup(std::process::id()).is_some_and(|path| path.starts_with('/')));
+    }
+
+    #[test]
+    fn test_top_processes_per_user() {
+        let owned = |pid, memory_gb, uid| ProcessInfo { uid, ..process(pid, "job", memory_gb, 1.0) };
+        let processes = vec![
+            owned(1, 0.5, Some(0)),
+            owned(2, 3.0, Some(4_000_000_001)),
+            owned(3, 1.0, Some(4_000_000_001)),
+            owned(4, 2.0, Some(4_000_000_001)),
+            owned(5, 2.5, Some(0)),
+            owned(6, 0.1, None),
+        ];
+        let users = top_processes_per_user(processes, 2);
+        let pids = |user: &str| users[user].iter().map(|p| p.pid).collect::<Vec<_>>();
+
+        assert_eq!(users.keys().collect::<Vec<_>>(), vec!["root", "uid 4000000001", "unknown"]);
+        assert_eq!(pids("root"), vec![5, 1]);
+        assert_eq!(pids("uid 4000000001"), vec![2, 4]); // The lightest one is cut
+        assert_eq!(pids("unknown"), vec![6]);
+
+        assert!(top_processes_per_user(Vec::new(), 2).is_empty());
+    }
+
+    #[test]
+    fn test_parse_status_uid() {
+        let status = "Name:\tsleep\nState:\tS (sleeping)\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(parse_status_uid(status), Some(1000)); // The real UID, not the effective one
+        assert_eq!(parse_status_uid("Name:\tsleep\n"), None);
+
+        let own = nix::unistd::getuid().as_raw();
+        assert_eq!(read_process_uid(std::process::id()), Some(own));
+        // sysinfo reports the same owner
+        let stats = get_system_stats(MemoryMetric::Rss, None).unwrap();
+        let this = stats.top_processes.iter().find(|p| p.pid == std::process::id());
+        assert!(this.map_or(true, |p| p.uid == Some(own)));
+    }
+
+    #[test]
+    fn test_parse_uptime_and_footprint() {
+        assert_eq!(parse_uptime("350735.47 234388.90\n"), Some(350735.47));
+        assert_eq!(parse_uptime(""), None);
+        assert_eq!(parse_uptime("-1 0"), None);
+
+        // 150 + 50 ticks of CPU over the 200s since it started at tick 10000 (100s after boot)
+        let stat = "4242 (kern (dbus)) S 1 4242 4242 0 -1 4194560 1 0 0 0 150 50 0 0 20 0 1 0 10000 0 0";
+        let status = "Name:\tkern\nVmRSS:\t   12800 kB\nThreads:\t4\n";
+        let footprint = parse_footprint(4242, stat, status, 300.0, 100.0).unwrap();
+        assert_eq!(footprint.pid, 4242);
+        assert_eq!(footprint.memory_mb, 12.5);
+        assert_eq!(footprint.cpu_time_secs, 2.0);
+        assert!((footprint.cpu_percent - 1.0).abs() < 1e-9);
+        assert_eq!(parse_footprint(4242, "4242 (kern) S 1", status, 300.0, 100.0), None);
+
+        let own = own_footprint().expect("this test process has a /proc entry");
+        assert_eq!(own.pid, std::process::id());
+        assert!(own.memory_mb > 0.0);
+
+        let mut stats = SystemStats::default();
+        stats.set_uptime(3600);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(now - stats.boot_time >= 3600 && now - stats.boot_time <= 3601);
+    }
+
+    #[test]
+    fn test_get_global_stats_is_fast() {
+        for _ in 0..3 {
+            let start = Instant::now();
+            let stats = get_global_stats(None).unwrap();
+            let elapsed = start.elapsed();
+            assert!(elapsed < Duration::from_millis(500), "get_global_stats took {:?}", elapsed);
+            assert!(stats.cpu_usage.is_finite());
+            assert!(stats.memory_percentage >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_sort_by_memory_with_nan() {
+        let mut processes = vec![
+            process(1, "small", 0.1, 0.0),
+            process(2, "bad-read", f64::NAN, 0.0),
+            process(3, "large", 2.0, 0.0),
+            process(4, "medium", 0.5, 0.0),
+        ];
+        sort_by_memory(&mut processes);
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_apply_cgroup_limits() {
+        let host = || SystemStats {
+            cpu_usage: 10.0,
+            cpu_count: 16,
+            total_memory_gb: 64.0,
+            used_memory_gb: 32.0,
+            memory_percentage: 50.0,
+            ..Default::default()
+        };
+        let gib = 1024 * 1024 * 1024;
+
+        // 4 GiB / 2 CPU container: 1 GiB used, 1s of CPU time over 1s = half its quota
+        let mut stats = host();
+        let limits = CgroupLimits {
+            memory_max_bytes: Some(4 * gib),
+            memory_current_bytes: Some(gib),
+            cpu_max_cores: Some(2.0),
+            ..Default::default()
+        };
+        stats.apply_cgroup_limits(limits, Some((Duration::from_secs(1), Duration::from_secs(1))));
+        assert_eq!(stats.total_memory_gb, 4.0);
+        assert_eq!(stats.used_memory_gb, 1.0);
+        assert_eq!(stats.memory_percentage, 25.0);
+        assert_eq!(stats.cpu_usage, 50.0);
+        assert_eq!(stats.cpu_capacity(), 2.0);
+        assert_eq!(stats.cpu_cores_used(), 1.0);
+        assert!(stats.cgroup.is_some());
+        assert_eq!(stats.memory_source(), MemorySource::Cgroup);
+
+        // A CPU-only limit leaves memory relative to the host
+        let mut stats = host();
+        stats.apply_cgroup_limits(CgroupLimits { cpu_max_cores: Some(2.0), ..Default::default() }, None);
+        assert_eq!(stats.memory_source(), MemorySource::Host);
+
+        // Limits looser than the machine change nothing
+        let mut stats = host();
+        let limits = CgroupLimits {
+            memory_max_bytes: Some(128 * gib),
+            cpu_max_cores: Some(32.0),
+            ..Default::default()
+        };
+        stats.apply_cgroup_limits(limits, None);
+        assert_eq!((stats.total_memory_gb, stats.cpu_usage, stats.cpu_capacity()), (64.0, 10.0, 16.0));
+        assert!(stats.cgroup.is_none());
+        assert_eq!(stats.memory_source(), MemorySource::Host);
+    }
+
+    #[test]
+    fn test_zero_total_memory_stats_stay_serializable() {
+        // sysinfo can report 0 total before its first refresh, and a cgroup
+        // can be capped at memory.max 0
+        let mut stats = SystemStats {
+            used_memory_gb: 1.5,
+            memory_percentage: memory_percentage(1.5, 0.0),
+            ..Default::default()
+        };
+        assert_eq!(stats.memory_percentage, 0.0);
+
+        let limits = CgroupLimits {
+            memory_max_bytes: Some(0),
+            memory_current_bytes: Some(4096),
+            ..Default::default()
+        };
+        stats.apply_cgroup_limits(limits, None);
+        assert_eq!(stats.total_memory_gb, 0.0);
+        assert_eq!(stats.memory_percentage, 0.0);
+
+        // NaN would serialize as null and break the extension's parser
+        let json = serde_json::json!({ "memory_percentage": stats.memory_percentage });
+        assert_eq!(json["memory_percentage"].as_f64(), Some(0.0));
+    }
+
+    #[test]
+    fn test_memory_percentage_degraded_readings() {
+        assert_eq!(memory_percentage(4.0, 16.0), 25.0);
+        // Containers that report no total memory
+        assert_eq!(memory_percentage(0.0, 0.0), 0.0);
+        assert_eq!(memory_percentage(1.0, 0.0), 0.0);
+        assert_eq!(memory_percentage(f64::NAN, 16.0), 0.0);
+        assert_eq!(memory_percentage(20.0, 16.0), 100.0);
+        assert_eq!(finite_or_zero(f64::NAN), 0.0);
+        assert_eq!(finite_or_zero(f64::INFINITY), 0.0);
+        assert_eq!(finite_or_zero(42.5), 42.5);
+    }
+
+    // Wait (up to 5s) until `pid` has been charged some CPU time: sysinfo only
+    // works out CPU% for a process whose first reading already had some
+    fn wait_for_cpu_time(pid: u32) {
+        let charged = || {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+            // utime and stime, fields 14 and 15
+            let after_comm = stat.rsplit_once(')').map_or("", |(_, rest)| rest);
+            after_comm.split_whitespace().skip(11).take(2).any(|ticks| ticks != "0")
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !charged() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_top_cpu_processes_from_real_samples() {
+        let spinner = std::process::Command::new("sh").args(["-c", "while :; do :; done"]).spawn().unwrap();
+        let idle = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        wait_for_cpu_time(spinner.id());
+        let pids = [spinner.id(), idle.id()];
+        let stats = get_system_stats(MemoryMetric::Rss, None);
+        // Stop the busy loop before anything can fail
+        for mut child in [spinner, idle] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+
+        let mut stats = stats.unwrap();
+        stats.top_processes.retain(|p| pids.contains(&p.pid));
+        let by_cpu = stats.top_cpu_processes();
+        assert_eq!(by_cpu.len(), 2);
+        assert_eq!(by_cpu[0].pid, pids[0]);
+        assert!(by_cpu[0].cpu_percentage > by_cpu[1].cpu_percentage);
+    }
+
+    #[test]
+    fn test_top_cpu_processes_is_stable_and_nan_safe() {
+        let stats = SystemStats {
+            // Memory-sorted, as collected
+            top_processes: vec![
+                process(30, "firefox", 1.5, 9.0),
+                process(10, "vanished", 0.9, f64::NAN),
+                process(20, "code", 0.8, 40.0),
+                process(40, "idle", 0.1, 9.0),
+            ],
+            ..Default::default()
+        };
+        let pids: Vec<u32> = stats.top_cpu_processes().iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![20, 30, 40, 10]);
+
+        let mut values = [1.0, f64::NAN, 3.0, f64::INFINITY];
+        values.sort_by(|a, b| descending(*a, *b));
+        assert_eq!(&values[..3], &[f64::INFINITY, 3.0, 1.0]);
+        assert!(values[3].is_nan());
+    }
+
+    #[test]
+    fn test_normalize_process_cpu() {
+        let mut stats = SystemStats {
+            cpu_count: 8,
+            top_processes: vec![process(1, "make", 1.0, 400.0), process(2, "idle", 0.1, 0.0)],
+            footprint: Some(ProcessFootprint { pid: 3, cpu_percent: 4.0, ..Default::default() }),
+            ..Default::default()
+        };
+        stats.normalize_process_cpu();
+        assert_eq!(stats.top_processes[0].cpu_percentage, 50.0);
+        assert_eq!(stats.top_processes[1].cpu_percentage, 0.0);
+        assert_eq!(stats.footprint.unwrap().cpu_percent, 0.5);
+
+        // No CPU count (e.g. a partial DBus reply) leaves values as they are
+        let mut processes = vec![process(1, "make", 1.0, 150.0)];
+        normalize_process_cpu(&mut processes, 0);
+        assert_eq!(processes[0].cpu_percentage, 150.0);
+    }
+
+    #[test]
+    fn test_group_processes() {
+        let processes = vec![
+            process(12, "chrome", 0.2, 1.0),
+            process(30, "code", 1.0, 20.0),
+            process(11, "chrome", 0.3, 2.0),
+            process(13, "chrome", 0.25, 30.0),
+        ];
+
+        let groups = group_processes(&processes);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "code");
+        assert_eq!(groups[1].name, "chrome");
+        assert_eq!(groups[1].pids, vec![11, 12, 13]);
+        assert_eq!(groups[1].count(), 3);
+        assert!((groups[1].memory_gb - 0.75).abs() < 1e-9);
+        assert!((groups[1].cpu_percentage - 33.0).abs() < 1e-9);
+
+        let mut by_cpu = groups.clone();
+        sort_groups(&mut by_cpu, ProcessSort::Cpu);
+        assert_eq!(by_cpu[0].name, "chrome");
+
+        let mut by_pid = groups;
+        sort_groups(&mut by_pid, ProcessSort::Pid);
+        assert_eq!(by_pid[0].name, "chrome");
+
+        assert!(group_processes(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_proc_stat() {
+        let kworker = "7 (kworker/0:1-events) I 2 0 0 0 -1 69238880 0 0 0 0 0 3 0 0 0 -20 1 0 17 0 0";
+        let stat = parse_proc_stat(kworker).unwrap();
+        assert_eq!(stat, ProcStat { state: 'I', ppid: 2, flags: 69238880, nice: Some(-20) });
+        assert!(stat.is_kernel_thread());
+
+        // comm with spaces and a closing parenthesis
+        let zombie = "4242 (Web (Content)) Z 4100 4100 4100 0 -1 4227076 0 0 0 0 0 0 0 0 20 0 1 0";
+        let stat = parse_proc_stat(zombie).unwrap();
+        assert_eq!((stat.state, stat.ppid, stat.nice), ('Z', 4100, Some(0)));
+        assert!(!stat.is_kernel_thread());
+
+        assert!(parse_proc_stat("garbage").is_none());
+        assert!(parse_proc_stat("1 (init) S").is_none());
+        // Truncated before the nice field
+        assert_eq!(parse_proc_stat("1 (init) S 0 1 1 0 -1 4194560").unwrap().nice, None);
+    }
+
+    #[test]
+    fn test_read_proc_stat_in() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("31337");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("stat"), "31337 (sleep) S 1 31337 31337 0 -1 4194304 0 0\n").unwrap();
+
+        let stat = read_proc_stat_in(temp_dir.path(), 31337).unwrap();
+        assert_eq!(stat.state, 'S');
+        assert!(read_proc_stat_in(temp_dir.path(), 31338).is_none());
+    }
+
+    // Captured from a Firefox content process (trimmed)
+    const SMAPS_ROLLUP: &str = "\
+55d6c2a4b000-7ffd3e5f2000 ---p 00000000 00:00 0                          [rollup]
+Rss:              412344 kB
+Pss:              168921 kB
+Pss_Dirty:        121036 kB
+Pss_Anon:         118712 kB
+Pss_File:          48277 kB
+Pss_Shmem:          1932 kB
+Shared_Clean:     226108 kB
+Private_Dirty:    119604 kB
+Swap:                  0 kB
+SwapPss:               0 kB
+";
+
+    const STATUS: &str = "\
+Name:\tIsolated Web Co
+Tgid:\t4242
+Pid:\t4242
+VmPeak:\t 2894220 kB
+VmRSS:\t  412344 kB
+RssAnon:\t  120416 kB
+";
+
+    fn write_proc(root: &Path, pid: u32, smaps_rollup: Option<&str>) {
+        let dir = root.join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("status"), STATUS).unwrap();
+        if let Some(contents) = smaps_rollup {
+            std::fs::write(dir.join("smaps_rollup"), contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_memory_fields() {
+        assert_eq!(parse_smaps_rollup_pss(SMAPS_ROLLUP), Some(168_921 * 1024));
+        assert_eq!(parse_status_rss(STATUS), Some(412_344 * 1024));
+        // Pss_Dirty etc. must not be mistaken for Pss
+        assert_eq!(parse_smaps_rollup_pss("Pss_Anon: 10 kB\n"), None);
+        assert_eq!(parse_status_rss(""), None);
+    }
+
+    #[test]
+    fn test_read_process_memory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_proc(root, 4242, Some(SMAPS_ROLLUP));
+        write_proc(root, 4343, None);
+
+        assert_eq!(read_process_memory(root, 4242, MemoryMetric::Rss), Some(412_344 * 1024));
+        assert_eq!(read_process_memory(root, 4242, MemoryMetric::Pss), Some(168_921 * 1024));
+        // Unreadable smaps_rollup falls back to RSS
+        assert_eq!(read_process_memory(root, 4343, MemoryMetric::Pss), Some(412_344 * 1024));
+        assert_eq!(read_process_memory(root, 4444, MemoryMetric::Pss), None);
+    }
+
+    #[test]
+    fn test_read_tgid_of_thread_group() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_proc(root, 4242, None);
+        // A thread of 4242, as it appears at the top level of /proc
+        let thread = root.join("4250");
+        std::fs::create_dir_all(&thread).unwrap();
+        std::fs::write(thread.join("status"), STATUS.replace("Pid:\t4242", "Pid:\t4250")).unwrap();
+
+        assert_eq!(read_tgid(root, 4242), Some(4242));
+        assert_eq!(read_tgid(root, 4250), Some(4242));
+        assert_eq!(read_tgid(root, 4444), None);
+    }
+
+    #[test]
+    fn test_diff_stats() {
+        let snapshot: Snapshot = serde_json::from_str(
+            r#"{
+                "cpu_usage": 20.0, "memory_percentage": 50.0, "temperature": 60.0, "source": "local",
+                "top_processes": [
+                    {"pid": 1, "name": "grower", "memory_gb": 1.0, "cpu_percentage": 0.0},
+                    {"pid": 2, "name": "shrinker", "memory_gb": 2.0, "cpu_percentage": 0.0},
+                    {"pid": 3, "name": "steady", "memory_gb": 1.0, "cpu_percentage": 0.0},
+                    {"pid": 4, "name": "exited", "memory_gb": 0.5, "cpu_percentage": 0.0},
+                    {"pid": 5, "name": "old-name", "memory_gb": 0.5, "cpu_percentage": 0.0},
+                    {"pid": 6, "name": "idle", "memory_gb": 0.0, "cpu_percentage": 0.0}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let stats = SystemStats {
+            cpu_usage: 35.0,
+            memory_percentage: 45.0,
+            temperature: 60.0,
+            top_processes: vec![
+                process(1, "grower", 1.5, 0.0),
+                process(2, "shrinker", 1.0, 0.0),
+                process(3, "steady", 1.05, 0.0),
+                process(5, "reused", 0.2, 0.0),
+                process(6, "idle", 0.3, 0.0),
+                process(7, "started", 0.1, 0.0),
+            ],
+            ..Default::default()
+        };
+
+        let diff = diff_stats(&snapshot, &stats);
+        let pids = |processes: &[SnapshotProcess]| processes.iter().map(|p| (p.pid, p.name.clone())).collect::<Vec<_>>();
+        assert_eq!(pids(&diff.new_processes), vec![(5, "reused".to_string()), (7, "started".to_string())]);
+        assert_eq!(pids(&diff.gone_processes), vec![(4, "exited".to_string()), (5, "old-name".to_string())]);
+        assert_eq!(diff.memory_increased.len(), 1);
+        assert_eq!(diff.memory_increased[0].name, "grower");
+        assert_eq!(diff.memory_increased[0].change_percent, 50.0);
+        assert_eq!(diff.memory_decreased.len(), 1);
+        assert_eq!(diff.memory_decreased[0].change_percent, -50.0);
+        assert_eq!(diff.cpu_usage.delta(), 15.0);
+        assert_eq!(diff.memory_percentage.delta(), -5.0);
+        assert_eq!(diff.temperature.delta(), 0.0);
+    }
+}
@@ -1,18 +1,22 @@
 use crate::config::NotificationConfig;
 use anyhow::Result;
 use notify_rust::Notification;
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+// Emergency mode is critical, so it has its own short cooldown
+const EMERGENCY_COOLDOWN: Duration = Duration::from_secs(5);
+
 /// Notification manager with rate limiting to avoid spam
 #[derive(Debug, Clone)]
 pub struct NotificationManager {
     enabled: bool,
     show_on_kill: bool,
     show_on_profile_switch: bool,
-    last_kill_notification: Option<Instant>,
-    last_emergency_notification: Option<Instant>,
-    last_warning_notification: Option<Instant>,
-    min_interval_between_notifications: Duration,
+    // Last notification per category, e.g. "kill:firefox" or "warn:CPU", so a
+    // CPU warning suppresses further CPU warnings but not RAM warnings
+    last_notification: HashMap<String, Instant>,
+    cooldown: Duration,
 }
 
 impl NotificationManager {
@@ -21,31 +25,44 @@ impl NotificationManager {
             enabled: config.enabled,
             show_on_kill: config.show_on_kill,
             show_on_profile_switch: config.show_on_profile_switch,
-            last_kill_notification: None,
-            last_emergency_notification: None,
-            last_warning_notification: None,
-            // Rate limit: 1 notification per 3 seconds to avoid spam
-            min_interval_between_notifications: Duration::from_secs(3),
+            last_notification: HashMap::new(),
+            cooldown: Duration::from_secs(config.cooldown_per_category_secs),
         }
     }
 
+    // True unless a notification of `category` was shown within `cooldown`
+    fn cooled_down(&self, category: &str, cooldown: Duration) -> bool {
+        self.last_notification
+            .get(category)
+            .filter(|last| last.elapsed() < cooldown)
+            .is_none()
+    }
+
+    fn mark_sent(&mut self, category: &str) {
+        self.last_notification.insert(category.to_string(), Instant::now());
+    }
+
     /// Show notification when a process is killed
-    pub fn notify_process_killed(&mut self, pid: u32, name: &str, count: usize) -> Result<()> {
+    pub fn notify_process_killed(
+        &mut self,
+        pid: u32,
+        name: &str,
+        count: usize,
+        reason: &str,
+    ) -> Result<()> {
         if !self.enabled || !self.show_on_kill {
             return Ok(());
         }
 
-        // Rate limiting
-        if let Some(last) = self.last_kill_notification {
-            if last.elapsed() < self.min_interval_between_notifications {
-                return Ok(());
-            }
+        let category = format!("kill:{}", name);
+        if !self.cooled_down(&category, self.cooldown) {
+            return Ok(());
         }
 
         let message = if count > 1 {
-            format!("Killed {} process(es) matching '{}'", count, name)
+            format!("Killed {} process(es) matching '{}' ({})", count, name, reason)
         } else {
-            format!("Killed process '{}' (PID: {})", name, pid)
+            format!("Killed process '{}' (PID: {}) - {}", name, pid, reason)
         };
 
         send_notification(
@@ -54,7 +71,31 @@ impl NotificationManager {
             notify_rust::Urgency::Normal,
         )?;
 
-        self.last_kill_notification = Some(Instant::now());
+        self.mark_sent(&category);
+        Ok(())
+    }
+
+    /// Show notification when a kill was attempted but the process survived,
+    /// so the user knows enforcement couldn't act
+    pub fn notify_kill_failed(&mut self, pid: u32, name: &str, error: &str) -> Result<()> {
+        if !self.enabled || !self.show_on_kill {
+            return Ok(());
+        }
+
+        let category = format!("kill_failed:{}", name);
+        if !self.cooled_down(&category, self.cooldown) {
+            return Ok(());
+        }
+
+        let message = format!("Could not kill '{}' (PID: {}) - {}", name, pid, error);
+
+        send_notification(
+            "⚠️ Kill Failed",
+            &message,
+            notify_rust::Urgency::Critical,
+        )?;
+
+        self.mark_sent(&category);
         Ok(())
     }
 
@@ -64,11 +105,8 @@ impl NotificationManager {
             return Ok(());
         }
 
-        // Emergency mode is critical, only rate limit by 5 seconds
-        if let Some(last) = self.last_emergency_notification {
-            if last.elapsed() < Duration::from_secs(5) {
-                return Ok(());
-            }
+        if !self.cooled_down("emergency", EMERGENCY_COOLDOWN) {
+            return Ok(());
         }
 
         let message = format!(
@@ -82,10 +120,51 @@ impl NotificationManager {
             notify_rust::Urgency::Critical,
         )?;
 
-        self.last_emergency_notification = Some(Instant::now());
+        self.mark_sent("emergency");
         Ok(())
     }
 
+    /// Alert-only emergency: shown every cycle without a cooldown, and kept on
+    /// screen until dismissed, since nothing is done about the temperature
+    pub fn notify_emergency_alert(&mut self, temperature: f64, critical_temp: f64, would_kill: &[String]) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let mut message = format!(
+            "Temperature {:.1}°C exceeds critical threshold {:.1}°C. Alert-only mode: nothing was killed",
+            temperature, critical_temp
+        );
+        if !would_kill.is_empty() {
+            message.push_str(&format!(" (would kill {})", would_kill.join(", ")));
+        }
+
+        show_notification(
+            "🔴 Emergency - Action Needed",
+            &message,
+            notify_rust::Urgency::Critical,
+            notify_rust::Timeout::Never,
+        )?;
+
+        self.mark_sent("emergency");
+        Ok(())
+    }
+
+    /// Alert-only mode: the process the enforcer would have killed for `reason`
+    pub fn notify_would_kill(&mut self, pid: u32, name: &str, reason: &str) -> Result<()> {
+        let message = format!("Would kill '{}' (PID: {}) - {} (alert-only mode, nothing was killed)", name, pid, reason);
+
+        self.notify_cooldown_warning(&format!("alert:{}", name), "⚠️ Limit Exceeded", &message)
+    }
+
+    /// A kill was called for `reason` but no candidate could be killed;
+    /// `skipped` says why, e.g. "4 protected, 2 critical"
+    pub fn notify_nothing_killable(&mut self, reason: &str, skipped: &str) -> Result<()> {
+        let message = format!("No process could be killed for {}: {}", reason, skipped);
+
+        self.notify_cooldown_warning(&format!("nothing_killable:{}", reason), "⚠️ Nothing to Kill", &message)
+    }
+
     /// Show notification for emergency mode deactivation
     pub fn notify_emergency_mode_resolved(&mut self, temperature: f64) -> Result<()> {
         if !self.enabled {
@@ -103,64 +182,98 @@ impl NotificationManager {
         Ok(())
     }
 
-    /// Show notification for resource limit exceeded
-    pub fn notify_resource_limit_exceeded(
-        &mut self,
-        resource_type: &str,
-        current: f64,
-        limit: f64,
-    ) -> Result<()> {
+    /// Show notification for memory emergency mode (swap nearly full)
+    pub fn notify_swap_emergency(&mut self, swap_percentage: f64, threshold: f64) -> Result<()> {
         if !self.enabled {
             return Ok(());
         }
 
-        // Rate limit warnings
-        if let Some(last) = self.last_warning_notification {
-            if last.elapsed() < self.min_interval_between_notifications {
-                return Ok(());
-            }
+        if !self.cooled_down("swap_emergency", EMERGENCY_COOLDOWN) {
+            return Ok(());
         }
 
         let message = format!(
-            "{} usage {:.1}% exceeds limit {:.1}%",
-            resource_type, current, limit
+            "⚠️ MEMORY EMERGENCY: Swap {:.1}% full (threshold {:.1}%) - the system is thrashing; killing the largest memory user",
+            swap_percentage, threshold
         );
 
         send_notification(
-            "⚠️ Resource Limit Exceeded",
+            "🔴 Memory Emergency Activated",
             &message,
             notify_rust::Urgency::Critical,
         )?;
 
-        self.last_warning_notification = Some(Instant::now());
+        self.mark_sent("swap_emergency");
         Ok(())
     }
 
-    /// Show notification when temperature warning threshold is reached
-    pub fn notify_temperature_warning(&mut self, temperature: f64, warning_temp: f64) -> Result<()> {
+    /// Show notification for memory emergency mode deactivation
+    pub fn notify_swap_emergency_resolved(&mut self, swap_percentage: f64) -> Result<()> {
         if !self.enabled {
             return Ok(());
         }
 
-        // Rate limit warnings
-        if let Some(last) = self.last_warning_notification {
-            if last.elapsed() < self.min_interval_between_notifications {
-                return Ok(());
-            }
-        }
+        let message = format!("Swap usage dropped to {:.1}% - memory back to normal", swap_percentage);
 
+        send_notification(
+            "🟢 Memory Emergency Resolved",
+            &message,
+            notify_rust::Urgency::Normal,
+        )?;
+
+        Ok(())
+    }
+
+    /// Show notification for resource limit exceeded
+    pub fn notify_resource_limit_exceeded(
+        &mut self,
+        resource_type: &str,
+        current: f64,
+        limit: f64,
+    ) -> Result<()> {
+        let message = format!(
+            "{} usage {:.1}% exceeds limit {:.1}%",
+            resource_type, current, limit
+        );
+
+        self.notify_cooldown_warning(&format!("warn:{}", resource_type), "⚠️ Resource Limit Exceeded", &message)
+    }
+
+    /// Show notification for a process over the profile's disk I/O limit
+    pub fn notify_io_limit_exceeded(&mut self, name: &str, mbps: f64, limit: f64) -> Result<()> {
+        let message = format!("{} disk I/O {:.1} MB/s exceeds limit {:.1} MB/s", name, mbps, limit);
+
+        self.notify_cooldown_warning("warn:io", "⚠️ Resource Limit Exceeded", &message)
+    }
+
+    /// Show notification when temperature warning threshold is reached
+    pub fn notify_temperature_warning(&mut self, temperature: f64, warning_temp: f64) -> Result<()> {
         let message = format!(
             "Temperature {:.1}°C exceeds warning threshold {:.1}°C",
             temperature, warning_temp
         );
 
-        send_notification(
-            "🌡️ Temperature Warning",
-            &message,
-            notify_rust::Urgency::Critical,
-        )?;
+        self.notify_cooldown_warning("warn:temperature", "🌡️ Temperature Warning", &message)
+    }
 
-        self.last_warning_notification = Some(Instant::now());
+    /// Show notification for a thermal zone over `kern thermal --alert`'s
+    /// threshold; each zone has its own cooldown
+    pub fn notify_thermal_alert(&mut self, zone: &str, temperature: f64, threshold: f64) -> Result<()> {
+        let message = format!("{} is at {:.1}°C (alert threshold {:.1}°C)", zone, temperature, threshold);
+
+        self.notify_cooldown_warning(&format!("thermal:{}", zone), "🌡️ Thermal Alert", &message)
+    }
+
+    /// Show a warning notification, unless one of the same `category`
+    /// (e.g. "warn:CPU") was shown within the configured cooldown
+    pub fn notify_cooldown_warning(&mut self, category: &str, title: &str, message: &str) -> Result<()> {
+        if !self.enabled || !self.cooled_down(category, self.cooldown) {
+            return Ok(());
+        }
+
+        send_notification(title, message, notify_rust::Urgency::Critical)?;
+
+        self.mark_sent(category);
         Ok(())
     }
 
@@ -204,19 +317,25 @@ impl NotificationManager {
 
 /// Internal helper to send a notification
 fn send_notification(title: &str, body: &str, urgency: notify_rust::Urgency) -> Result<()> {
+    show_notification(title, body, urgency, notify_rust::Timeout::Milliseconds(5000))
+}
+
+// send_notification with a custom timeout
+fn show_notification(title: &str, body: &str, urgency: notify_rust::Urgency, timeout: notify_rust::Timeout) -> Result<()> {
     // Check if we're running in a display environment
     if std::env::var("DISPLAY").is_err() && std::env::var("WAYLAND_DISPLAY").is_err() {
         // No display, silently skip notification (common on headless systems)
         return Ok(());
     }
 
-    Notification::new()
-        .summary(title)
-        .body(body)
-        .urgency(urgency)
-        .timeout(5000) // 5 second timeout
-        .show()
-        .ok(); // Ignore errors (e.g., no notification daemon running)
+    let mut notification = Notification::new();
+    notification.summary(title).body(body).timeout(timeout);
+    // Urgency is a freedesktop hint; macOS notifications don't have one
+    #[cfg(not(target_os = "macos"))]
+    notification.urgency(urgency);
+    #[cfg(target_os = "macos")]
+    let _ = urgency;
+    notification.show().ok(); // Ignore errors (e.g., no notification daemon running)
 
     Ok(())
 }
@@ -260,13 +379,65 @@ mod tests {
         let mut manager = NotificationManager::new(&config);
 
         // First kill notification should work
-        assert!(manager.notify_process_killed(1234, "test", 1).is_ok());
+        assert!(manager.notify_process_killed(1234, "test", 1, "test").is_ok());
+        let first = manager.last_notification["kill:test"];
+
+        // Second one for the same process is rate limited (we don't actually send it, so no error)
+        assert!(manager.notify_process_killed(5678, "test", 1, "test").is_ok());
+        assert_eq!(manager.last_notification["kill:test"], first);
+
+        // A different process has its own cooldown
+        assert!(manager.notify_process_killed(5678, "other", 1, "test").is_ok());
+        assert!(manager.last_notification.contains_key("kill:other"));
+    }
+
+    #[test]
+    fn test_kill_failed_rate_limited_separately() {
+        let config = NotificationConfig::default();
+        let mut manager = NotificationManager::new(&config);
+
+        assert!(manager.notify_process_killed(1234, "test", 1, "test").is_ok());
+        // A failure is reported even right after a success for the same name
+        assert!(manager.notify_kill_failed(5678, "test", "Operation not permitted").is_ok());
+        let first = manager.last_notification["kill_failed:test"];
+
+        assert!(manager.notify_kill_failed(5679, "test", "Operation not permitted").is_ok());
+        assert_eq!(manager.last_notification["kill_failed:test"], first);
+        assert_eq!(manager.last_notification.len(), 2);
+    }
+
+    #[test]
+    fn test_warning_cooldown_per_resource() {
+        let config = NotificationConfig::default();
+        let mut manager = NotificationManager::new(&config);
 
-        // Second one should be rate limited (we don't actually send it, so no error)
-        assert!(manager.notify_process_killed(5678, "test", 1).is_ok());
+        assert!(manager.notify_resource_limit_exceeded("CPU", 95.0, 80.0).is_ok());
+        let first = manager.last_notification["warn:CPU"];
+
+        // Another CPU warning is suppressed, but RAM and temperature are not
+        assert!(manager.notify_resource_limit_exceeded("CPU", 97.0, 80.0).is_ok());
+        assert_eq!(manager.last_notification["warn:CPU"], first);
+        assert!(manager.notify_resource_limit_exceeded("RAM", 90.0, 75.0).is_ok());
+        assert!(manager.notify_temperature_warning(80.0, 75.0).is_ok());
+        assert!(manager.last_notification.contains_key("warn:RAM"));
+        assert!(manager.last_notification.contains_key("warn:temperature"));
+
+        // Kills and emergencies are tracked separately from warnings
+        assert!(manager.notify_emergency_mode(90.0, 85.0).is_ok());
+        assert!(manager.last_notification.contains_key("emergency"));
+        assert_eq!(manager.last_notification.len(), 4);
+    }
+
+    #[test]
+    fn test_zero_cooldown_never_suppresses() {
+        let config = NotificationConfig { cooldown_per_category_secs: 0, ..Default::default() };
+        let mut manager = NotificationManager::new(&config);
 
-        // But the timestamp should still be updated
-        assert!(manager.last_kill_notification.is_some());
+        assert!(manager.notify_cooldown_warning("warn:CPU", "title", "first").is_ok());
+        let first = manager.last_notification["warn:CPU"];
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(manager.notify_cooldown_warning("warn:CPU", "title", "second").is_ok());
+        assert!(manager.last_notification["warn:CPU"] > first);
     }
 
     #[test]
@@ -276,7 +447,7 @@ mod tests {
         let mut manager = NotificationManager::new(&config);
 
         // No notifications should be sent when disabled
-        assert!(manager.notify_process_killed(1234, "test", 1).is_ok());
+        assert!(manager.notify_process_killed(1234, "test", 1, "test").is_ok());
         assert!(manager.notify_emergency_mode(90.0, 85.0).is_ok());
         assert!(manager.notify_profile_switched("old", "new").is_ok());
     }
@@ -288,8 +459,8 @@ mod tests {
         let mut manager = NotificationManager::new(&config);
 
         // Kill notification should not be sent when show_on_kill is false
-        assert!(manager.notify_process_killed(1234, "test", 1).is_ok());
-        assert!(manager.last_kill_notification.is_none());
+        assert!(manager.notify_process_killed(1234, "test", 1, "test").is_ok());
+        assert!(manager.last_notification.is_empty());
     }
 
     #[test]
@@ -0,0 +1,418 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::path::Path;
+
+use crate::config::TemperatureConfig;
+use crate::monitor::{self, ProcessInfo, SystemStats};
+use crate::stats::Trend;
+
+// Gauge colors: fine, warning, critical
+const GREEN: &str = "#2e7d32";
+const YELLOW: &str = "#f9a825";
+const RED: &str = "#c62828";
+
+/// Default text between the fields of `render_status_line`
+pub const LINE_SEPARATOR: &str = " | ";
+
+/// The current state on one line for status bars and tmux, e.g.
+/// `CPU 42% | RAM 61% | TEMP 54C | top:firefox`. Numbers are whole and plain;
+/// `top` (the heaviest process by memory) is left out when there are no processes.
+pub fn render_status_line(stats: &SystemStats, separator: &str) -> String {
+    let mut fields = vec![
+        format!("CPU {:.0}%", stats.cpu_usage),
+        format!("RAM {:.0}%", stats.memory_percentage),
+        format!("TEMP {:.0}C", stats.temperature_max),
+    ];
+    if let Some(top) = stats.top_processes.first() {
+        // Spaces and control characters would break splitting the line
+        let name: String = top
+            .name
+            .chars()
+            .map(|c| if c.is_whitespace() || c.is_control() { '_' } else { c })
+            .collect();
+        fields.push(format!("top:{}", name));
+    }
+    fields.join(separator)
+}
+
+/// Version of the JSON written by `--output-file` and `kern dbus --status-file`.
+/// Bumped when a field is removed or changes meaning, not when one is added.
+pub const OUTPUT_FILE_VERSION: u32 = 1;
+
+/// `data` in the envelope output files share:
+/// `{"format_version": 1, "written_at": <Unix seconds>, "data": ...}`
+pub fn output_file_json(data: serde_json::Value) -> serde_json::Value {
+    let written_at = chrono::Utc::now().timestamp();
+    serde_json::json!({
+        "format_version": OUTPUT_FILE_VERSION,
+        "written_at": written_at,
+        "data": data,
+    })
+}
+
+/// Write `data` to `path` in its output file envelope, see [`write_atomic`]
+pub fn write_output_file(path: &Path, data: serde_json::Value) -> Result<()> {
+    let contents = serde_json::to_string_pretty(&output_file_json(data))?;
+    write_atomic(path, format!("{}\n", contents).as_bytes())
+}
+
+/// Replace `path` with `contents` so readers see either the old file or the
+/// new one, never a partial write: write a temporary file next to it, sync
+/// it, then rename it over `path`. The temporary file is always created
+/// fresh (mode 0644), never through a symlink or into an existing file, so
+/// a root kern writing to a shared directory can't be pointed at another file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Output file {} has no file name", path.display()))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = create_new(&temp_path)
+        .or_else(|e| match e.kind() {
+            // Left behind by a crash, or planted: unlink it (never its target) and retry once
+            std::io::ErrorKind::AlreadyExists => {
+                std::fs::remove_file(&temp_path)?;
+                create_new(&temp_path)
+            }
+            _ => Err(e),
+        })
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    let written = (|| -> std::io::Result<()> {
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    written.map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        anyhow!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+// A new file at `path`, failing if anything (a symlink included) is already there
+fn create_new(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o644)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+}
+
+/// Widest command line `render_process_table` shows, in characters
+pub const COMMAND_WIDTH: usize = 60;
+
+/// `text` cut to at most `max_chars` characters, ending in "…" when cut
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// Processes as a numbered table (from 1), for picking some of them, e.g.
+/// `  1  4242     alice      2.50   15.3  /usr/lib/firefox/firefox -contentproc`.
+/// `command` gives each process's command line, cut to `COMMAND_WIDTH`.
+pub fn render_process_table(processes: &[ProcessInfo], command: impl Fn(&ProcessInfo) -> String) -> Vec<String> {
+    let mut lines = vec![format!("{:>3}  {:<8} {:<10} {:>7} {:>6}  {}", "#", "PID", "USER", "MEM(GB)", "CPU%", "COMMAND")];
+    for (i, process) in processes.iter().enumerate() {
+        lines.push(format!(
+            "{:>3}  {:<8} {:<10} {:>7.2} {:>6.1}  {}",
+            i + 1,
+            process.pid,
+            truncate(&monitor::owner_name(process.uid), 10),
+            process.memory_gb,
+            process.cpu_percentage,
+            truncate(&command(process), COMMAND_WIDTH)
+        ));
+    }
+    lines
+}
+
+// Sparkline levels, lowest first; the ASCII ones are for terminals without UTF-8
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_SPARK_LEVELS: [char; 8] = ['_', '.', ',', '-', '=', '+', '*', '#'];
+
+/// One character per reading, scaled between the lowest and highest of
+/// `values`, e.g. "▁▂▃▅▇". A flat series (or a non-finite reading) is drawn
+/// at the lowest level.
+pub fn render_sparkline(values: &[f32], ascii: bool) -> String {
+    let levels = if ascii { &ASCII_SPARK_LEVELS } else { &SPARK_LEVELS };
+    let finite = values.iter().copied().filter(|value| value.is_finite());
+    let min = finite.clone().fold(f32::INFINITY, f32::min);
+    let max = finite.fold(f32::NEG_INFINITY, f32::max);
+    let top = (levels.len() - 1) as f32;
+
+    values
+        .iter()
+        .map(|value| {
+            if !value.is_finite() || max <= min {
+                return levels[0];
+            }
+            levels[(((value - min) / (max - min)) * top).round() as usize]
+        })
+        .collect()
+}
+
+/// ↑, ↓ or → for a trend (^, v or - with `ascii`)
+pub fn trend_arrow(trend: &Trend, ascii: bool) -> &'static str {
+    match (trend, ascii) {
+        (Trend::Rising, false) => "↑",
+        (Trend::Falling, false) => "↓",
+        (Trend::Stable, false) => "→",
+        (Trend::Rising, true) => "^",
+        (Trend::Falling, true) => "v",
+        (Trend::Stable, true) => "-",
+    }
+}
+
+/// Uptime in its two largest units, e.g. "3d 4h", "4h 12m" or "12m"
+pub fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// Self-contained HTML snippet of the current system state (inline CSS only,
+/// so it survives being pasted into dashboards and emails)
+pub fn render_status_html(stats: &SystemStats, temperature: &TemperatureConfig) -> String {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    render_status_html_at(stats, temperature, &timestamp)
+}
+
+/// `snippet` wrapped in a complete HTML document
+pub fn full_page(snippet: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>kern status</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        snippet
+    )
+}
+
+fn render_status_html_at(stats: &SystemStats, temperature: &TemperatureConfig, timestamp: &str) -> String {
+    let mut html = String::from(
+        "<div class=\"kern-status\" style=\"font-family:sans-serif;max-width:720px;margin:0 auto\">\n",
+    );
+    html.push_str(&format!(
+        "<p style=\"color:#666;font-size:0.9em\">kern status at {}</p>\n",
+        escape(timestamp)
+    ));
+
+    html.push_str("<div style=\"display:flex;flex-wrap:wrap;gap:12px;margin-bottom:16px\">\n");
+    let temp = stats.temperature_max;
+    html.push_str(&gauge("CPU", &format!("{:.1}%", stats.cpu_usage), stats.cpu_usage, load_color(stats.cpu_usage)));
+    html.push_str(&gauge(
+        "RAM",
+        &format!("{:.1}% of {:.1} GB", stats.memory_percentage, stats.total_memory_gb),
+        stats.memory_percentage,
+        load_color(stats.memory_percentage),
+    ));
+    // Scaled against the critical threshold: a full bar means emergency mode
+    html.push_str(&gauge(
+        "Temperature",
+        &format!("{:.1} °C", temp),
+        temp / temperature.critical * 100.0,
+        threshold_color(temp, temperature.warning, temperature.critical),
+    ));
+    html.push_str("</div>\n");
+
+    html.push_str("<div style=\"overflow-x:auto\">\n<table style=\"width:100%;border-collapse:collapse\">\n");
+    html.push_str("<thead><tr style=\"text-align:left;border-bottom:2px solid #ccc\">");
+    for header in ["PID", "Name", "Memory (GB)", "CPU %"] {
+        html.push_str(&format!("<th style=\"padding:4px 8px\">{}</th>", header));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for p in stats.top_processes.iter().take(10) {
+        html.push_str(&format!(
+            "<tr style=\"border-bottom:1px solid #eee\"><td style=\"padding:4px 8px\">{}</td>\
+             <td style=\"padding:4px 8px\">{}</td><td style=\"padding:4px 8px\">{:.2}</td>\
+             <td style=\"padding:4px 8px\">{:.2}</td></tr>\n",
+            p.pid,
+            escape(&p.name),
+            p.memory_gb,
+            p.cpu_percentage
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</div>\n</div>\n");
+    html
+}
+
+// One labelled bar; `percent` is clamped so a bad reading can't overflow the layout
+fn gauge(label: &str, value: &str, percent: f64, color: &str) -> String {
+    let width = if percent.is_finite() { percent.clamp(0.0, 100.0) } else { 0.0 };
+    format!(
+        "<div style=\"flex:1 1 200px\">\
+         <div>{} <strong>{}</strong></div>\
+         <div style=\"background:#eee;border-radius:4px;height:10px\">\
+         <div style=\"width:{:.1}%;height:10px;border-radius:4px;background:{}\"></div></div></div>\n",
+        label,
+        escape(value),
+        width,
+        color
+    )
+}
+
+fn load_color(percent: f64) -> &'static str {
+    threshold_color(percent, 75.0, 90.0)
+}
+
+fn threshold_color(value: f64, warning: f64, critical: f64) -> &'static str {
+    if value >= critical {
+        RED
+    } else if value >= warning {
+        YELLOW
+    } else {
+        GREEN
+    }
+}
+
+// Process names are arbitrary text
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_stats() -> SystemStats {
+        let process = |pid, name: &str, memory_gb, cpu_percentage| ProcessInfo {
+            pid,
+            name: name.to_string(),
+            memory_gb,
+            cpu_percentage,
+            ..Default::default()
+        };
+        SystemStats {
+            cpu_usage: 42.5,
+            cpu_count: 8,
+            total_memory_gb: 16.0,
+            used_memory_gb: 14.72,
+            memory_percentage: 92.0,
+            temperature: 80.0,
+            temperature_avg: 71.0,
+            temperature_max: 80.0,
+            top_processes: vec![
+                process(4242, "firefox", 2.5, 15.25),
+                process(1337, "<script>&\"build\"", 1.0, 99.0),
+            ],
+            cgroup: None,
+            cpu_pressure: None,
+            mem_pressure: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_status_html_matches_fixture() {
+        let html = render_status_html_at(&sample_stats(), &TemperatureConfig::default(), "2026-01-02 03:04:05");
+        assert_eq!(html, include_str!("../tests/test_output/status.html"));
+    }
+
+    #[test]
+    fn test_full_page_wraps_snippet() {
+        let page = full_page("<div>snippet</div>\n");
+        assert!(page.starts_with("<!DOCTYPE html>\n<html>\n<head>"));
+        assert!(page.contains("<body>\n<div>snippet</div>\n</body>\n</html>"));
+    }
+
+    #[test]
+    fn test_render_status_line() {
+        let mut stats = sample_stats();
+        assert_eq!(render_status_line(&stats, LINE_SEPARATOR), "CPU 42% | RAM 92% | TEMP 80C | top:firefox");
+        assert_eq!(render_status_line(&stats, ","), "CPU 42%,RAM 92%,TEMP 80C,top:firefox");
+
+        stats.top_processes[0].name = "Web Content\n".to_string();
+        assert!(render_status_line(&stats, " ").ends_with("top:Web_Content_"));
+
+        stats.top_processes.clear();
+        assert_eq!(render_status_line(&stats, LINE_SEPARATOR), "CPU 42% | RAM 92% | TEMP 80C");
+    }
+
+    #[test]
+    fn test_render_sparkline() {
+        assert_eq!(render_sparkline(&[0.0, 10.0, 20.0, 40.0, 70.0], false), "▁▂▃▅█");
+        assert_eq!(render_sparkline(&[70.0, 0.0, 35.0], true), "#_=");
+        assert_eq!(render_sparkline(&[55.0, 55.0, f32::NAN], false), "▁▁▁");
+        assert_eq!(render_sparkline(&[], false), "");
+        assert_eq!(trend_arrow(&Trend::Rising, false), "↑");
+        assert_eq!(trend_arrow(&Trend::Falling, true), "v");
+    }
+
+    #[test]
+    fn test_render_process_table() {
+        assert_eq!(truncate("firefox", 7), "firefox");
+        assert_eq!(truncate("firefox", 5), "fire…");
+        assert_eq!(truncate("", 0), "");
+
+        let processes = sample_stats().top_processes;
+        let long = format!("/usr/lib/firefox/firefox {}", "-a ".repeat(40));
+        let lines = render_process_table(&processes, |p| if p.pid == 4242 { long.clone() } else { p.name.clone() });
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "  #  PID      USER       MEM(GB)   CPU%  COMMAND");
+        assert!(lines[1].starts_with("  1  4242     unknown       2.50   15.2  /usr/lib/firefox/firefox -a"), "{}", lines[1]);
+        assert!(lines[1].ends_with('…') && lines[1].chars().count() == 41 + COMMAND_WIDTH, "{}", lines[1]);
+        assert!(lines[2].starts_with("  2  1337"), "{}", lines[2]);
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(45), "0m");
+        assert_eq!(format_uptime(12 * 60 + 59), "12m");
+        assert_eq!(format_uptime(4 * 3600 + 12 * 60), "4h 12m");
+        assert_eq!(format_uptime(3 * 86_400 + 4 * 3600 + 59 * 60), "3d 4h");
+    }
+
+    #[test]
+    fn test_gauge_clamps_bad_readings() {
+        assert!(gauge("CPU", "NaN", f64::NAN, GREEN).contains("width:0.0%"));
+        assert!(gauge("Temperature", "130 °C", 150.0, RED).contains("width:100.0%"));
+    }
+
+    #[test]
+    fn test_write_output_file_replaces_atomically() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("status.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_output_file(&path, serde_json::json!({"cpu_usage": 12.5})).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["format_version"], OUTPUT_FILE_VERSION);
+        assert_eq!(written["data"], serde_json::json!({"cpu_usage": 12.5}));
+        assert!(written["written_at"].as_i64().unwrap() > 0);
+        // No temporary file left next to it
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        assert!(write_atomic(&temp_dir.path().join("missing/status.json"), b"{}").is_err());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_does_not_follow_symlink() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let victim = temp_dir.path().join("shadow");
+        std::fs::write(&victim, "secret").unwrap();
+        let path = temp_dir.path().join("status.json");
+        // Planted where write_atomic puts its temporary file
+        let planted = temp_dir.path().join(format!(".status.json.{}.tmp", std::process::id()));
+        std::os::unix::fs::symlink(&victim, &planted).unwrap();
+
+        write_atomic(&path, b"{}").unwrap();
+        assert_eq!(std::fs::read_to_string(&victim).unwrap(), "secret");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+        assert!(std::fs::symlink_metadata(&planted).is_err());
+    }
+}
@@ -1,10 +1,12 @@
+use crate::config::EnforcementMode;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Profile {
     pub name: String,
     pub description: String,
@@ -16,19 +18,93 @@ pub struct Profile {
     pub limits: ProfileResourceLimits, // Resource limits for this profile
     #[serde(default)]
     pub auto_activate: AutoActivateConfig, // Auto-activation rules
+    #[serde(default)]
+    pub kill_strategy: Option<KillStrategy>, // Overrides victim selection for every limit
+    #[serde(default = "default_enforce")]
+    pub enforce: bool, // false = monitor-only: alert on limits but never kill
+    #[serde(default)]
+    pub enforcement: ProfileEnforcement, // Overrides kern.yaml's enforcement settings
+    #[serde(default)]
+    pub group_processes: bool, // Pick victims by application (summed over same-named processes)
+    #[serde(default)]
+    pub priority: Option<u32>, // Auto-activation priority: lower wins, unset ranks last
+    #[serde(default)]
+    pub restart_after_kill: Vec<String>, // Processes relaunched once conditions settle after the enforcer kills them
+}
+
+/// How the enforcer picks which process to kill when a limit is exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillStrategy {
+    HeaviestCpu,
+    HeaviestMemory,
+    HeaviestCpuPlusMemory, // Equal-weight sum of CPU and memory, each relative to the heaviest
+    Weighted, // Like HeaviestCpuPlusMemory, weighted per exceeded limit by kern.yaml's victim_weights
+    HeaviestIo, // Disk reads plus writes per second
+    Oldest,
+    Newest,
+}
+
+impl KillStrategy {
+    /// Short human-readable description used in logs and notifications
+    pub fn describe(&self) -> &'static str {
+        match self {
+            KillStrategy::HeaviestCpu => "highest CPU consumer",
+            KillStrategy::HeaviestMemory => "highest memory consumer",
+            KillStrategy::HeaviestCpuPlusMemory => "highest combined CPU and memory consumer",
+            KillStrategy::Weighted => "highest weighted CPU and memory score",
+            KillStrategy::HeaviestIo => "highest disk I/O consumer",
+            KillStrategy::Oldest => "oldest process",
+            KillStrategy::Newest => "newest process",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProfileResourceLimits {
     #[serde(default = "default_max_cpu")]
     pub max_cpu_percent: f64, 
     #[serde(default = "default_max_ram")]
     pub max_ram_percent: f64,
+    #[serde(default)]
+    pub max_cpu_cores: Option<f64>, // Absolute CPU cap in cores' worth, checked alongside max_cpu_percent
+    #[serde(default)]
+    pub max_ram_gb: Option<f64>, // Absolute RAM cap, checked alongside max_ram_percent
+    #[serde(default)]
+    pub max_mem_pressure: Option<f64>, // PSI memory "some" avg10 (% of time stalled); ignored without PSI
+    #[serde(default)]
+    pub max_io_mbps: Option<f64>, // Per-process disk reads plus writes, in MB/s
     #[serde(default = "default_max_temp")]
     pub max_temp: f64,
+    #[serde(default)]
+    pub temp_warning: Option<f64>, // Overrides temperature.warning from kern.yaml
+    #[serde(default)]
+    pub temp_critical: Option<f64>, // Overrides temperature.critical (emergency mode)
+    #[serde(default)]
+    pub action: LimitAction, // What to do with the heaviest process when CPU/RAM limits are exceeded
+    #[serde(default = "default_cgroup_cpu_percent")]
+    pub cgroup_cpu_percent: f64, // CPU quota (share of the whole machine) for cgroup_limit
+    #[serde(default)]
+    pub cgroup_memory_high_mb: Option<u64>, // memory.high for cgroup_limit (unlimited if unset)
+}
+
+/// Action taken on the offending process when a CPU or RAM limit is exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    #[default]
+    Kill,
+    CgroupLimit, // Move it into a CPU/memory-limited cgroup instead of killing it
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Per-profile enforcement settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileEnforcement {
+    #[serde(default)]
+    pub mode: EnforcementMode, // "alert" makes this profile alert-only; kern.yaml's alert applies to every profile
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AutoActivateConfig { 
     #[serde(default)]
     pub enabled: bool,
@@ -36,11 +112,23 @@ pub struct AutoActivateConfig {
     pub triggers: Vec<AutoActivateTrigger>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AutoActivateTrigger {
     #[serde(rename = "type")]
     pub trigger_type: Option<String>,
     pub command_contains: Option<String>,
+    #[serde(default)]
+    pub idle_minutes: Option<u64>, // type "idle": activate once the session has been idle this long
+}
+
+impl AutoActivateTrigger {
+    /// How long the session must be idle for an "idle" trigger to fire
+    pub fn idle_threshold(&self) -> Option<Duration> {
+        match self.trigger_type.as_deref() {
+            Some("idle") => self.idle_minutes.map(|minutes| Duration::from_secs(minutes * 60)),
+            _ => None,
+        }
+    }
 }
 
 // Default values
@@ -56,16 +144,65 @@ fn default_max_temp() -> f64 {
     85.0
 }
 
+fn default_cgroup_cpu_percent() -> f64 {
+    25.0
+}
+
+fn default_enforce() -> bool {
+    true
+}
+
 impl Default for ProfileResourceLimits {
     fn default() -> Self {
         Self {
             max_cpu_percent: default_max_cpu(),
             max_ram_percent: default_max_ram(),
+            max_cpu_cores: None,
+            max_ram_gb: None,
+            max_mem_pressure: None,
+            max_io_mbps: None,
             max_temp: default_max_temp(),
+            temp_warning: None,
+            temp_critical: None,
+            action: LimitAction::default(),
+            cgroup_cpu_percent: default_cgroup_cpu_percent(),
+            cgroup_memory_high_mb: None,
         }
     }
 }
 
+impl ProfileResourceLimits {
+    /// CPU limit as a share of the whole machine: max_cpu_percent, or max_cpu_cores if stricter
+    pub fn effective_cpu_percent(&self, cpu_capacity: f64) -> f64 {
+        effective_percent(self.max_cpu_percent, self.max_cpu_cores, cpu_capacity)
+    }
+
+    /// RAM limit as a share of total memory: max_ram_percent, or max_ram_gb if stricter
+    pub fn effective_ram_percent(&self, total_memory_gb: f64) -> f64 {
+        effective_percent(self.max_ram_percent, self.max_ram_gb, total_memory_gb)
+    }
+
+    /// Absolute limits larger than this machine (they can never be exceeded)
+    pub fn capacity_warnings(&self, cpu_capacity: f64, total_memory_gb: f64) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(cores) = self.max_cpu_cores.filter(|cores| *cores > cpu_capacity) {
+            warnings.push(format!("max_cpu_cores {} exceeds the {} CPUs on this machine", cores, cpu_capacity));
+        }
+        if let Some(gb) = self.max_ram_gb.filter(|gb| *gb > total_memory_gb) {
+            warnings.push(format!("max_ram_gb {} exceeds the {:.1} GB of RAM on this machine", gb, total_memory_gb));
+        }
+        warnings
+    }
+}
+
+// Fold an absolute limit into a percentage of `capacity`, keeping the stricter one
+fn effective_percent(percent: f64, absolute: Option<f64>, capacity: f64) -> f64 {
+    match absolute {
+        Some(absolute) if capacity > 0.0 => percent.min(absolute / capacity * 100.0),
+        _ => percent,
+    }
+}
+
 impl Default for AutoActivateConfig {
     fn default() -> Self {
         Self {
@@ -84,13 +221,19 @@ impl Default for Profile {
             kill_on_activate: Vec::new(),
             limits: ProfileResourceLimits::default(),
             auto_activate: AutoActivateConfig::default(),
+            kill_strategy: None,
+            enforce: default_enforce(),
+            enforcement: ProfileEnforcement::default(),
+            group_processes: false,
+            priority: None,
+            restart_after_kill: Vec::new(),
         }
     }
 }
 
 impl Profile {
     /// Load a single profile from a YAML file
-    pub fn load_from_file(path: &PathBuf) -> Result<Self> {
+    pub fn load_from_file(path: &Path) -> Result<Self> {
         let contents = fs::read_to_string(path)?;
         let profile: Profile = serde_yaml::from_str(&contents)?;
         profile.validate()?;
@@ -98,7 +241,14 @@ impl Profile {
     }
 
     /// Validate profile values
-    fn validate(&self) -> Result<()> {
+    ///
+    /// ```
+    /// let mut profile = kern::profiles::Profile { name: "coding".to_string(), ..Default::default() };
+    /// assert!(profile.validate().is_ok());
+    /// profile.limits.max_ram_percent = 120.0;
+    /// assert!(profile.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
         // Validate name is not empty
         if self.name.is_empty() {
             return Err(anyhow!("Profile name cannot be empty"));
@@ -119,6 +269,24 @@ impl Profile {
             ));
         }
 
+        for (field, value) in [
+            ("max_cpu_cores", self.limits.max_cpu_cores),
+            ("max_ram_gb", self.limits.max_ram_gb),
+            ("max_io_mbps", self.limits.max_io_mbps),
+        ] {
+            if let Some(value) = value {
+                if value.is_nan() || value < 0.0 {
+                    return Err(anyhow!("Invalid {}: {} (must be >= 0)", field, value));
+                }
+            }
+        }
+
+        if let Some(pressure) = self.limits.max_mem_pressure {
+            if !(0.0..=100.0).contains(&pressure) {
+                return Err(anyhow!("Invalid max_mem_pressure: {} (must be 0-100)", pressure));
+            }
+        }
+
         // Validate temperature (0-120°C is reasonable range)
         if !(0.0..=120.0).contains(&self.limits.max_temp) {
             return Err(anyhow!(
@@ -127,6 +295,41 @@ impl Profile {
             ));
         }
 
+        if !(1.0..=100.0).contains(&self.limits.cgroup_cpu_percent) {
+            return Err(anyhow!(
+                "Invalid cgroup_cpu_percent: {} (must be 1-100)",
+                self.limits.cgroup_cpu_percent
+            ));
+        }
+
+        // Validate temperature threshold overrides
+        for (field, value) in [
+            ("temp_warning", self.limits.temp_warning),
+            ("temp_critical", self.limits.temp_critical),
+        ] {
+            if let Some(value) = value {
+                if !(0.0..=120.0).contains(&value) {
+                    return Err(anyhow!("Invalid {}: {} (must be 0-120°C)", field, value));
+                }
+            }
+        }
+
+        if let (Some(warning), Some(critical)) = (self.limits.temp_warning, self.limits.temp_critical) {
+            if critical <= warning {
+                return Err(anyhow!(
+                    "Invalid temperatures: temp_critical ({}) must be > temp_warning ({})",
+                    critical,
+                    warning
+                ));
+            }
+        }
+
+        for trigger in &self.auto_activate.triggers {
+            if trigger.trigger_type.as_deref() == Some("idle") && !trigger.idle_minutes.is_some_and(|minutes| minutes > 0) {
+                return Err(anyhow!("Invalid idle trigger: idle_minutes must be set and > 0"));
+            }
+        }
+
         Ok(())
     }
 }
@@ -165,10 +368,10 @@ impl ProfileManager {
                                 profiles.insert(profile_name, profile);
                             }
                             Err(e) => {
-                                eprintln!(
-                                    "Warning: Failed to load profile {}: {}",
+                                crate::log::warning(&format!(
+                                    "Failed to load profile {}: {}",
                                     profile_name, e
-                                );
+                                ));
                             }
                         }
                     }
@@ -183,6 +386,8 @@ impl ProfileManager {
             ));
         }
 
+        validate_priorities(&profiles)?;
+
         // Default to "normal" profile if it exists, otherwise use first available
         let current_profile = if profiles.contains_key("normal") {
             "normal".to_string()
@@ -230,6 +435,32 @@ impl ProfileManager {
         Ok(())
     }
 
+    /// Pick the profile to switch to when the auto-activation triggers of `triggered`
+    /// fired in the same cycle: the lowest `priority` wins, ties go to the name that sorts
+    /// first. Returns None if nothing should change, including when the active profile
+    /// outranks the winner.
+    pub fn resolve_auto_activation<'a>(&self, triggered: &[&'a str]) -> Option<&'a str> {
+        // Profiles without a priority rank after every numbered one
+        let priority_rank = |profile: &Profile| profile.priority.map_or(u64::MAX, u64::from);
+        let winner = triggered
+            .iter()
+            .filter_map(|name| self.profiles.get(*name).map(|profile| (*name, profile)))
+            .min_by(|(a_name, a), (b_name, b)| {
+                priority_rank(a).cmp(&priority_rank(b)).then(a_name.cmp(b_name))
+            })
+            .map(|(name, _)| name)?;
+
+        if winner == self.current_profile {
+            return None;
+        }
+        if let Ok(current) = self.current() {
+            if priority_rank(current) < priority_rank(&self.profiles[winner]) {
+                return None;
+            }
+        }
+        Some(winner)
+    }
+
     /// Get a specific profile by name
     pub fn get(&self, profile_name: &str) -> Option<&Profile> {
         self.profiles.get(profile_name)
@@ -265,6 +496,192 @@ impl ProfileManager {
         Ok(())
     }
 
+    /// Validate `profile`, write it to `<config_dir>/profiles/<name>.yaml` and
+    /// make it available. Fails if a profile with that name already exists;
+    /// use `create_profile_overwrite` to replace it.
+    pub fn create_profile(&mut self, profile: Profile) -> Result<PathBuf> {
+        self.write_profile(profile, false)
+    }
+
+    /// Like `create_profile`, but replaces an existing profile with the same name
+    pub fn create_profile_overwrite(&mut self, profile: Profile) -> Result<PathBuf> {
+        self.write_profile(profile, true)
+    }
+
+    /// Copy `source_name` to a new profile `new_name`: the same file with its
+    /// `name` changed, available immediately. Fails if `new_name` already exists.
+    pub fn duplicate(&mut self, source_name: &str, new_name: &str) -> Result<PathBuf> {
+        let Some(source) = self.profiles.get(source_name) else {
+            return Err(anyhow!(
+                "Profile '{}' not found. Available: {}",
+                source_name,
+                self.list_names().join(", ")
+            ));
+        };
+
+        // Copy the YAML rather than the parsed profile, so keys kern doesn't know about survive
+        let source_path = self.config_dir.join("profiles").join(format!("{}.yaml", source_name));
+        let mut yaml: serde_yaml::Value = match fs::read_to_string(&source_path) {
+            Ok(contents) => serde_yaml::from_str(&contents)?,
+            Err(_) => serde_yaml::to_value(source)?, // Built in, not loaded from a file
+        };
+        yaml.as_mapping_mut()
+            .ok_or_else(|| anyhow!("{} is not a YAML mapping", source_path.display()))?
+            .insert("name".into(), new_name.into());
+
+        let profile: Profile = serde_yaml::from_value(yaml.clone())?;
+        self.write_profile_yaml(profile, &yaml, false)
+    }
+
+    fn write_profile(&mut self, profile: Profile, overwrite: bool) -> Result<PathBuf> {
+        let yaml = serde_yaml::to_value(&profile)?;
+        self.write_profile_yaml(profile, &yaml, overwrite)
+    }
+
+    // Write `yaml`, which parses as `profile`, to the profile's file
+    fn write_profile_yaml(&mut self, profile: Profile, yaml: &serde_yaml::Value, overwrite: bool) -> Result<PathBuf> {
+        profile.validate()?;
+
+        // The name becomes a file name, so it must not escape the profiles directory
+        if profile.name.contains('/') || profile.name.starts_with('.') {
+            return Err(anyhow!(
+                "Invalid profile name '{}' (must not contain '/' or start with '.')",
+                profile.name
+            ));
+        }
+
+        let profiles_dir = self.config_dir.join("profiles");
+        let path = profiles_dir.join(format!("{}.yaml", profile.name));
+
+        if !overwrite && (self.profiles.contains_key(&profile.name) || path.exists()) {
+            return Err(anyhow!("Profile '{}' already exists", profile.name));
+        }
+
+        fs::create_dir_all(&profiles_dir)?;
+        fs::write(&path, serde_yaml::to_string(yaml)?)?;
+        self.profiles.insert(profile.name.clone(), profile);
+        Ok(path)
+    }
+
+    /// Set one `limits` field of `profile_name` (e.g. "max_cpu_percent") and
+    /// save it. `value` is parsed as YAML: a number, `null` to clear an
+    /// optional limit, or e.g. `cgroup_limit` for `action`. The result must
+    /// pass `Profile::validate`.
+    pub fn set_limit(&mut self, profile_name: &str, key: &str, value: &str) -> Result<&Profile> {
+        let known = serde_yaml::to_value(ProfileResourceLimits::default())?;
+        if known.get(key).is_none() {
+            return Err(anyhow!("Unknown limit '{}'", key));
+        }
+        let value: serde_yaml::Value =
+            serde_yaml::from_str(value).map_err(|e| anyhow!("Invalid value for {}: {}", key, e))?;
+
+        self.edit_profile_file(profile_name, |yaml| {
+            let limits = yaml
+                .entry("limits".into())
+                .or_insert_with(|| serde_yaml::Mapping::new().into());
+            match limits.as_mapping_mut() {
+                Some(limits) => {
+                    limits.insert(key.into(), value);
+                    Ok(())
+                }
+                None => Err(anyhow!("'limits' is not a mapping")),
+            }
+        })
+    }
+
+    /// Add `process` to the `protected` list of `profile_name` and save it.
+    /// Returns false if it was already protected.
+    pub fn add_protected(&mut self, profile_name: &str, process: &str) -> Result<bool> {
+        if process.trim().is_empty() {
+            return Err(anyhow!("Process name cannot be empty"));
+        }
+        if self.get(profile_name).is_some_and(|p| p.protected.iter().any(|name| name == process)) {
+            return Ok(false);
+        }
+        self.edit_protected(profile_name, |protected| protected.push(process.into()))?;
+        Ok(true)
+    }
+
+    /// Remove `process` from the `protected` list of `profile_name` and save it.
+    /// Returns false if it wasn't protected.
+    pub fn remove_protected(&mut self, profile_name: &str, process: &str) -> Result<bool> {
+        if !self.get(profile_name).is_some_and(|p| p.protected.iter().any(|name| name == process)) {
+            return Ok(false);
+        }
+        self.edit_protected(profile_name, |protected| protected.retain(|name| name.as_str() != Some(process)))?;
+        Ok(true)
+    }
+
+    fn edit_protected(&mut self, profile_name: &str, edit: impl FnOnce(&mut Vec<serde_yaml::Value>)) -> Result<&Profile> {
+        self.edit_profile_file(profile_name, |yaml| {
+            let protected = yaml
+                .entry("protected".into())
+                .or_insert_with(|| serde_yaml::Value::Sequence(Vec::new()));
+            match protected.as_sequence_mut() {
+                Some(protected) => {
+                    edit(protected);
+                    Ok(())
+                }
+                None => Err(anyhow!("'protected' is not a list")),
+            }
+        })
+    }
+
+    // Edit `<config_dir>/profiles/<profile_name>.yaml` as a YAML mapping, so
+    // keys kern doesn't know about survive. Nothing is written, and the loaded
+    // profile is unchanged, unless the result parses and validates.
+    fn edit_profile_file(
+        &mut self,
+        profile_name: &str,
+        edit: impl FnOnce(&mut serde_yaml::Mapping) -> Result<()>,
+    ) -> Result<&Profile> {
+        if !self.profiles.contains_key(profile_name) {
+            return Err(anyhow!(
+                "Profile '{}' not found. Available: {}",
+                profile_name,
+                self.list_names().join(", ")
+            ));
+        }
+
+        let path = self.config_dir.join("profiles").join(format!("{}.yaml", profile_name));
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+        let mapping = yaml
+            .as_mapping_mut()
+            .ok_or_else(|| anyhow!("{} is not a YAML mapping", path.display()))?;
+        edit(mapping)?;
+
+        let profile: Profile = serde_yaml::from_value(yaml.clone())?;
+        profile.validate()?;
+        fs::write(&path, serde_yaml::to_string(&yaml)?)?;
+
+        self.profiles.insert(profile_name.to_string(), profile);
+        Ok(&self.profiles[profile_name])
+    }
+
+    /// The profile a fresh enforcer starts with: `requested` if given,
+    /// otherwise the one saved by `kern mode`, otherwise `default_profile`
+    /// (from kern.yaml) if it exists, otherwise the manager's default
+    pub fn startup_profile(&self, requested: Option<&str>, default_profile: &str) -> Result<&Profile> {
+        if let Some(name) = requested {
+            return self.get(name).ok_or_else(|| {
+                anyhow!("Profile '{}' not found. Available: {}", name, self.list_names().join(", "))
+            });
+        }
+
+        let state_file = self.config_dir.join(".state");
+        let saved = if state_file.exists() {
+            Some(fs::read_to_string(&state_file)?.trim().to_string())
+        } else {
+            None
+        };
+        let name = [saved.as_deref(), Some(default_profile)]
+            .into_iter()
+            .flatten()
+            .find(|name| self.profiles.contains_key(*name))
+            .unwrap_or(&self.current_profile);
+        self.get(name).ok_or_else(|| anyhow!("Profile '{}' not found", name))
+    }
+
     /// Load saved profile state from config directory
     pub fn load_state(&mut self) -> Result<()> {
         let state_file = self.config_dir.join(".state");
@@ -278,32 +695,74 @@ impl ProfileManager {
         Ok(())
     }
 
-    /// Print all profiles summary
-    pub fn print_summary(&self) {
-        println!("📋 Available Profiles");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    /// Summary of every profile, marking the current one
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "📋 Available Profiles".to_string(),
+            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+        ];
         for (name, profile) in self.list_all() {
             let is_current = if name == self.current_profile {
                 " (current)"
             } else {
                 ""
             };
-            println!("{}{}", name, is_current);
-            println!("  └─ {}", profile.description);
-            println!(
-                "     CPU: {}%, RAM: {}%, Temp: {}°C",
+            lines.push(format!("{}{}", name, is_current));
+            lines.push(format!("  └─ {}", profile.description));
+            lines.push(format!(
+                "     CPU: {}%{}, RAM: {}%{}, Temp: {}°C",
                 profile.limits.max_cpu_percent,
+                profile.limits.max_cpu_cores.map(|cores| format!(" / {} cores", cores)).unwrap_or_default(),
                 profile.limits.max_ram_percent,
+                profile.limits.max_ram_gb.map(|gb| format!(" / {} GB", gb)).unwrap_or_default(),
                 profile.limits.max_temp
-            );
-            println!(
-                "     Protected: {} | Kill on activate: {}",
+            ));
+            if let Some(pressure) = profile.limits.max_mem_pressure {
+                lines.push(format!("     Memory pressure: {}%", pressure));
+            }
+            if let Some(io) = profile.limits.max_io_mbps {
+                lines.push(format!("     Disk I/O per process: {} MB/s", io));
+            }
+            lines.push(format!(
+                "     Protected: {} | Kill on activate: {}{}{}",
                 profile.protected.len(),
-                profile.kill_on_activate.len()
-            );
-            println!();
+                profile.kill_on_activate.len(),
+                if !profile.enforce {
+                    " | monitor-only"
+                } else if profile.enforcement.mode == EnforcementMode::Alert {
+                    " | alert-only"
+                } else {
+                    ""
+                },
+                if profile.group_processes { " | grouped" } else { "" }
+            ));
+            lines.push(String::new());
+        }
+        lines
+    }
+}
+
+// Two auto-activating profiles with the same priority could never be resolved
+fn validate_priorities(profiles: &HashMap<String, Profile>) -> Result<()> {
+    let mut seen: HashMap<u32, &str> = HashMap::new();
+    let mut names: Vec<&String> = profiles.keys().collect();
+    names.sort();
+
+    for name in names {
+        let profile = &profiles[name];
+        let Some(priority) = profile.priority.filter(|_| profile.auto_activate.enabled) else {
+            continue;
+        };
+        if let Some(other) = seen.insert(priority, name) {
+            return Err(anyhow!(
+                "Profiles '{}' and '{}' both auto-activate with priority {} (priorities must be unique)",
+                other,
+                name,
+                priority
+            ));
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -330,10 +789,7 @@ mod tests {
         let mut profile = Profile {
             name: "test".to_string(),
             description: "Test profile".to_string(),
-            protected: vec![],
-            kill_on_activate: vec![],
-            limits: ProfileResourceLimits::default(),
-            auto_activate: AutoActivateConfig::default(),
+            ..Default::default()
         };
 
         // Invalid: negative CPU
@@ -354,10 +810,7 @@ mod tests {
         let mut profile = Profile {
             name: "test".to_string(),
             description: "Test profile".to_string(),
-            protected: vec![],
-            kill_on_activate: vec![],
-            limits: ProfileResourceLimits::default(),
-            auto_activate: AutoActivateConfig::default(),
+            ..Default::default()
         };
 
         // Invalid: negative RAM
@@ -371,6 +824,18 @@ mod tests {
         // Valid
         profile.limits.max_ram_percent = 70.0;
         assert!(profile.validate().is_ok());
+
+        profile.limits.max_mem_pressure = Some(120.0);
+        assert!(profile.validate().is_err());
+        profile.limits.max_mem_pressure = Some(f64::NAN);
+        assert!(profile.validate().is_err());
+        profile.limits.max_mem_pressure = Some(20.0);
+        assert!(profile.validate().is_ok());
+
+        profile.limits.max_io_mbps = Some(-1.0);
+        assert!(profile.validate().unwrap_err().to_string().contains("max_io_mbps"));
+        profile.limits.max_io_mbps = Some(50.0);
+        assert!(profile.validate().is_ok());
     }
 
     #[test]
@@ -378,10 +843,7 @@ mod tests {
         let mut profile = Profile {
             name: "test".to_string(),
             description: "Test profile".to_string(),
-            protected: vec![],
-            kill_on_activate: vec![],
-            limits: ProfileResourceLimits::default(),
-            auto_activate: AutoActivateConfig::default(),
+            ..Default::default()
         };
 
         // Invalid: negative temperature
@@ -397,15 +859,39 @@ mod tests {
         assert!(profile.validate().is_ok());
     }
 
+    #[test]
+    fn test_profile_validation_temperature_overrides() {
+        let mut profile = Profile {
+            name: "test".to_string(),
+            description: "Test profile".to_string(),
+            ..Default::default()
+        };
+
+        // Invalid: critical not higher than warning
+        profile.limits.temp_warning = Some(90.0);
+        profile.limits.temp_critical = Some(85.0);
+        assert!(profile.validate().is_err());
+
+        // Invalid: out of range
+        profile.limits.temp_warning = Some(80.0);
+        profile.limits.temp_critical = Some(130.0);
+        assert!(profile.validate().is_err());
+
+        // Valid: both set and ordered
+        profile.limits.temp_critical = Some(95.0);
+        assert!(profile.validate().is_ok());
+
+        // Valid: a single override falls back to the global value for the other
+        profile.limits.temp_warning = None;
+        assert!(profile.validate().is_ok());
+    }
+
     #[test]
     fn test_profile_validation_empty_name() {
         let profile = Profile {
             name: String::new(),
             description: "Test profile".to_string(),
-            protected: vec![],
-            kill_on_activate: vec![],
-            limits: ProfileResourceLimits::default(),
-            auto_activate: AutoActivateConfig::default(),
+            ..Default::default()
         };
 
         assert!(profile.validate().is_err());
@@ -458,7 +944,331 @@ description: "Minimal profile"
         assert_eq!(profile.limits.max_cpu_percent, 90.0);
         assert_eq!(profile.limits.max_ram_percent, 85.0);
         assert_eq!(profile.limits.max_temp, 85.0);
+        assert!(profile.kill_strategy.is_none());
+        assert!(profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_profile_kill_strategy() {
+        let yaml = r#"
+name: "Strategy"
+description: "Kill the newest process first"
+kill_strategy: newest
+"#;
+
+        let profile: Profile = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(profile.kill_strategy, Some(KillStrategy::Newest));
+        assert!(!profile.group_processes);
+
+        let profile: Profile =
+            serde_yaml::from_str("name: browse\ndescription: ''\ngroup_processes: true").unwrap();
+        assert!(profile.group_processes);
+
+        let profile: Profile =
+            serde_yaml::from_str("name: both\ndescription: ''\nkill_strategy: heaviest_cpu_plus_memory").unwrap();
+        assert_eq!(profile.kill_strategy, Some(KillStrategy::HeaviestCpuPlusMemory));
+
+        let yaml = r#"
+name: "Bad Strategy"
+description: "Unknown strategy"
+kill_strategy: random
+"#;
+        assert!(serde_yaml::from_str::<Profile>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_parse_idle_trigger() {
+        let yaml = r#"
+name: "Idle"
+description: "Low-power profile while away"
+auto_activate:
+  enabled: true
+  triggers:
+    - type: "idle"
+      idle_minutes: 15
+    - command_contains: "make"
+"#;
+
+        let profile: Profile = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let triggers = &profile.auto_activate.triggers;
+        assert_eq!(triggers[0].idle_threshold(), Some(Duration::from_secs(900)));
+        assert_eq!(triggers[1].idle_threshold(), None);
+        assert!(profile.validate().is_ok());
+
+        for minutes in ["", "\n      idle_minutes: 0"] {
+            let yaml = format!("name: Idle\ndescription: Away\nauto_activate:\n  triggers:\n    - type: idle{}\n", minutes);
+            let profile: Profile = serde_yaml::from_str(&yaml).unwrap();
+            assert!(profile.validate().unwrap_err().to_string().contains("idle_minutes"));
+        }
+    }
+
+    #[test]
+    fn test_parse_profile_cgroup_limit_action() {
+        let yaml = r#"
+name: "Coding"
+description: "Throttle the IDE instead of killing it"
+limits:
+  max_cpu_percent: 80
+  action: cgroup_limit
+  cgroup_cpu_percent: 40
+  cgroup_memory_high_mb: 2048
+"#;
+
+        let mut profile: Profile = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(profile.limits.action, LimitAction::CgroupLimit);
+        assert_eq!(profile.limits.cgroup_cpu_percent, 40.0);
+        assert_eq!(profile.limits.cgroup_memory_high_mb, Some(2048));
+        assert!(profile.validate().is_ok());
+
+        profile.limits.cgroup_cpu_percent = 0.0;
+        assert!(profile.validate().is_err());
+
+        let profile: Profile = serde_yaml::from_str("name: plain\ndescription: ''").unwrap();
+        assert_eq!(profile.limits.action, LimitAction::Kill);
+    }
+
+    #[test]
+    fn test_absolute_limits() {
+        let yaml = "name: big\ndescription: ''\nlimits:\n  max_cpu_percent: 90\n  max_cpu_cores: 8\n  max_ram_gb: 16\n";
+        let mut profile: Profile = serde_yaml::from_str(yaml).unwrap();
         assert!(profile.validate().is_ok());
+
+        // 8 of 64 cores is stricter than 90%; on 8 cores the percentage wins
+        assert_eq!(profile.limits.effective_cpu_percent(64.0), 12.5);
+        assert_eq!(profile.limits.effective_cpu_percent(8.0), 90.0);
+        assert_eq!(profile.limits.effective_ram_percent(64.0), 25.0);
+        assert_eq!(profile.limits.effective_ram_percent(0.0), 85.0);
+        assert!(profile.limits.capacity_warnings(64.0, 64.0).is_empty());
+
+        let warnings = profile.limits.capacity_warnings(4.0, 8.0);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("max_cpu_cores 8"));
+
+        profile.limits.max_ram_gb = Some(-1.0);
+        assert!(profile.validate().is_err());
+        profile.limits.max_ram_gb = None;
+        profile.limits.max_cpu_cores = Some(f64::NAN);
+        assert!(profile.validate().is_err());
+    }
+
+    fn manager_with_profiles(profiles: &[(&str, &str)]) -> Result<(tempfile::TempDir, ProfileManager)> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(&profiles_dir).unwrap();
+        for (name, extra) in profiles {
+            let yaml = format!("name: {}\ndescription: ''\n{}", name, extra);
+            fs::write(profiles_dir.join(format!("{}.yaml", name)), yaml).unwrap();
+        }
+        let manager = ProfileManager::new(Some(temp_dir.path().to_path_buf()))?;
+        Ok((temp_dir, manager))
+    }
+
+    const AUTO: &str = "auto_activate:\n  enabled: true\n";
+
+    #[test]
+    fn test_resolve_auto_activation_three_way_conflict() {
+        let (_dir, mut manager) = manager_with_profiles(&[
+            ("normal", ""),
+            ("building", &format!("priority: 20\n{}", AUTO)),
+            ("gaming", &format!("priority: 10\n{}", AUTO)),
+            ("coding", &format!("priority: 30\n{}", AUTO)),
+        ])
+        .unwrap();
+        manager.current_profile = "normal".to_string();
+
+        // Lowest priority number wins, regardless of order
+        assert_eq!(manager.resolve_auto_activation(&["coding", "building", "gaming"]), Some("gaming"));
+        assert_eq!(manager.resolve_auto_activation(&["coding", "building"]), Some("building"));
+
+        // The active profile outranks a lower-priority trigger, and isn't re-activated
+        manager.current_profile = "building".to_string();
+        assert_eq!(manager.resolve_auto_activation(&["coding"]), None);
+        assert_eq!(manager.resolve_auto_activation(&["coding", "building"]), None);
+        assert_eq!(manager.resolve_auto_activation(&["coding", "building", "gaming"]), Some("gaming"));
+        assert_eq!(manager.resolve_auto_activation(&[]), None);
+    }
+
+    #[test]
+    fn test_resolve_auto_activation_ties_break_by_name() {
+        let (_dir, manager) = manager_with_profiles(&[
+            ("normal", ""),
+            ("zeta", AUTO),
+            ("alpha", AUTO),
+            ("mid", AUTO),
+        ])
+        .unwrap();
+
+        // No priorities at all: alphabetical
+        assert_eq!(manager.resolve_auto_activation(&["zeta", "mid", "alpha"]), Some("alpha"));
+        assert_eq!(manager.resolve_auto_activation(&["unknown"]), None);
+    }
+
+    #[test]
+    fn test_duplicate_auto_activation_priorities_rejected() {
+        let result = manager_with_profiles(&[
+            ("normal", ""),
+            ("a", &format!("priority: 5\n{}", AUTO)),
+            ("b", &format!("priority: 5\n{}", AUTO)),
+            ("c", &format!("priority: 7\n{}", AUTO)),
+        ]);
+        let error = result.err().expect("duplicate priorities must be rejected").to_string();
+        assert!(error.contains("'a' and 'b'"));
+
+        // Shared priorities are fine when only one of them auto-activates
+        assert!(manager_with_profiles(&[("normal", "priority: 5\n"), ("a", &format!("priority: 5\n{}", AUTO))]).is_ok());
+    }
+
+    fn manager_with_normal_profile() -> (tempfile::TempDir, ProfileManager) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(&profiles_dir).unwrap();
+        fs::write(profiles_dir.join("normal.yaml"), "name: normal\ndescription: Default\n").unwrap();
+        let manager = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        (temp_dir, manager)
+    }
+
+    #[test]
+    fn test_startup_profile_resolution() {
+        let (temp_dir, _) = manager_with_normal_profile();
+        fs::write(temp_dir.path().join("profiles/strict.yaml"), "name: Strict Mode\ndescription: Low limits\n").unwrap();
+        fs::write(temp_dir.path().join("profiles/coding.yaml"), "name: coding\ndescription: Coding\n").unwrap();
+        let manager = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+
+        // kern.yaml's default_profile, or the manager's default if it doesn't exist
+        assert_eq!(manager.startup_profile(None, "coding").unwrap().name, "coding");
+        assert_eq!(manager.startup_profile(None, "missing").unwrap().name, "normal");
+
+        // A profile saved by `kern mode` wins over default_profile
+        fs::write(temp_dir.path().join(".state"), "strict\n").unwrap();
+        assert_eq!(manager.startup_profile(None, "coding").unwrap().name, "Strict Mode");
+
+        // --profile wins over both, and must exist
+        assert_eq!(manager.startup_profile(Some("coding"), "normal").unwrap().name, "coding");
+        let error = manager.startup_profile(Some("missing"), "normal").unwrap_err().to_string();
+        assert!(error.contains("'missing' not found"));
+    }
+
+    #[test]
+    fn test_set_limit_keeps_other_fields() {
+        let (temp_dir, _) = manager_with_normal_profile();
+        let path = temp_dir.path().join("profiles/normal.yaml");
+        fs::write(&path, "name: normal\ndescription: Default\nprotected: [code]\nnotes: kept as is\n").unwrap();
+        let mut manager = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+
+        assert_eq!(manager.set_limit("normal", "max_cpu_percent", "50").unwrap().limits.max_cpu_percent, 50.0);
+        manager.set_limit("normal", "action", "cgroup_limit").unwrap();
+        manager.set_limit("normal", "max_ram_gb", "null").unwrap();
+
+        let reloaded = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        let profile = reloaded.get("normal").unwrap();
+        assert_eq!(profile.limits.max_cpu_percent, 50.0);
+        assert_eq!(profile.limits.action, LimitAction::CgroupLimit);
+        assert_eq!(profile.protected, vec!["code".to_string()]);
+        assert!(fs::read_to_string(&path).unwrap().contains("notes: kept as is"));
+    }
+
+    #[test]
+    fn test_set_limit_rejects_invalid_values() {
+        let (temp_dir, mut manager) = manager_with_normal_profile();
+        let path = temp_dir.path().join("profiles/normal.yaml");
+        let before = fs::read_to_string(&path).unwrap();
+
+        let error = manager.set_limit("normal", "max_cpu_percent", "150").unwrap_err().to_string();
+        assert!(error.contains("must be 0-100"), "{}", error);
+        assert!(manager.set_limit("normal", "max_cpu_percent", "lots").is_err());
+        assert!(manager.set_limit("normal", "max_gpu_percent", "50").unwrap_err().to_string().contains("Unknown limit"));
+        assert!(manager.set_limit("missing", "max_cpu_percent", "50").is_err());
+
+        // Nothing changed, in memory or on disk
+        assert_eq!(manager.get("normal").unwrap().limits.max_cpu_percent, 90.0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_add_and_remove_protected() {
+        let (temp_dir, mut manager) = manager_with_normal_profile();
+
+        assert!(manager.add_protected("normal", "obs").unwrap());
+        assert!(!manager.add_protected("normal", "obs").unwrap());
+        assert!(manager.add_protected("normal", " ").is_err());
+        let reloaded = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        assert_eq!(reloaded.get("normal").unwrap().protected, vec!["obs".to_string()]);
+
+        assert!(manager.remove_protected("normal", "obs").unwrap());
+        assert!(!manager.remove_protected("normal", "obs").unwrap());
+        assert!(manager.get("normal").unwrap().protected.is_empty());
+    }
+
+    #[test]
+    fn test_create_profile() {
+        let (temp_dir, mut manager) = manager_with_normal_profile();
+        let profile = Profile {
+            name: "focus".to_string(),
+            description: "Deep work".to_string(),
+            kill_on_activate: vec!["slack".to_string()],
+            ..Default::default()
+        };
+
+        let path = manager.create_profile(profile).unwrap();
+        assert_eq!(path, temp_dir.path().join("profiles/focus.yaml"));
+        assert_eq!(manager.get("focus").unwrap().kill_on_activate, vec!["slack".to_string()]);
+
+        // Round-trips through the file loader
+        let reloaded = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        assert_eq!(reloaded.get("focus").unwrap().description, "Deep work");
+    }
+
+    #[test]
+    fn test_create_profile_rejects_duplicates_and_invalid() {
+        let (_temp_dir, mut manager) = manager_with_normal_profile();
+        let normal = Profile {
+            name: "normal".to_string(),
+            description: "Replacement".to_string(),
+            ..Default::default()
+        };
+        assert!(manager.create_profile(normal.clone()).is_err());
+        assert_eq!(manager.get("normal").unwrap().description, "Default");
+
+        manager.create_profile_overwrite(normal).unwrap();
+        assert_eq!(manager.get("normal").unwrap().description, "Replacement");
+
+        let mut invalid = Profile { name: "hot".to_string(), ..Default::default() };
+        invalid.limits.max_cpu_percent = 150.0;
+        assert!(manager.create_profile(invalid).is_err());
+
+        let escaping = Profile { name: "../evil".to_string(), ..Default::default() };
+        assert!(manager.create_profile(escaping).is_err());
+        assert!(manager.get("hot").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_profile() {
+        let (temp_dir, _) = manager_with_normal_profile();
+        fs::write(
+            temp_dir.path().join("profiles/normal.yaml"),
+            "name: normal\ndescription: Default\nlimits:\n  max_cpu_percent: 70\nnotes: hand-written\n",
+        )
+        .unwrap();
+        let mut manager = ProfileManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+
+        let path = manager.duplicate("normal", "focus").unwrap();
+        assert_eq!(path, temp_dir.path().join("profiles/focus.yaml"));
+        assert!(path.exists());
+
+        // Available without reloading, identical apart from the name
+        let focus = manager.get("focus").unwrap();
+        assert_eq!(focus.name, "focus");
+        assert_eq!(focus.description, "Default");
+        assert_eq!(focus.limits.max_cpu_percent, 70.0);
+        assert!(manager.list_names().contains(&"focus".to_string()));
+        assert!(fs::read_to_string(&path).unwrap().contains("notes: hand-written"));
+        assert_eq!(manager.get("normal").unwrap().name, "normal");
+
+        let error = manager.duplicate("normal", "focus").unwrap_err().to_string();
+        assert!(error.contains("already exists"), "{}", error);
+        assert!(manager.duplicate("missing", "other").unwrap_err().to_string().contains("not found"));
+        assert!(manager.duplicate("normal", "../evil").is_err());
+        assert!(manager.get("other").is_none());
     }
 }
 
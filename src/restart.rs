@@ -0,0 +1,305 @@
+//! Relaunching processes the enforcer killed, for profiles' `restart_after_kill`
+//!
+//! A kill captures the victim's command line, working directory, environment
+//! and owner (`capture`); the enforcer queues it in a `RestartQueue` and relaunches it
+//! once CPU, RAM and temperature have stayed under the limits for
+//! `restart.stabilization_secs`.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Window `restart.max_per_hour` counts relaunches over
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// How to start a killed process again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RestartCommand {
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub cwd: PathBuf,
+    pub uid: u32,
+    pub gid: u32,
+    #[serde(skip)] // May hold secrets; never logged or sent to clients
+    pub env: Vec<(String, String)>,
+}
+
+/// Why a queued relaunch was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartSkip {
+    AlreadyRunning, // A process with the same name is running again
+    RateLimited { max_per_hour: u32 },
+}
+
+impl fmt::Display for RestartSkip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartSkip::AlreadyRunning => write!(f, "already running"),
+            RestartSkip::RateLimited { max_per_hour } => write!(f, "restarted {} times in the last hour", max_per_hour),
+        }
+    }
+}
+
+/// Capture what `relaunch` needs from a live process. None once it has exited,
+/// for kernel threads (empty command line), without access to its cwd or
+/// environment (another user's process, without root), or when its real
+/// user and group can't be read.
+///
+/// The owner comes from the real IDs in /proc/<pid>/status, never from who
+/// owns /proc/<pid>: the kernel makes that root for any non-dumpable process,
+/// which would relaunch a user's program as root.
+pub fn capture(pid: u32, name: &str) -> Option<RestartCommand> {
+    capture_in(Path::new("/proc"), pid, name)
+}
+
+fn capture_in(proc_root: &Path, pid: u32, name: &str) -> Option<RestartCommand> {
+    let dir = proc_root.join(pid.to_string());
+    let cmdline = parse_cmdline(&std::fs::read(dir.join("cmdline")).ok()?);
+    if cmdline.is_empty() {
+        return None;
+    }
+    let status = std::fs::read_to_string(dir.join("status")).ok()?;
+    Some(RestartCommand {
+        name: name.to_string(),
+        cmdline,
+        cwd: std::fs::read_link(dir.join("cwd")).ok()?,
+        uid: parse_real_id(&status, "Uid:")?,
+        gid: parse_real_id(&status, "Gid:")?,
+        env: parse_environ(&std::fs::read(dir.join("environ")).ok()?),
+    })
+}
+
+// NUL-separated arguments, usually with a trailing NUL
+fn parse_cmdline(contents: &[u8]) -> Vec<String> {
+    contents
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+// The real ID from "Uid:\t1000\t1000\t1000\t1000" (real, effective, saved, filesystem)
+fn parse_real_id(status: &str, field: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|ids| ids.split_whitespace().next())
+        .and_then(|id| id.parse().ok())
+}
+
+// NUL-separated KEY=VALUE pairs from /proc/<pid>/environ
+fn parse_environ(contents: &[u8]) -> Vec<(String, String)> {
+    parse_cmdline(contents)
+        .into_iter()
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Start `command` again, detached from kern (own process group, no stdio),
+/// with the environment it had rather than kern's. As root, it runs as its
+/// original user and group. Returns the new PID.
+pub fn relaunch(command: &RestartCommand) -> std::io::Result<u32> {
+    let (program, args) = command
+        .cmdline
+        .split_first()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty command line"))?;
+    let mut process = Command::new(program);
+    process
+        .args(args)
+        .env_clear()
+        .envs(command.env.iter().map(|(key, value)| (key, value)))
+        .current_dir(&command.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    if nix::unistd::geteuid().is_root() {
+        // setgid, then setuid (dropping root's supplementary groups)
+        process.gid(command.gid).uid(command.uid);
+    }
+    let mut child = process.spawn()?;
+    let pid = child.id();
+    // Reap it whenever it exits, so it never lingers as a zombie of kern
+    std::thread::spawn(move || child.wait());
+    Ok(pid)
+}
+
+/// Killed processes waiting to be relaunched, and recent relaunches per name
+#[derive(Debug, Clone, Default)]
+pub struct RestartQueue {
+    pending: Vec<RestartCommand>,
+    calm_since: Option<Instant>,
+    history: HashMap<String, VecDeque<Instant>>,
+}
+
+impl RestartQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a relaunch; a newer kill of the same name replaces the queued one
+    pub fn push(&mut self, command: RestartCommand) {
+        self.pending.retain(|pending| pending.name != command.name);
+        self.pending.push(command);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn pending(&self) -> &[RestartCommand] {
+        &self.pending
+    }
+
+    /// Take the queued relaunches once conditions have been `calm` for
+    /// `stabilization` without a break; a sample that isn't calm starts over
+    pub fn due(&mut self, calm: bool, now: Instant, stabilization: Duration) -> Vec<RestartCommand> {
+        if !calm {
+            self.calm_since = None;
+            return Vec::new();
+        }
+        let since = *self.calm_since.get_or_insert(now);
+        if self.pending.is_empty() || now.duration_since(since) < stabilization {
+            return Vec::new();
+        }
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Count a relaunch of `name` at `now`, unless it already had `max_per_hour`
+    /// in the past hour
+    pub fn allow(&mut self, name: &str, now: Instant, max_per_hour: u32) -> bool {
+        let history = self.history.entry(name.to_string()).or_default();
+        while history.front().is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW) {
+            history.pop_front();
+        }
+        if history.len() >= max_per_hour as usize {
+            return false;
+        }
+        history.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str) -> RestartCommand {
+        RestartCommand {
+            name: name.to_string(),
+            cmdline: vec![format!("/usr/bin/{}", name), "--daemon".to_string()],
+            cwd: PathBuf::from("/home/dev"),
+            uid: 1000,
+            gid: 1000,
+            env: vec![("HOME".to_string(), "/home/dev".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(parse_cmdline(b"/usr/bin/syncthing\0-no-browser\0"), vec!["/usr/bin/syncthing", "-no-browser"]);
+        assert_eq!(parse_cmdline(b"sleep\x00600"), vec!["sleep", "600"]);
+        assert!(parse_cmdline(b"").is_empty());
+
+        // This test process
+        let own = capture(std::process::id(), "kern-test").unwrap();
+        assert_eq!(own.cwd, std::env::current_dir().unwrap());
+        assert_eq!(own.uid, nix::unistd::getuid().as_raw());
+        assert!(!own.cmdline.is_empty());
+    }
+
+    #[test]
+    fn test_capture_uses_real_ids_from_status() {
+        // Whoever runs the test owns the fake /proc/<pid>, as root does for a
+        // non-dumpable process; only the status lines may count
+        let proc_root = tempfile::tempdir().unwrap();
+        let dir = proc_root.path().join("4242");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("cmdline"), b"/home/dev/bin/sync\0--daemon\0").unwrap();
+        std::os::unix::fs::symlink("/home/dev", dir.join("cwd")).unwrap();
+        std::fs::write(dir.join("environ"), b"HOME=/home/dev\0LANG=C.UTF-8\0").unwrap();
+        std::fs::write(dir.join("status"), "Name:\tsync\nUid:\t1000\t0\t0\t0\nGid:\t1001\t0\t0\t0\n").unwrap();
+
+        let captured = capture_in(proc_root.path(), 4242, "sync").unwrap();
+        assert_eq!((captured.uid, captured.gid), (1000, 1001));
+        assert_eq!(captured.cwd, PathBuf::from("/home/dev"));
+        assert_eq!(
+            captured.env,
+            vec![("HOME".to_string(), "/home/dev".to_string()), ("LANG".to_string(), "C.UTF-8".to_string())]
+        );
+
+        // No readable owner: nothing to relaunch as
+        std::fs::write(dir.join("status"), "Name:\tsync\n").unwrap();
+        assert!(capture_in(proc_root.path(), 4242, "sync").is_none());
+    }
+
+    #[test]
+    fn test_due_after_stabilization() {
+        let start = Instant::now();
+        let stabilization = Duration::from_secs(60);
+        let mut queue = RestartQueue::new();
+        queue.push(command("syncthing"));
+        queue.push(command("syncthing")); // A second kill doesn't queue a second launch
+        assert_eq!(queue.pending().len(), 1);
+
+        assert!(queue.due(true, start, stabilization).is_empty());
+        // Pressure again: the calm period starts over
+        assert!(queue.due(false, start + Duration::from_secs(30), stabilization).is_empty());
+        assert!(queue.due(true, start + Duration::from_secs(40), stabilization).is_empty());
+        assert!(queue.due(true, start + Duration::from_secs(90), stabilization).is_empty());
+
+        let due = queue.due(true, start + Duration::from_secs(100), stabilization);
+        assert_eq!(due, vec![command("syncthing")]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_allow_rate_limits_per_name() {
+        let start = Instant::now();
+        let mut queue = RestartQueue::new();
+        assert!(queue.allow("syncthing", start, 2));
+        assert!(queue.allow("syncthing", start + Duration::from_secs(60), 2));
+        assert!(!queue.allow("syncthing", start + Duration::from_secs(120), 2));
+        assert!(queue.allow("dropbox", start + Duration::from_secs(120), 2));
+
+        // The first relaunch leaves the one-hour window
+        assert!(queue.allow("syncthing", start + Duration::from_secs(3600), 2));
+        assert!(!queue.allow("syncthing", start + Duration::from_secs(3601), 2));
+    }
+
+    #[test]
+    fn test_relaunch_starts_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("relaunched");
+        let relaunched = RestartCommand {
+            name: "sh".to_string(),
+            cmdline: vec!["/bin/sh".to_string(), "-c".to_string(), "pwd > relaunched; echo \"$KERN_TEST_VAR\" >> relaunched".to_string()],
+            cwd: dir.path().to_path_buf(),
+            uid: nix::unistd::getuid().as_raw(),
+            gid: nix::unistd::getgid().as_raw(),
+            env: vec![("KERN_TEST_VAR".to_string(), "from-victim".to_string())],
+        };
+        assert!(relaunch(&relaunched).unwrap() > 0);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !marker.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        let written = std::fs::read_to_string(&marker).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(Path::new(lines[0]), dir.path().canonicalize().unwrap());
+        // The victim's environment, not kern's
+        assert_eq!(lines[1], "from-victim");
+
+        assert!(relaunch(&RestartCommand { cmdline: Vec::new(), ..relaunched }).is_err());
+    }
+}
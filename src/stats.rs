@@ -57,6 +57,37 @@ pub fn detect_trend(readings: Vec<f32>) -> Trend {
     }
 }
 
+/// The `p`th percentile (0-100) of `values`, interpolating linearly between
+/// the two nearest ranks. Sorts `values` in place. None when empty or when
+/// `p` is out of range.
+pub fn percentile(values: &mut [f64], p: f64) -> Option<f64> {
+    if values.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let rank = p / 100.0 * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(values[lower] + (values[upper] - values[lower]) * (rank - lower as f64))
+}
+
+/// Middle value of `values` (the mean of the two middle ones for an even
+/// count). Sorts `values` in place; None when empty.
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    percentile(values, 50.0)
+}
+
+/// Population standard deviation of `values`
+///
+/// Returns 0.0 if the slice is empty to avoid panics
+pub fn stddev(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
 /// Estimate time until system reaches critical temperature
 ///
 /// This is a placeholder implementation that returns a default duration.
@@ -109,6 +140,40 @@ mod tests {
         assert_eq!(detect_trend(vec![48.0, 52.0, 50.0, 53.0]), Trend::Stable);
     }
 
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&mut [], 99.0), None);
+        assert_eq!(percentile(&mut [42.0], 0.0), Some(42.0));
+        assert_eq!(percentile(&mut [42.0], 99.0), Some(42.0));
+        assert_eq!(percentile(&mut [1.0, 2.0], 101.0), None);
+        assert_eq!(percentile(&mut [1.0, 2.0], f64::NAN), None);
+
+        // Unsorted input is sorted in place
+        let mut values = [30.0, 10.0, 50.0, 20.0, 40.0];
+        assert_eq!(percentile(&mut values, 0.0), Some(10.0));
+        assert_eq!(values, [10.0, 20.0, 30.0, 40.0, 50.0]);
+        assert_eq!(percentile(&mut values, 100.0), Some(50.0));
+        assert_eq!(percentile(&mut values, 25.0), Some(20.0));
+        // Between ranks: 99% of the way from 40 to 50
+        assert!((percentile(&mut values, 99.0).unwrap() - 49.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&mut []), None);
+        assert_eq!(median(&mut [7.0]), Some(7.0));
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_stddev() {
+        assert_eq!(stddev(&[]), 0.0);
+        assert_eq!(stddev(&[5.0]), 0.0);
+        assert_eq!(stddev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 2.0);
+        assert_eq!(stddev(&[1.0, 3.0]), 1.0);
+    }
+
     #[test]
     fn test_estimate_time_to_overheat() {
         let duration = estimate_time_to_overheat();
@@ -0,0 +1,249 @@
+//! systemd service integration: sd_notify readiness, status and watchdog
+//! messages, and sockets passed in by socket activation.
+//!
+//! Everything here is a no-op when kern isn't started by systemd (no
+//! NOTIFY_SOCKET / LISTEN_FDS) or not running on Linux.
+
+use std::os::unix::io::RawFd;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// First file descriptor systemd passes with socket activation (SD_LISTEN_FDS_START)
+const LISTEN_FDS_START: RawFd = 3;
+
+/// How often `Notifier::sleep_unless` checks whether to stop
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Sends sd_notify messages to the service manager
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notifier {
+    socket: Option<PathBuf>,     // NOTIFY_SOCKET; a leading '@' is an abstract socket
+    watchdog: Option<Duration>, // WatchdogSec, when it applies to this process
+}
+
+impl Notifier {
+    /// Read NOTIFY_SOCKET, WATCHDOG_USEC and WATCHDOG_PID
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let socket = var("NOTIFY_SOCKET").map(PathBuf::from);
+        // WATCHDOG_PID names the process that must ping; without it, the main process
+        let for_us = var("WATCHDOG_PID")
+            .map(|pid| pid.parse::<u32>().ok() == Some(std::process::id()))
+            .unwrap_or(true);
+        let watchdog = var("WATCHDOG_USEC")
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| *usec > 0 && for_us)
+            .map(Duration::from_micros);
+        Self { socket, watchdog }
+    }
+
+    /// Whether systemd is listening (Type=notify)
+    pub fn enabled(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    /// The watchdog timeout, if systemd expects WATCHDOG=1 pings
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        self.watchdog.filter(|_| self.enabled())
+    }
+
+    /// Startup is complete
+    pub fn ready(&self) {
+        self.notify("READY=1");
+    }
+
+    /// One line shown by `systemctl status`
+    pub fn status(&self, status: &str) {
+        // A newline would start a new assignment
+        self.notify(&format!("STATUS={}", status.replace('\n', " ")));
+    }
+
+    /// Tell the watchdog we're alive (nothing without WatchdogSec)
+    pub fn watchdog(&self) {
+        if self.watchdog_timeout().is_some() {
+            self.notify("WATCHDOG=1");
+        }
+    }
+
+    /// Ping the watchdog if `next_ping` has come, and schedule the next ping
+    /// half a timeout later; for waits that block outside `sleep`, such as a
+    /// graceful kill
+    pub fn watchdog_due(&self, next_ping: &mut Instant) {
+        let Some(timeout) = self.watchdog_timeout() else { return };
+        let now = Instant::now();
+        if now >= *next_ping {
+            self.watchdog();
+            *next_ping = now + timeout / 2;
+        }
+    }
+
+    /// Shutting down
+    pub fn stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    /// Sleep for `duration`, pinging the watchdog at least twice per timeout so
+    /// only a stalled cycle, never a long monitor_interval, trips it
+    pub fn sleep(&self, duration: Duration) {
+        self.sleep_unless(duration, || false);
+    }
+
+    /// Like `sleep`, but return early once `stop` is true (checked every
+    /// STOP_POLL), e.g. when a shutdown signal arrived
+    pub fn sleep_unless(&self, duration: Duration, stop: impl Fn() -> bool) {
+        let deadline = Instant::now() + duration;
+        let mut next_ping = Instant::now();
+        while !stop() {
+            self.watchdog_due(&mut next_ping);
+            let now = Instant::now();
+            let mut step = STOP_POLL;
+            if self.watchdog_timeout().is_some() {
+                step = step.min(next_ping.saturating_duration_since(now));
+            }
+            let left = deadline.saturating_duration_since(now);
+            if left.is_zero() {
+                return;
+            }
+            std::thread::sleep(left.min(step));
+        }
+    }
+
+    // Failures are ignored: systemd not listening must never stop kern
+    fn notify(&self, state: &str) {
+        if let Some(socket) = &self.socket {
+            let _ = send(socket, state);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send(socket: &std::path::Path, state: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+    let path = socket.as_os_str().as_bytes();
+    let address = match path.strip_prefix(b"@") {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None => SocketAddr::from_pathname(socket)?,
+    };
+    UnixDatagram::unbound()?.send_to_addr(state.as_bytes(), &address)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send(_socket: &std::path::Path, _state: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Sockets systemd opened for this process (socket activation), in the order
+/// of the .socket unit's Listen= lines. Empty unless LISTEN_PID is us.
+pub fn listen_fds() -> Vec<RawFd> {
+    let var = |name: &str| std::env::var(name).ok().and_then(|value| value.parse::<u32>().ok());
+    match (var("LISTEN_PID"), var("LISTEN_FDS")) {
+        (Some(pid), Some(count)) if pid == std::process::id() => {
+            (LISTEN_FDS_START..LISTEN_FDS_START + count as RawFd).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+
+    fn receiver() -> (tempfile::TempDir, PathBuf, UnixDatagram) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("notify");
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        (temp_dir, path, socket)
+    }
+
+    fn receive(socket: &UnixDatagram) -> String {
+        let mut buffer = [0; 256];
+        let len = socket.recv(&mut buffer).unwrap();
+        String::from_utf8_lossy(&buffer[..len]).to_string()
+    }
+
+    #[test]
+    fn test_notifier_sends_datagrams() {
+        let (_temp_dir, path, socket) = receiver();
+        let notifier = Notifier { socket: Some(path), watchdog: Some(Duration::from_secs(10)) };
+
+        notifier.ready();
+        assert_eq!(receive(&socket), "READY=1");
+        notifier.status("Enforcing 'coding'\nsecond line");
+        assert_eq!(receive(&socket), "STATUS=Enforcing 'coding' second line");
+        notifier.watchdog();
+        assert_eq!(receive(&socket), "WATCHDOG=1");
+    }
+
+    #[test]
+    fn test_sleep_pings_the_watchdog() {
+        let (_temp_dir, path, socket) = receiver();
+        let notifier = Notifier { socket: Some(path), watchdog: Some(Duration::from_millis(40)) };
+
+        notifier.sleep(Duration::from_millis(100));
+        socket.set_nonblocking(true).unwrap();
+        let mut pings = 0;
+        let mut buffer = [0; 64];
+        while socket.recv(&mut buffer).is_ok() {
+            pings += 1;
+        }
+        assert!(pings >= 4, "only {} pings in 100ms with a 40ms watchdog", pings);
+    }
+
+    #[test]
+    fn test_graceful_kill_pings_the_watchdog() {
+        use crate::killer::{kill_process, GracefulWait};
+
+        let (_temp_dir, path, socket) = receiver();
+        let notifier = Notifier { socket: Some(path), watchdog: Some(Duration::from_millis(100)) };
+        // Ignores SIGTERM, so the whole timeout is spent waiting
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; while true; do sleep 0.05; done"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200)); // Let the trap be installed
+        let wait = GracefulWait { timeout: Duration::from_millis(500), poll: Duration::from_millis(10), watchdog: Some(&notifier) };
+        assert_eq!(kill_process(child.id(), true, wait), Ok(()));
+        child.wait().unwrap();
+
+        socket.set_nonblocking(true).unwrap();
+        let mut pings = 0;
+        let mut buffer = [0; 64];
+        while socket.recv(&mut buffer).is_ok() {
+            pings += 1;
+        }
+        assert!(pings >= 8, "only {} pings in a 500ms wait with a 100ms watchdog", pings);
+    }
+
+    #[test]
+    fn test_sleep_unless_returns_when_stopped() {
+        let notifier = Notifier::default();
+        let started = Instant::now();
+        let stop_at = started + Duration::from_millis(50);
+        notifier.sleep_unless(Duration::from_secs(10), || Instant::now() >= stop_at);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let started = Instant::now();
+        notifier.sleep_unless(Duration::from_millis(150), || false);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_without_systemd_nothing_is_sent() {
+        let notifier = Notifier::default();
+        assert!(!notifier.enabled());
+        assert!(notifier.watchdog_timeout().is_none());
+        // Must not fail or block
+        notifier.ready();
+        notifier.sleep(Duration::from_millis(1));
+
+        // A watchdog is meaningless without a socket to ping
+        let notifier = Notifier { socket: None, watchdog: Some(Duration::from_secs(1)) };
+        assert!(notifier.watchdog_timeout().is_none());
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+const CPU_SYSFS_ROOT: &str = "/sys/devices/system/cpu";
+
+/// Frequency settings of one CPU before kern throttled it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuFreqState {
+    pub cpu: String,
+    pub scaling_max_freq: Option<String>,
+    pub scaling_governor: Option<String>,
+}
+
+/// Caps CPU frequency through cpufreq sysfs as a non-destructive emergency action.
+///
+/// The original settings are persisted to a runtime file *before* anything is
+/// changed, so they can be restored on emergency exit or, after a crash, the
+/// next time the enforcer starts.
+#[derive(Debug, Clone)]
+pub struct CpuThrottle {
+    cpu_root: PathBuf,
+    state_path: PathBuf,
+}
+
+impl Default for CpuThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuThrottle {
+    pub fn new() -> Self {
+        Self::with_paths(PathBuf::from(CPU_SYSFS_ROOT), default_state_path())
+    }
+
+    /// Use a different sysfs root and state file (fixtures in tests)
+    pub fn with_paths(cpu_root: PathBuf, state_path: PathBuf) -> Self {
+        Self { cpu_root, state_path }
+    }
+
+    pub fn state_path(&self) -> &Path {
+        &self.state_path
+    }
+
+    // cpuN/cpufreq directories, ordered by CPU number
+    fn cpufreq_dirs(&self) -> Vec<(String, PathBuf)> {
+        let Ok(entries) = std::fs::read_dir(&self.cpu_root) else {
+            return Vec::new();
+        };
+
+        let mut dirs: Vec<(u32, String, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let index = name.strip_prefix("cpu")?.parse::<u32>().ok()?;
+                let cpufreq = entry.path().join("cpufreq");
+                cpufreq.is_dir().then_some((index, name, cpufreq))
+            })
+            .collect();
+
+        dirs.sort_by_key(|(index, _, _)| *index);
+        dirs.into_iter().map(|(_, name, path)| (name, path)).collect()
+    }
+
+    /// Fail with a clear error if cpufreq is missing or not writable (usually: not root)
+    pub fn check_writable(&self) -> Result<()> {
+        let dirs = self.cpufreq_dirs();
+        if dirs.is_empty() {
+            return Err(anyhow!(
+                "No cpufreq interface found under {} (CPU frequency scaling unsupported)",
+                self.cpu_root.display()
+            ));
+        }
+
+        for (_, dir) in &dirs {
+            let path = dir.join("scaling_max_freq");
+            OpenOptions::new().write(true).open(&path).map_err(|e| {
+                anyhow!(
+                    "Cannot write {}: {} (CPU throttling requires root)",
+                    path.display(),
+                    e
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Current frequency settings of every CPU
+    pub fn snapshot(&self) -> Vec<CpuFreqState> {
+        self.cpufreq_dirs()
+            .into_iter()
+            .map(|(cpu, dir)| CpuFreqState {
+                cpu,
+                scaling_max_freq: read_trimmed(&dir.join("scaling_max_freq")),
+                scaling_governor: read_trimmed(&dir.join("scaling_governor")),
+            })
+            .collect()
+    }
+
+    /// Whether saved settings are waiting to be restored
+    pub fn is_throttled(&self) -> bool {
+        self.state_path.exists()
+    }
+
+    /// Drop every CPU to its minimum frequency and the powersave governor.
+    /// Returns the number of CPUs changed.
+    pub fn throttle(&self) -> Result<usize> {
+        self.check_writable()?;
+
+        // Keep the originals from an earlier throttle that was never restored
+        if !self.is_throttled() {
+            self.save_state(&self.snapshot())?;
+        }
+
+        let mut changed = 0;
+        for (_, dir) in self.cpufreq_dirs() {
+            let mut touched = false;
+
+            let governors = read_trimmed(&dir.join("scaling_available_governors")).unwrap_or_default();
+            if governors.split_whitespace().any(|g| g == "powersave") {
+                write_value(&dir.join("scaling_governor"), "powersave")?;
+                touched = true;
+            }
+
+            if let Some(min_freq) = read_trimmed(&dir.join("cpuinfo_min_freq")) {
+                write_value(&dir.join("scaling_max_freq"), &min_freq)?;
+                touched = true;
+            }
+
+            if touched {
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Write back the saved settings and remove the state file.
+    /// Returns the number of CPUs restored (0 if nothing was throttled).
+    pub fn restore(&self) -> Result<usize> {
+        if !self.is_throttled() {
+            return Ok(0);
+        }
+
+        let content = std::fs::read_to_string(&self.state_path)
+            .with_context(|| format!("Failed to read {}", self.state_path.display()))?;
+        let saved: Vec<CpuFreqState> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", self.state_path.display()))?;
+
+        let mut restored = 0;
+        for state in &saved {
+            let dir = self.cpu_root.join(&state.cpu).join("cpufreq");
+            // Governor first: switching governors can reset the frequency cap
+            if let Some(governor) = &state.scaling_governor {
+                write_value(&dir.join("scaling_governor"), governor)?;
+            }
+            if let Some(max_freq) = &state.scaling_max_freq {
+                write_value(&dir.join("scaling_max_freq"), max_freq)?;
+            }
+            restored += 1;
+        }
+
+        std::fs::remove_file(&self.state_path)
+            .with_context(|| format!("Failed to remove {}", self.state_path.display()))?;
+        Ok(restored)
+    }
+
+    fn save_state(&self, state: &[CpuFreqState]) -> Result<()> {
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename so a crash never leaves a half-written state file
+        let tmp = self.state_path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(state)?)?;
+        std::fs::rename(&tmp, &self.state_path)?;
+        Ok(())
+    }
+}
+
+/// Runtime file holding the pre-throttle settings ($XDG_RUNTIME_DIR/kern, else /run/kern)
+pub fn default_state_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/run"));
+    runtime_dir.join("kern").join("cpu-throttle.json")
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn write_value(path: &Path, value: &str) -> Result<()> {
+    std::fs::write(path, value).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // Fake sysfs with two CPUs; cpu1 has no powersave governor
+    fn fixture() -> (TempDir, CpuThrottle) {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("cpu");
+        for (cpu, governors) in [("cpu0", "performance powersave"), ("cpu1", "performance")] {
+            let dir = root.join(cpu).join("cpufreq");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("scaling_max_freq"), "3600000\n").unwrap();
+            std::fs::write(dir.join("cpuinfo_min_freq"), "800000\n").unwrap();
+            std::fs::write(dir.join("scaling_governor"), "performance\n").unwrap();
+            std::fs::write(dir.join("scaling_available_governors"), governors).unwrap();
+        }
+        std::fs::create_dir_all(root.join("cpufreq")).unwrap(); // Not a CPU
+        let throttle = CpuThrottle::with_paths(root, temp_dir.path().join("run/cpu-throttle.json"));
+        (temp_dir, throttle)
+    }
+
+    fn value(throttle: &CpuThrottle, cpu: &str, file: &str) -> String {
+        read_trimmed(&throttle.cpu_root.join(cpu).join("cpufreq").join(file)).unwrap()
+    }
+
+    #[test]
+    fn test_throttle_and_restore() {
+        let (_dir, throttle) = fixture();
+        assert!(!throttle.is_throttled());
+
+        assert_eq!(throttle.throttle().unwrap(), 2);
+        assert!(throttle.is_throttled());
+        assert_eq!(value(&throttle, "cpu0", "scaling_max_freq"), "800000");
+        assert_eq!(value(&throttle, "cpu0", "scaling_governor"), "powersave");
+        assert_eq!(value(&throttle, "cpu1", "scaling_max_freq"), "800000");
+        assert_eq!(value(&throttle, "cpu1", "scaling_governor"), "performance");
+
+        assert_eq!(throttle.restore().unwrap(), 2);
+        assert!(!throttle.is_throttled());
+        assert_eq!(value(&throttle, "cpu0", "scaling_max_freq"), "3600000");
+        assert_eq!(value(&throttle, "cpu0", "scaling_governor"), "performance");
+
+        // Nothing left to restore
+        assert_eq!(throttle.restore().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_repeated_throttle_keeps_original_state() {
+        let (_dir, throttle) = fixture();
+        throttle.throttle().unwrap();
+        throttle.throttle().unwrap();
+
+        throttle.restore().unwrap();
+        assert_eq!(value(&throttle, "cpu0", "scaling_max_freq"), "3600000");
+    }
+
+    #[test]
+    fn test_restore_after_crash() {
+        let (_dir, throttle) = fixture();
+        throttle.throttle().unwrap();
+
+        // A new instance (e.g. after a restart) finds the persisted state
+        let after_restart =
+            CpuThrottle::with_paths(throttle.cpu_root.clone(), throttle.state_path().to_path_buf());
+        assert!(after_restart.is_throttled());
+        assert_eq!(after_restart.restore().unwrap(), 2);
+        assert_eq!(value(&throttle, "cpu1", "scaling_max_freq"), "3600000");
+    }
+
+    #[test]
+    fn test_missing_cpufreq_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let throttle = CpuThrottle::with_paths(
+            temp_dir.path().join("cpu"),
+            temp_dir.path().join("cpu-throttle.json"),
+        );
+        assert!(throttle.check_writable().is_err());
+        assert!(throttle.throttle().is_err());
+        assert!(!throttle.is_throttled());
+    }
+}
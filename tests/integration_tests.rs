@@ -253,6 +253,1290 @@ fn test_plan_documentation_exists() {
     assert!(contents.contains("PHASE"), "Plan should contain phase information");
 }
 
+// CLI tests run the kern binary against a temporary config directory.
+// The session bus address points nowhere so a real daemon is never touched.
+fn kern_command(config_home: &std::path::Path) -> std::process::Command {
+    let mut cmd = std::process::Command::new(env!("CARGO_BIN_EXE_kern"));
+    cmd.env("XDG_CONFIG_HOME", config_home)
+        .env("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent/kern-test-bus")
+        .env("DBUS_SYSTEM_BUS_ADDRESS", "unix:path=/nonexistent/kern-test-system-bus")
+        .env("XDG_RUNTIME_DIR", config_home)
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY");
+    cmd
+}
+
+fn write_test_profiles(config_home: &std::path::Path) {
+    let profiles_dir = config_home.join("kern").join("profiles");
+    fs::create_dir_all(&profiles_dir).unwrap();
+
+    fs::write(
+        profiles_dir.join("normal.yaml"),
+        "name: \"Normal Mode\"\ndescription: \"Standard\"\n",
+    )
+    .unwrap();
+    fs::write(
+        profiles_dir.join("strict.yaml"),
+        "name: \"Strict Mode\"\ndescription: \"Low limits\"\nlimits:\n  max_cpu_percent: 50\n  max_ram_percent: 60\n  max_temp: 70\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_enforce_rejects_unknown_profile() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+
+    // Fails before the enforcer loop starts
+    let output = kern_command(temp_dir.path())
+        .args(["enforce", "--profile", "missing"])
+        .output()
+        .expect("Failed to run kern");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Profile 'missing' not found"), "stderr: {}", stderr);
+    assert!(stderr.contains("normal") && stderr.contains("strict"));
+}
+
+#[test]
+fn test_enforce_uses_profile_limits() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::create_dir_all(&profiles_dir).unwrap();
+    // Monitor-only, so the enforcer under test can never kill anything
+    fs::write(
+        profiles_dir.join("normal.yaml"),
+        "name: normal\ndescription: Strict\nenforce: false\nlimits:\n  max_cpu_percent: 50\n  max_ram_percent: 60\n",
+    )
+    .unwrap();
+
+    let log_path = temp_dir.path().join("enforcer.log");
+    let mut child = kern_command(temp_dir.path())
+        .args(["enforce", "--log-file"])
+        .arg(&log_path)
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to run kern");
+
+    // The loop never exits: wait for its startup lines, then stop it
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let mut log = String::new();
+    while std::time::Instant::now() < deadline && !log.contains("Press Ctrl+C") {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        log = fs::read_to_string(&log_path).unwrap_or_default();
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(log.contains("Enforcing profile 'normal': CPU 50%, RAM 60% (monitor-only)"), "log: {}", log);
+}
+
+#[test]
+fn test_enforce_duration_ends_with_summary() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::create_dir_all(&profiles_dir).unwrap();
+    fs::write(profiles_dir.join("normal.yaml"), "name: normal\ndescription: Monitor only\nenforce: false\n").unwrap();
+
+    let started = std::time::Instant::now();
+    let output = kern_command(temp_dir.path())
+        .args(["enforce", "--json", "--interval-ms", "200", "--duration", "1s"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap_or_default()).unwrap();
+    assert_eq!(last["event"], "session_ended");
+    assert_eq!(last["stop"], "duration");
+    assert!(last["cycles"].as_u64().unwrap() >= 1);
+    assert_eq!(last["kills"], 0);
+
+    let output = kern_command(temp_dir.path()).args(["enforce", "--duration", "0s"]).output().unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_enforce_notifies_systemd() {
+    use std::os::unix::net::UnixDatagram;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::create_dir_all(&profiles_dir).unwrap();
+    fs::write(profiles_dir.join("normal.yaml"), "name: normal\ndescription: Monitor only\nenforce: false\n").unwrap();
+
+    // Stand in for systemd's notification socket
+    let socket_path = temp_dir.path().join("notify");
+    let socket = UnixDatagram::bind(&socket_path).unwrap();
+    socket.set_read_timeout(Some(std::time::Duration::from_secs(10))).unwrap();
+
+    let mut child = kern_command(temp_dir.path())
+        .args(["enforce", "--interval-ms", "100"])
+        .env("NOTIFY_SOCKET", &socket_path)
+        .env("WATCHDOG_USEC", "1000000")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to run kern");
+
+    let mut messages = Vec::new();
+    let mut buffer = [0; 512];
+    while messages.len() < 4 {
+        let Ok(len) = socket.recv(&mut buffer) else { break };
+        messages.push(String::from_utf8_lossy(&buffer[..len]).to_string());
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(messages.first().map(String::as_str), Some("READY=1"), "messages: {:?}", messages);
+    assert!(messages.contains(&"STATUS=Monitoring 'normal' - normal".to_string()), "messages: {:?}", messages);
+    assert!(messages.contains(&"WATCHDOG=1".to_string()), "messages: {:?}", messages);
+}
+
+#[test]
+fn test_profile_duplicate() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+
+    let output = kern_command(temp_dir.path())
+        .args(["profile", "duplicate", "strict", "focus"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let copy = fs::read_to_string(temp_dir.path().join("kern/profiles/focus.yaml")).unwrap();
+    assert!(copy.contains("name: focus") && copy.contains("max_temp: 70"), "{}", copy);
+
+    // Usable straight away, and never overwritten
+    let output = kern_command(temp_dir.path()).args(["mode", "focus"]).output().expect("Failed to run kern");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let output = kern_command(temp_dir.path())
+        .args(["profile", "duplicate", "normal", "focus"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+}
+
+#[test]
+fn test_mode_switches_profile_and_saves_state() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+
+    let output = kern_command(temp_dir.path())
+        .args(["mode", "strict"])
+        .output()
+        .expect("Failed to run kern");
+
+    assert!(output.status.success(), "kern mode should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Switched to 'strict'"));
+    assert!(stdout.contains("CPU 50%"));
+
+    let state = fs::read_to_string(temp_dir.path().join("kern").join(".state"))
+        .expect("State file should be written");
+    assert_eq!(state.trim(), "strict");
+}
+
+#[test]
+fn test_cli_goes_through_running_daemon() {
+    use std::io::BufRead;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+
+    // A private bus, so no real session is touched
+    let socket = temp_dir.path().join("bus");
+    let Ok(mut bus) = std::process::Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address=1"])
+        .arg(format!("--address=unix:path={}", socket.display()))
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    else {
+        eprintln!("dbus-daemon not installed, skipping");
+        return;
+    };
+    let mut address = String::new();
+    std::io::BufReader::new(bus.stdout.take().unwrap()).read_line(&mut address).unwrap();
+    let kern = |args: &[&str]| {
+        kern_command(temp_dir.path())
+            .env("DBUS_SESSION_BUS_ADDRESS", address.trim())
+            .args(args)
+            .output()
+            .expect("Failed to run kern")
+    };
+
+    let mut daemon = kern_command(temp_dir.path())
+        .env("DBUS_SESSION_BUS_ADDRESS", address.trim())
+        .arg("dbus")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to run kern dbus");
+
+    // Wait until `kern status` is answered by the daemon
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let mut status = serde_json::Value::Null;
+    while std::time::Instant::now() < deadline && status["source"] != "daemon" {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        status = serde_json::from_slice(&kern(&["status", "--json"]).stdout).unwrap_or_default();
+    }
+    let local_status: serde_json::Value =
+        serde_json::from_slice(&kern(&["--no-daemon", "status", "--json"]).stdout).unwrap_or_default();
+
+    let via_daemon = kern(&["mode", "strict"]);
+    let daemon_state = fs::read_to_string(temp_dir.path().join("kern").join(".state")).unwrap_or_default();
+    let local = kern(&["--no-daemon", "mode", "normal"]);
+
+    // Uniquely named copy of sleep, so only this test's process can match
+    let bin = temp_dir.path().join("kernviadaemon");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut victim = std::process::Command::new(&bin).arg("30").spawn().unwrap();
+    let kill = kern(&["kill", "--via-daemon", "kernviadaemon"]);
+    let victim_exited = victim.wait().is_ok();
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+    bus.kill().unwrap();
+    bus.wait().unwrap();
+
+    assert_eq!(status["source"], "daemon", "status: {}", status);
+    assert_eq!(local_status["source"], "local");
+
+    let stdout = String::from_utf8_lossy(&via_daemon.stdout);
+    assert!(stdout.contains("Switched to 'strict' (Strict Mode) through the running kern daemon"), "{}", stdout);
+    assert_eq!(daemon_state.trim(), "strict");
+
+    let stdout = String::from_utf8_lossy(&local.stdout);
+    assert!(stdout.contains("Switched to 'normal'") && !stdout.contains("kern daemon"), "{}", stdout);
+
+    let stdout = String::from_utf8_lossy(&kill.stdout);
+    assert!(stdout.contains("kern daemon killed 1 process(es)"), "{}", stdout);
+    assert!(victim_exited);
+}
+
+#[test]
+fn test_ctl_through_control_socket_without_dbus() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+    fs::write(temp_dir.path().join("kern").join("kern.yaml"), "control_socket:\n  enabled: true\n").unwrap();
+    let socket = temp_dir.path().join("kern").join("control.sock");
+    let ctl = |args: &[&str]| {
+        kern_command(temp_dir.path()).arg("ctl").args(args).output().expect("Failed to run kern ctl")
+    };
+
+    let before = ctl(&["enforcer"]);
+
+    // kern_command points DBus at nothing, so only the socket is served
+    let mut daemon = kern_command(temp_dir.path())
+        .arg("dbus")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to run kern dbus");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while std::time::Instant::now() < deadline && !ctl(&["enforcer"]).status.success() {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    let mode = std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&socket).unwrap().permissions());
+
+    let switched = ctl(&["mode", "strict"]);
+    let enforcer = ctl(&["enforcer"]);
+    let missing = ctl(&["mode", "missing"]);
+    let kill_log = ctl(&["kill-log", "--limit", "5"]);
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+
+    assert!(!before.status.success());
+    assert!(String::from_utf8_lossy(&before.stderr).contains("Could not connect"));
+    assert_eq!(mode & 0o777, 0o600);
+
+    assert!(switched.status.success(), "{}", String::from_utf8_lossy(&switched.stderr));
+    assert_eq!(String::from_utf8_lossy(&switched.stdout).trim(), "true");
+    let enforcer: serde_json::Value = serde_json::from_slice(&enforcer.stdout).expect("enforcer JSON");
+    assert_eq!(enforcer["active_profile"], "strict");
+
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("not found"));
+    let kill_log: serde_json::Value = serde_json::from_slice(&kill_log.stdout).expect("kill log JSON");
+    assert!(kill_log.is_array());
+}
+
+#[test]
+fn test_kill_via_daemon_falls_back_without_one() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--via-daemon", "kern-no-such-process"])
+        .output()
+        .expect("Failed to run kern");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No kern daemon running; killing locally"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No running process found"));
+}
+
+#[test]
+fn test_kill_timeout_is_validated() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--timeout", "15", "kern-no-such-process"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No running process found"));
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--timeout", "61", "kern-no-such-process"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--timeout"));
+}
+
+#[test]
+fn test_kill_newest_spares_the_others() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    // Uniquely named copy of sleep, so only this test's processes can match
+    let bin = temp_dir.path().join("kernkillnewest");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut older = std::process::Command::new(&bin).arg("30").spawn().unwrap();
+    // Start times have one-second resolution
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let mut newer = std::process::Command::new(&bin).arg("30").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--newest", "kernkillnewest"])
+        .output()
+        .expect("Failed to run kern");
+    let newer_exited = newer.wait().is_ok();
+    let older_running = older.try_wait().unwrap().is_none();
+    older.kill().unwrap();
+    older.wait().unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("Selected PID {} (newest, started", newer.id())), "{}", stdout);
+    assert!(stdout.contains("Killed 1 process(es)"), "{}", stdout);
+    assert!(newer_exited && older_running);
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--oldest", "--heaviest", "kernkillnewest"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_kill_older_than_keeps_recent_processes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let bin = temp_dir.path().join("kernkillolder");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut child = std::process::Command::new(&bin).arg("30").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--older-than", "1h", "kernkillolder"])
+        .output()
+        .expect("Failed to run kern");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains(&format!("PID {}", child.id())) && stdout.contains("(too recent, kept)"), "{}", stdout);
+    assert!(stdout.contains("No process matching 'kernkillolder' has been running longer than 1h"), "{}", stdout);
+    assert!(child.try_wait().unwrap().is_none());
+
+    // Start times have one-second resolution
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--older-than", "1s", "kernkillolder"])
+        .output()
+        .expect("Failed to run kern");
+    let killed = child.wait().is_ok();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 process(es) running longer than 1s"), "{}", stdout);
+    assert!(stdout.contains("Killed 1 process(es)"), "{}", stdout);
+    assert!(killed);
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--older-than", "soon", "kernkillolder"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_kill_many_without_terminal_needs_yes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let bin = temp_dir.path().join("kernkillmany");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    // One more than the default kill_confirmation_threshold (5)
+    let mut children: Vec<_> = (0..6).map(|_| std::process::Command::new(&bin).arg("30").spawn().unwrap()).collect();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "kernkillmany"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("stdin is not a terminal (pass --yes)"), "{}", stderr);
+    assert!(children.iter_mut().all(|child| child.try_wait().unwrap().is_none()));
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "kill", "--yes", "kernkillmany"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run kern");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Killed 6 process(es)"), "{}", stdout);
+    for child in &mut children {
+        child.wait().unwrap();
+    }
+}
+
+#[test]
+fn test_mode_unknown_profile_exits_with_code_2() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+
+    let output = kern_command(temp_dir.path())
+        .args(["mode", "does-not-exist"])
+        .output()
+        .expect("Failed to run kern");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not found"));
+    assert!(!temp_dir.path().join("kern").join(".state").exists());
+}
+
+#[test]
+fn test_renice_by_pid_reports_old_and_new_json() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["renice", "--pid", &child.id().to_string(), "--nice", "12", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(results[0]["pid"], child.id());
+    assert_eq!(results[0]["name"], "sleep");
+    assert_eq!(results[0]["new"], 12);
+    assert!(results[0]["old"].is_i64());
+
+    // Logged like kills
+    let log = fs::read_to_string(temp_dir.path().join("kern").join("kern.log")).unwrap();
+    assert!(log.contains("RENICE"));
+}
+
+#[test]
+fn test_renice_by_name_positional_value_and_show_nice() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    // Uniquely named copy of sleep, so only this test's process can match
+    let bin = temp_dir.path().join("kernnicetest");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut child = std::process::Command::new(&bin).arg("5").spawn().unwrap();
+
+    let renice = kern_command(temp_dir.path())
+        .args(["renice", "kernnicetest", "11", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let list = kern_command(temp_dir.path())
+        .args(["-q", "list", "--show-nice", "--filter-name", "kernnicetest"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let results: serde_json::Value = serde_json::from_slice(&renice.stdout).expect("valid JSON");
+    assert_eq!(results[0]["pid"], child.id());
+    assert_eq!(results[0]["new"], 11);
+
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    assert!(stdout.lines().next().unwrap().contains("NICE"));
+    let row = stdout.lines().find(|line| line.contains("kernnicetest")).expect("process row");
+    assert!(row.split_whitespace().any(|field| field == "11"));
+}
+
+#[test]
+fn test_find_reports_matches_and_protection() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let kern_dir = temp_dir.path().join("kern");
+    fs::create_dir_all(&kern_dir).unwrap();
+    fs::write(kern_dir.join("kern.yaml"), "protected_processes: [\"kernfindtest\"]\n").unwrap();
+    // Uniquely named copy of sleep, so only this test's process can match
+    let bin = temp_dir.path().join("kernfindtest");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut child = std::process::Command::new(&bin).arg("5").spawn().unwrap();
+
+    let find = kern_command(temp_dir.path())
+        .args(["find", "FINDTEST", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let found: serde_json::Value = serde_json::from_slice(&find.stdout).expect("valid JSON");
+    assert_eq!(found["query"], "FINDTEST");
+    let matches = found["matches"].as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["pid"], child.id());
+    assert_eq!(matches[0]["name"], "kernfindtest");
+    assert_eq!(matches[0]["protected"], true);
+    assert_eq!(matches[0]["critical"], false);
+
+    let none = kern_command(temp_dir.path())
+        .args(["-q", "find", "kern-no-such-process"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(String::from_utf8_lossy(&none.stdout).contains("No running process matches"));
+}
+
+#[test]
+fn test_renice_rejects_out_of_range_value() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["renice", "kern-no-such-process", "20"])
+        .output()
+        .expect("Failed to run kern");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("-20..=19"));
+}
+
+#[test]
+fn test_quiet_flag_suppresses_summary() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path()).args(["status"]).output().expect("Failed to run kern");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("KERN Configuration Summary"));
+
+    for args in [["status", "-q"], ["--quiet", "status"]] {
+        let output = kern_command(temp_dir.path()).args(args).output().expect("Failed to run kern");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(!stdout.contains("KERN Configuration Summary"));
+        assert!(!stdout.contains("━"));
+        assert!(stdout.lines().next().unwrap().starts_with("CPU: "));
+    }
+}
+
+#[test]
+fn test_color_flag_controls_escape_codes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::create_dir_all(&profiles_dir).unwrap();
+    // Any RAM use is over this limit
+    fs::write(profiles_dir.join("normal.yaml"), "name: \"Normal\"\ndescription: \"Tight\"\nlimits:\n  max_ram_percent: 0\n").unwrap();
+
+    let run = |args: &[&str], no_color: bool| {
+        let mut cmd = kern_command(temp_dir.path());
+        if no_color {
+            cmd.env("NO_COLOR", "1");
+        } else {
+            cmd.env_remove("NO_COLOR");
+        }
+        let output = cmd.args(args).output().expect("Failed to run kern");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let colored = run(&["-q", "--color", "always", "status"], true);
+    let ram = colored.lines().find(|line| line.starts_with("RAM:")).unwrap();
+    assert!(ram.contains("\x1b[31m"), "over-limit RAM should be red: {:?}", ram);
+
+    // Not a terminal, so auto means no color; never and JSON never color
+    assert!(!run(&["-q", "status"], false).contains('\x1b'));
+    assert!(!run(&["-q", "--color", "never", "status"], false).contains('\x1b'));
+    assert!(!run(&["--color", "always", "status", "--json"], false).contains('\x1b'));
+}
+
+#[test]
+fn test_status_format_html() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--format", "html"])
+        .output()
+        .expect("Failed to run kern");
+    let snippet = String::from_utf8_lossy(&output.stdout);
+    // Nothing but the snippet: no config summary in front of it
+    assert!(snippet.starts_with("<div class=\"kern-status\""));
+    assert!(snippet.contains("<table"));
+    assert!(!snippet.contains("<html>"));
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--format", "html", "--full-page"])
+        .output()
+        .expect("Failed to run kern");
+    let page = String::from_utf8_lossy(&output.stdout);
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.trim_end().ends_with("</html>"));
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--full-page"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_doctor_reports_signal_permissions() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let output = kern_command(temp_dir.path())
+        .args(["doctor", "--json", "--count", "5"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("doctor --json prints JSON");
+    let processes = report["processes"].as_array().unwrap();
+    assert!(processes.len() <= 5);
+    assert!(processes.iter().all(|p| p["can_signal"].is_boolean()));
+    let denied = processes.iter().filter(|p| p["can_signal"] == false).count();
+    assert_eq!(report["cannot_signal"], denied);
+    if report["root"] == true {
+        assert_eq!(report["can_kill_any_user"], true);
+        assert_eq!(denied, 0);
+    }
+}
+
+#[test]
+fn test_status_profile_preview() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::write(
+        profiles_dir.join("preview.yaml"),
+        "name: preview\ndescription: \"Kills sleep\"\nlimits:\n  max_cpu_percent: 100\n  max_ram_percent: 100\nkill_on_activate:\n  - sleep\n",
+    )
+    .unwrap();
+    fs::write(
+        profiles_dir.join("watch.yaml"),
+        "name: watch\ndescription: \"Monitor only\"\nenforce: false\nkill_on_activate:\n  - sleep\n",
+    )
+    .unwrap();
+    let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--profile", "preview", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    // Exit status 3: the preview includes kills
+    assert_eq!(output.status.code(), Some(3), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let preview: serde_json::Value = serde_json::from_slice(&output.stdout).expect("status --profile --json prints JSON");
+    assert_eq!(preview["profile"], "preview");
+    let kill = preview["would_kill"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|kill| kill["pid"] == child.id())
+        .expect("the sleep child would be killed");
+    assert_eq!(kill["reason"], "profile_activation");
+    // Only a preview: the child is still running
+    assert!(child.try_wait().unwrap().is_none());
+
+    // A monitor-only profile never kills
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--profile", "watch", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let preview: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(preview["would_kill"], serde_json::json!([]));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_explain_process() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    write_test_profiles(temp_dir.path());
+    let profiles_dir = temp_dir.path().join("kern").join("profiles");
+    fs::write(
+        profiles_dir.join("guard.yaml"),
+        "name: guard\ndescription: \"Guards the editor\"\nprotected:\n  - kern-test-editor\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("kern").join(".state"), "guard").unwrap();
+
+    let explain = |name: &str| -> serde_json::Value {
+        let output = kern_command(temp_dir.path())
+            .args(["explain", name, "--json"])
+            .output()
+            .expect("Failed to run kern");
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        serde_json::from_slice(&output.stdout).expect("explain --json prints JSON")
+    };
+
+    let systemd = explain("systemd");
+    assert_eq!(systemd["critical"], true);
+    assert_eq!(systemd["critical_reason"], "builtin");
+    assert_eq!(systemd["would_kill"], false);
+    assert!(systemd["verdict"].as_str().unwrap().contains("critical"));
+
+    let editor = explain("kern-test-editor");
+    assert_eq!(editor["profile"], "guard");
+    assert_eq!(editor["critical"], false);
+    assert_eq!(editor["protected_profile"], true);
+    assert_eq!(editor["processes"], serde_json::json!([]));
+    assert!(editor["verdict"].as_str().unwrap().contains("protected by profile 'guard'"));
+    assert!(editor["limits"]["cpu"]["limit"].is_number());
+}
+
+#[test]
+fn test_status_no_processes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--no-processes", "-q"])
+        .output()
+        .expect("Failed to run kern");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.lines().next().unwrap().starts_with("CPU: "));
+    assert!(stdout.contains("RAM: "));
+    assert!(!stdout.contains("Top processes"));
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--no-processes", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("status JSON");
+    assert!(json["memory_percentage"].is_number());
+    assert!(json.get("top_processes").is_none());
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--no-processes", "--format", "html"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_status_compact_line() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let run = |args: &[&str]| {
+        let output = kern_command(temp_dir.path()).args(args).output().expect("Failed to run kern");
+        assert!(output.status.success(), "kern {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Exactly one line and no config summary, even without -q
+    let line = run(&["status", "--compact"]);
+    assert_eq!(line.lines().count(), 1, "{:?}", line);
+    let fields: Vec<&str> = line.trim_end().split(" | ").collect();
+    assert!(fields[0].starts_with("CPU ") && fields[0].ends_with('%'));
+    assert!(fields[1].starts_with("RAM ") && fields[1].ends_with('%'));
+    assert!(fields[2].starts_with("TEMP ") && fields[2].ends_with('C'));
+    assert!(fields[3].starts_with("top:"));
+
+    let line = run(&["status", "--format", "line", "--separator", ";", "--no-processes"]);
+    assert_eq!(line.trim_end().split(';').count(), 3, "{:?}", line);
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--separator", ";"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_config_check_defaults() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "config", "check-defaults"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("All settings match"));
+
+    let kern_dir = temp_dir.path().join("kern");
+    fs::create_dir_all(&kern_dir).unwrap();
+    fs::write(kern_dir.join("kern.yaml"), "monitor_interval: 5\n").unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["-q", "config", "check-defaults"])
+        .output()
+        .expect("Failed to run kern");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "monitor_interval: 2s (default) → 5s (current)");
+
+    let output = kern_command(temp_dir.path())
+        .args(["config", "check-defaults", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let diffs: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(diffs, serde_json::json!([
+        {"path": "monitor_interval", "default_value": "2s", "current_value": "5s"}
+    ]));
+}
+
+#[test]
+fn test_status_diff() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let snapshot = temp_dir.path().join("before.json");
+    fs::write(
+        &snapshot,
+        r#"{"cpu_usage": 0.0, "memory_percentage": 0.0, "temperature": 0.0,
+            "top_processes": [{"pid": 4294967295, "name": "long-gone", "memory_gb": 1.0}]}"#,
+    )
+    .unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--diff"])
+        .arg(&snapshot)
+        .arg("--json")
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(diff["gone_processes"], serde_json::json!([{"pid": 4294967295u32, "name": "long-gone", "memory_gb": 1.0}]));
+    assert!(!diff["new_processes"].as_array().unwrap().is_empty());
+    assert_eq!(diff["cpu_usage"]["old"], 0.0);
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "-q", "--diff"])
+        .arg(&snapshot)
+        .output()
+        .expect("Failed to run kern");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("  - long-gone (PID: 4294967295) - 1.00 GB"), "{}", stdout);
+
+    fs::write(&snapshot, "not json").unwrap();
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--diff"])
+        .arg(&snapshot)
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_output_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("status.json");
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--json", "--output-file"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).expect("valid JSON");
+    assert_eq!(written["format_version"], 1);
+    assert!(written["data"]["cpu_usage"].is_number());
+
+    let output = kern_command(temp_dir.path())
+        .args(["list", "--json", "--count", "3", "--output-file"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success());
+    let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).expect("valid JSON");
+    assert!(written["data"].as_array().is_some_and(|rows| rows.len() <= 3));
+
+    // Text has no file format
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--output-file"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--output-file needs --json"));
+}
+
+#[test]
+fn test_env_overrides_config_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let kern_dir = temp_dir.path().join("kern");
+    fs::create_dir_all(&kern_dir).unwrap();
+    fs::write(kern_dir.join("kern.yaml"), "monitor_interval: 5\nlimits:\n  max_cpu_percent: 70\n").unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["config", "check-defaults", "--json"])
+        .env("KERN_MONITOR_INTERVAL", "500ms")
+        .env("KERN_MAX_CPU_PERCENT", "60")
+        .output()
+        .expect("Failed to run kern");
+    let diffs: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(diffs, serde_json::json!([
+        {"path": "limits.max_cpu_percent", "default_value": "90.0", "current_value": "60.0"},
+        {"path": "monitor_interval", "default_value": "2s", "current_value": "500ms"}
+    ]));
+
+    let output = kern_command(temp_dir.path())
+        .args(["config", "check-defaults"])
+        .env("KERN_TEMP_CRITICAL", "hot")
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("KERN_TEMP_CRITICAL"));
+}
+
+#[test]
+fn test_temperature_sensor_override() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let sensor = temp_dir.path().join("temp1_input");
+    fs::write(&sensor, "61000\n").unwrap();
+    let kern_dir = temp_dir.path().join("kern");
+    fs::create_dir_all(&kern_dir).unwrap();
+    fs::write(
+        kern_dir.join("kern.yaml"),
+        format!("temperature_sensor_path: \"{}\"\n", sensor.display()),
+    )
+    .unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(status["temperature"], 61.0);
+    assert_eq!(status["temperature_avg"], 61.0);
+
+    // An unreadable sensor is rejected before the enforcer starts
+    let output = kern_command(temp_dir.path())
+        .args(["enforce", "--temperature-sensor", "/nonexistent/temp1_input"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("temperature_sensor_path"));
+}
+
+#[test]
+fn test_list_filter_name_and_sort() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["list", "--json", "--filter-name", "SLEEP", "--sort", "pid", "--count", "1000"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let processes: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert!(processes.iter().all(|p| p["name"].as_str().unwrap().contains("sleep")));
+    assert!(processes.iter().any(|p| p["pid"] == child.id()));
+    let pids: Vec<u64> = processes.iter().map(|p| p["pid"].as_u64().unwrap()).collect();
+    assert!(pids.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn test_list_wide_reports_io_rates() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["list", "--wide", "--json", "--filter-name", "sleep", "--count", "all"])
+        .output()
+        .expect("Failed to run kern");
+    let table = kern_command(temp_dir.path())
+        .args(["-q", "list", "--wide", "--count", "1"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let processes: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let sleep = processes.iter().find(|p| p["pid"] == child.id()).expect("sleep listed");
+    // Sleeping the whole sample window: no disk I/O
+    assert_eq!(sleep["io_read_mbps"], 0.0);
+    assert_eq!(sleep["io_write_mbps"], 0.0);
+    let header = String::from_utf8_lossy(&table.stdout).lines().next().unwrap_or_default().to_string();
+    assert!(header.contains("READ MB/s") && header.contains("WRITE MB/s"), "{}", header);
+
+    let grouped = kern_command(temp_dir.path())
+        .args(["list", "--wide", "--group"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!grouped.status.success());
+}
+
+#[test]
+fn test_normalize_cpu_flag_is_reported() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--json", "--no-daemon", "--normalize-cpu"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(status["cpu_normalized"], true);
+    // A share of the whole machine can't go past 100%
+    for process in status["top_processes"].as_array().unwrap() {
+        assert!(process["cpu_percentage"].as_f64().unwrap() <= 100.0 + 1e-6, "{}", process);
+    }
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--json", "--no-daemon"])
+        .output()
+        .expect("Failed to run kern");
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(status["cpu_normalized"], false);
+}
+
+#[test]
+fn test_list_count_all() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    // More instances than the default count of 20, under a name only this test uses
+    let bin = temp_dir.path().join("kerncounttest");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut children: Vec<_> = (0..22)
+        .map(|_| std::process::Command::new(&bin).arg("5").spawn().unwrap())
+        .collect();
+
+    let list = |extra: &[&str]| {
+        let output = kern_command(temp_dir.path())
+            .args(["list", "--json", "--filter-name", "kerncounttest"])
+            .args(extra)
+            .output()
+            .expect("Failed to run kern");
+        serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout).expect("valid JSON").len()
+    };
+    let default_count = list(&[]);
+    let all = list(&["--count", "all"]);
+    let zero = list(&["--count", "0"]);
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    assert_eq!(default_count, 20);
+    assert_eq!(all, 22);
+    assert_eq!(zero, 22);
+
+    let output = kern_command(temp_dir.path())
+        .args(["list", "--count", "100001"])
+        .output()
+        .expect("Failed to run kern");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_list_ndjson_streams_one_process_per_line() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let bin = temp_dir.path().join("kernndjsontest");
+    fs::copy("/bin/sleep", &bin).unwrap();
+    let mut children: Vec<_> = (0..3)
+        .map(|_| std::process::Command::new(&bin).arg("5").spawn().unwrap())
+        .collect();
+
+    let list = |extra: &[&str]| {
+        let output = kern_command(temp_dir.path())
+            .args(["list", "--format", "ndjson", "--filter-name", "kernndjsontest"])
+            .args(extra)
+            .output()
+            .expect("Failed to run kern");
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("each line is a JSON object"))
+            .collect::<Vec<_>>()
+    };
+    let streamed = list(&["--sort", "none"]);
+    let limited = list(&["--sort", "none", "--count", "2"]);
+    let sorted = list(&["--sort", "pid"]);
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    let mut pids: Vec<u64> = streamed.iter().map(|p| p["pid"].as_u64().unwrap()).collect();
+    pids.sort_unstable();
+    let mut expected: Vec<u64> = children.iter().map(|child| child.id() as u64).collect();
+    expected.sort_unstable();
+    assert_eq!(pids, expected);
+    assert!(streamed.iter().all(|p| p["name"] == "kernndjsontest"));
+    assert_eq!(limited.len(), 2);
+    let sorted: Vec<u64> = sorted.iter().map(|p| p["pid"].as_u64().unwrap()).collect();
+    assert_eq!(sorted, expected);
+}
+
+#[test]
+fn test_list_group_sums_same_named_processes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut children: Vec<_> = (0..3)
+        .map(|_| std::process::Command::new("sleep").arg("5").spawn().unwrap())
+        .collect();
+
+    let output = kern_command(temp_dir.path())
+        .args(["list", "--json", "--group", "--filter-name", "sleep", "--count", "1000"])
+        .output()
+        .expect("Failed to run kern");
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    let groups: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let sleep = groups.iter().find(|g| g["name"] == "sleep").expect("sleep group");
+    assert!(sleep["count"].as_u64().unwrap() >= 3);
+    for child in &children {
+        assert!(sleep["pids"].as_array().unwrap().contains(&serde_json::json!(child.id())));
+    }
+}
+
+#[test]
+fn test_zombies_are_tagged_and_not_killed() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    // Uniquely named copies of sleep, so only this test's processes can match
+    let zombie_bin = temp_dir.path().join("kernzombie");
+    let parent_bin = temp_dir.path().join("kernzparent");
+    fs::copy("/bin/sleep", &zombie_bin).unwrap();
+    fs::copy("/bin/sleep", &parent_bin).unwrap();
+
+    // The shell execs into the parent, which never reaps the short-lived child
+    let script = format!("{} 0.1 & exec {} 5", zombie_bin.display(), parent_bin.display());
+    let mut parent = std::process::Command::new("sh").args(["-c", &script]).spawn().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let list = kern_command(temp_dir.path())
+        .args(["list", "--json", "--filter-name", "kernzombie"])
+        .output()
+        .expect("Failed to run kern");
+    let kill = kern_command(temp_dir.path())
+        .args(["kill", "kernzombie"])
+        .output()
+        .expect("Failed to run kern");
+    parent.kill().unwrap();
+    parent.wait().unwrap();
+
+    let processes: Vec<serde_json::Value> = serde_json::from_slice(&list.stdout).expect("valid JSON");
+    assert_eq!(processes.len(), 1);
+    assert_eq!(processes[0]["state"], "Z");
+    assert_eq!(processes[0]["ppid"], parent.id());
+
+    let stdout = String::from_utf8_lossy(&kill.stdout);
+    assert!(stdout.contains("it is a zombie"));
+    assert!(stdout.contains(&format!("parent (PID {})", parent.id())));
+    assert!(!stdout.contains("Killed"));
+}
+
+#[test]
+fn test_list_hides_kernel_threads_by_default() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let names = |args: &[&str]| -> Vec<String> {
+        let output = kern_command(temp_dir.path()).args(args).output().expect("Failed to run kern");
+        let processes: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).expect("valid JSON");
+        processes.iter().map(|p| p["name"].as_str().unwrap().to_string()).collect()
+    };
+
+    let default = names(&["list", "--json", "--count", "100000"]);
+    assert!(!default.iter().any(|name| name.starts_with("kworker")));
+
+    // Only checkable where the kernel's threads are visible (not in every container)
+    if fs::read_dir("/proc").unwrap().filter_map(Result::ok).any(|e| {
+        fs::read_to_string(e.path().join("comm")).is_ok_and(|comm| comm.starts_with("kworker"))
+    }) {
+        let all = names(&["list", "--json", "--count", "100000", "--kernel-threads"]);
+        assert!(all.iter().any(|name| name.starts_with("kworker")));
+    }
+}
+
+#[test]
+fn test_list_watch_exits_cleanly_on_sigint() {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let child = kern_command(temp_dir.path())
+        .args(["list", "--watch", "1", "--count", "3"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run kern");
+
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    // Our own child: the only process this test ever signals
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGINT).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Last refresh:"));
+    assert!(stdout.contains("\x1b[H"));
+    // Cursor is shown again on exit
+    assert!(stdout.ends_with("\x1b[?25h\n"));
+}
+
+#[test]
+fn test_status_reports_uptime_and_own_processes() {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("status JSON");
+    assert!(json["uptime_secs"].as_u64().unwrap() > 0);
+    assert!(json["boot_time"].as_u64().unwrap() > 0);
+    assert!(json["self"]["memory_mb"].as_f64().unwrap() > 0.0);
+    assert!(json["self"]["cpu_percent"].is_number());
+
+    let child = kern_command(temp_dir.path())
+        .args(["-q", "--monitor"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run kern");
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+
+    let output = kern_command(temp_dir.path())
+        .args(["status", "--self", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("status --self JSON");
+    let monitor = json["processes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["pid"] == child.id())
+        .cloned();
+
+    // Our own child: the only process this test ever signals
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGINT).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let monitor = monitor.expect("the monitor is listed");
+    assert_eq!(monitor["role"], "monitor");
+    assert!(monitor["memory_mb"].as_f64().unwrap() > 0.0);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Monitor used"));
+}
+
+#[test]
+fn test_ionice_refuses_critical_process() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let kern_dir = temp_dir.path().join("kern");
+    fs::create_dir_all(&kern_dir).unwrap();
+    fs::write(kern_dir.join("kern.yaml"), "critical_processes:\n  - sleep\n").unwrap();
+    let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+
+    let output = kern_command(temp_dir.path())
+        .args(["ionice", "--pid", &child.id().to_string(), "--class", "idle", "--json"])
+        .output()
+        .expect("Failed to run kern");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let error = result["error"].as_str().unwrap();
+    assert!(error.contains("Cannot ionice 'sleep'"));
+    assert!(error.contains("critical"));
+}
+
 // Integration tests for actual functionality
 // These tests verify the modules work correctly together
 